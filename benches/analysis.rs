@@ -0,0 +1,47 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use sonica::audio::analysis::{self, AnalysisOptions};
+use sonica::audio::decode::AudioData;
+use sonica::framerate::FrameRate;
+
+const SAMPLE_RATE: u32 = 44_100;
+const SAMPLES: usize = 2_000_000;
+
+/// A bass line, a melody, and a hi-hat-like burst every half second: enough
+/// spread across the spectrum that every band and the beat detector do work.
+fn test_signal() -> AudioData {
+    let samples = (0..SAMPLES)
+        .map(|i| {
+            let t = i as f32 / SAMPLE_RATE as f32;
+            let bass = (t * 55.0 * std::f32::consts::TAU).sin() * 0.5;
+            let melody = (t * (440.0 + 220.0 * (t * 0.5).sin()) * std::f32::consts::TAU).sin() * 0.3;
+            let burst = if t.fract() % 0.5 < 0.02 {
+                ((i as u32).wrapping_mul(2_654_435_761) >> 16) as f32 / 65_536.0 - 0.5
+            } else {
+                0.0
+            };
+            bass + melody + burst * 0.4
+        })
+        .collect();
+    AudioData {
+        samples,
+        sides: None,
+        sample_rate: SAMPLE_RATE,
+        tags: Default::default(),
+    }
+}
+
+fn analyze(c: &mut Criterion) {
+    let audio = test_signal();
+    let fps = FrameRate::integer(30);
+    let options = AnalysisOptions::default();
+
+    let mut group = c.benchmark_group("analysis");
+    group.sample_size(10);
+    group.bench_function("analyze 2M samples", |b| {
+        b.iter(|| analysis::analyze(&audio, fps, &options).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, analyze);
+criterion_main!(benches);
@@ -0,0 +1,70 @@
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use sonica::audio::decode::AudioData;
+use sonica::job::RenderJob;
+
+const SAMPLE_RATE: u32 = 44_100;
+/// Frames rendered per iteration: the first two seconds at 30fps.
+const FRAMES: usize = 60;
+
+/// Three seconds of a bass tone under a melody, so the bars and waveform move
+/// every frame.
+fn test_signal() -> AudioData {
+    let samples = (0..SAMPLE_RATE as usize * 3)
+        .map(|i| {
+            let t = i as f32 / SAMPLE_RATE as f32;
+            let bass = (t * 55.0 * std::f32::consts::TAU).sin() * 0.5;
+            let melody = (t * (440.0 + 220.0 * (t * 2.0).sin()) * std::f32::consts::TAU).sin() * 0.3;
+            bass + melody
+        })
+        .collect();
+    AudioData {
+        samples,
+        sides: None,
+        sample_rate: SAMPLE_RATE,
+        tags: Default::default(),
+    }
+}
+
+/// Frames per second of a 1080p `frequency_bars` render with one bloom pass,
+/// rendering and reading back one frame at a time against `RenderJob::run`'s
+/// pipelined loop, which submits the next frame before reading this one.
+/// Skipped when there is no GPU.
+fn render(c: &mut Criterion) {
+    let job = RenderJob::new("bench.wav").effects(vec!["bloom".into()]);
+    let analysis = job.analyze(&test_signal()).unwrap();
+    let mut renderer = match job.renderer(&analysis) {
+        Ok(renderer) => renderer,
+        Err(err) => {
+            eprintln!("Skipping render benchmarks: {err}");
+            return;
+        }
+    };
+    let frames = &analysis.frames[..FRAMES];
+
+    let mut group = c.benchmark_group("render 1080p");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(FRAMES as u64));
+    group.bench_function("sequential readback", |b| {
+        b.iter(|| {
+            for (frame_idx, frame) in frames.iter().enumerate() {
+                renderer.render_frame(frame_idx, frame).unwrap();
+            }
+        })
+    });
+    group.bench_function("pipelined readback", |b| {
+        b.iter(|| {
+            let mut submitted = Some(renderer.submit_frame(0, &frames[0]).unwrap());
+            for frame_idx in 0..frames.len() {
+                let current = submitted.take().unwrap();
+                if let Some(next) = frames.get(frame_idx + 1) {
+                    submitted = Some(renderer.submit_frame(frame_idx + 1, next).unwrap());
+                }
+                renderer.finish_frame(current).unwrap();
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, render);
+criterion_main!(benches);
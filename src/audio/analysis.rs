@@ -1,32 +1,439 @@
 use anyhow::Result;
+use std::borrow::Cow;
+use std::ops::Range;
+use std::time::{Duration, Instant};
 use rayon::prelude::*;
 use rustfft::{num_complex::Complex, FftPlanner};
 
+use super::beatgrid::{self, BeatGrid};
 use super::decode::AudioData;
+use super::pitch;
+use super::resample;
+use crate::framerate::FrameRate;
 use super::features::{FrameFeatures, GlobalAnalysis, SmoothedFrame};
 
-const FFT_SIZE: usize = 2048;
-const HOP_SIZE: usize = 1024;
+/// `--fft-size` default: ~21.5Hz bins at 44.1kHz.
+pub const DEFAULT_FFT_SIZE: usize = 2048;
+/// `--hop-size` default: pass 1's onset detection steps half a window.
+pub const DEFAULT_HOP_SIZE: usize = 1024;
+/// Smallest `--fft-size`; below it the bass bands span fewer bins than
+/// there are bands.
+pub const MIN_FFT_SIZE: usize = 256;
+/// Largest `--fft-size`, about 0.7s of audio at 48kHz per window.
+const MAX_FFT_SIZE: usize = 32768;
+/// Span in `DEFAULT_HOP_SIZE` hops of the local mean a beat has to rise
+/// above; other hop sizes keep the same span in time.
+const BEAT_THRESHOLD_HOPS: usize = 20;
+/// Fewest bins `bins_within` will reduce to; below this the spectrum
+/// templates turn visibly blocky.
+const MIN_FFT_BINS: usize = 64;
+/// Waveform points kept per frame, each uploaded as a `[min, max]` pair.
+const WAVEFORM_POINTS: usize = 512;
+/// Window for the low bands with `--dual-fft`: ~5.4Hz bins at 44.1kHz instead
+/// of ~21.5Hz, at the cost of time smearing that the slow-moving bass bands
+/// tolerate.
+const BASS_FFT_SIZE: usize = 8192;
+/// Sample amplitude treated as silence (-120 dBFS). Normalization peaks are
+/// floored here so a silent or dither-only track maps to 0, not full scale.
+const SILENCE_AMPLITUDE: f32 = 1e-6;
+/// Frequency `--spectral-tilt` leaves unchanged.
+const TILT_PIVOT_HZ: f32 = 1000.0;
+/// Lowest frequency of a `--freq-scale log` spectrum, where a log axis
+/// starting at 0 Hz would spend its first bins on nothing audible.
+const LOG_SCALE_MIN_HZ: f32 = 20.0;
+/// Time constant of the onset-strength low-pass: long enough to bridge the
+/// frames of one attack, short enough to fall between beats.
+const ONSET_ENVELOPE_SECONDS: f32 = 0.1;
+/// Edges in Hz of the seven bands, sub_bass through brilliance. The first
+/// two come from the bass spectrum with `--dual-fft`.
+const BANDS: [(f32, f32); 7] = [
+    (20.0, 60.0),
+    (60.0, 250.0),
+    (250.0, 500.0),
+    (500.0, 2000.0),
+    (2000.0, 4000.0),
+    (4000.0, 6000.0),
+    (6000.0, 20000.0),
+];
+
+/// Tuning for the smoothing and normalization pass.
+#[derive(Debug, Clone)]
+pub struct AnalysisOptions {
+    /// Bidirectional EMA factor (0.0-1.0; higher = calmer motion).
+    pub smoothing: f32,
+    /// `smoothing` for the FFT bins only; `None` uses `smoothing`.
+    pub smoothing_bins: Option<f32>,
+    /// `smoothing` for bass/mid/high and the per-side bands only.
+    pub smoothing_bands: Option<f32>,
+    /// `smoothing` for rms only.
+    pub smoothing_rms: Option<f32>,
+    /// Half-life in seconds of the per-bin normalization peak. 0 normalizes
+    /// each bin by its lifetime peak.
+    pub bin_peak_decay: f32,
+    /// Lowest per-bin normalization peak, in dB relative to the loudest bin
+    /// of the track, so rarely-excited bins can't blow noise up to full scale.
+    pub bin_noise_floor_db: f32,
+    /// Gain in dB per octave around `TILT_PIVOT_HZ`, applied to the
+    /// normalized bins to offset the ~1/f fall-off of natural audio.
+    pub spectral_tilt: f32,
+    /// Keep only the output bins at or above this frequency in Hz.
+    pub freq_min: Option<f32>,
+    /// Keep only the output bins at or below this frequency in Hz.
+    pub freq_max: Option<f32>,
+    /// Measure sub_bass/bass with a `BASS_FFT_SIZE` FFT instead of `fft_size`.
+    pub dual_fft: bool,
+    /// Measure the bands of the left and right channels separately too.
+    /// Needs `AudioData::sides`; without them both sides get the mono bands.
+    pub stereo_bands: bool,
+    /// Reduce the left and right channels to waveforms of their own too, in
+    /// `SmoothedFrame::waveform_left`/`waveform_right`. Needs
+    /// `AudioData::sides`; without them both get the mono waveform.
+    pub stereo_waveform: bool,
+    /// Seconds `beat_intensity` stays at 1.0 after a beat before decaying.
+    pub beat_hold: f32,
+    /// Shape of the `beat_intensity` fall-off after the hold.
+    pub beat_decay: BeatDecay,
+    /// Beats to use instead of the detected ones.
+    pub beat_grid: Option<BeatGrid>,
+    /// Resample to this rate before analysis, so band edges fall on the same
+    /// FFT bins and windows span the same time whatever the input's rate.
+    pub analysis_rate: Option<u32>,
+    /// Keep this many FFT bins per frame (a power of two up to `fft_size / 2`),
+    /// each the average of its neighbours, to bound memory on long renders.
+    pub fft_bins: Option<usize>,
+    /// Center (and optionally rescale) each frame's waveform before upload.
+    pub waveform_normalize: Option<WaveformNormalize>,
+    /// How each frame's samples are reduced to the waveform's points.
+    pub waveform_sampling: WaveformSampling,
+    /// Frequency axis of the output bins.
+    pub freq_scale: FreqScale,
+    /// Samples per FFT window, for pass 1's onsets and pass 2's spectrum: a
+    /// power of two from `MIN_FFT_SIZE`. Larger windows resolve low notes,
+    /// smaller ones follow fast percussion.
+    pub fft_size: usize,
+    /// Samples between pass 1's onset windows, a power of two up to
+    /// `fft_size`. Pass 2 steps by frame, so this only moves beat detection.
+    pub hop_size: usize,
+}
+
+/// `--freq-scale`: how the output bins are spaced between their lowest
+/// frequency and Nyquist.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum FreqScale {
+    /// Evenly in Hz, as the FFT computes them.
+    #[default]
+    Linear,
+    /// Evenly in octaves from `LOG_SCALE_MIN_HZ`, so each octave gets the
+    /// same share of the bins.
+    Log,
+    /// Evenly in mels, close to linear below ~700 Hz and logarithmic above.
+    Mel,
+}
+
+impl FreqScale {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value {
+            "linear" => Ok(Self::Linear),
+            "log" => Ok(Self::Log),
+            "mel" => Ok(Self::Mel),
+            other => anyhow::bail!("Invalid frequency scale '{}'. Use linear, log or mel", other),
+        }
+    }
+
+    /// Where `hz` sits on the axis, in the scale's own units.
+    fn position(self, hz: f32) -> f32 {
+        match self {
+            Self::Linear => hz,
+            Self::Log => hz.max(LOG_SCALE_MIN_HZ).ln(),
+            Self::Mel => 2595.0 * (1.0 + hz / 700.0).log10(),
+        }
+    }
 
-pub fn analyze(audio: &AudioData, fps: u32, smoothing: f32) -> Result<(GlobalAnalysis, Vec<SmoothedFrame>)> {
-    let samples = &audio.samples;
-    let sr = audio.sample_rate;
+    /// Inverse of [`Self::position`].
+    fn frequency(self, position: f32) -> f32 {
+        match self {
+            Self::Linear => position,
+            Self::Log => position.exp(),
+            Self::Mel => 700.0 * (10f32.powf(position / 2595.0) - 1.0),
+        }
+    }
+
+    /// Frequency `fraction` (0.0-1.0) of the way along the axis, from its
+    /// lowest frequency to `nyquist`.
+    fn frequency_at(self, fraction: f32, nyquist: f32) -> f32 {
+        let low = self.position(if self == Self::Log { LOG_SCALE_MIN_HZ } else { 0.0 });
+        let high = self.position(nyquist);
+        self.frequency(low + (high - low) * fraction)
+    }
+}
+
+/// `--waveform-sampling`: how a frame's samples are reduced to
+/// `WAVEFORM_POINTS` `[min, max]` pairs.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum WaveformSampling {
+    /// The lowest and highest sample in each point's span, so transients
+    /// between points still reach the scope.
+    #[default]
+    Envelope,
+    /// One sample at each point's start, stored as both min and max: the
+    /// thin trace of an oscilloscope, which can alias and miss peaks.
+    Point,
+}
+
+impl WaveformSampling {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value {
+            "envelope" => Ok(Self::Envelope),
+            "point" => Ok(Self::Point),
+            other => anyhow::bail!("Invalid waveform sampling '{}'. Use envelope or point", other),
+        }
+    }
+
+    /// Interleaved `[min, max]` pairs, one per point, for up to
+    /// `WAVEFORM_POINTS` points (fewer if the frame has fewer samples).
+    fn downsample(self, samples: &[f32]) -> Vec<f32> {
+        if samples.is_empty() {
+            return vec![0.0; WAVEFORM_POINTS * 2];
+        }
+        let points = WAVEFORM_POINTS.min(samples.len());
+        (0..points)
+            .flat_map(|i| {
+                let span = &samples[i * samples.len() / points..(i + 1) * samples.len() / points];
+                match self {
+                    Self::Envelope => {
+                        let min = span.iter().copied().fold(f32::INFINITY, f32::min);
+                        let max = span.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+                        [min, max]
+                    }
+                    Self::Point => [span[0], span[0]],
+                }
+            })
+            .collect()
+    }
+}
+
+/// Frames whose centered waveform peaks below this (-60 dBFS) are not
+/// scaled up by `WaveformNormalize::Amplitude`, so silence stays flat
+/// instead of becoming full-height noise.
+const WAVEFORM_NORMALIZE_FLOOR: f32 = 1e-3;
+
+/// `--waveform-normalize`: how each frame's waveform is conditioned for
+/// oscilloscope-style templates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WaveformNormalize {
+    /// Subtract the frame's mean, so a DC offset can't push the trace off
+    /// the center line.
+    Center,
+    /// Center, then scale the frame's peak to this amplitude (0-1), so quiet
+    /// passages still fill the scope.
+    Amplitude(f32),
+}
+
+impl WaveformNormalize {
+    pub fn parse(value: &str) -> Result<Self> {
+        if value == "center" {
+            return Ok(Self::Center);
+        }
+        match value.parse::<f32>() {
+            Ok(amplitude) if amplitude > 0.0 && amplitude <= 1.0 => Ok(Self::Amplitude(amplitude)),
+            _ => anyhow::bail!(
+                "Invalid waveform normalization '{}'. Use center, or a peak amplitude above 0 and up to 1",
+                value
+            ),
+        }
+    }
+
+    fn apply(self, waveform: &[f32]) -> Vec<f32> {
+        let mean = waveform.iter().sum::<f32>() / waveform.len().max(1) as f32;
+        let mut centered: Vec<f32> = waveform.iter().map(|sample| sample - mean).collect();
+        if let Self::Amplitude(amplitude) = self {
+            let peak = centered.iter().fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+            if peak >= WAVEFORM_NORMALIZE_FLOOR {
+                centered.iter_mut().for_each(|sample| *sample *= amplitude / peak);
+            }
+        }
+        centered
+    }
+}
+
+/// How `beat_intensity` falls back to zero after a beat.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BeatDecay {
+    /// Multiply by a constant each frame (0.9 per 100ms): a sharp drop that
+    /// tails off slowly.
+    Exponential,
+    /// Subtract a constant each frame, crossing 0.5 at the same moment as
+    /// the exponential curve and reaching zero at twice that.
+    Linear,
+}
+
+impl BeatDecay {
+    pub fn parse(name: &str) -> Result<Self> {
+        match name {
+            "exponential" => Ok(Self::Exponential),
+            "linear" => Ok(Self::Linear),
+            other => anyhow::bail!(
+                "Unknown beat decay '{}'. Valid shapes: exponential, linear",
+                other
+            ),
+        }
+    }
+}
+
+impl Default for AnalysisOptions {
+    fn default() -> Self {
+        Self {
+            smoothing: 0.85,
+            smoothing_bins: None,
+            smoothing_bands: None,
+            smoothing_rms: None,
+            bin_peak_decay: 0.0,
+            bin_noise_floor_db: -60.0,
+            spectral_tilt: 0.0,
+            freq_min: None,
+            freq_max: None,
+            dual_fft: false,
+            stereo_bands: false,
+            stereo_waveform: false,
+            beat_hold: 0.0,
+            beat_decay: BeatDecay::Exponential,
+            beat_grid: None,
+            analysis_rate: None,
+            fft_bins: None,
+            waveform_normalize: None,
+            waveform_sampling: WaveformSampling::Envelope,
+            freq_scale: FreqScale::Linear,
+            fft_size: DEFAULT_FFT_SIZE,
+            hop_size: DEFAULT_HOP_SIZE,
+        }
+    }
+}
+
+impl AnalysisOptions {
+    /// Whether the analysis reads the left and right channels, so decoding
+    /// should keep them in `AudioData::sides`.
+    pub fn needs_sides(&self) -> bool {
+        self.stereo_bands || self.stereo_waveform
+    }
+}
+
+/// Check an FFT window and hop size: both powers of two, the window between
+/// `MIN_FFT_SIZE` and `MAX_FFT_SIZE` and the hop no longer than the window.
+pub fn check_fft_sizes(fft_size: usize, hop_size: usize) -> Result<()> {
+    if !fft_size.is_power_of_two() || !(MIN_FFT_SIZE..=MAX_FFT_SIZE).contains(&fft_size) {
+        anyhow::bail!(
+            "FFT size {} must be a power of two from {} to {}",
+            fft_size,
+            MIN_FFT_SIZE,
+            MAX_FFT_SIZE
+        );
+    }
+    if !hop_size.is_power_of_two() || hop_size > fft_size {
+        anyhow::bail!("Hop size {} must be a power of two no larger than the FFT size ({})", hop_size, fft_size);
+    }
+    Ok(())
+}
+
+/// `SILENCE_AMPLITUDE` as the magnitude of an `fft_size` FFT: a
+/// Hann-windowed sine of amplitude `A` peaks at about `A * fft_size / 4`.
+fn silence_magnitude(fft_size: usize) -> f32 {
+    SILENCE_AMPLITUDE * fft_size as f32 / 4.0
+}
+
+pub fn analyze(
+    audio: &AudioData,
+    fps: FrameRate,
+    options: &AnalysisOptions,
+) -> crate::error::Result<(GlobalAnalysis, Vec<SmoothedFrame>)> {
+    analyze_timed(audio, fps, options).map(|(global, frames, _)| (global, frames))
+}
+
+/// [`analyze`], also returning the wall-clock time of each of the three passes.
+pub fn analyze_timed(
+    audio: &AudioData,
+    fps: FrameRate,
+    options: &AnalysisOptions,
+) -> crate::error::Result<(GlobalAnalysis, Vec<SmoothedFrame>, [Duration; 3])> {
+    check_fft_sizes(options.fft_size, options.hop_size)?;
+    if options.dual_fft && options.fft_size >= BASS_FFT_SIZE {
+        log::warn!(
+            "--dual-fft: the {}-point FFT is already at least as long as the {}-point bass window",
+            options.fft_size,
+            BASS_FFT_SIZE
+        );
+    }
+
+    // A NaN or infinite sample would poison every FFT window around it.
+    let mut samples = Cow::Borrowed(audio.samples.as_slice());
+    let non_finite = audio.samples.iter().filter(|s| !s.is_finite()).count();
+    if non_finite > 0 {
+        log::warn!("Replacing {} non-finite audio samples with silence", non_finite);
+        samples = Cow::Owned(audio.samples.iter().map(|&s| if s.is_finite() { s } else { 0.0 }).collect());
+    }
+    let mut sides = audio.sides.as_ref().filter(|_| options.needs_sides()).map(Cow::Borrowed);
+    if options.stereo_waveform && sides.is_none() {
+        log::info!("--stereo: mono input, so both channels get the same waveform and bands");
+    } else if options.stereo_bands && sides.is_none() {
+        log::info!("--stereo-bands: mono input, so both sides get the same bands");
+    }
+
+    let mut sr = audio.sample_rate;
+    if let Some(rate) = options.analysis_rate.filter(|&rate| rate != sr) {
+        log::info!("Resampling {} Hz to {} Hz for analysis", sr, rate);
+        samples = Cow::Owned(resample::resample(&samples, sr, rate)?);
+        if let Some([left, right]) = sides.as_deref() {
+            sides = Some(Cow::Owned([resample::resample(left, sr, rate)?, resample::resample(right, sr, rate)?]));
+        }
+        sr = rate;
+    }
+    let samples: &[f32] = &samples;
     let duration = samples.len() as f32 / sr as f32;
-    let total_frames = (duration * fps as f32).ceil() as usize;
+    let total_frames = fps.frames_in(duration);
 
     log::info!("Pass 1: Global analysis...");
-    let global = pass1_global(samples, sr, duration);
+    let started = Instant::now();
+    let global = pass1_global(samples, sr, duration, options.fft_size, options.hop_size, options.beat_grid.as_ref());
+
+    let global_time = started.elapsed();
 
     log::info!("Pass 2: Per-frame FFT ({} frames)...", total_frames);
-    let raw_frames = pass2_per_frame(samples, sr, fps, total_frames);
+    let started = Instant::now();
+    let raw_frames = pass2_per_frame(
+        samples,
+        sides.as_deref().filter(|_| options.stereo_bands),
+        sides.as_deref().filter(|_| options.stereo_waveform),
+        sr,
+        fps,
+        total_frames,
+        options.fft_size,
+        options.dual_fft,
+        options.fft_bins.filter(|&count| count < options.fft_size / 2),
+        options.waveform_sampling,
+        options.freq_scale,
+    );
 
-    log::info!("Pass 3: Smoothing & normalization (smoothing={:.2})...", smoothing);
-    let smoothed = pass3_smooth(&raw_frames, &global, fps, duration, smoothing);
+    let per_frame_time = started.elapsed();
+
+    log::info!(
+        "Pass 3: Smoothing & normalization (smoothing={:.2}, bin peak decay={:.1}s, noise floor={:.0}dB)...",
+        options.smoothing, options.bin_peak_decay, options.bin_noise_floor_db
+    );
+    let started = Instant::now();
+    let smoothed = pass3_smooth(&raw_frames, &global, fps, duration, options);
 
-    Ok((global, smoothed))
+    Ok((global, smoothed, [global_time, per_frame_time, started.elapsed()]))
 }
 
-fn pass1_global(samples: &[f32], sample_rate: u32, duration: f32) -> GlobalAnalysis {
+/// Levels, beats, and tempo of the whole track. A `beat_grid` replaces onset
+/// detection, and its intervals give the tempo.
+fn pass1_global(
+    samples: &[f32],
+    sample_rate: u32,
+    duration: f32,
+    fft_size: usize,
+    hop_size: usize,
+    beat_grid: Option<&BeatGrid>,
+) -> GlobalAnalysis {
     let peak_amplitude = samples.iter().map(|s| s.abs()).fold(0.0f32, f32::max);
 
     // RMS in windows
@@ -37,24 +444,55 @@ fn pass1_global(samples: &[f32], sample_rate: u32, duration: f32) -> GlobalAnaly
         peak_rms = peak_rms.max(rms);
     }
 
-    // Onset detection via spectral flux
+    let (beat_times, tempo_bpm) = match beat_grid {
+        Some(grid) => {
+            let beat_times = grid.beat_times(duration);
+            log::info!("Beat grid: {} beats, onset detection skipped", beat_times.len());
+            let tempo_bpm = beatgrid::grid_tempo(&beat_times).unwrap_or_else(|| estimate_tempo(&beat_times));
+            (beat_times, tempo_bpm)
+        }
+        None => {
+            let beat_times = onset_beats(samples, sample_rate, fft_size, hop_size);
+            let tempo_bpm = estimate_tempo(&beat_times);
+            (beat_times, tempo_bpm)
+        }
+    };
+
+    log::info!(
+        "Global: peak_rms={:.4}, peak_amp={:.4}, beats={}, tempo={:.1} BPM",
+        peak_rms, peak_amplitude, beat_times.len(), tempo_bpm
+    );
+
+    GlobalAnalysis {
+        sample_rate,
+        total_samples: samples.len(),
+        duration,
+        peak_rms,
+        peak_amplitude,
+        beat_times,
+        tempo_bpm,
+    }
+}
+
+/// Beat times from onset detection via spectral flux.
+fn onset_beats(samples: &[f32], sample_rate: u32, fft_size: usize, hop_size: usize) -> Vec<f32> {
     let mut planner = FftPlanner::<f32>::new();
-    let fft = planner.plan_fft_forward(FFT_SIZE);
-    let hann = hann_window(FFT_SIZE);
+    let fft = planner.plan_fft_forward(fft_size);
+    let hann = hann_window(fft_size);
 
-    let mut prev_magnitudes = vec![0.0f32; FFT_SIZE / 2];
+    let mut prev_magnitudes = vec![0.0f32; fft_size / 2];
     let mut flux_values: Vec<(f32, f32)> = Vec::new(); // (time, flux)
 
     let mut pos = 0;
-    while pos + FFT_SIZE <= samples.len() {
-        let mut buffer: Vec<Complex<f32>> = samples[pos..pos + FFT_SIZE]
+    while pos + fft_size <= samples.len() {
+        let mut buffer: Vec<Complex<f32>> = samples[pos..pos + fft_size]
             .iter()
             .enumerate()
             .map(|(i, &s)| Complex::new(s * hann[i], 0.0))
             .collect();
         fft.process(&mut buffer);
 
-        let magnitudes: Vec<f32> = buffer[..FFT_SIZE / 2].iter().map(|c| c.norm()).collect();
+        let magnitudes: Vec<f32> = buffer[..fft_size / 2].iter().map(|c| c.norm()).collect();
 
         let flux: f32 = magnitudes
             .iter()
@@ -65,37 +503,21 @@ fn pass1_global(samples: &[f32], sample_rate: u32, duration: f32) -> GlobalAnaly
         let time = pos as f32 / sample_rate as f32;
         flux_values.push((time, flux));
         prev_magnitudes = magnitudes;
-        pos += HOP_SIZE;
+        pos += hop_size;
     }
 
     // Adaptive threshold for beat detection
-    let beat_times = detect_beats(&flux_values);
-
-    // Tempo estimation
-    let tempo_bpm = estimate_tempo(&beat_times);
-
-    log::info!(
-        "Global: peak_rms={:.4}, peak_amp={:.4}, beats={}, tempo={:.1} BPM",
-        peak_rms, peak_amplitude, beat_times.len(), tempo_bpm
-    );
-
-    GlobalAnalysis {
-        sample_rate,
-        total_samples: samples.len(),
-        duration,
-        peak_rms,
-        peak_amplitude,
-        beat_times,
-        tempo_bpm,
-    }
+    let window = (BEAT_THRESHOLD_HOPS * DEFAULT_HOP_SIZE / hop_size).max(1);
+    detect_beats(&flux_values, window)
 }
 
-fn detect_beats(flux_values: &[(f32, f32)]) -> Vec<f32> {
+/// Onsets in `(time, flux)` pairs: local peaks above 1.5x the mean flux of
+/// the `window` values on either side.
+fn detect_beats(flux_values: &[(f32, f32)], window: usize) -> Vec<f32> {
     if flux_values.is_empty() {
         return Vec::new();
     }
 
-    let window = 20; // ~200ms at typical hop rate
     let mut beat_times = Vec::new();
 
     for i in 0..flux_values.len() {
@@ -152,55 +574,62 @@ fn estimate_tempo(beat_times: &[f32]) -> f32 {
     60.0 / median_interval
 }
 
+#[allow(clippy::too_many_arguments)]
 fn pass2_per_frame(
     samples: &[f32],
+    sides: Option<&[Vec<f32>; 2]>,
+    waveform_sides: Option<&[Vec<f32>; 2]>,
     sample_rate: u32,
-    fps: u32,
+    fps: FrameRate,
     total_frames: usize,
+    fft_size: usize,
+    dual_fft: bool,
+    fft_bin_count: Option<usize>,
+    waveform_sampling: WaveformSampling,
+    freq_scale: FreqScale,
 ) -> Vec<FrameFeatures> {
-    let samples_per_frame = sample_rate as f32 / fps as f32;
-    let freq_resolution = sample_rate as f32 / FFT_SIZE as f32;
-    let hann = hann_window(FFT_SIZE);
+    let samples_per_frame = sample_rate as f32 / fps.as_f32();
+    let freq_resolution = sample_rate as f32 / fft_size as f32;
+    let silence = silence_magnitude(fft_size);
+    let hann = hann_window(fft_size);
+    let bass_hann = if dual_fft { hann_window(BASS_FFT_SIZE) } else { Vec::new() };
 
     (0..total_frames)
         .into_par_iter()
-        .map(|frame_idx| {
-            let center = (frame_idx as f32 * samples_per_frame) as usize;
-            let start = center.saturating_sub(FFT_SIZE / 2);
-            let end = (start + FFT_SIZE).min(samples.len());
-
-            // Extract windowed samples
-            let mut fft_input: Vec<Complex<f32>> = vec![Complex::new(0.0, 0.0); FFT_SIZE];
-            for i in 0..(end - start) {
-                fft_input[i] = Complex::new(samples[start + i] * hann[i], 0.0);
-            }
-
-            // Per-thread FFT planner (rayon-safe)
-            let mut planner = FftPlanner::<f32>::new();
-            let fft = planner.plan_fft_forward(FFT_SIZE);
-            fft.process(&mut fft_input);
-
-            let half = FFT_SIZE / 2;
-            let fft_bins: Vec<f32> = fft_input[..half].iter().map(|c| c.norm()).collect();
-
-            // Band energies
-            let band_energy = |low_hz: f32, high_hz: f32| -> f32 {
-                let low_bin = (low_hz / freq_resolution) as usize;
-                let high_bin = ((high_hz / freq_resolution) as usize).min(half);
-                if low_bin >= high_bin {
-                    return 0.0;
-                }
-                let sum: f32 = fft_bins[low_bin..high_bin].iter().map(|&x| x * x).sum();
-                (sum / (high_bin - low_bin) as f32).sqrt()
+        // One FFT planner per rayon job, reused for every frame it handles:
+        // the planner caches each plan, so the FFTs are only planned once.
+        .map_init(FftPlanner::<f32>::new, |planner, frame_idx| {
+            let center = fps.frame_sample(frame_idx, sample_rate);
+
+            let fft_bins = spectrum(samples, center, &hann, planner);
+
+            // Band energies; with --dual-fft the low bands come from a longer window
+            let bands = |samples: &[f32], fft_bins: &[f32], planner: &mut FftPlanner<f32>| {
+                let long_bins;
+                let (bass_bins, bass_resolution): (&[f32], f32) = if dual_fft {
+                    long_bins = bass_spectrum(samples, center, &bass_hann, fft_size, planner);
+                    (&long_bins, sample_rate as f32 / BASS_FFT_SIZE as f32)
+                } else {
+                    (fft_bins, freq_resolution)
+                };
+                std::array::from_fn(|band| {
+                    let (low_hz, high_hz) = BANDS[band];
+                    if band < 2 {
+                        band_energy(bass_bins, bass_resolution, low_hz, high_hz)
+                    } else {
+                        band_energy(fft_bins, freq_resolution, low_hz, high_hz)
+                    }
+                })
+            };
+            let mono_bands: [f32; 7] = bands(samples, &fft_bins, planner);
+            let [sub_bass, bass, low_mid, mid, upper_mid, presence, brilliance] = mono_bands;
+            let side_bands = match sides {
+                Some(sides) => sides.each_ref().map(|side| {
+                    let side_bins = spectrum(side, center, &hann, planner);
+                    bands(side, &side_bins, planner)
+                }),
+                None => [mono_bands; 2],
             };
-
-            let sub_bass = band_energy(20.0, 60.0);
-            let bass = band_energy(60.0, 250.0);
-            let low_mid = band_energy(250.0, 500.0);
-            let mid = band_energy(500.0, 2000.0);
-            let upper_mid = band_energy(2000.0, 4000.0);
-            let presence = band_energy(4000.0, 6000.0);
-            let brilliance = band_energy(6000.0, 20000.0);
 
             // RMS
             let frame_start = (center).saturating_sub(samples_per_frame as usize / 2);
@@ -215,7 +644,7 @@ fn pass2_per_frame(
 
             // Spectral centroid
             let total_energy: f32 = fft_bins.iter().sum();
-            let spectral_centroid = if total_energy > 1e-10 {
+            let spectral_centroid = if total_energy > silence {
                 fft_bins
                     .iter()
                     .enumerate()
@@ -226,21 +655,20 @@ fn pass2_per_frame(
                 0.0
             };
 
-            // Waveform samples for this frame (downsample to ~512 points)
-            let waveform_len = 512.min(frame_samples.len());
-            let waveform: Vec<f32> = if frame_samples.is_empty() {
-                vec![0.0; 512]
-            } else {
-                (0..waveform_len)
-                    .map(|i| {
-                        let idx = i * frame_samples.len() / waveform_len;
-                        frame_samples[idx]
-                    })
-                    .collect()
-            };
+            let dominant_frequency = pitch::dominant_frequency(&fft_bins, freq_resolution, silence);
+
+            let waveform = waveform_sampling.downsample(frame_samples);
+            let side_waveforms = waveform_sides
+                .map(|sides| sides.each_ref().map(|side| waveform_sampling.downsample(&side[frame_start..frame_end])));
 
             FrameFeatures {
-                fft_bins,
+                fft_bins: match (freq_scale, fft_bin_count) {
+                    (FreqScale::Linear, Some(count)) => average_bins(&fft_bins, count),
+                    (FreqScale::Linear, None) => fft_bins,
+                    (scale, count) => {
+                        rebin_spectrum(&fft_bins, scale, sample_rate, count.unwrap_or(fft_bins.len()))
+                    }
+                },
                 sub_bass,
                 bass,
                 low_mid,
@@ -248,27 +676,138 @@ fn pass2_per_frame(
                 upper_mid,
                 presence,
                 brilliance,
+                side_bands,
                 rms,
                 spectral_centroid,
+                dominant_frequency,
                 spectral_flux: 0.0, // computed in sequential post-pass
                 waveform,
+                side_waveforms,
+            }
+        })
+        .collect()
+}
+
+/// Average groups of neighbouring bins down to `count`. Bands, centroid and
+/// pitch are measured on the full spectrum first; templates index bins by
+/// their fraction of the range, so fewer bins keep the same frequency axis.
+fn average_bins(bins: &[f32], count: usize) -> Vec<f32> {
+    let group = bins.len() / count;
+    bins.chunks_exact(group)
+        .map(|chunk| chunk.iter().sum::<f32>() / group as f32)
+        .collect()
+}
+
+/// Remap linear FFT `bins` (0 Hz to Nyquist) onto `out_bins` bins spaced
+/// evenly along `scale`. Each output bin averages the input bins whose
+/// frequency falls in its span; spans narrower than one input bin, at the
+/// bottom of a log or mel axis, interpolate between neighbours instead.
+pub fn rebin_spectrum(bins: &[f32], scale: FreqScale, sample_rate: u32, out_bins: usize) -> Vec<f32> {
+    if bins.is_empty() || out_bins == 0 {
+        return vec![0.0; out_bins];
+    }
+    let nyquist = sample_rate as f32 / 2.0;
+    let bin_hz = nyquist / bins.len() as f32;
+    // Position along the input bins of the edge `k` of `out_bins`.
+    let edge = |k: usize| scale.frequency_at(k as f32 / out_bins as f32, nyquist) / bin_hz;
+    (0..out_bins)
+        .map(|k| {
+            let (low, high) = (edge(k), edge(k + 1));
+            let start = (low.ceil() as usize).min(bins.len());
+            let end = (high.ceil() as usize).min(bins.len());
+            if start < end {
+                bins[start..end].iter().sum::<f32>() / (end - start) as f32
+            } else {
+                let position = ((low + high) / 2.0).min((bins.len() - 1) as f32);
+                let below = position.floor() as usize;
+                let above = (below + 1).min(bins.len() - 1);
+                let fraction = position - below as f32;
+                bins[below] * (1.0 - fraction) + bins[above] * fraction
             }
         })
         .collect()
 }
 
+/// Estimated peak memory in bytes of analysing `frames` frames with `bins`
+/// FFT bins each: pass 3 holds the raw, forward-smoothed, backward-smoothed
+/// and output bins at once, plus the raw and output waveforms, and with
+/// `--bin-peak-decay` the decaying peak of every bin.
+pub fn analysis_memory(frames: usize, bins: usize, options: &AnalysisOptions) -> u64 {
+    let bin_copies = if options.bin_peak_decay > 0.0 { 5 } else { 4 };
+    let floats_per_frame = bin_copies * bins + 2 * 2 * WAVEFORM_POINTS;
+    frames as u64 * floats_per_frame as u64 * std::mem::size_of::<f32>() as u64
+}
+
+/// The most FFT bins per frame (halving from `full_bins`) whose analysis of
+/// `frames` frames fits in `budget` bytes, or `None` if even `MIN_FFT_BINS`
+/// doesn't.
+pub fn bins_within(frames: usize, full_bins: usize, budget: u64, options: &AnalysisOptions) -> Option<usize> {
+    std::iter::successors(Some(full_bins), |&bins| Some(bins / 2))
+        .take_while(|&bins| bins >= MIN_FFT_BINS)
+        .find(|&bins| analysis_memory(frames, bins, options) <= budget)
+}
+
+/// RMS magnitude of the bins covering `low_hz..high_hz`.
+fn band_energy(bins: &[f32], freq_resolution: f32, low_hz: f32, high_hz: f32) -> f32 {
+    let low_bin = (low_hz / freq_resolution) as usize;
+    let high_bin = ((high_hz / freq_resolution) as usize).min(bins.len());
+    if low_bin >= high_bin {
+        return 0.0;
+    }
+    let sum: f32 = bins[low_bin..high_bin].iter().map(|&x| x * x).sum();
+    (sum / (high_bin - low_bin) as f32).sqrt()
+}
+
+/// Magnitudes of an FFT the length of `hann`, centred on `center`.
+fn spectrum(samples: &[f32], center: usize, hann: &[f32], planner: &mut FftPlanner<f32>) -> Vec<f32> {
+    let fft_size = hann.len();
+    let start = center.saturating_sub(fft_size / 2);
+    let end = (start + fft_size).min(samples.len());
+
+    let mut input = vec![Complex::new(0.0, 0.0); fft_size];
+    for i in 0..end.saturating_sub(start) {
+        input[i] = Complex::new(samples[start + i] * hann[i], 0.0);
+    }
+    planner.plan_fft_forward(fft_size).process(&mut input);
+
+    input[..fft_size / 2].iter().map(|c| c.norm()).collect()
+}
+
+/// Magnitudes of a `BASS_FFT_SIZE` FFT centred on `center`, scaled down by
+/// the window-length ratio so its band energies match the `fft_size` ones.
+fn bass_spectrum(
+    samples: &[f32],
+    center: usize,
+    hann: &[f32],
+    fft_size: usize,
+    planner: &mut FftPlanner<f32>,
+) -> Vec<f32> {
+    let start = center.saturating_sub(BASS_FFT_SIZE / 2);
+    let end = (start + BASS_FFT_SIZE).min(samples.len());
+
+    let mut input = vec![Complex::new(0.0, 0.0); BASS_FFT_SIZE];
+    for i in 0..end.saturating_sub(start) {
+        input[i] = Complex::new(samples[start + i] * hann[i], 0.0);
+    }
+    planner.plan_fft_forward(BASS_FFT_SIZE).process(&mut input);
+
+    let scale = fft_size as f32 / BASS_FFT_SIZE as f32;
+    input[..BASS_FFT_SIZE / 2].iter().map(|c| c.norm() * scale).collect()
+}
+
 fn pass3_smooth(
     raw: &[FrameFeatures],
     global: &GlobalAnalysis,
-    fps: u32,
+    fps: FrameRate,
     _duration: f32,
-    smoothing: f32,
+    options: &AnalysisOptions,
 ) -> Vec<SmoothedFrame> {
     if raw.is_empty() {
         return Vec::new();
     }
 
     let n = raw.len();
+    let silence = silence_magnitude(options.fft_size);
     let num_bins = raw[0].fft_bins.len();
 
     // Compute spectral flux sequentially
@@ -283,9 +822,12 @@ fn pass3_smooth(
         flux_values[i] = flux;
     }
 
+    let onset_envelope = onset_envelope(&flux_values, fps);
+
     // Find peaks for normalization
-    let peak_rms = global.peak_rms.max(1e-10);
-    let peak_flux = flux_values.iter().copied().fold(0.0f32, f32::max).max(1e-10);
+    let peak_onset = onset_envelope.iter().copied().fold(0.0f32, f32::max).max(silence);
+    let peak_rms = global.peak_rms.max(SILENCE_AMPLITUDE);
+    let peak_flux = flux_values.iter().copied().fold(0.0f32, f32::max).max(silence);
     let max_centroid = raw
         .iter()
         .map(|f| f.spectral_centroid)
@@ -293,15 +835,33 @@ fn pass3_smooth(
         .max(1e-10);
 
     // Find peak per FFT bin for normalization
-    let mut peak_bins = vec![1e-10f32; num_bins];
+    let mut peak_bins = vec![silence; num_bins];
     for frame in raw {
         for (i, &val) in frame.fft_bins.iter().enumerate() {
             peak_bins[i] = peak_bins[i].max(val);
         }
     }
+    let loudest_bin = peak_bins.iter().copied().fold(silence, f32::max);
+    let bin_floor = (loudest_bin * 10f32.powf(options.bin_noise_floor_db / 20.0)).max(silence);
+    let frequencies = bin_frequencies(num_bins, global.sample_rate, options.freq_scale);
+    let tilt = tilt_gains(&frequencies, options.spectral_tilt);
+    let bin_range = spectrum_range(&frequencies, options.freq_min, options.freq_max);
+    if bin_range.len() < num_bins {
+        log::info!(
+            "Spectrum limited to {:.0}-{:.0} Hz ({} of {} bins)",
+            options.freq_min.unwrap_or(0.0),
+            options.freq_max.unwrap_or(global.sample_rate as f32 / 2.0),
+            bin_range.len(),
+            num_bins
+        );
+    }
+    let decaying_peaks = (options.bin_peak_decay > 0.0)
+        .then(|| decaying_bin_peaks(raw, peak_decay_per_frame(options.bin_peak_decay, fps)));
 
-    // Bidirectional EMA smoothing
-    let alpha = 1.0 - smoothing; // smoothing=0.85 → alpha=0.15 (default behavior)
+    // Bidirectional EMA smoothing, with a factor per channel
+    let alpha = |smoothing: Option<f32>| 1.0 - smoothing.unwrap_or(options.smoothing); // 0.85 → 0.15
+    let (alpha_bins, alpha_bands, alpha_rms) =
+        (alpha(options.smoothing_bins), alpha(options.smoothing_bands), alpha(options.smoothing_rms));
 
     // Forward pass
     let mut forward_bins: Vec<Vec<f32>> = vec![vec![0.0; num_bins]; n];
@@ -325,15 +885,15 @@ fn pass3_smooth(
             .zip(&raw[i].fft_bins)
             .zip(previous)
         {
-            *output = alpha * input + (1.0 - alpha) * previous_value;
+            *output = alpha_bins * input + (1.0 - alpha_bins) * previous_value;
         }
-        forward_rms[i] = alpha * raw[i].rms + (1.0 - alpha) * forward_rms[i - 1];
+        forward_rms[i] = alpha_rms * raw[i].rms + (1.0 - alpha_rms) * forward_rms[i - 1];
         let bass_val = raw[i].sub_bass + raw[i].bass;
         let mid_val = raw[i].low_mid + raw[i].mid;
         let high_val = raw[i].upper_mid + raw[i].presence + raw[i].brilliance;
-        forward_bass[i] = alpha * bass_val + (1.0 - alpha) * forward_bass[i - 1];
-        forward_mid[i] = alpha * mid_val + (1.0 - alpha) * forward_mid[i - 1];
-        forward_high[i] = alpha * high_val + (1.0 - alpha) * forward_high[i - 1];
+        forward_bass[i] = alpha_bands * bass_val + (1.0 - alpha_bands) * forward_bass[i - 1];
+        forward_mid[i] = alpha_bands * mid_val + (1.0 - alpha_bands) * forward_mid[i - 1];
+        forward_high[i] = alpha_bands * high_val + (1.0 - alpha_bands) * forward_high[i - 1];
     }
 
     // Backward pass
@@ -358,74 +918,105 @@ fn pass3_smooth(
             .zip(&raw[i].fft_bins)
             .zip(following)
         {
-            *output = alpha * input + (1.0 - alpha) * following_value;
+            *output = alpha_bins * input + (1.0 - alpha_bins) * following_value;
         }
-        backward_rms[i] = alpha * raw[i].rms + (1.0 - alpha) * backward_rms[i + 1];
+        backward_rms[i] = alpha_rms * raw[i].rms + (1.0 - alpha_rms) * backward_rms[i + 1];
         let bass_val = raw[i].sub_bass + raw[i].bass;
         let mid_val = raw[i].low_mid + raw[i].mid;
         let high_val = raw[i].upper_mid + raw[i].presence + raw[i].brilliance;
-        backward_bass[i] = alpha * bass_val + (1.0 - alpha) * backward_bass[i + 1];
-        backward_mid[i] = alpha * mid_val + (1.0 - alpha) * backward_mid[i + 1];
-        backward_high[i] = alpha * high_val + (1.0 - alpha) * backward_high[i + 1];
+        backward_bass[i] = alpha_bands * bass_val + (1.0 - alpha_bands) * backward_bass[i + 1];
+        backward_mid[i] = alpha_bands * mid_val + (1.0 - alpha_bands) * backward_mid[i + 1];
+        backward_high[i] = alpha_bands * high_val + (1.0 - alpha_bands) * backward_high[i + 1];
     }
 
     // Peak values for band normalization
-    let peak_bass = forward_bass.iter().copied().fold(0.0f32, f32::max).max(1e-10);
-    let peak_mid = forward_mid.iter().copied().fold(0.0f32, f32::max).max(1e-10);
-    let peak_high = forward_high.iter().copied().fold(0.0f32, f32::max).max(1e-10);
+    let peak_bass = forward_bass.iter().copied().fold(0.0f32, f32::max).max(silence);
+    let peak_mid = forward_mid.iter().copied().fold(0.0f32, f32::max).max(silence);
+    let peak_high = forward_high.iter().copied().fold(0.0f32, f32::max).max(silence);
+
+    // Left/right bands, each normalized by its peak over both sides so the
+    // louder side reads louder
+    let side_bands: [[Vec<f32>; 7]; 2] = std::array::from_fn(|side| {
+        std::array::from_fn(|band| {
+            let series: Vec<f32> = raw.iter().map(|f| f.side_bands[side][band]).collect();
+            smooth_bidirectional(&series, alpha_bands)
+        })
+    });
+    let peak_side_bands: [f32; 7] = std::array::from_fn(|band| {
+        side_bands
+            .iter()
+            .flat_map(|side| &side[band])
+            .copied()
+            .fold(silence, f32::max)
+    });
 
     // Beat tracking
-    let beat_decay = 0.9f32.powf(1.0 / fps as f32 * 10.0); // ~100ms decay
-
-    let mut beat_intensity = 0.0f32;
+    let mut beat_envelope = BeatEnvelope::new(options.beat_hold, options.beat_decay, fps);
     let mut frames: Vec<SmoothedFrame> = Vec::with_capacity(n);
+    let condition_waveform = |waveform: &[f32]| match options.waveform_normalize {
+        Some(normalize) => normalize.apply(waveform),
+        None => waveform.to_vec(),
+    };
 
     for i in 0..n {
-        let time = i as f32 / fps as f32;
+        let time = fps.frame_time(i);
 
         // Check if this frame is on a beat
         let is_beat = global.beat_times.iter().any(|&bt| {
             let frame_time = time;
-            (frame_time - bt).abs() < 0.5 / fps as f32
+            (frame_time - bt).abs() < 0.5 / fps.as_f32()
         });
 
-        if is_beat {
-            beat_intensity = 1.0;
-        } else {
-            beat_intensity *= beat_decay;
-        }
+        let beat_intensity = beat_envelope.next(is_beat);
 
         // Beat phase
         let beat_phase = compute_beat_phase(time, &global.beat_times);
 
         // Average forward + backward, then normalize
-        let smoothed_bins: Vec<f32> = (0..num_bins)
+        let bin_peaks = decaying_peaks.as_ref().map_or(&peak_bins, |peaks| &peaks[i]);
+        let smoothed_bins: Vec<f32> = bin_range
+            .clone()
             .map(|j| {
                 let avg = (forward_bins[i][j] + backward_bins[i][j]) * 0.5;
-                (avg / peak_bins[j]).min(1.0)
+                (normalize(avg, bin_peaks[j].max(bin_floor)) * tilt[j]).min(1.0)
             })
             .collect();
 
-        let rms = ((forward_rms[i] + backward_rms[i]) * 0.5 / peak_rms).min(1.0);
-        let bass = ((forward_bass[i] + backward_bass[i]) * 0.5 / peak_bass).min(1.0);
-        let mid = ((forward_mid[i] + backward_mid[i]) * 0.5 / peak_mid).min(1.0);
-        let high = ((forward_high[i] + backward_high[i]) * 0.5 / peak_high).min(1.0);
-
-        let spectral_centroid = (raw[i].spectral_centroid / max_centroid).min(1.0);
-        let spectral_flux = (flux_values[i] / peak_flux).min(1.0);
+        let rms = normalize((forward_rms[i] + backward_rms[i]) * 0.5, peak_rms);
+        let bass = normalize((forward_bass[i] + backward_bass[i]) * 0.5, peak_bass);
+        let mid = normalize((forward_mid[i] + backward_mid[i]) * 0.5, peak_mid);
+        let high = normalize((forward_high[i] + backward_high[i]) * 0.5, peak_high);
+
+        let spectral_centroid = normalize(raw[i].spectral_centroid, max_centroid);
+        let spectral_flux = normalize(flux_values[i], peak_flux);
+        let [bands_left, bands_right] = side_bands
+            .each_ref()
+            .map(|side| std::array::from_fn(|band| normalize(side[band][i], peak_side_bands[band])));
+        let [waveform_left, waveform_right] = match &raw[i].side_waveforms {
+            Some(sides) => sides.each_ref().map(|side| condition_waveform(side)),
+            None => [Vec::new(), Vec::new()],
+        };
 
         frames.push(SmoothedFrame {
             fft_bins: smoothed_bins,
             bass,
             mid,
             high,
+            bands_left,
+            bands_right,
             rms,
             spectral_centroid,
             spectral_flux,
+            spectral_centroid_hz: raw[i].spectral_centroid,
+            spectral_flux_raw: flux_values[i],
+            onset_strength: normalize(onset_envelope[i], peak_onset),
+            dominant_frequency_hz: raw[i].dominant_frequency,
             beat_intensity,
             beat_phase,
             is_beat,
-            waveform: raw[i].waveform.clone(),
+            waveform: condition_waveform(&raw[i].waveform),
+            waveform_left,
+            waveform_right,
             time,
         });
     }
@@ -433,6 +1024,159 @@ fn pass3_smooth(
     frames
 }
 
+/// Onset strength over time: the (already half-wave rectified) spectral flux
+/// through a causal one-pole low-pass with an `ONSET_ENVELOPE_SECONDS` time
+/// constant, so it swells with each attack and falls away smoothly without
+/// rising ahead of it.
+fn onset_envelope(flux_values: &[f32], fps: FrameRate) -> Vec<f32> {
+    let alpha = 1.0 - (-1.0 / (ONSET_ENVELOPE_SECONDS * fps.as_f32())).exp();
+    let mut level = 0.0;
+    flux_values
+        .iter()
+        .map(|&flux| {
+            level += alpha * (flux - level);
+            level
+        })
+        .collect()
+}
+
+/// Average of a forward and a backward EMA over `values`, for zero phase delay.
+fn smooth_bidirectional(values: &[f32], alpha: f32) -> Vec<f32> {
+    let mut forward = values.to_vec();
+    for i in 1..forward.len() {
+        forward[i] = alpha * values[i] + (1.0 - alpha) * forward[i - 1];
+    }
+    let mut backward = values.to_vec();
+    for i in (0..backward.len().saturating_sub(1)).rev() {
+        backward[i] = alpha * values[i] + (1.0 - alpha) * backward[i + 1];
+    }
+    forward.iter().zip(&backward).map(|(f, b)| (f + b) * 0.5).collect()
+}
+
+/// Frequency in Hz of each of `num_bins` output bins on `scale`: the bin's
+/// start for linear bins, as the FFT reports them, and the middle of its
+/// span on the axis otherwise.
+fn bin_frequencies(num_bins: usize, sample_rate: u32, scale: FreqScale) -> Vec<f32> {
+    let nyquist = sample_rate as f32 / 2.0;
+    (0..num_bins)
+        .map(|j| match scale {
+            FreqScale::Linear => j as f32 * nyquist / num_bins as f32,
+            scale => scale.frequency_at((j as f32 + 0.5) / num_bins as f32, nyquist),
+        })
+        .collect()
+}
+
+/// Bins whose frequency (ascending, from [`bin_frequencies`]) lies within
+/// `freq_min..=freq_max`. Always at least one bin, so a range above Nyquist
+/// keeps the top bin rather than uploading an empty buffer.
+fn spectrum_range(frequencies: &[f32], freq_min: Option<f32>, freq_max: Option<f32>) -> Range<usize> {
+    let num_bins = frequencies.len();
+    let start = freq_min.map_or(0, |hz| frequencies.partition_point(|&f| f < hz)).min(num_bins - 1);
+    let end = freq_max.map_or(num_bins, |hz| frequencies.partition_point(|&f| f <= hz));
+    start..end.max(start + 1)
+}
+
+/// Per-bin gain for `--spectral-tilt`: `db_per_octave` for every octave
+/// above `TILT_PIVOT_HZ` (negative below it), by bin frequency.
+fn tilt_gains(frequencies: &[f32], db_per_octave: f32) -> Vec<f32> {
+    frequencies
+        .iter()
+        .map(|&hz| {
+            let octaves = (hz.max(20.0) / TILT_PIVOT_HZ).log2();
+            10f32.powf(db_per_octave * octaves / 20.0)
+        })
+        .collect()
+}
+
+/// `value / peak` clamped to 0.0-1.0, with anything non-finite mapped to 0
+/// so it can never reach a shader uniform.
+fn normalize(value: f32, peak: f32) -> f32 {
+    let normalized = value / peak;
+    if normalized.is_finite() {
+        normalized.clamp(0.0, 1.0)
+    } else {
+        0.0
+    }
+}
+
+/// `beat_intensity` over time: jumps to 1.0 on a beat, holds for
+/// `hold_frames`, then decays with the chosen shape.
+struct BeatEnvelope {
+    hold_frames: usize,
+    decay: BeatDecay,
+    /// Exponential per-frame multiplier (0.9 per 100ms).
+    factor: f32,
+    /// Linear per-frame step, matching the exponential half-life.
+    step: f32,
+    intensity: f32,
+    frames_since_beat: usize,
+}
+
+impl BeatEnvelope {
+    fn new(hold: f32, decay: BeatDecay, fps: FrameRate) -> Self {
+        let factor = 0.9f32.powf(1.0 / fps.as_f32() * 10.0);
+        let half_life_frames = 0.5f32.ln() / factor.ln();
+        Self {
+            hold_frames: (hold * fps.as_f32()).round() as usize,
+            decay,
+            factor,
+            step: 0.5 / half_life_frames,
+            intensity: 0.0,
+            frames_since_beat: usize::MAX,
+        }
+    }
+
+    fn next(&mut self, is_beat: bool) -> f32 {
+        if is_beat {
+            self.intensity = 1.0;
+            self.frames_since_beat = 0;
+        } else {
+            self.frames_since_beat = self.frames_since_beat.saturating_add(1);
+            if self.frames_since_beat > self.hold_frames {
+                self.intensity = match self.decay {
+                    BeatDecay::Exponential => self.intensity * self.factor,
+                    BeatDecay::Linear => (self.intensity - self.step).max(0.0),
+                };
+            }
+        }
+        self.intensity
+    }
+}
+
+/// Per-frame multiplier that halves a peak every `half_life` seconds.
+fn peak_decay_per_frame(half_life: f32, fps: FrameRate) -> f32 {
+    0.5f32.powf(1.0 / (half_life * fps.as_f32()))
+}
+
+/// Per-frame, per-bin normalization peaks that jump to each new maximum and
+/// then decay by `decay` per frame. Running the envelope in both directions
+/// and taking the larger lets a bin's scale rise ahead of a loud passage as
+/// well as fall after it, so quiet sections are shown relative to their own
+/// dynamics instead of the loudest moment of the track.
+fn decaying_bin_peaks(raw: &[FrameFeatures], decay: f32) -> Vec<Vec<f32>> {
+    let n = raw.len();
+    let num_bins = raw[0].fft_bins.len();
+
+    let mut peaks: Vec<Vec<f32>> = Vec::with_capacity(n);
+    let mut running = vec![0.0f32; num_bins];
+    for frame in raw {
+        for (peak, &val) in running.iter_mut().zip(&frame.fft_bins) {
+            *peak = val.max(*peak * decay);
+        }
+        peaks.push(running.clone());
+    }
+
+    running.fill(0.0);
+    for (frame, frame_peaks) in raw.iter().zip(peaks.iter_mut()).rev() {
+        for ((peak, &val), forward) in running.iter_mut().zip(&frame.fft_bins).zip(frame_peaks.iter_mut()) {
+            *peak = val.max(*peak * decay);
+            *forward = forward.max(*peak);
+        }
+    }
+
+    peaks
+}
+
 fn compute_beat_phase(time: f32, beat_times: &[f32]) -> f32 {
     if beat_times.is_empty() {
         return 0.0;
@@ -472,3 +1216,465 @@ fn hann_window(size: usize) -> Vec<f32> {
         })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_centers_stay_in_sync_over_long_tracks() {
+        // 15 minutes at 44.1kHz and 24fps: 1837.5 samples per frame, where
+        // float accumulation used to drift.
+        let (sample_rate, fps) = (44_100, FrameRate::integer(24));
+        let last_frame = 15 * 60 * 24 - 1;
+
+        let true_time = last_frame as f64 / 24.0;
+        let center_time = fps.frame_sample(last_frame, sample_rate) as f64 / sample_rate as f64;
+
+        assert!((center_time - true_time).abs() < 1.0 / sample_rate as f64);
+    }
+
+    #[test]
+    fn frame_centers_are_exact_for_integer_rates() {
+        assert_eq!(FrameRate::integer(30).frame_sample(0, 48_000), 0);
+        assert_eq!(FrameRate::integer(30).frame_sample(1, 48_000), 1600);
+        assert_eq!(FrameRate::integer(24).frame_sample(3, 44_100), 5512);
+    }
+
+    fn frame_with_bins(fft_bins: Vec<f32>) -> FrameFeatures {
+        FrameFeatures {
+            fft_bins,
+            ..FrameFeatures::default()
+        }
+    }
+
+    #[test]
+    fn fft_size_sets_the_bin_count_and_resolution() {
+        assert!(check_fft_sizes(DEFAULT_FFT_SIZE, DEFAULT_HOP_SIZE).is_ok());
+        assert!(check_fft_sizes(3000, 1024).is_err(), "not a power of two");
+        assert!(check_fft_sizes(128, 64).is_err(), "below MIN_FFT_SIZE");
+        assert!(check_fft_sizes(1024, 2048).is_err(), "hop longer than the window");
+
+        // A 100Hz tone: the peak bin moves with the resolution, the frequency doesn't.
+        let sample_rate = 44_100;
+        let audio = AudioData {
+            samples: (0..sample_rate)
+                .map(|i| (2.0 * std::f32::consts::PI * 100.0 * i as f32 / sample_rate as f32).sin())
+                .collect(),
+            sides: None,
+            sample_rate: sample_rate as u32,
+            tags: Default::default(),
+        };
+        for fft_size in [512, 8192] {
+            let options = AnalysisOptions { fft_size, hop_size: fft_size / 4, ..AnalysisOptions::default() };
+            let frame = &analyze(&audio, FrameRate::integer(10), &options).unwrap().1[5];
+            assert_eq!(frame.fft_bins.len(), fft_size / 2);
+            assert!((frame.dominant_frequency_hz - 100.0).abs() < sample_rate as f32 / fft_size as f32);
+        }
+    }
+
+    #[test]
+    fn dual_fft_keeps_a_sub_bass_tone_out_of_the_bass_band() {
+        // A 45Hz tone sits in sub_bass (20-60Hz), but at 2048 points the
+        // ~21Hz bins smear it into the bass band.
+        let sample_rate = 44_100;
+        let samples: Vec<f32> = (0..sample_rate)
+            .map(|i| (2.0 * std::f32::consts::PI * 45.0 * i as f32 / sample_rate as f32).sin())
+            .collect();
+
+        let single = &pass2_per_frame(&samples, None, None, sample_rate, FrameRate::integer(10), 10, DEFAULT_FFT_SIZE, false, None, WaveformSampling::Envelope, FreqScale::Linear)[5];
+        let dual = &pass2_per_frame(&samples, None, None, sample_rate, FrameRate::integer(10), 10, DEFAULT_FFT_SIZE, true, None, WaveformSampling::Envelope, FreqScale::Linear)[5];
+
+        assert!(dual.sub_bass / dual.bass > 4.0 * single.sub_bass / single.bass);
+        assert_eq!(dual.mid, single.mid);
+    }
+
+    #[test]
+    fn stereo_bands_follow_each_channel() {
+        // Bass on the left, a 1kHz tone on the right.
+        let sample_rate = 44_100;
+        let tone = |freq: f32| -> Vec<f32> {
+            (0..sample_rate)
+                .map(|i| 0.5 * (2.0 * std::f32::consts::PI * freq * i as f32 / sample_rate as f32).sin())
+                .collect()
+        };
+        let (left, right) = (tone(100.0), tone(1000.0));
+        let audio = AudioData {
+            samples: left.iter().zip(&right).map(|(l, r)| (l + r) / 2.0).collect(),
+            sides: Some([left, right]),
+            sample_rate: sample_rate as u32,
+            tags: Default::default(),
+        };
+        let options = AnalysisOptions { stereo_bands: true, ..AnalysisOptions::default() };
+        let frame = &analyze(&audio, FrameRate::integer(10), &options).unwrap().1[5];
+
+        assert!(frame.bands_left[1] > 0.9 && frame.bands_right[1] < 0.1, "{:?}", frame.bands_right);
+        assert!(frame.bands_right[3] > 0.9 && frame.bands_left[3] < 0.1, "{:?}", frame.bands_left);
+
+        let mono = &analyze(&audio, FrameRate::integer(10), &AnalysisOptions::default()).unwrap().1[5];
+        assert_eq!(mono.bands_left, mono.bands_right);
+    }
+
+    #[test]
+    fn stereo_waveforms_follow_each_channel() {
+        // A tone on the left, silence on the right.
+        let sample_rate = 44_100;
+        let left: Vec<f32> = (0..sample_rate)
+            .map(|i| 0.5 * (2.0 * std::f32::consts::PI * 440.0 * i as f32 / sample_rate as f32).sin())
+            .collect();
+        let right = vec![0.0; left.len()];
+        let audio = AudioData {
+            samples: left.iter().map(|l| l / 2.0).collect(),
+            sides: Some([left, right]),
+            sample_rate: sample_rate as u32,
+            tags: Default::default(),
+        };
+        let options = AnalysisOptions { stereo_waveform: true, ..AnalysisOptions::default() };
+        let stereo = &analyze(&audio, FrameRate::integer(10), &options).unwrap().1[5];
+        let peak = |waveform: &[f32]| waveform.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+        assert!((peak(&stereo.waveform_left) - 0.5).abs() < 0.01, "{}", peak(&stereo.waveform_left));
+        assert_eq!(peak(&stereo.waveform_right), 0.0);
+
+        // The mono waveform is untouched, and stays the only one without the option.
+        let mono = &analyze(&audio, FrameRate::integer(10), &AnalysisOptions::default()).unwrap().1[5];
+        assert_eq!(stereo.waveform, mono.waveform);
+        assert!(mono.waveform_left.is_empty() && mono.waveform_right.is_empty());
+    }
+
+    #[test]
+    fn beat_envelope_holds_then_decays_with_the_chosen_shape() {
+        let beats = [true, false, false, false, false, false];
+        let fps = FrameRate::integer(10);
+
+        let mut default = BeatEnvelope::new(0.0, BeatDecay::Exponential, fps);
+        let exponential: Vec<f32> = beats.iter().map(|&b| default.next(b)).collect();
+        assert_eq!(exponential[0], 1.0);
+        assert!((exponential[1] - 0.9).abs() < 1e-6, "0.9 per 100ms as before");
+
+        let mut held = BeatEnvelope::new(0.2, BeatDecay::Exponential, fps);
+        let held: Vec<f32> = beats.iter().map(|&b| held.next(b)).collect();
+        assert_eq!(held[..3], [1.0, 1.0, 1.0]);
+        assert!((held[3] - 0.9).abs() < 1e-6);
+
+        let mut linear = BeatEnvelope::new(0.0, BeatDecay::Linear, fps);
+        let linear: Vec<f32> = beats.iter().map(|&b| linear.next(b)).collect();
+        let steps: Vec<f32> = linear.windows(2).map(|w| w[0] - w[1]).collect();
+        assert!(steps.iter().all(|step| (step - steps[0]).abs() < 1e-5));
+    }
+
+    #[test]
+    fn bin_peaks_decay_by_half_life() {
+        // Half-life of 1s at 2fps: the peak halves every two frames.
+        let decay = peak_decay_per_frame(1.0, FrameRate::integer(2));
+        let raw: Vec<FrameFeatures> = [0.0, 0.0, 8.0, 0.0, 0.0, 0.0, 0.0]
+            .iter()
+            .map(|&v| frame_with_bins(vec![v]))
+            .collect();
+
+        let peaks: Vec<f32> = decaying_bin_peaks(&raw, decay).iter().map(|p| p[0]).collect();
+
+        assert!((peaks[2] - 8.0).abs() < 1e-4);
+        assert!((peaks[4] - 4.0).abs() < 1e-4);
+        assert!((peaks[6] - 2.0).abs() < 1e-4);
+        // The backward envelope raises the scale ahead of the peak too.
+        assert!((peaks[0] - 4.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn waveform_normalize_centers_and_scales_each_frame() {
+        let offset = [0.3, 0.5, 0.3, 0.1];
+        let close = |actual: Vec<f32>, expected: [f32; 4]| {
+            assert!(actual.iter().zip(expected).all(|(a, b)| (a - b).abs() < 1e-5), "{actual:?}");
+        };
+        close(WaveformNormalize::Center.apply(&offset), [0.0, 0.2, 0.0, -0.2]);
+        close(WaveformNormalize::Amplitude(0.8).apply(&offset), [0.0, 0.8, 0.0, -0.8]);
+        // Near-silence is centered but not blown up.
+        assert!(WaveformNormalize::Amplitude(0.8).apply(&[0.2001, 0.1999]).iter().all(|s| s.abs() < 1e-3));
+
+        assert_eq!(WaveformNormalize::parse("center").unwrap(), WaveformNormalize::Center);
+        assert_eq!(WaveformNormalize::parse("0.9").unwrap(), WaveformNormalize::Amplitude(0.9));
+        for bad in ["0", "1.5", "loud"] {
+            assert!(WaveformNormalize::parse(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn envelope_sampling_keeps_peaks_that_point_sampling_misses() {
+        // A one-sample click between point-sampling positions.
+        let mut samples = vec![0.0f32; WAVEFORM_POINTS * 4];
+        samples[WAVEFORM_POINTS * 2 + 1] = 0.9;
+        samples[WAVEFORM_POINTS + 2] = -0.7;
+
+        let envelope = WaveformSampling::Envelope.downsample(&samples);
+        assert_eq!(envelope.len(), WAVEFORM_POINTS * 2);
+        assert_eq!(envelope.iter().copied().fold(0.0, f32::max), 0.9);
+        assert_eq!(envelope.iter().copied().fold(0.0, f32::min), -0.7);
+        assert!(envelope.chunks(2).all(|pair| pair[0] <= pair[1]));
+
+        let point = WaveformSampling::Point.downsample(&samples);
+        assert!(point.iter().all(|&s| s == 0.0), "stride sampling skips the clicks");
+        // Short frames keep one point per sample; silence keeps the full size.
+        assert_eq!(WaveformSampling::Envelope.downsample(&[0.5, -0.5]), [0.5, 0.5, -0.5, -0.5]);
+        assert_eq!(WaveformSampling::Point.downsample(&[]).len(), WAVEFORM_POINTS * 2);
+
+        assert_eq!(WaveformSampling::parse("point").unwrap(), WaveformSampling::Point);
+        assert!(WaveformSampling::parse("rms").is_err());
+    }
+
+    #[test]
+    fn each_channel_smooths_with_its_own_factor() {
+        // One loud frame in silence: unsmoothed channels stay a single spike.
+        let raw: Vec<FrameFeatures> = (0..9)
+            .map(|i| {
+                let level = if i == 4 { 1.0 } else { 0.0 };
+                FrameFeatures { rms: level, bass: level, ..frame_with_bins(vec![level]) }
+            })
+            .collect();
+        let global = GlobalAnalysis {
+            sample_rate: 44_100,
+            total_samples: 0,
+            duration: 0.9,
+            peak_rms: 1.0,
+            peak_amplitude: 1.0,
+            beat_times: Vec::new(),
+            tempo_bpm: 0.0,
+        };
+        let options = AnalysisOptions {
+            smoothing: 0.9,
+            smoothing_rms: Some(0.0),
+            smoothing_bands: Some(0.5),
+            ..AnalysisOptions::default()
+        };
+        let frames = pass3_smooth(&raw, &global, FrameRate::integer(10), 0.9, &options);
+
+        assert_eq!(frames[3].rms, 0.0);
+        assert!(frames[3].bass > 0.0 && frames[2].bass < frames[3].bass);
+        // The bins fall off slowest, keeping the global factor.
+        let spread = |frame: &SmoothedFrame| frame.fft_bins[0] / frames[4].fft_bins[0];
+        assert!(spread(&frames[3]) > frames[3].bass / frames[4].bass);
+    }
+
+    #[test]
+    fn frequency_limits_keep_the_bins_in_range() {
+        // 1024 bins at 44.1kHz: ~21.5Hz per bin.
+        let linear = bin_frequencies(1024, 44_100, FreqScale::Linear);
+        assert_eq!(spectrum_range(&linear, None, None), 0..1024);
+        assert_eq!(spectrum_range(&linear, Some(40.0), Some(12_000.0)), 2..558);
+        assert_eq!(spectrum_range(&linear, None, Some(30_000.0)), 0..1024);
+        assert_eq!(spectrum_range(&linear, Some(30_000.0), None), 1023..1024);
+        // Fewer bins keep the same frequencies.
+        let fewer = bin_frequencies(256, 44_100, FreqScale::Linear);
+        assert_eq!(spectrum_range(&fewer, Some(40.0), Some(12_000.0)), 1..140);
+
+        let raw: Vec<FrameFeatures> = (0..4).map(|_| frame_with_bins(vec![1.0; 1024])).collect();
+        let options = AnalysisOptions {
+            freq_min: Some(40.0),
+            freq_max: Some(12_000.0),
+            ..AnalysisOptions::default()
+        };
+        let global = GlobalAnalysis {
+            sample_rate: 44_100,
+            total_samples: 0,
+            duration: 0.4,
+            peak_rms: 1.0,
+            peak_amplitude: 1.0,
+            beat_times: Vec::new(),
+            tempo_bpm: 0.0,
+        };
+        let frames = pass3_smooth(&raw, &global, FrameRate::integer(10), 0.4, &options);
+        assert!(frames.iter().all(|frame| frame.fft_bins.len() == 556));
+    }
+
+    #[test]
+    fn log_and_mel_scales_give_the_low_end_more_bins() {
+        let bins: Vec<f32> = (0..1024).map(|i| (i % 7) as f32).collect();
+        assert_eq!(rebin_spectrum(&bins, FreqScale::Linear, 44_100, 256), average_bins(&bins, 256));
+        // A flat spectrum stays flat, averaged or interpolated.
+        let flat = rebin_spectrum(&[1.0; 1024], FreqScale::Log, 44_100, 1024);
+        assert!(flat.iter().all(|&v| (v - 1.0).abs() < 1e-6));
+
+        let below_1k = |scale| bin_frequencies(64, 44_100, scale).iter().filter(|&&hz| hz < 1000.0).count();
+        let (linear, mel, log) = (below_1k(FreqScale::Linear), below_1k(FreqScale::Mel), below_1k(FreqScale::Log));
+        assert_eq!(linear, 3);
+        assert!(mel > 4 * linear && log > mel, "linear {linear}, mel {mel}, log {log}");
+
+        // A 100Hz peak spreads across the log bins around it.
+        let mut peak = vec![0.0; 1024];
+        peak[5] = 1.0;
+        let log_bins = rebin_spectrum(&peak, FreqScale::Log, 44_100, 64);
+        assert!(log_bins.iter().filter(|&&v| v > 0.0).count() >= 3);
+        assert_eq!(rebin_spectrum(&peak, FreqScale::Linear, 44_100, 64).iter().filter(|&&v| v > 0.0).count(), 1);
+    }
+
+    #[test]
+    fn spectral_tilt_gains_by_octave_around_the_pivot() {
+        // 1024 bins at 44.1kHz: ~21.5Hz per bin.
+        let bin_of = |hz: f32| (hz / (44_100.0 / 2048.0)).round() as usize;
+        let frequencies = bin_frequencies(1024, 44_100, FreqScale::Linear);
+        let gains = tilt_gains(&frequencies, 3.0);
+        let db = |hz: f32| 20.0 * gains[bin_of(hz)].log10();
+
+        assert!(db(1000.0).abs() < 0.1);
+        assert!((db(4000.0) - 6.0).abs() < 0.1);
+        assert!((db(250.0) + 6.0).abs() < 0.2);
+        assert!(gains[0].is_finite() && gains[0] > 0.0);
+        assert!(tilt_gains(&frequencies, 0.0).iter().all(|&g| g == 1.0));
+    }
+
+    #[test]
+    fn raw_centroid_is_reported_in_hz() {
+        // One second at 1kHz, then one second at 5kHz.
+        let sample_rate = 44_100;
+        let samples = (0..sample_rate * 2)
+            .map(|i| {
+                let freq = if i < sample_rate { 1000.0 } else { 5000.0 };
+                0.5 * (2.0 * std::f32::consts::PI * freq * i as f32 / sample_rate as f32).sin()
+            })
+            .collect();
+        let audio = AudioData {
+            samples,
+            sides: None,
+            sample_rate: sample_rate as u32,
+            tags: Default::default(),
+        };
+        let (_, frames) = analyze(&audio, FrameRate::integer(10), &AnalysisOptions::default()).unwrap();
+        let (low, high) = (&frames[5], &frames[15]);
+
+        assert!((low.spectral_centroid_hz - 1000.0).abs() < 50.0, "{}", low.spectral_centroid_hz);
+        assert!((high.spectral_centroid_hz - 5000.0).abs() < 250.0, "{}", high.spectral_centroid_hz);
+        assert!((high.spectral_centroid - 1.0).abs() < 0.05, "the normalized centroid is unchanged");
+        assert!((low.dominant_frequency_hz - 1000.0).abs() < 5.0, "{}", low.dominant_frequency_hz);
+        assert!(frames.iter().all(|f| f.spectral_flux_raw >= 0.0 && f.spectral_flux_raw.is_finite()));
+    }
+
+    #[test]
+    fn onset_envelope_follows_attacks_without_anticipating_them() {
+        let mut flux = vec![0.0f32; 30];
+        flux[10] = 1.0;
+        flux[11] = 0.5;
+
+        let envelope = onset_envelope(&flux, FrameRate::integer(30));
+
+        assert!(envelope[..10].iter().all(|&level| level == 0.0));
+        let peak = (0..envelope.len()).max_by(|&a, &b| envelope[a].total_cmp(&envelope[b])).unwrap();
+        assert!((10..=11).contains(&peak), "peaks at the attack, not frame {peak}");
+        assert!(envelope[12..].windows(2).all(|pair| pair[1] < pair[0]), "decays smoothly");
+        assert!(envelope[12] > 0.1 * envelope[peak], "continuous rather than a one-frame spike");
+    }
+
+    #[test]
+    fn reduced_bins_keep_the_frequency_axis_and_fit_the_budget() {
+        let full = DEFAULT_FFT_SIZE / 2;
+        let bins: Vec<f32> = (0..full).map(|i| i as f32).collect();
+        let reduced = average_bins(&bins, 256);
+        assert_eq!(reduced.len(), 256);
+        assert_eq!(reduced[0], 1.5);
+        assert_eq!(reduced[255], 1021.5);
+
+        let frames = 216_000; // an hour at 60fps
+        let defaults = AnalysisOptions::default();
+        assert_eq!(bins_within(frames, full, u64::MAX, &defaults), Some(full));
+        let budget = 2 * 1024 * 1024 * 1024;
+        let bins = bins_within(frames, full, budget, &defaults).unwrap();
+        assert!(bins < full && analysis_memory(frames, bins, &defaults) <= budget);
+        assert!(analysis_memory(frames, bins * 2, &defaults) > budget, "keeps as many bins as fit");
+        assert_eq!(bins_within(frames, full, 1024 * 1024, &defaults), None);
+
+        // Decaying bin peaks are one more copy of every bin.
+        let decaying = AnalysisOptions { bin_peak_decay: 2.0, ..Default::default() };
+        assert!(analysis_memory(frames, bins, &decaying) > analysis_memory(frames, bins, &defaults));
+        let budget = analysis_memory(frames, full, &defaults);
+        assert_eq!(bins_within(frames, full, budget, &defaults), Some(full));
+        let decaying_bins = bins_within(frames, full, budget, &decaying).unwrap();
+        assert!(decaying_bins < full && analysis_memory(frames, decaying_bins, &decaying) <= budget);
+
+        let audio = AudioData {
+            samples: (0..44_100).map(|i| (i as f32 * 0.05).sin()).collect(),
+            sides: None,
+            sample_rate: 44_100,
+            tags: Default::default(),
+        };
+        let options = AnalysisOptions { fft_bins: Some(128), ..Default::default() };
+        let (_, frames) = analyze(&audio, FrameRate::integer(10), &options).unwrap();
+        assert!(frames.iter().all(|frame| frame.fft_bins.len() == 128));
+    }
+
+    /// Every normalized feature of a frame, for range checks.
+    fn normalized_fields(frame: &SmoothedFrame) -> Vec<(&'static str, f32)> {
+        let mut fields = vec![
+            ("bass", frame.bass),
+            ("mid", frame.mid),
+            ("high", frame.high),
+            ("rms", frame.rms),
+            ("spectral_centroid", frame.spectral_centroid),
+            ("spectral_flux", frame.spectral_flux),
+            ("onset_strength", frame.onset_strength),
+            ("beat_intensity", frame.beat_intensity),
+            ("beat_phase", frame.beat_phase),
+        ];
+        fields.extend(frame.fft_bins.iter().map(|&bin| ("fft_bins", bin)));
+        fields
+    }
+
+    #[test]
+    fn silence_and_near_silence_stay_finite_and_in_range() {
+        let sample_rate = 44_100;
+        let inputs = [
+            ("digital silence", vec![0.0f32; sample_rate * 2]),
+            // Dither-level noise just above the division guards.
+            ("near silence", (0..sample_rate * 2).map(|i| if i % 7 == 0 { 1e-9 } else { 0.0 }).collect()),
+            ("shorter than one frame", vec![0.0f32; 100]),
+            ("corrupt samples", (0..sample_rate * 2).map(|i| if i % 1000 == 0 { f32::NAN } else { 0.0 }).collect()),
+        ];
+
+        for (name, samples) in inputs {
+            let audio = AudioData {
+                samples,
+                sides: None,
+                sample_rate: sample_rate as u32,
+                tags: Default::default(),
+            };
+            for options in [
+                AnalysisOptions::default(),
+                AnalysisOptions {
+                    bin_peak_decay: 2.0,
+                    dual_fft: true,
+                    ..AnalysisOptions::default()
+                },
+            ] {
+                let (global, frames) = analyze(&audio, FrameRate::integer(30), &options).unwrap();
+                assert!(global.tempo_bpm.is_finite(), "{name}: tempo");
+                assert!(!frames.is_empty(), "{name}: no frames");
+                for frame in &frames {
+                    for (field, value) in normalized_fields(frame) {
+                        assert!((0.0..=1.0).contains(&value), "{name}: {field} = {value} at {}s", frame.time);
+                    }
+                    // Nothing here is audible, so nothing should move.
+                    for (field, value) in normalized_fields(frame).into_iter().filter(|(f, _)| !f.starts_with("beat")) {
+                        assert!(value < 0.01, "{name}: {field} = {value} at {}s", frame.time);
+                    }
+                    assert!(frame.waveform.iter().all(|s| s.is_finite()), "{name}: waveform");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn a_reused_planner_gives_the_same_spectra_as_a_fresh_one() {
+        let samples = (0..20_000).map(|i| (i as f32 * 0.031).sin() * (i as f32 * 0.0007).cos()).collect::<Vec<_>>();
+        let hann = hann_window(DEFAULT_FFT_SIZE);
+        let bass_hann = hann_window(BASS_FFT_SIZE);
+        let mut reused = FftPlanner::<f32>::new();
+        for center in (0..samples.len()).step_by(1500) {
+            let mut fresh = FftPlanner::<f32>::new();
+            assert_eq!(
+                spectrum(&samples, center, &hann, &mut reused),
+                spectrum(&samples, center, &hann, &mut fresh)
+            );
+            assert_eq!(
+                bass_spectrum(&samples, center, &bass_hann, DEFAULT_FFT_SIZE, &mut reused),
+                bass_spectrum(&samples, center, &bass_hann, DEFAULT_FFT_SIZE, &mut fresh)
+            );
+        }
+    }
+}
@@ -0,0 +1,319 @@
+use anyhow::{Context, Result};
+use std::path::Path;
+
+/// Microseconds per quarter note until a Standard MIDI File sets a tempo (120 BPM).
+const DEFAULT_MIDI_TEMPO: u32 = 500_000;
+
+/// `--beat-grid`: authoritative beat times that replace onset detection.
+///
+/// Either a text file with one time in seconds per line (blank lines and
+/// `#` comments are skipped), a JSON array of times in seconds, or a
+/// Standard MIDI File whose tempo map puts a beat on every quarter note.
+#[derive(Debug, Clone)]
+pub struct BeatGrid {
+    source: GridSource,
+    /// Seconds of the grid before the rendered clip (`--start`).
+    offset: f32,
+}
+
+#[derive(Debug, Clone)]
+enum GridSource {
+    Times(Vec<f32>),
+    /// Tempo changes as (tick, microseconds per quarter note), starting at tick 0.
+    TempoMap { ticks_per_quarter: u16, tempos: Vec<(u64, u32)> },
+}
+
+impl BeatGrid {
+    pub fn load(path: &Path) -> Result<Self> {
+        let bytes = std::fs::read(path)
+            .with_context(|| format!("Failed to read beat grid: {}", path.display()))?;
+        let grid = if bytes.starts_with(b"MThd") {
+            Self::parse_midi(&bytes)
+        } else {
+            std::str::from_utf8(&bytes)
+                .context("expected one time in seconds per line, a JSON array, or a MIDI file")
+                .and_then(|text| match text.trim_start().starts_with('[') {
+                    true => Self::parse_json(text),
+                    false => Self::parse_times(text),
+                })
+        };
+        grid.with_context(|| format!("Invalid beat grid: {}", path.display()))
+    }
+
+    pub fn parse_times(text: &str) -> Result<Self> {
+        let mut times: Vec<f32> = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let time: f32 = line
+                .parse()
+                .with_context(|| format!("line {}: '{}' is not a time in seconds", number + 1, line))?;
+            push_time(&mut times, time).with_context(|| format!("line {}", number + 1))?;
+        }
+        Self::from_times(times)
+    }
+
+    /// A JSON array of beat times in seconds, such as `[0.5, 1.0, 1.5]`.
+    pub fn parse_json(text: &str) -> Result<Self> {
+        let values: Vec<f32> = serde_json::from_str(text).context("expected a JSON array of times in seconds")?;
+        let mut times = Vec::with_capacity(values.len());
+        for (index, time) in values.into_iter().enumerate() {
+            push_time(&mut times, time).with_context(|| format!("beat {}", index + 1))?;
+        }
+        Self::from_times(times)
+    }
+
+    fn from_times(times: Vec<f32>) -> Result<Self> {
+        if times.is_empty() {
+            anyhow::bail!("no beat times found");
+        }
+        Ok(Self { source: GridSource::Times(times), offset: 0.0 })
+    }
+
+    /// Read the tempo map of a Standard MIDI File. Notes and every other event
+    /// are skipped; only `Set Tempo` meta events are used.
+    pub fn parse_midi(bytes: &[u8]) -> Result<Self> {
+        let mut reader = MidiReader { bytes, pos: 0 };
+        if reader.take(4)? != b"MThd" || reader.u32()? < 6 {
+            anyhow::bail!("missing MIDI header");
+        }
+        let _format = reader.u16()?;
+        let _tracks = reader.u16()?;
+        let division = reader.u16()?;
+        if division & 0x8000 != 0 || division == 0 {
+            anyhow::bail!("SMPTE time division is not supported; export with ticks per quarter note");
+        }
+
+        let mut tempos = Vec::new();
+        while reader.pos < bytes.len() {
+            let id = reader.take(4)?;
+            let len = reader.u32()? as usize;
+            let chunk = reader.take(len)?;
+            if id == b"MTrk" {
+                read_track_tempos(chunk, &mut tempos)?;
+            }
+        }
+
+        tempos.sort_by_key(|&(tick, _)| tick);
+        if tempos.first().is_none_or(|&(tick, _)| tick > 0) {
+            tempos.insert(0, (0, DEFAULT_MIDI_TEMPO));
+        }
+        Ok(Self {
+            source: GridSource::TempoMap { ticks_per_quarter: division, tempos },
+            offset: 0.0,
+        })
+    }
+
+    /// The same grid for a clip that starts `start` seconds into the track:
+    /// beat times count from the clip's start and earlier beats are dropped.
+    pub fn starting_at(mut self, start: f32) -> Self {
+        self.offset = start;
+        self
+    }
+
+    /// Beat times up to `duration` seconds.
+    pub fn beat_times(&self, duration: f32) -> Vec<f32> {
+        let offset = self.offset;
+        self.track_times(offset + duration)
+            .into_iter()
+            .filter(|&t| t >= offset)
+            .map(|t| t - offset)
+            .collect()
+    }
+
+    /// Beat times up to `end` seconds of the whole track.
+    fn track_times(&self, end: f32) -> Vec<f32> {
+        match &self.source {
+            GridSource::Times(times) => times.iter().copied().take_while(|&t| t <= end).collect(),
+            GridSource::TempoMap { ticks_per_quarter, tempos } => {
+                let tpq = *ticks_per_quarter as u64;
+                let mut times = Vec::new();
+                // Seconds at the start of the current tempo segment.
+                let mut segment_start = 0.0f64;
+                let mut segment = 0;
+                for beat in 0u64.. {
+                    let tick = beat * tpq;
+                    while segment + 1 < tempos.len() && tempos[segment + 1].0 <= tick {
+                        let (from, tempo) = tempos[segment];
+                        segment_start += (tempos[segment + 1].0 - from) as f64 * tempo as f64 / tpq as f64 / 1e6;
+                        segment += 1;
+                    }
+                    let (from, tempo) = tempos[segment];
+                    let time = segment_start + (tick - from) as f64 * tempo as f64 / tpq as f64 / 1e6;
+                    if time > end as f64 {
+                        break;
+                    }
+                    times.push(time as f32);
+                }
+                times
+            }
+        }
+    }
+}
+
+/// Append `time` to `times`, which must stay increasing.
+fn push_time(times: &mut Vec<f32>, time: f32) -> Result<()> {
+    if !(time.is_finite() && time >= 0.0) {
+        anyhow::bail!("time must be a non-negative number of seconds");
+    }
+    if let Some(&last) = times.last() {
+        if time <= last {
+            anyhow::bail!(
+                "beat at {}s is not after the previous one at {}s; times must be increasing",
+                time,
+                last
+            );
+        }
+    }
+    times.push(time);
+    Ok(())
+}
+
+/// Tempo from the median interval of `beat_times`, without the plausible-range
+/// filtering applied to detected beats.
+pub fn grid_tempo(beat_times: &[f32]) -> Option<f32> {
+    let mut intervals: Vec<f32> = beat_times.windows(2).map(|w| w[1] - w[0]).collect();
+    if intervals.is_empty() {
+        return None;
+    }
+    intervals.sort_by(|a, b| a.total_cmp(b));
+    Some(60.0 / intervals[intervals.len() / 2])
+}
+
+fn read_track_tempos(track: &[u8], tempos: &mut Vec<(u64, u32)>) -> Result<()> {
+    let mut reader = MidiReader { bytes: track, pos: 0 };
+    let mut tick = 0u64;
+    let mut running_status = 0u8;
+    while reader.pos < track.len() {
+        tick += reader.vlq()? as u64;
+        let mut status = reader.u8()?;
+        if status < 0x80 {
+            // Running status: this byte was the first data byte.
+            if running_status == 0 {
+                anyhow::bail!("data byte without a status byte");
+            }
+            reader.pos -= 1;
+            status = running_status;
+        }
+        match status {
+            0xFF => {
+                let kind = reader.u8()?;
+                let len = reader.vlq()? as usize;
+                let data = reader.take(len)?;
+                if kind == 0x51 && len == 3 {
+                    let tempo = u32::from_be_bytes([0, data[0], data[1], data[2]]);
+                    if tempo == 0 {
+                        anyhow::bail!("tempo event at tick {} has a zero tempo", tick);
+                    }
+                    tempos.push((tick, tempo));
+                } else if kind == 0x2F {
+                    break;
+                }
+            }
+            0xF0 | 0xF7 => {
+                let len = reader.vlq()? as usize;
+                reader.take(len)?;
+            }
+            0x80..=0xEF => {
+                running_status = status;
+                let data_bytes = if matches!(status & 0xF0, 0xC0 | 0xD0) { 1 } else { 2 };
+                reader.take(data_bytes)?;
+            }
+            other => anyhow::bail!("unexpected status byte {:#04x}", other),
+        }
+    }
+    Ok(())
+}
+
+struct MidiReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> MidiReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self.pos.checked_add(len).filter(|&end| end <= self.bytes.len());
+        let end = end.context("MIDI data ends early")?;
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        let b = self.take(2)?;
+        Ok(u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        let b = self.take(4)?;
+        Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    /// Variable-length quantity: 7 bits per byte, high bit set on all but the last.
+    fn vlq(&mut self) -> Result<u32> {
+        let mut value = 0u32;
+        for _ in 0..4 {
+            let byte = self.u8()?;
+            value = (value << 7) | (byte & 0x7F) as u32;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        anyhow::bail!("variable-length value is longer than 4 bytes")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_grid_must_be_increasing() {
+        let grid = BeatGrid::parse_times("# exported grid\n0.5\n1.0\n\n1.5 # downbeat\n2.0\n").unwrap();
+        assert_eq!(grid.beat_times(1.6), vec![0.5, 1.0, 1.5]);
+        assert_eq!(grid_tempo(&grid.beat_times(10.0)), Some(120.0));
+        assert_eq!(grid.clone().starting_at(0.75).beat_times(0.8), vec![0.25, 0.75]);
+
+        assert!(BeatGrid::parse_times("1.0\n0.5\n").is_err());
+        assert!(BeatGrid::parse_times("1.0\n1.0\n").is_err());
+        assert!(BeatGrid::parse_times("-1\n").is_err());
+        assert!(BeatGrid::parse_times("# nothing\n").is_err());
+    }
+
+    #[test]
+    fn json_grid_is_an_array_of_seconds() {
+        let grid = BeatGrid::parse_json("[0.5, 1.0, 1.5, 2]").unwrap();
+        assert_eq!(grid.beat_times(10.0), vec![0.5, 1.0, 1.5, 2.0]);
+
+        let error = BeatGrid::parse_json("[0.5, 0.25]").unwrap_err();
+        assert!(format!("{error:#}").contains("beat 2"), "{error:#}");
+        assert!(BeatGrid::parse_json("[]").is_err());
+        assert!(BeatGrid::parse_json("{\"beats\": [1.0]}").is_err());
+    }
+
+    #[test]
+    fn midi_tempo_map_places_a_beat_per_quarter_note() {
+        // 96 ticks per quarter; 120 BPM, then 60 BPM from beat 2, with a
+        // note in between using running status.
+        let track: &[u8] = &[
+            0x00, 0xFF, 0x51, 0x03, 0x07, 0xA1, 0x20, // tempo 500000
+            0x00, 0x90, 0x3C, 0x64, // note on
+            0x60, 0x3C, 0x00, // running status note off, delta 96
+            0x60, 0xFF, 0x51, 0x03, 0x0F, 0x42, 0x40, // delta 96, tempo 1000000
+            0x00, 0xFF, 0x2F, 0x00,
+        ];
+        let mut smf = b"MThd\0\0\0\x06\0\0\0\x01\0\x60MTrk".to_vec();
+        smf.extend((track.len() as u32).to_be_bytes());
+        smf.extend(track);
+
+        let grid = BeatGrid::parse_midi(&smf).unwrap();
+        assert_eq!(grid.beat_times(3.0), vec![0.0, 0.5, 1.0, 2.0, 3.0]);
+        assert!(BeatGrid::parse_midi(&smf[..smf.len() - 3]).is_err());
+    }
+}
@@ -0,0 +1,408 @@
+use anyhow::Result;
+use std::fmt;
+use symphonia::core::audio::{ChannelLabel, Channels, Position};
+
+use super::decode::Downmix;
+
+/// A speaker position, named as FFmpeg names them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Speaker {
+    FrontLeft,
+    FrontRight,
+    FrontCenter,
+    Lfe,
+    BackLeft,
+    BackRight,
+    FrontLeftOfCenter,
+    FrontRightOfCenter,
+    BackCenter,
+    SideLeft,
+    SideRight,
+    /// A channel without a known position (discrete, height, ambisonic...).
+    Other,
+}
+
+/// Every named speaker with its FFmpeg name and Symphonia position.
+const SPEAKERS: &[(Speaker, &str, Position)] = &[
+    (Speaker::FrontLeft, "FL", Position::FRONT_LEFT),
+    (Speaker::FrontRight, "FR", Position::FRONT_RIGHT),
+    (Speaker::FrontCenter, "FC", Position::FRONT_CENTER),
+    (Speaker::Lfe, "LFE", Position::LFE1),
+    (Speaker::BackLeft, "BL", Position::REAR_LEFT),
+    (Speaker::BackRight, "BR", Position::REAR_RIGHT),
+    (Speaker::FrontLeftOfCenter, "FLC", Position::FRONT_LEFT_CENTER),
+    (Speaker::FrontRightOfCenter, "FRC", Position::FRONT_RIGHT_CENTER),
+    (Speaker::BackCenter, "BC", Position::REAR_CENTER),
+    (Speaker::SideLeft, "SL", Position::SIDE_LEFT),
+    (Speaker::SideRight, "SR", Position::SIDE_RIGHT),
+];
+
+/// Named layouts in FFmpeg's channel order.
+const LAYOUTS: &[(&str, &str)] = &[
+    ("mono", "FC"),
+    ("stereo", "FL,FR"),
+    ("2.1", "FL,FR,LFE"),
+    ("3.0", "FL,FR,FC"),
+    ("quad", "FL,FR,BL,BR"),
+    ("5.0", "FL,FR,FC,BL,BR"),
+    ("5.0(side)", "FL,FR,FC,SL,SR"),
+    ("5.1", "FL,FR,FC,LFE,BL,BR"),
+    ("5.1(side)", "FL,FR,FC,LFE,SL,SR"),
+    ("6.1", "FL,FR,FC,LFE,BC,SL,SR"),
+    ("7.1", "FL,FR,FC,LFE,BL,BR,SL,SR"),
+];
+
+impl Speaker {
+    /// An FFmpeg channel name (`FL`, `LFE`, ...), or `L`/`R`/`C` for the fronts.
+    fn parse(name: &str) -> Option<Self> {
+        let name = name.trim().to_ascii_uppercase();
+        let name = match name.as_str() {
+            "L" => "FL",
+            "R" => "FR",
+            "C" => "FC",
+            other => other,
+        };
+        SPEAKERS.iter().find(|(_, n, _)| *n == name).map(|(speaker, _, _)| *speaker)
+    }
+
+    fn name(self) -> &'static str {
+        SPEAKERS
+            .iter()
+            .find(|(speaker, _, _)| *speaker == self)
+            .map_or("?", |(_, name, _)| name)
+    }
+
+    fn from_position(position: Position) -> Self {
+        SPEAKERS
+            .iter()
+            .find(|(_, _, p)| *p == position)
+            .map_or(Self::Other, |(speaker, _, _)| *speaker)
+    }
+
+    /// Weight in the ITU-R BS.775 fold-down, taken to mono as
+    /// `(Lo + Ro) / 2`: centre channels feed both sides at -3dB, surrounds
+    /// one side at -3dB, and the LFE is left out.
+    fn mono_weight(self) -> f32 {
+        const MINUS_3DB: f32 = std::f32::consts::FRAC_1_SQRT_2;
+        match self {
+            Self::FrontLeft | Self::FrontRight | Self::FrontLeftOfCenter | Self::FrontRightOfCenter => 0.5,
+            Self::FrontCenter | Self::BackCenter => MINUS_3DB,
+            Self::BackLeft | Self::BackRight | Self::SideLeft | Self::SideRight => 0.5 * MINUS_3DB,
+            Self::Lfe => 0.0,
+            Self::Other => 0.5,
+        }
+    }
+}
+
+/// The speaker of each channel, in interleaved order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChannelLayout(Vec<Speaker>);
+
+impl ChannelLayout {
+    /// Parse `--channel-layout`: a named layout (`stereo`, `5.1`, `7.1`...)
+    /// or the channel names in order (`FL,FR,FC,LFE,SL,SR`).
+    pub fn parse(spec: &str) -> Result<Self> {
+        let spec = spec.trim();
+        let names = LAYOUTS
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(spec))
+            .map_or(spec, |(_, channels)| channels);
+        let speakers = names
+            .split(',')
+            .map(|name| {
+                Speaker::parse(name).ok_or_else(|| {
+                    let layouts: Vec<&str> = LAYOUTS.iter().map(|(name, _)| *name).collect();
+                    anyhow::anyhow!(
+                        "Unknown channel layout '{}'. Use one of {} or channel names like FL,FR,FC,LFE,BL,BR",
+                        spec,
+                        layouts.join(", ")
+                    )
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self(speakers))
+    }
+
+    /// The layout a decoder reports. Channels without a position (a plain
+    /// discrete count, for one) are `Other` and fold with equal weight.
+    pub fn from_symphonia(channels: &Channels, count: usize) -> Self {
+        let speakers = match channels {
+            // Positioned channels are interleaved in ascending bit order.
+            Channels::Positioned(positions) => positions.iter().map(Speaker::from_position).collect(),
+            Channels::Custom(labels) => labels
+                .iter()
+                .map(|label| match label {
+                    ChannelLabel::Positioned(position) => Speaker::from_position(*position),
+                    _ => Speaker::Other,
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        if speakers.len() == count {
+            Self(speakers)
+        } else {
+            Self::discrete(count)
+        }
+    }
+
+    /// `count` channels without positions, which fold with equal weight.
+    pub fn discrete(count: usize) -> Self {
+        Self(vec![Speaker::Other; count])
+    }
+
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    fn is_known(&self) -> bool {
+        self.0.iter().any(|&speaker| speaker != Speaker::Other)
+    }
+
+    fn position(&self, speaker: Speaker) -> Option<usize> {
+        self.0.iter().position(|&s| s == speaker)
+    }
+}
+
+impl fmt::Display for ChannelLayout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<&str> = self.0.iter().map(|speaker| speaker.name()).collect();
+        write!(f, "{}", names.join(", "))
+    }
+}
+
+/// One entry of `--channels`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ChannelRef {
+    Speaker(Speaker),
+    /// Zero-based; written one-based on the command line.
+    Index(usize),
+}
+
+/// `--channel-layout` and `--channels`: how the decoder should read the
+/// stream's channels and which of them feed the analysis.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChannelSelection {
+    layout: Option<ChannelLayout>,
+    channels: Vec<ChannelRef>,
+}
+
+impl ChannelSelection {
+    /// `layout` overrides the layout the file declares; `channels` picks a
+    /// subset by name (`FL`, `L`, `LFE`...) or 1-based index.
+    pub fn parse(layout: Option<&str>, channels: &[String]) -> Result<Self> {
+        let layout = layout.map(ChannelLayout::parse).transpose()?;
+        let channels = channels
+            .iter()
+            .map(|name| {
+                if let Some(speaker) = Speaker::parse(name) {
+                    return Ok(ChannelRef::Speaker(speaker));
+                }
+                match name.trim().parse::<usize>() {
+                    Ok(index) if index >= 1 => Ok(ChannelRef::Index(index - 1)),
+                    _ => anyhow::bail!(
+                        "Unknown channel '{}' in --channels: use a name (L, R, C, FL, FR, FC, LFE, BL, BR, SL, SR...) or a 1-based index",
+                        name
+                    ),
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { layout, channels })
+    }
+
+    pub fn layout_override(&self) -> Option<&ChannelLayout> {
+        self.layout.as_ref()
+    }
+
+    pub fn has_subset(&self) -> bool {
+        !self.channels.is_empty()
+    }
+
+    /// Work out which channels of a `stream` layout to fold, and how.
+    pub fn resolve(&self, stream: ChannelLayout, downmix: Downmix) -> Result<Fold> {
+        let layout = match self.layout {
+            Some(ref layout) if layout.len() != stream.len() => anyhow::bail!(
+                "--channel-layout has {} channels but the stream has {}",
+                layout.len(),
+                stream.len()
+            ),
+            Some(ref layout) => layout.clone(),
+            None => stream,
+        };
+
+        if self.has_subset() {
+            let indices = self
+                .channels
+                .iter()
+                .map(|channel| match *channel {
+                    ChannelRef::Speaker(speaker) => layout.position(speaker).ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "--channels {}: the stream has no such channel (layout: {}); set --channel-layout if the file's is wrong",
+                            speaker.name(),
+                            layout
+                        )
+                    }),
+                    ChannelRef::Index(index) if index < layout.len() => Ok(index),
+                    ChannelRef::Index(index) => anyhow::bail!(
+                        "--channels {}: the stream only has {} channels",
+                        index + 1,
+                        layout.len()
+                    ),
+                })
+                .collect::<Result<Vec<_>>>()?;
+            // Channels picked by hand count equally.
+            let weights = vec![1.0 / indices.len() as f32; indices.len()];
+            return Ok(Fold::new(downmix, indices, weights));
+        }
+
+        if !layout.is_known() {
+            return Ok(Fold::flat(downmix, layout.len()));
+        }
+
+        // Fronts first, so mid/left/right find them; the LFE is left out.
+        let fronts: Vec<usize> = [Speaker::FrontLeft, Speaker::FrontRight]
+            .iter()
+            .filter_map(|&speaker| layout.position(speaker))
+            .collect();
+        let rest = (0..layout.len()).filter(|i| !fronts.contains(i) && layout.0[*i] != Speaker::Lfe);
+        let mut indices: Vec<usize> = fronts.iter().copied().chain(rest).collect();
+        if indices.is_empty() {
+            indices.push(0);
+        }
+        let weights = if layout.len() > 2 {
+            indices.iter().map(|&i| layout.0[i].mono_weight()).collect()
+        } else {
+            vec![1.0 / indices.len() as f32; indices.len()]
+        };
+        Ok(Fold::new(downmix, indices, weights))
+    }
+
+    /// Arguments for the FFmpeg fallback decoder to output just the
+    /// `--channels` subset, in order; FFmpeg resolves names against the
+    /// layout it reads from the file.
+    pub fn ffmpeg_pan(&self) -> Option<String> {
+        if !self.has_subset() {
+            return None;
+        }
+        let mut pan = format!("pan={}c", self.channels.len());
+        for (out, channel) in self.channels.iter().enumerate() {
+            match *channel {
+                ChannelRef::Speaker(speaker) => pan.push_str(&format!("|c{}={}", out, speaker.name())),
+                ChannelRef::Index(index) => pan.push_str(&format!("|c{}=c{}", out, index)),
+            }
+        }
+        Some(pan)
+    }
+
+    /// Number of channels `ffmpeg_pan` outputs.
+    pub fn subset_len(&self) -> usize {
+        self.channels.len()
+    }
+}
+
+/// Folds one interleaved frame to a mono sample: the chosen `Downmix` over
+/// the selected channels. `indices[0]` and `indices[1]` are the channels
+/// that mid/left/right read.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fold {
+    downmix: Downmix,
+    indices: Vec<usize>,
+    weights: Vec<f32>,
+}
+
+impl Fold {
+    fn new(downmix: Downmix, indices: Vec<usize>, weights: Vec<f32>) -> Self {
+        Self {
+            downmix,
+            indices,
+            weights,
+        }
+    }
+
+    /// Every channel with equal weight, for streams without a known layout.
+    pub fn flat(downmix: Downmix, channels: usize) -> Self {
+        let channels = channels.max(1);
+        Self::new(downmix, (0..channels).collect(), vec![1.0 / channels as f32; channels])
+    }
+
+    pub fn apply(&self, frame: &[f32]) -> f32 {
+        let picked = || self.indices.iter().map(|&i| frame[i]);
+        let first = self.indices[0];
+        let second = self.indices[1.min(self.indices.len() - 1)];
+        match self.downmix {
+            Downmix::Average => picked().zip(&self.weights).map(|(s, w)| s * w).sum(),
+            Downmix::Rms => {
+                let rms = (picked().map(|s| s * s).sum::<f32>() / self.indices.len() as f32).sqrt();
+                rms.copysign(picked().sum::<f32>())
+            }
+            Downmix::Mid => frame[first] + frame[second],
+            Downmix::Left => frame[first],
+            Downmix::Right => frame[second],
+        }
+    }
+
+    /// The left and right samples of one interleaved frame: the channels
+    /// that `Left` and `Right` read.
+    pub fn sides(&self, frame: &[f32]) -> [f32; 2] {
+        let first = self.indices[0];
+        let second = self.indices[1.min(self.indices.len() - 1)];
+        [frame[first], frame[second]]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn surround_folds_with_bs775_weights_and_drops_the_lfe() {
+        let fold = ChannelSelection::default()
+            .resolve(ChannelLayout::parse("5.1").unwrap(), Downmix::Average)
+            .unwrap();
+
+        // FL FR FC LFE BL BR
+        assert!((fold.apply(&[1.0, 1.0, 0.0, 0.0, 0.0, 0.0]) - 1.0).abs() < 1e-6);
+        assert!((fold.apply(&[0.0, 0.0, 1.0, 0.0, 0.0, 0.0]) - std::f32::consts::FRAC_1_SQRT_2).abs() < 1e-6);
+        assert!((fold.apply(&[0.0, 0.0, 0.0, 0.0, 1.0, 1.0]) - std::f32::consts::FRAC_1_SQRT_2).abs() < 1e-6);
+        assert_eq!(fold.apply(&[0.0, 0.0, 0.0, 1.0, 0.0, 0.0]), 0.0);
+
+        // Stereo stays a plain average.
+        let stereo = ChannelSelection::default()
+            .resolve(ChannelLayout::parse("stereo").unwrap(), Downmix::Average)
+            .unwrap();
+        assert_eq!(stereo, Fold::flat(Downmix::Average, 2));
+    }
+
+    #[test]
+    fn channels_pick_a_subset_by_name_or_index() {
+        let layout = ChannelLayout::parse("FL,FC,FR,SL,SR,LFE").unwrap();
+
+        let lr = ChannelSelection::parse(None, &["L".into(), "R".into()]).unwrap();
+        let fold = lr.resolve(layout.clone(), Downmix::Right).unwrap();
+        assert_eq!(fold.apply(&[0.1, 0.2, 0.3, 0.4, 0.5, 0.6]), 0.3);
+        assert_eq!(lr.ffmpeg_pan().as_deref(), Some("pan=2c|c0=FL|c1=FR"));
+
+        let lfe = ChannelSelection::parse(None, &["6".into()]).unwrap();
+        let fold = lfe.resolve(layout.clone(), Downmix::Average).unwrap();
+        assert_eq!(fold.apply(&[0.1, 0.2, 0.3, 0.4, 0.5, 0.6]), 0.6);
+        assert_eq!(lfe.ffmpeg_pan().as_deref(), Some("pan=1c|c0=c5"));
+
+        let back = ChannelSelection::parse(None, &["BL".into()]).unwrap();
+        assert!(back.resolve(layout.clone(), Downmix::Average).is_err());
+        assert!(ChannelSelection::parse(None, &["0".into()]).is_err());
+        assert!(ChannelSelection::parse(Some("stereo"), &[]).unwrap().resolve(layout, Downmix::Average).is_err());
+    }
+
+    #[test]
+    fn reads_symphonia_positions_in_interleaved_order() {
+        let positions = Position::FRONT_LEFT | Position::FRONT_RIGHT | Position::FRONT_CENTER | Position::LFE1;
+        let layout = ChannelLayout::from_symphonia(&Channels::Positioned(positions), 4);
+        assert_eq!(layout.to_string(), "FL, FR, FC, LFE");
+
+        let unknown = ChannelLayout::from_symphonia(&Channels::Discrete(6), 6);
+        assert!(!unknown.is_known());
+        assert_eq!(
+            ChannelSelection::default().resolve(unknown, Downmix::Average).unwrap(),
+            Fold::flat(Downmix::Average, 6)
+        );
+    }
+}
@@ -0,0 +1,262 @@
+use anyhow::{Context, Result};
+use std::path::Path;
+
+use super::decode::AudioTags;
+
+/// CUE sheet INDEX times count frames of 1/75 second.
+const CUE_FRAMES_PER_SECOND: f32 = 75.0;
+
+/// `--cue-sheet`: where each song of a mix starts and what it is called.
+///
+/// Reads a single-file CUE sheet (`TRACK` / `TITLE` / `PERFORMER` /
+/// `INDEX 01 mm:ss:ff`) or a plain tracklist with one `[h:]mm:ss Title` per
+/// line, as in video chapter lists. Blank lines and `#` comments are skipped
+/// in tracklists.
+#[derive(Debug, Clone)]
+pub struct CueSheet {
+    /// Start time in seconds and display title, in increasing start order.
+    tracks: Vec<(f32, String)>,
+}
+
+impl CueSheet {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read cue sheet: {}", path.display()))?;
+        Self::parse(&text).with_context(|| format!("Invalid cue sheet: {}", path.display()))
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let text = text.trim_start_matches('\u{feff}');
+        let is_cue = text.lines().any(|line| {
+            let command = line.split_whitespace().next().unwrap_or_default();
+            command.eq_ignore_ascii_case("TRACK") || command.eq_ignore_ascii_case("INDEX")
+        });
+        let tracks = if is_cue { parse_cue(text)? } else { parse_tracklist(text)? };
+
+        if tracks.is_empty() {
+            anyhow::bail!("no tracks found");
+        }
+        for pair in tracks.windows(2) {
+            if pair[1].0 <= pair[0].0 {
+                anyhow::bail!(
+                    "'{}' starts at {}s, not after '{}' at {}s; tracks must be in order",
+                    pair[1].1,
+                    pair[1].0,
+                    pair[0].1,
+                    pair[0].0
+                );
+            }
+        }
+        Ok(Self { tracks })
+    }
+
+    /// The same sheet for a clip that starts `start` seconds into the mix, so
+    /// it can be read at the clip's frame times.
+    pub fn starting_at(mut self, start: f32) -> Self {
+        for (track_start, _) in &mut self.tracks {
+            *track_start -= start;
+        }
+        self
+    }
+
+    pub fn track_count(&self) -> usize {
+        self.tracks.len()
+    }
+
+    /// Title of the track playing at `time`, or `None` before the first one.
+    pub fn title_at(&self, time: f32) -> Option<&str> {
+        let index = self.tracks.partition_point(|(start, _)| *start <= time);
+        index.checked_sub(1).map(|i| self.tracks[i].1.as_str())
+    }
+
+    /// Brightness at `time` for a dip to black `width` seconds long centred
+    /// on each boundary between tracks: 0.0 at the boundary, 1.0 away from it.
+    pub fn separator_gain(&self, time: f32, width: f32) -> f32 {
+        let half = width / 2.0;
+        if half <= 0.0 {
+            return 1.0;
+        }
+        self.tracks
+            .iter()
+            .skip(1)
+            .map(|(start, _)| ((time - start).abs() / half).min(1.0))
+            .fold(1.0, f32::min)
+    }
+}
+
+fn parse_cue(text: &str) -> Result<Vec<(f32, String)>> {
+    struct Track {
+        number: String,
+        title: Option<String>,
+        performer: Option<String>,
+        start: Option<f32>,
+    }
+
+    let mut files = 0;
+    let mut album_performer: Option<String> = None;
+    let mut tracks: Vec<Track> = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        let (command, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let rest = rest.trim();
+        match command.to_ascii_uppercase().as_str() {
+            "FILE" => {
+                files += 1;
+                if files > 1 {
+                    anyhow::bail!("line {}: only cue sheets for a single audio file are supported", number + 1);
+                }
+            }
+            "TRACK" => tracks.push(Track {
+                number: rest.split_whitespace().next().unwrap_or_default().to_string(),
+                title: None,
+                performer: None,
+                start: None,
+            }),
+            "TITLE" => {
+                if let Some(track) = tracks.last_mut() {
+                    track.title = Some(unquote(rest));
+                }
+            }
+            "PERFORMER" => match tracks.last_mut() {
+                Some(track) => track.performer = Some(unquote(rest)),
+                None => album_performer = Some(unquote(rest)),
+            },
+            "INDEX" => {
+                let mut fields = rest.split_whitespace();
+                if fields.next() != Some("01") {
+                    continue;
+                }
+                let track = tracks
+                    .last_mut()
+                    .with_context(|| format!("line {}: INDEX before the first TRACK", number + 1))?;
+                let time = fields.next().unwrap_or_default();
+                track.start = Some(
+                    parse_cue_time(time)
+                        .with_context(|| format!("line {}: '{}' is not an mm:ss:ff time", number + 1, time))?,
+                );
+            }
+            _ => {}
+        }
+    }
+
+    tracks
+        .into_iter()
+        .map(|track| {
+            let start = track
+                .start
+                .with_context(|| format!("track {} has no INDEX 01 start time", track.number))?;
+            let tags = AudioTags {
+                title: track.title,
+                artist: track.performer.or_else(|| album_performer.clone()),
+                ..Default::default()
+            };
+            let title = tags.display_title().unwrap_or_else(|| format!("Track {}", track.number));
+            Ok((start, title))
+        })
+        .collect()
+}
+
+fn parse_tracklist(text: &str) -> Result<Vec<(f32, String)>> {
+    let mut tracks = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (time, title) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let start = parse_clock_time(time)
+            .with_context(|| format!("line {}: expected '[h:]mm:ss Title', got '{}'", number + 1, line))?;
+        let title = title.trim().trim_start_matches(['-', '–', '—']).trim();
+        if title.is_empty() {
+            anyhow::bail!("line {}: track at {} has no title", number + 1, time);
+        }
+        tracks.push((start, title.to_string()));
+    }
+    Ok(tracks)
+}
+
+/// `mm:ss:ff`, where `ff` counts 1/75 second frames.
+fn parse_cue_time(value: &str) -> Option<f32> {
+    let mut parts = value.split(':').map(|part| part.parse::<u32>().ok());
+    let (minutes, seconds, frames) = (parts.next()??, parts.next()??, parts.next()??);
+    if parts.next().is_some() || seconds >= 60 || frames >= CUE_FRAMES_PER_SECOND as u32 {
+        return None;
+    }
+    Some(minutes as f32 * 60.0 + seconds as f32 + frames as f32 / CUE_FRAMES_PER_SECOND)
+}
+
+/// `mm:ss` or `h:mm:ss`, with optional fractional seconds.
+fn parse_clock_time(value: &str) -> Option<f32> {
+    let parts: Vec<&str> = value.split(':').collect();
+    if !(2..=3).contains(&parts.len()) {
+        return None;
+    }
+    let seconds: f32 = parts.last()?.parse().ok()?;
+    if !(0.0..60.0).contains(&seconds) {
+        return None;
+    }
+    let minutes = parts[..parts.len() - 1]
+        .iter()
+        .try_fold(0u32, |total, part| Some(total * 60 + part.parse::<u32>().ok()?))?;
+    Some(minutes as f32 * 60.0 + seconds)
+}
+
+fn unquote(value: &str) -> String {
+    let value = value.trim();
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .unwrap_or(value)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_a_single_file_cue_sheet() {
+        let sheet = CueSheet::parse(
+            "PERFORMER \"Various\"\nTITLE \"Summer Mix\"\nFILE \"mix.wav\" WAVE\n\
+             \x20 TRACK 01 AUDIO\n    TITLE \"Opening\"\n    PERFORMER \"DJ One\"\n    INDEX 01 00:00:00\n\
+             \x20 TRACK 02 AUDIO\n    TITLE \"Second Song\"\n    INDEX 00 03:58:00\n    INDEX 01 04:00:37\n",
+        )
+        .unwrap();
+
+        assert_eq!(sheet.track_count(), 2);
+        assert_eq!(sheet.title_at(10.0), Some("DJ One — Opening"));
+        assert_eq!(sheet.title_at(239.0), Some("DJ One — Opening"));
+        assert_eq!(sheet.title_at(241.0), Some("Various — Second Song"));
+
+        assert!(CueSheet::parse("FILE \"a.wav\" WAVE\nFILE \"b.wav\" WAVE\n  TRACK 01 AUDIO\n    INDEX 01 00:00:00\n").is_err());
+        assert!(CueSheet::parse("  TRACK 01 AUDIO\n    TITLE \"No start\"\n").is_err());
+    }
+
+    #[test]
+    fn reads_a_chapter_style_tracklist() {
+        let sheet = CueSheet::parse("# side A\n0:00 Intro\n2:30.5 - Artist — Song\n1:02:03 Finale\n").unwrap();
+        assert_eq!(sheet.title_at(0.0), Some("Intro"));
+        assert_eq!(sheet.title_at(151.0), Some("Artist — Song"));
+        assert_eq!(sheet.title_at(3723.0), Some("Finale"));
+
+        let clipped = sheet.starting_at(150.0);
+        assert_eq!(clipped.title_at(0.0), Some("Intro"));
+        assert_eq!(clipped.title_at(1.0), Some("Artist — Song"));
+
+        assert!(CueSheet::parse("2:00 Later\n1:00 Earlier\n").is_err());
+        assert!(CueSheet::parse("1:75 Bad seconds\n").is_err());
+        assert!(CueSheet::parse("1:00\n").is_err());
+    }
+
+    #[test]
+    fn separators_dip_at_track_boundaries() {
+        let sheet = CueSheet::parse("0:05 One\n0:10 Two\n").unwrap();
+        assert_eq!(sheet.title_at(1.0), None);
+        assert_eq!(sheet.separator_gain(10.0, 1.0), 0.0);
+        assert_eq!(sheet.separator_gain(10.25, 1.0), 0.5);
+        assert_eq!(sheet.separator_gain(7.0, 1.0), 1.0);
+        // The first track's start is not a boundary between songs.
+        assert_eq!(sheet.separator_gain(5.0, 1.0), 1.0);
+        assert_eq!(sheet.separator_gain(10.0, 0.0), 1.0);
+    }
+}
@@ -5,15 +5,214 @@ use symphonia::core::codecs::audio::AudioDecoderOptions;
 use symphonia::core::formats::probe::Hint;
 use symphonia::core::formats::{FormatOptions, TrackType};
 use symphonia::core::io::MediaSourceStream;
-use symphonia::core::meta::MetadataOptions;
+use symphonia::core::meta::{MetadataOptions, MetadataRevision, StandardTag, StandardVisualKey};
+use symphonia::core::units::TimeBase;
+
+use super::channels::{ChannelLayout, ChannelSelection, Fold};
+use crate::error::SonicaError;
 
 pub struct AudioData {
     pub samples: Vec<f32>,
+    /// Left and right channels, decoded only when asked for and the stream
+    /// has more than one channel.
+    pub sides: Option<[Vec<f32>; 2]>,
     pub sample_rate: u32,
+    pub tags: AudioTags,
+}
+
+impl AudioData {
+    /// Repeat the track end to end until it lasts `seconds`, cutting the last
+    /// repetition short (or the track itself, if it is longer).
+    pub fn repeat_to(&mut self, seconds: f32) {
+        let len = (seconds * self.sample_rate as f32).round() as usize;
+        let tile = |samples: &mut Vec<f32>| {
+            if samples.is_empty() {
+                return;
+            }
+            let original = samples.len();
+            samples.resize(len, 0.0);
+            for i in original..len {
+                samples[i] = samples[i % original];
+            }
+        };
+        tile(&mut self.samples);
+        for side in self.sides.iter_mut().flatten() {
+            tile(side);
+        }
+    }
+
+    /// The channels as the soundtrack carries them: the sides if decoded,
+    /// otherwise the mono samples.
+    pub fn channels(&self) -> Vec<&[f32]> {
+        match &self.sides {
+            Some([left, right]) => vec![left, right],
+            None => vec![&self.samples],
+        }
+    }
+
+    /// Scale the samples, and the sides if decoded, by a linear `gain`.
+    pub fn apply_gain(&mut self, gain: f32) {
+        for sample in self.samples.iter_mut().chain(self.sides.iter_mut().flatten().flatten()) {
+            *sample *= gain;
+        }
+    }
+
+    /// Keep only `start..end` seconds of the track, so analysis and every
+    /// time derived from it count from the clip's start. An `end` past the
+    /// track, or none, keeps everything after `start`.
+    pub fn clip(&mut self, start: f32, end: Option<f32>) -> Result<()> {
+        let duration = self.samples.len() as f32 / self.sample_rate as f32;
+        if let Some(end) = end.filter(|&end| end <= start) {
+            anyhow::bail!("--end ({}s) must be after --start ({}s)", end, start);
+        }
+        if start >= duration {
+            anyhow::bail!("--start {}s is past the end of the {:.1}s track", start, duration);
+        }
+        if let Some(end) = end.filter(|&end| end > duration) {
+            log::info!("--end {}s is past the end of the {:.1}s track; rendering to the end", end, duration);
+        }
+        let to_sample = |seconds: f32| ((seconds as f64 * self.sample_rate as f64).round() as usize).min(self.samples.len());
+        let range = to_sample(start)..end.map_or(self.samples.len(), to_sample);
+        let cut = |samples: &mut Vec<f32>| {
+            samples.truncate(range.end);
+            samples.drain(..range.start);
+        };
+        cut(&mut self.samples);
+        for side in self.sides.iter_mut().flatten() {
+            cut(side);
+        }
+        Ok(())
+    }
+}
+
+/// Track metadata read from the input's tags (ID3, Vorbis comments, ...).
+/// Empty when the file had none or was decoded through the FFmpeg fallback.
+#[derive(Debug, Default)]
+pub struct AudioTags {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub replay_gain: ReplayGain,
+    /// Embedded picture, still encoded (JPEG, PNG, ...): the front cover if
+    /// one is marked as such, otherwise the first.
+    pub cover_art: Option<Vec<u8>>,
+}
+
+/// ReplayGain tags: gains in dB and peaks as linear sample amplitude.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ReplayGain {
+    pub track_gain: Option<f32>,
+    pub track_peak: Option<f32>,
+    pub album_gain: Option<f32>,
+    pub album_peak: Option<f32>,
+}
+
+impl ReplayGain {
+    /// Linear gain for `mode` ("track" or "album", each falling back to the
+    /// other), reduced if needed so the tagged peak doesn't exceed full scale.
+    pub fn linear_gain(&self, mode: &str) -> Option<f32> {
+        let track = self.track_gain.map(|gain| (gain, self.track_peak));
+        let album = self.album_gain.map(|gain| (gain, self.album_peak));
+        let (gain_db, peak) = match mode {
+            "album" => album.or(track),
+            _ => track.or(album),
+        }?;
+
+        let gain = 10f32.powf(gain_db / 20.0);
+        Some(match peak {
+            Some(peak) if peak > 0.0 => gain.min(1.0 / peak),
+            _ => gain,
+        })
+    }
+}
+
+/// Parse a ReplayGain value such as `-6.54 dB` or `0.988525`.
+fn parse_replay_gain_value(value: &str) -> Option<f32> {
+    let value = value.trim();
+    let number = value
+        .strip_suffix("dB")
+        .or_else(|| value.strip_suffix("db"))
+        .unwrap_or(value);
+    number.trim().parse().ok().filter(|v: &f32| v.is_finite())
+}
+
+impl AudioTags {
+    fn from_revision(revision: &MetadataRevision) -> Self {
+        let mut tags = Self::default();
+        let mut album_artist = None;
+        for tag in &revision.media.tags {
+            match &tag.std {
+                Some(StandardTag::TrackTitle(title)) => tags.title = Some(title.to_string()),
+                Some(StandardTag::Artist(artist)) => tags.artist = Some(artist.to_string()),
+                Some(StandardTag::AlbumArtist(artist)) => album_artist = Some(artist.to_string()),
+                Some(StandardTag::ReplayGainTrackGain(v)) => tags.replay_gain.track_gain = parse_replay_gain_value(v),
+                Some(StandardTag::ReplayGainTrackPeak(v)) => tags.replay_gain.track_peak = parse_replay_gain_value(v),
+                Some(StandardTag::ReplayGainAlbumGain(v)) => tags.replay_gain.album_gain = parse_replay_gain_value(v),
+                Some(StandardTag::ReplayGainAlbumPeak(v)) => tags.replay_gain.album_peak = parse_replay_gain_value(v),
+                _ => {}
+            }
+        }
+        tags.artist = tags.artist.or(album_artist);
+        let visuals = &revision.media.visuals;
+        tags.cover_art = visuals
+            .iter()
+            .find(|visual| visual.usage == Some(StandardVisualKey::FrontCover))
+            .or(visuals.first())
+            .map(|visual| visual.data.to_vec());
+        tags
+    }
+
+    /// Title overlay text: "Artist — Title", or whichever of the two is set.
+    pub fn display_title(&self) -> Option<String> {
+        let title = self.title.as_deref().map(str::trim).filter(|t| !t.is_empty());
+        let artist = self.artist.as_deref().map(str::trim).filter(|a| !a.is_empty());
+        match (artist, title) {
+            (Some(artist), Some(title)) => Some(format!("{artist} — {title}")),
+            (None, Some(only)) | (Some(only), None) => Some(only.to_string()),
+            (None, None) => None,
+        }
+    }
 }
 
-pub fn decode_audio(path: &Path) -> Result<AudioData> {
-    let audio = match decode_with_symphonia(path) {
+/// How multichannel audio is folded to the mono signal that gets analyzed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Downmix {
+    /// Arithmetic mean of all channels.
+    Average,
+    /// Root mean square of all channels, signed like their sum. Keeps
+    /// out-of-phase stereo content from cancelling out.
+    Rms,
+    /// Sum of the left and right channels.
+    Mid,
+    Left,
+    Right,
+}
+
+impl Downmix {
+    pub fn parse(name: &str) -> Result<Self> {
+        match name {
+            "average" => Ok(Self::Average),
+            "rms" => Ok(Self::Rms),
+            "mid" => Ok(Self::Mid),
+            "left" => Ok(Self::Left),
+            "right" => Ok(Self::Right),
+            other => anyhow::bail!(
+                "Unknown downmix '{}'. Valid modes: average, rms, mid, left, right",
+                other
+            ),
+        }
+    }
+
+}
+
+/// Decode to the mono analysis signal; with `keep_sides`, also keep the left
+/// and right channels in `AudioData::sides`.
+pub fn decode_audio(
+    path: &Path,
+    downmix: Downmix,
+    selection: &ChannelSelection,
+    keep_sides: bool,
+) -> crate::error::Result<AudioData> {
+    let audio = match decode_with_symphonia(path, downmix, selection, keep_sides) {
         Ok(audio) => audio,
         Err(symphonia_error) => {
             log::warn!(
@@ -21,12 +220,13 @@ pub fn decode_audio(path: &Path) -> Result<AudioData> {
                 path.display(),
                 symphonia_error
             );
-            decode_with_ffmpeg(path).map_err(|ffmpeg_error| {
-                anyhow!(
-                    "Failed to decode audio with both Symphonia and FFmpeg.\n\
+            decode_with_ffmpeg(path, downmix, selection, keep_sides).map_err(|ffmpeg_error| SonicaError::Decode {
+                path: path.to_path_buf(),
+                source: anyhow!(
+                    "Failed with both Symphonia and FFmpeg.\n\
                      Symphonia: {symphonia_error:#}\n\
                      FFmpeg: {ffmpeg_error:#}"
-                )
+                ),
             })?
         }
     };
@@ -41,7 +241,137 @@ pub fn decode_audio(path: &Path) -> Result<AudioData> {
     Ok(audio)
 }
 
-fn decode_with_symphonia(path: &Path) -> Result<AudioData> {
+/// Sample encoding of `--raw-input` PCM.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RawSampleFormat {
+    /// 32-bit float, little-endian.
+    F32le,
+    /// Signed 16-bit integer, little-endian.
+    S16le,
+}
+
+impl RawSampleFormat {
+    pub fn parse(name: &str) -> Result<Self> {
+        match name {
+            "f32le" => Ok(Self::F32le),
+            "s16le" => Ok(Self::S16le),
+            other => anyhow::bail!("Unknown raw sample format '{}'. Valid formats: f32le, s16le", other),
+        }
+    }
+
+    /// FFmpeg's `-f` demuxer for the format, which is also its name.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::F32le => "f32le",
+            Self::S16le => "s16le",
+        }
+    }
+
+    fn bytes(self) -> usize {
+        match self {
+            Self::F32le => 4,
+            Self::S16le => 2,
+        }
+    }
+
+    fn sample(self, bytes: &[u8]) -> f32 {
+        match self {
+            Self::F32le => f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            Self::S16le => i16::from_le_bytes([bytes[0], bytes[1]]) as f32 / 32768.0,
+        }
+    }
+}
+
+/// `--raw-input`: headerless interleaved PCM, which has no header to probe,
+/// so the sample rate and channel count are given instead.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RawPcm {
+    pub format: RawSampleFormat,
+    pub sample_rate: u32,
+    pub channels: usize,
+}
+
+impl RawPcm {
+    /// FFmpeg input options that read the file the same way, for muxing it
+    /// as the soundtrack.
+    pub fn ffmpeg_input_args(&self) -> [String; 6] {
+        [
+            "-f".into(),
+            self.format.name().into(),
+            "-ar".into(),
+            self.sample_rate.to_string(),
+            "-ac".into(),
+            self.channels.to_string(),
+        ]
+    }
+}
+
+/// Read headerless PCM straight into `AudioData`, bypassing Symphonia and
+/// FFmpeg probing. Raw dumps carry no tags.
+pub fn decode_raw(
+    path: &Path,
+    raw: RawPcm,
+    downmix: Downmix,
+    selection: &ChannelSelection,
+    keep_sides: bool,
+) -> crate::error::Result<AudioData> {
+    let decode = || -> Result<AudioData> {
+        let bytes = std::fs::read(path).with_context(|| format!("Failed to read raw audio: {}", path.display()))?;
+        let frame_size = raw.format.bytes() * raw.channels;
+        if bytes.len() % frame_size != 0 {
+            anyhow::bail!(
+                "{} bytes is not a whole number of {}-byte frames ({} channel(s) of {}); check --raw-input and --raw-channels",
+                bytes.len(),
+                frame_size,
+                raw.channels,
+                raw.format.name()
+            );
+        }
+        let fold = selection.resolve(ChannelLayout::discrete(raw.channels), downmix)?;
+        let interleaved: Vec<f32> =
+            bytes.chunks_exact(raw.format.bytes()).map(|sample| raw.format.sample(sample)).collect();
+        let sides = (keep_sides && raw.channels > 1).then(|| {
+            let (left, right) = interleaved
+                .chunks_exact(raw.channels)
+                .map(|frame| {
+                    let [left, right] = fold.sides(frame);
+                    (left, right)
+                })
+                .unzip();
+            [left, right]
+        });
+        Ok(AudioData {
+            samples: interleaved.chunks_exact(raw.channels).map(|frame| fold.apply(frame)).collect(),
+            sides,
+            sample_rate: raw.sample_rate,
+            tags: AudioTags::default(),
+        })
+    };
+    let audio = decode().map_err(|source| SonicaError::Decode { path: path.to_path_buf(), source })?;
+
+    log::info!(
+        "Read raw {} audio: {} samples, {}Hz, {:.1}s",
+        raw.format.name(),
+        audio.samples.len(),
+        audio.sample_rate,
+        audio.samples.len() as f32 / audio.sample_rate as f32
+    );
+
+    Ok(audio)
+}
+
+/// Largest share of packets that may fail to decode before Symphonia gives
+/// up on the file (and `decode_audio` falls back to FFmpeg). A few corrupt
+/// packets are bridged with silence; more than this and the timing can't be
+/// trusted.
+const MAX_DROPPED_PACKET_FRACTION: f32 = 0.02;
+
+fn decode_with_symphonia(
+    path: &Path,
+    downmix: Downmix,
+    selection: &ChannelSelection,
+    keep_sides: bool,
+) -> Result<AudioData> {
     let file = std::fs::File::open(path)
         .with_context(|| format!("Failed to open audio file: {}", path.display()))?;
 
@@ -56,11 +386,18 @@ fn decode_with_symphonia(path: &Path) -> Result<AudioData> {
         .probe(&hint, mss, FormatOptions::default(), MetadataOptions::default())
         .context("Failed to probe audio format")?;
 
+    let tags = format
+        .metadata()
+        .skip_to_latest()
+        .map(AudioTags::from_revision)
+        .unwrap_or_default();
+
     let track = format
         .default_track(TrackType::Audio)
         .context("No audio tracks found")?;
 
     let track_id = track.id;
+    let time_base = track.time_base;
     let codec_params = track
         .codec_params
         .as_ref()
@@ -69,48 +406,138 @@ fn decode_with_symphonia(path: &Path) -> Result<AudioData> {
 
     let channels = codec_params.channels.as_ref().map_or(1, |c| c.count());
     let sample_rate = codec_params.sample_rate.context("Unknown sample rate")?;
+    let layout = match codec_params.channels {
+        Some(ref declared) => ChannelLayout::from_symphonia(declared, channels),
+        None => ChannelLayout::from_symphonia(&Default::default(), channels),
+    };
+    if channels > 2 || selection.has_subset() || selection.layout_override().is_some() {
+        log::info!("Channel layout: {}", selection.layout_override().unwrap_or(&layout));
+    }
+    let fold = selection.resolve(layout, downmix)?;
 
     let mut decoder = symphonia::default::get_codecs()
         .make_audio_decoder(codec_params, &AudioDecoderOptions::default())
         .context("Failed to create audio decoder")?;
 
     let mut all_samples: Vec<f32> = Vec::new();
+    let mut sides = (keep_sides && channels > 1).then(|| [Vec::new(), Vec::new()]);
     let mut packet_samples: Vec<f32> = Vec::new();
+    let mut first_pts = None;
+    let mut packets = 0usize;
+    let mut dropped = 0usize;
+    let mut gap_pending = false;
+    let mut filled = 0usize;
 
     while let Some(packet) = format.next_packet()? {
         if packet.track_id != track_id {
             continue;
         }
+        packets += 1;
+        let first_pts = *first_pts.get_or_insert(packet.pts.get());
 
         let decoded = match decoder.decode(&packet) {
             Ok(d) => d,
-            Err(symphonia::core::errors::Error::DecodeError(_)) => continue,
+            Err(symphonia::core::errors::Error::DecodeError(err)) => {
+                log::debug!("Dropped undecodable packet at ts {}: {}", packet.pts, err);
+                dropped += 1;
+                gap_pending = true;
+                continue;
+            }
             Err(e) => return Err(e.into()),
         };
 
+        // After dropped packets, place this one by its timestamp instead of
+        // appending, so the rest of the track doesn't shift earlier.
+        if std::mem::take(&mut gap_pending) {
+            if let Some(time_base) = time_base {
+                let position = sample_position(packet.pts.get() - first_pts, time_base, sample_rate);
+                filled += pad_to(&mut all_samples, position);
+                for side in sides.iter_mut().flatten() {
+                    pad_to(side, position);
+                }
+            }
+        }
+
         decoded.copy_to_vec_interleaved(&mut packet_samples);
 
         // Downmix to mono
         if channels == 1 {
             all_samples.extend_from_slice(&packet_samples);
         } else {
-            for frame_samples in packet_samples.chunks(channels) {
-                let mono: f32 = frame_samples.iter().sum::<f32>() / channels as f32;
-                all_samples.push(mono);
+            all_samples.extend(packet_samples.chunks(channels).map(|frame| fold.apply(frame)));
+        }
+        if let Some([left, right]) = sides.as_mut() {
+            for frame in packet_samples.chunks(channels) {
+                let [l, r] = fold.sides(frame);
+                left.push(l);
+                right.push(r);
             }
         }
     }
 
+    if dropped > 0 {
+        let fraction = dropped as f32 / packets as f32;
+        if fraction > MAX_DROPPED_PACKET_FRACTION {
+            anyhow::bail!(
+                "{} of {} packets ({:.1}%) failed to decode; the stream is too damaged to analyze reliably",
+                dropped,
+                packets,
+                fraction * 100.0
+            );
+        }
+        log::warn!(
+            "Dropped {} of {} undecodable packets; filled {:.2}s with silence to keep the visuals in sync",
+            dropped,
+            packets,
+            filled as f32 / sample_rate as f32
+        );
+    }
+
     Ok(AudioData {
         samples: all_samples,
+        sides,
         sample_rate,
+        tags,
     })
 }
 
+/// Sample index `ticks` time-base units into the stream.
+fn sample_position(ticks: i64, time_base: TimeBase, sample_rate: u32) -> usize {
+    let position = ticks as i128 * time_base.numer.get() as i128 * sample_rate as i128
+        / time_base.denom.get() as i128;
+    position.max(0) as usize
+}
+
+/// Pad `samples` with silence up to `position`, returning how many samples
+/// were added. Never truncates.
+fn pad_to(samples: &mut Vec<f32>, position: usize) -> usize {
+    let missing = position.saturating_sub(samples.len());
+    samples.resize(samples.len() + missing, 0.0);
+    missing
+}
+
 const FFMPEG_FALLBACK_SAMPLE_RATE: u32 = 48_000;
 
-fn decode_with_ffmpeg(path: &Path) -> Result<AudioData> {
+fn decode_with_ffmpeg(
+    path: &Path,
+    downmix: Downmix,
+    selection: &ChannelSelection,
+    keep_sides: bool,
+) -> Result<AudioData> {
     let sample_rate = FFMPEG_FALLBACK_SAMPLE_RATE.to_string();
+    // FFmpeg reads the layout itself: a `--channels` subset is extracted with
+    // its pan filter, and its own mono fold already follows the surround
+    // coefficients. Other modes fold a stereo stream here.
+    if selection.layout_override().is_some() {
+        log::warn!("--channel-layout is ignored by the FFmpeg decoder, which reads the layout from the file");
+    }
+    let (channels, channel_args) = match selection.ffmpeg_pan() {
+        Some(pan) => (selection.subset_len(), vec!["-af".to_string(), pan]),
+        None if downmix == Downmix::Average && !keep_sides => (1, vec!["-ac".to_string(), "1".to_string()]),
+        None => (2, vec!["-ac".to_string(), "2".to_string()]),
+    };
+    // The picked channels arrive in order, so they fold with equal weight.
+    let fold = Fold::flat(downmix, channels);
     let output = Command::new("ffmpeg")
         .args([
             "-hide_banner",
@@ -120,10 +547,9 @@ fn decode_with_ffmpeg(path: &Path) -> Result<AudioData> {
             "-i",
         ])
         .arg(path)
+        .arg("-vn")
+        .args(&channel_args)
         .args([
-            "-vn",
-            "-ac",
-            "1",
             "-ar",
             &sample_rate,
             "-f",
@@ -140,14 +566,25 @@ fn decode_with_ffmpeg(path: &Path) -> Result<AudioData> {
         anyhow::bail!("FFmpeg audio decoder exited with an error:\n{stderr}");
     }
 
-    let samples = parse_f32le(&output.stdout)?;
+    let mut samples = parse_f32le(&output.stdout)?;
+    let mut sides = None;
+    if channels > 1 {
+        if keep_sides {
+            sides = Some([0, 1].map(|side| {
+                samples.chunks_exact(channels).map(|frame| fold.sides(frame)[side]).collect()
+            }));
+        }
+        samples = samples.chunks_exact(channels).map(|frame| fold.apply(frame)).collect();
+    }
     if samples.is_empty() {
         anyhow::bail!("FFmpeg audio decoder returned no samples");
     }
 
     Ok(AudioData {
         samples,
+        sides,
         sample_rate: FFMPEG_FALLBACK_SAMPLE_RATE,
+        tags: AudioTags::default(),
     })
 }
 
@@ -166,6 +603,25 @@ fn parse_f32le(bytes: &[u8]) -> Result<Vec<f32>> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn dropped_packets_are_bridged_by_timestamp() {
+        // 1/44100 time base, as most audio tracks use: ticks are samples.
+        let sample_tick = TimeBase::try_new(1, 44_100).unwrap();
+        assert_eq!(sample_position(2048, sample_tick, 44_100), 2048);
+        // A millisecond time base (Matroska) at 48kHz.
+        let millis = TimeBase::try_new(1, 1000).unwrap();
+        assert_eq!(sample_position(1500, millis, 48_000), 72_000);
+        assert_eq!(sample_position(-20, millis, 48_000), 0);
+
+        // Packets 0 and 1 decoded (1024 samples each), packet 2 dropped:
+        // packet 3 belongs at 3072, not 2048.
+        let mut samples = vec![0.5f32; 2048];
+        assert_eq!(pad_to(&mut samples, 3072), 1024);
+        assert_eq!(samples.len(), 3072);
+        assert_eq!(samples[2048], 0.0);
+        assert_eq!(pad_to(&mut samples, 1000), 0, "never truncates");
+    }
+
     #[test]
     fn parses_little_endian_float_samples() {
         let expected = [-1.0f32, -0.25, 0.0, 0.5, 1.0];
@@ -179,6 +635,158 @@ mod tests {
         assert_eq!(samples, expected);
     }
 
+    #[test]
+    fn reads_raw_pcm_by_the_given_format() {
+        let path = std::env::temp_dir().join(format!("sonica-raw-test-{}.pcm", std::process::id()));
+        // Two stereo s16le frames: (0.5, -0.5) and (0.25, 0.25).
+        let bytes: Vec<u8> = [16384i16, -16384, 8192, 8192].iter().flat_map(|s| s.to_le_bytes()).collect();
+        std::fs::write(&path, &bytes).unwrap();
+        let raw = RawPcm { format: RawSampleFormat::S16le, sample_rate: 8000, channels: 2 };
+
+        let audio = decode_raw(&path, raw, Downmix::Average, &ChannelSelection::default(), true).unwrap();
+        assert_eq!(audio.samples, [0.0, 0.25]);
+        assert_eq!(audio.sides, Some([vec![0.5, 0.25], vec![-0.5, 0.25]]));
+        assert_eq!(audio.sample_rate, 8000);
+
+        let left = decode_raw(&path, raw, Downmix::Left, &ChannelSelection::default(), false).unwrap();
+        assert_eq!(left.samples, [0.5, 0.25]);
+
+        // Six bytes are one and a half f32le stereo frames.
+        std::fs::write(&path, &bytes[..6]).unwrap();
+        let f32_stereo = RawPcm { format: RawSampleFormat::F32le, ..raw };
+        let err = decode_raw(&path, f32_stereo, Downmix::Average, &ChannelSelection::default(), false);
+        std::fs::remove_file(&path).unwrap();
+        let Err(SonicaError::Decode { source, .. }) = err else { panic!("read a partial frame") };
+        assert!(source.to_string().contains("not a whole number"), "{source}");
+        assert!(RawSampleFormat::parse("u8").is_err());
+    }
+
+    #[test]
+    fn repeats_to_fill_the_target_length() {
+        let mut audio = AudioData {
+            samples: vec![1.0, 2.0, 3.0],
+            sides: Some([vec![1.0, 2.0, 3.0], vec![-1.0, -2.0, -3.0]]),
+            sample_rate: 2,
+            tags: AudioTags::default(),
+        };
+        audio.repeat_to(4.0);
+        assert_eq!(audio.samples, [1.0, 2.0, 3.0, 1.0, 2.0, 3.0, 1.0, 2.0]);
+        assert_eq!(audio.sides.as_ref().unwrap()[1][3..5], [-1.0, -2.0]);
+
+        audio.repeat_to(1.0);
+        assert_eq!(audio.samples, [1.0, 2.0]);
+    }
+
+    #[test]
+    fn clips_to_the_time_window() {
+        let track = || AudioData {
+            samples: (0..10).map(|i| i as f32).collect(),
+            sides: Some([(0..10).map(|i| i as f32).collect(), (0..10).map(|i| -i as f32).collect()]),
+            sample_rate: 2,
+            tags: AudioTags::default(),
+        };
+        let mut audio = track();
+        audio.clip(1.0, Some(3.5)).unwrap();
+        assert_eq!(audio.samples, [2.0, 3.0, 4.0, 5.0, 6.0]);
+        assert_eq!(audio.sides.as_ref().unwrap()[1], [-2.0, -3.0, -4.0, -5.0, -6.0]);
+
+        // An end past the track is clamped to it.
+        let mut audio = track();
+        audio.clip(4.0, Some(60.0)).unwrap();
+        assert_eq!(audio.samples, [8.0, 9.0]);
+
+        assert!(track().clip(3.0, Some(3.0)).is_err());
+        assert!(track().clip(5.0, None).is_err(), "start at the end of the track");
+    }
+
+    #[test]
+    fn missing_input_is_a_decode_error() {
+        let path = Path::new("does-not-exist.wav");
+        let Err(err) = decode_audio(path, Downmix::Average, &ChannelSelection::default(), false) else {
+            panic!("decoded a missing file");
+        };
+        assert!(matches!(err, SonicaError::Decode { ref path, .. } if path.ends_with("does-not-exist.wav")));
+    }
+
+    #[test]
+    fn formats_title_from_tags() {
+        let tags = AudioTags {
+            title: Some("Windowlicker".into()),
+            artist: Some("Aphex Twin".into()),
+            ..AudioTags::default()
+        };
+        assert_eq!(tags.display_title().as_deref(), Some("Aphex Twin — Windowlicker"));
+
+        let title_only = AudioTags {
+            title: Some("Untitled".into()),
+            artist: Some("  ".into()),
+            ..AudioTags::default()
+        };
+        assert_eq!(title_only.display_title().as_deref(), Some("Untitled"));
+        assert_eq!(AudioTags::default().display_title(), None);
+    }
+
+    #[test]
+    fn downmixes_average() {
+        assert_eq!(Fold::flat(Downmix::Average, 2).apply(&[0.5, -0.5]), 0.0);
+        assert_eq!(Fold::flat(Downmix::Average, 2).apply(&[0.2, 0.6]), 0.4);
+    }
+
+    #[test]
+    fn downmixes_rms() {
+        // Fully out-of-phase stereo keeps its energy instead of cancelling.
+        assert!((Fold::flat(Downmix::Rms, 2).apply(&[0.5, -0.5]) - 0.5).abs() < 1e-6);
+        assert!((Fold::flat(Downmix::Rms, 2).apply(&[-0.6, -0.8]) + 0.5f32.sqrt()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn downmixes_mid() {
+        assert_eq!(Fold::flat(Downmix::Mid, 2).apply(&[0.25, 0.5]), 0.75);
+        assert_eq!(Fold::flat(Downmix::Mid, 2).apply(&[0.5, -0.5]), 0.0);
+    }
+
+    #[test]
+    fn downmixes_left_and_right() {
+        assert_eq!(Fold::flat(Downmix::Left, 2).apply(&[0.25, 0.5]), 0.25);
+        assert_eq!(Fold::flat(Downmix::Right, 2).apply(&[0.25, 0.5]), 0.5);
+        assert_eq!(Fold::flat(Downmix::Right, 1).apply(&[0.25]), 0.25);
+    }
+
+    #[test]
+    fn rejects_unknown_downmix() {
+        assert_eq!(Downmix::parse("mid").unwrap(), Downmix::Mid);
+        assert!(Downmix::parse("side").is_err());
+    }
+
+    #[test]
+    fn parses_replay_gain_values() {
+        assert_eq!(parse_replay_gain_value("-6.54 dB"), Some(-6.54));
+        assert_eq!(parse_replay_gain_value("+2.00 dB"), Some(2.0));
+        assert_eq!(parse_replay_gain_value("0.988525"), Some(0.988525));
+        assert_eq!(parse_replay_gain_value("loud"), None);
+    }
+
+    #[test]
+    fn replay_gain_falls_back_and_respects_peak() {
+        let gain = ReplayGain {
+            track_gain: Some(-6.0),
+            track_peak: Some(0.9),
+            album_gain: None,
+            album_peak: None,
+        };
+        assert!((gain.linear_gain("track").unwrap() - 0.501).abs() < 1e-3);
+        assert_eq!(gain.linear_gain("album"), gain.linear_gain("track"));
+
+        // +12 dB would push a 0.5 peak to 2.0, so it is capped at 2x.
+        let loud = ReplayGain {
+            album_gain: Some(12.0),
+            album_peak: Some(0.5),
+            ..ReplayGain::default()
+        };
+        assert_eq!(loud.linear_gain("album"), Some(2.0));
+        assert_eq!(ReplayGain::default().linear_gain("track"), None);
+    }
+
     #[test]
     fn rejects_truncated_float_stream() {
         assert!(parse_f32le(&[0, 1, 2]).is_err());
@@ -0,0 +1,107 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use super::features::{GlobalAnalysis, SmoothedFrame};
+use crate::framerate::FrameRate;
+
+/// Version of the `--dump-analysis` layout. Bump it when a field is renamed
+/// or removed or changes meaning; adding fields keeps the version.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// The per-frame arrays left out of a dump unless `--dump-fft` asks for them:
+/// together they are most of a frame's size.
+const SPECTRUM_FIELDS: [&str; 4] = ["fft_bins", "waveform", "waveform_left", "waveform_right"];
+
+/// `--dump-analysis`: the features sonica renders from, for driving other
+/// visualizers. Field names are those of [`GlobalAnalysis`] and
+/// [`SmoothedFrame`].
+#[derive(Debug, Serialize)]
+struct AnalysisDump<'a> {
+    schema: u32,
+    sonica_version: &'static str,
+    /// `30` or `24000/1001`; frame N is at `frames[N].time`.
+    fps: String,
+    global: &'a GlobalAnalysis,
+    frames: Vec<serde_json::Value>,
+}
+
+/// Write the analysis as JSON to `path`. Without `spectra`, each frame's FFT
+/// bins and waveforms are omitted.
+pub fn write_analysis(
+    path: &Path,
+    fps: FrameRate,
+    global: &GlobalAnalysis,
+    frames: &[SmoothedFrame],
+    spectra: bool,
+) -> Result<()> {
+    let frames = frames
+        .iter()
+        .map(|frame| {
+            let mut value = serde_json::to_value(frame)?;
+            if let (false, Some(fields)) = (spectra, value.as_object_mut()) {
+                for field in SPECTRUM_FIELDS {
+                    fields.remove(field);
+                }
+            }
+            Ok(value)
+        })
+        .collect::<serde_json::Result<Vec<_>>>()?;
+    let dump = AnalysisDump {
+        schema: SCHEMA_VERSION,
+        sonica_version: env!("CARGO_PKG_VERSION"),
+        fps: fps.to_string(),
+        global,
+        frames,
+    };
+
+    let file = std::fs::File::create(path)
+        .with_context(|| format!("Failed to create analysis dump: {}", path.display()))?;
+    let mut writer = BufWriter::new(file);
+    serde_json::to_writer(&mut writer, &dump)?;
+    writer
+        .flush()
+        .with_context(|| format!("Failed to write analysis dump: {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::analysis::{analyze, AnalysisOptions};
+    use crate::audio::decode::{AudioData, AudioTags};
+
+    #[test]
+    fn dumps_a_versioned_schema_without_spectra_by_default() {
+        let audio = AudioData {
+            samples: (0..22_050).map(|i| (i as f32 * 0.05).sin() * 0.5).collect(),
+            sides: None,
+            sample_rate: 22_050,
+            tags: AudioTags::default(),
+        };
+        let fps = FrameRate::integer(10);
+        let (global, frames) = analyze(&audio, fps, &AnalysisOptions::default()).unwrap();
+
+        let path = std::env::temp_dir().join(format!("sonica-analysis-test-{}.json", std::process::id()));
+        let read = |spectra| {
+            write_analysis(&path, fps, &global, &frames, spectra).unwrap();
+            serde_json::from_str::<serde_json::Value>(&std::fs::read_to_string(&path).unwrap()).unwrap()
+        };
+
+        let dump = read(false);
+        assert_eq!(dump["schema"], 1);
+        assert_eq!(dump["fps"], "10");
+        assert_eq!(dump["global"]["sample_rate"], 22_050);
+        assert!(dump["global"]["beat_times"].is_array());
+        let dumped = dump["frames"].as_array().unwrap();
+        assert_eq!(dumped.len(), frames.len());
+        for field in ["time", "rms", "bass", "mid", "high", "spectral_centroid", "spectral_flux", "is_beat", "beat_phase"] {
+            assert!(dumped[3].get(field).is_some(), "missing {field}");
+        }
+        assert!(dumped[3].get("fft_bins").is_none() && dumped[3].get("waveform").is_none());
+
+        let full = read(true);
+        assert_eq!(full["frames"][3]["fft_bins"].as_array().unwrap().len(), frames[3].fft_bins.len());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
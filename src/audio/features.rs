@@ -1,4 +1,6 @@
-#[derive(Clone, Debug)]
+use serde::Serialize;
+
+#[derive(Clone, Debug, Default, Serialize)]
 pub struct FrameFeatures {
     /// FFT magnitude bins (N/2 elements, linear scale)
     pub fft_bins: Vec<f32>,
@@ -10,19 +12,26 @@ pub struct FrameFeatures {
     pub upper_mid: f32,  // 2-4 kHz
     pub presence: f32,   // 4-6 kHz
     pub brilliance: f32, // 6-20 kHz
+    /// The seven bands of the left and right channels; both equal the bands
+    /// above unless stereo bands were measured
+    pub side_bands: [[f32; 7]; 2],
     /// RMS energy (linear)
     pub rms: f32,
     /// Spectral centroid (Hz)
     pub spectral_centroid: f32,
+    /// Frequency of the strongest bin (Hz), 0 for silent frames
+    pub dominant_frequency: f32,
     /// Spectral flux (change from previous frame)
     #[allow(dead_code)]
     pub spectral_flux: f32,
-    /// Raw waveform samples for this frame
+    /// Waveform of this frame as interleaved `[min, max]` pairs
     pub waveform: Vec<f32>,
+    /// Waveforms of the left and right channels, when measured
+    pub side_waveforms: Option<[Vec<f32>; 2]>,
 }
 
 /// Smoothed and normalized per-frame data (Pass 3 output), ready for GPU
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct SmoothedFrame {
     /// FFT magnitude bins, smoothed and normalized (0.0-1.0)
     pub fft_bins: Vec<f32>,
@@ -30,27 +39,43 @@ pub struct SmoothedFrame {
     pub bass: f32,
     pub mid: f32,
     pub high: f32,
+    /// The seven band energies (sub_bass through brilliance) of the left and
+    /// right channels, smoothed and normalized (0.0-1.0). Equal for mono input.
+    pub bands_left: [f32; 7],
+    pub bands_right: [f32; 7],
     /// RMS energy, normalized (0.0-1.0)
     pub rms: f32,
     /// Spectral centroid, normalized (0.0-1.0)
     pub spectral_centroid: f32,
     /// Spectral flux, normalized (0.0-1.0)
     pub spectral_flux: f32,
+    /// Spectral centroid (Hz), 0 for silent frames
+    pub spectral_centroid_hz: f32,
+    /// Spectral flux (summed FFT magnitude increase), not normalized
+    pub spectral_flux_raw: f32,
+    /// Onset strength: the spectral flux low-pass filtered into a continuous
+    /// envelope, normalized (0.0-1.0)
+    pub onset_strength: f32,
+    /// Frequency of the loudest bin between A0 and C8 (Hz), 0 for silent frames
+    pub dominant_frequency_hz: f32,
     /// Beat intensity (1.0 at onset, exponential decay)
     pub beat_intensity: f32,
     /// Beat phase (0.0-1.0 within current beat interval)
     pub beat_phase: f32,
     /// Is this frame on a beat onset?
     pub is_beat: bool,
-    /// Waveform samples for this frame
+    /// Waveform of this frame as interleaved `[min, max]` pairs
     pub waveform: Vec<f32>,
+    /// Waveforms of the left and right channels, laid out like `waveform`.
+    /// Empty unless stereo waveforms were measured.
+    pub waveform_left: Vec<f32>,
+    pub waveform_right: Vec<f32>,
     /// Time in seconds
     pub time: f32,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct GlobalAnalysis {
-    #[allow(dead_code)]
     pub sample_rate: u32,
     #[allow(dead_code)]
     pub total_samples: usize,
@@ -59,6 +84,5 @@ pub struct GlobalAnalysis {
     #[allow(dead_code)]
     pub peak_amplitude: f32,
     pub beat_times: Vec<f32>,
-    #[allow(dead_code)]
     pub tempo_bpm: f32,
 }
@@ -0,0 +1,174 @@
+//! Integrated loudness after ITU-R BS.1770 / EBU R128: K-weighting, 400 ms
+//! blocks overlapping by 75%, an absolute gate at -70 LUFS and a relative
+//! gate 10 LU below the ungated level.
+
+/// Blocks quieter than this never count, so silence doesn't pull the
+/// measurement down.
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+
+/// Blocks this far below the level of the blocks that passed the absolute
+/// gate are dropped too.
+const RELATIVE_GATE_LU: f64 = 10.0;
+
+const BLOCK_SECONDS: f64 = 0.4;
+const STEP_SECONDS: f64 = 0.1;
+
+/// Integrated loudness in LUFS of `channels`, equally long and each K-weighted
+/// on its own, with their block powers summed at BS.1770's weight of 1 for
+/// left, right and centre. `None` when the audio is shorter than one 400 ms
+/// block or every block is gated out as silence.
+pub fn integrated_loudness(channels: &[&[f32]], sample_rate: u32) -> Option<f32> {
+    let block = (BLOCK_SECONDS * sample_rate as f64).round() as usize;
+    let step = (STEP_SECONDS * sample_rate as f64).round() as usize;
+    let len = channels.iter().map(|channel| channel.len()).min().unwrap_or(0);
+    if block == 0 || len < block {
+        return None;
+    }
+
+    // Running sum of the K-weighted signals' squares, so each block's summed
+    // mean square is one subtraction.
+    let mut energy = vec![0.0f64; len + 1];
+    for channel in channels {
+        let mut shelf = Biquad::high_shelf(sample_rate);
+        let mut high_pass = Biquad::high_pass(sample_rate);
+        let mut total = 0.0f64;
+        for (i, &sample) in channel[..len].iter().enumerate() {
+            let weighted = high_pass.process(shelf.process(sample as f64));
+            total += weighted * weighted;
+            energy[i + 1] += total;
+        }
+    }
+    let powers: Vec<f64> = (0..=(len - block) / step)
+        .map(|i| (energy[i * step + block] - energy[i * step]) / block as f64)
+        .collect();
+
+    let gated_mean = |threshold: f64| {
+        let kept: Vec<f64> = powers.iter().copied().filter(|&power| loudness(power) > threshold).collect();
+        (!kept.is_empty()).then(|| kept.iter().sum::<f64>() / kept.len() as f64)
+    };
+    let ungated = gated_mean(ABSOLUTE_GATE_LUFS)?;
+    let relative_gate = loudness(ungated) - RELATIVE_GATE_LU;
+    let integrated = gated_mean(relative_gate.max(ABSOLUTE_GATE_LUFS))?;
+    Some(loudness(integrated) as f32)
+}
+
+/// Linear gain that brings audio measured at `loudness` LUFS to `target`.
+pub fn gain_to(loudness: f32, target: f32) -> f32 {
+    10f32.powf((target - loudness) / 20.0)
+}
+
+/// LUFS of a K-weighted mean square.
+fn loudness(power: f64) -> f64 {
+    -0.691 + 10.0 * power.log10()
+}
+
+/// Direct form I biquad, coefficients normalized by `a0`.
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    x: [f64; 2],
+    y: [f64; 2],
+}
+
+impl Biquad {
+    /// BS.1770's first K-weighting stage, a +4 dB shelf above ~1.7 kHz
+    /// modelling the head. Designed for `sample_rate` from the analog
+    /// prototype behind the standard's 48 kHz coefficients, as libebur128
+    /// does, so it matches them at 48 kHz.
+    fn high_shelf(sample_rate: u32) -> Self {
+        let (gain_db, f0, q) = (3.999_843_853_973_347, 1_681.974_450_955_533, 0.707_175_236_955_419_6);
+        let k = Self::prewarp(sample_rate, f0);
+        let vh = 10f64.powf(gain_db / 20.0);
+        let vb = vh.powf(0.499_666_774_154_541_6);
+        Self::new(
+            [vh + vb * k / q + k * k, 2.0 * (k * k - vh), vh - vb * k / q + k * k],
+            [1.0 + k / q + k * k, 2.0 * (k * k - 1.0), 1.0 - k / q + k * k],
+        )
+    }
+
+    /// BS.1770's second stage, the RLB high-pass at ~38 Hz. The standard's
+    /// numerator is `[1, -2, 1]`, unscaled by `a0`.
+    fn high_pass(sample_rate: u32) -> Self {
+        let (f0, q) = (38.135_470_876_024_44, 0.500_327_037_323_877_3);
+        let k = Self::prewarp(sample_rate, f0);
+        let a0 = 1.0 + k / q + k * k;
+        Self::new([a0, -2.0 * a0, a0], [a0, 2.0 * (k * k - 1.0), 1.0 - k / q + k * k])
+    }
+
+    /// Bilinear-transform frequency warping of `f0`.
+    fn prewarp(sample_rate: u32, f0: f64) -> f64 {
+        (std::f64::consts::PI * f0 / sample_rate as f64).tan()
+    }
+
+    fn new(b: [f64; 3], a: [f64; 3]) -> Self {
+        Self {
+            b: b.map(|b| b / a[0]),
+            a: [a[1] / a[0], a[2] / a[0]],
+            x: [0.0; 2],
+            y: [0.0; 2],
+        }
+    }
+
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b[0] * x + self.b[1] * self.x[0] + self.b[2] * self.x[1] - self.a[0] * self.y[0] - self.a[1] * self.y[1];
+        self.x = [x, self.x[0]];
+        self.y = [y, self.y[0]];
+        y
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(amplitude: f32, seconds: f32, sample_rate: u32) -> Vec<f32> {
+        (0..(seconds * sample_rate as f32) as usize)
+            .map(|i| amplitude * (2.0 * std::f32::consts::PI * 997.0 * i as f32 / sample_rate as f32).sin())
+            .collect()
+    }
+
+    #[test]
+    fn full_scale_sine_reads_minus_three_lufs() {
+        // BS.1770's reference: a 0 dBFS 997 Hz sine in one channel is -3.01 LUFS.
+        for sample_rate in [44_100, 48_000, 96_000] {
+            let loudness = integrated_loudness(&[&sine(1.0, 5.0, sample_rate)], sample_rate).unwrap();
+            assert!((loudness + 3.01).abs() < 0.05, "{sample_rate} Hz: {loudness}");
+        }
+        let quiet = integrated_loudness(&[&sine(0.1, 5.0, 48_000)], 48_000).unwrap();
+        assert!((quiet + 23.01).abs() < 0.05, "{quiet}");
+    }
+
+    #[test]
+    fn channels_sum_their_power() {
+        // The same full-scale sine in left and right is 3 dB louder than in
+        // one channel, where a mono downmix would read it as one.
+        let tone = sine(1.0, 5.0, 48_000);
+        let stereo = integrated_loudness(&[&tone, &tone], 48_000).unwrap();
+        assert!(stereo.abs() < 0.05, "{stereo}");
+
+        let silent = vec![0.0; tone.len()];
+        let one_sided = integrated_loudness(&[&tone, &silent], 48_000).unwrap();
+        assert!((one_sided + 3.01).abs() < 0.05, "{one_sided}");
+    }
+
+    #[test]
+    fn silence_is_gated_out() {
+        let mut samples = vec![0.0; 48_000 * 5];
+        samples.extend(sine(0.1, 5.0, 48_000));
+        // Only the few blocks straddling the start of the tone count below
+        // its level.
+        let loudness = integrated_loudness(&[&samples], 48_000).unwrap();
+        assert!((loudness + 23.01).abs() < 0.2, "{loudness}");
+
+        assert_eq!(integrated_loudness(&[&[0.0; 48_000]], 48_000), None);
+        assert_eq!(integrated_loudness(&[&[0.5; 100]], 48_000), None, "shorter than a block");
+    }
+
+    #[test]
+    fn gain_reaches_the_target() {
+        let samples = sine(0.05, 3.0, 48_000);
+        let gain = gain_to(integrated_loudness(&[&samples], 48_000).unwrap(), -14.0);
+        let louder: Vec<f32> = samples.iter().map(|s| s * gain).collect();
+        assert!((integrated_loudness(&[&louder], 48_000).unwrap() + 14.0).abs() < 0.01);
+    }
+}
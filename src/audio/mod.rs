@@ -1,3 +1,11 @@
+pub mod channels;
+pub mod cuesheet;
 pub mod decode;
+pub mod export;
 pub mod analysis;
+pub mod beatgrid;
 pub mod features;
+pub mod loudness;
+pub mod pitch;
+pub mod resample;
+pub mod source;
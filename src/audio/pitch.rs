@@ -0,0 +1,72 @@
+/// Lowest and highest frequencies considered for the dominant pitch: A0 to C8,
+/// the range of a piano.
+const MIN_PITCH_HZ: f32 = 27.5;
+const MAX_PITCH_HZ: f32 = 4186.0;
+
+const NOTE_NAMES: [&str; 12] = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
+
+/// Frequency of the strongest bin in the piano range, refined by fitting a
+/// parabola through it and its neighbours. 0 when every bin is at or below
+/// `floor`.
+pub fn dominant_frequency(bins: &[f32], freq_resolution: f32, floor: f32) -> f32 {
+    let low = ((MIN_PITCH_HZ / freq_resolution) as usize).max(1);
+    let high = ((MAX_PITCH_HZ / freq_resolution).ceil() as usize).min(bins.len().saturating_sub(1));
+    if low >= high {
+        return 0.0;
+    }
+    let Some((peak, &magnitude)) = bins[low..high]
+        .iter()
+        .enumerate()
+        .max_by(|a, b| a.1.total_cmp(b.1))
+        .map(|(i, m)| (i + low, m))
+    else {
+        return 0.0;
+    };
+    if magnitude <= floor {
+        return 0.0;
+    }
+
+    let (left, right) = (bins[peak - 1], bins[peak + 1]);
+    let curvature = left - 2.0 * magnitude + right;
+    let offset = if curvature < 0.0 { 0.5 * (left - right) / curvature } else { 0.0 };
+    (peak as f32 + offset.clamp(-0.5, 0.5)) * freq_resolution
+}
+
+/// Nearest equal-tempered note to `hz` with A4 = 440 Hz, e.g. "A4" or "C#3".
+pub fn note_name(hz: f32) -> Option<String> {
+    if !(hz.is_finite() && hz > 0.0) {
+        return None;
+    }
+    let midi = (69.0 + 12.0 * (hz / 440.0).log2()).round() as i32;
+    if !(0..=127).contains(&midi) {
+        return None;
+    }
+    Some(format!("{}{}", NOTE_NAMES[midi as usize % 12], midi / 12 - 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_equal_tempered_notes() {
+        assert_eq!(note_name(440.0).as_deref(), Some("A4"));
+        assert_eq!(note_name(261.63).as_deref(), Some("C4"));
+        assert_eq!(note_name(277.0).as_deref(), Some("C#4"));
+        assert_eq!(note_name(27.5).as_deref(), Some("A0"));
+        assert_eq!(note_name(0.0), None);
+    }
+
+    #[test]
+    fn interpolates_between_bins() {
+        // A peak between bins 20 and 21, nearer 21.
+        let mut bins = vec![0.0f32; 64];
+        bins[19] = 0.2;
+        bins[20] = 0.8;
+        bins[21] = 1.0;
+        bins[22] = 0.4;
+        let hz = dominant_frequency(&bins, 10.0, 0.01);
+        assert!(hz > 205.0 && hz < 215.0, "{hz}");
+        assert_eq!(dominant_frequency(&vec![0.0; 64], 10.0, 0.01), 0.0);
+    }
+}
@@ -0,0 +1,64 @@
+use anyhow::{Context, Result};
+
+/// Resample mono f32 audio from `from_rate` to `to_rate` Hz using rubato's
+/// sinc interpolator.
+pub fn resample(samples: &[f32], from_rate: u32, to_rate: u32) -> Result<Vec<f32>> {
+    use rubato::audioadapter_buffers::direct::SequentialSlice;
+    use rubato::{
+        Async, FixedAsync, Resampler, SincInterpolationParameters, SincInterpolationType,
+        WindowFunction,
+    };
+
+    if from_rate == to_rate || samples.is_empty() {
+        return Ok(samples.to_vec());
+    }
+
+    let params = SincInterpolationParameters {
+        sinc_len: 256,
+        f_cutoff: None, // automatic cutoff for the chosen sinc length and window
+        interpolation: SincInterpolationType::Linear,
+        oversampling_factor: 256,
+        window: WindowFunction::BlackmanHarris2,
+    };
+
+    let ratio = to_rate as f64 / from_rate as f64;
+    let mut resampler = Async::<f32>::new_sinc(
+        ratio,
+        2.0, // max relative ratio
+        &params,
+        1024, // chunk size in frames
+        1,    // mono
+        FixedAsync::Input,
+    )
+    .context("Failed to create resampler")?;
+
+    let input = SequentialSlice::new(samples, 1, samples.len())
+        .map_err(|e| anyhow::anyhow!("Failed to wrap input samples: {e}"))?;
+    let output = resampler
+        .process_all(&input, samples.len(), None)
+        .context("Resampling failed")?;
+
+    Ok(output.take_data())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_duration_and_pitch() {
+        // One second of 1 kHz at 48 kHz, resampled to 44.1 kHz.
+        let tone: Vec<f32> = (0..48_000)
+            .map(|i| (2.0 * std::f32::consts::PI * 1000.0 * i as f32 / 48_000.0).sin())
+            .collect();
+        let out = resample(&tone, 48_000, 44_100).unwrap();
+        assert!(out.len().abs_diff(44_100) <= 4, "{} samples", out.len());
+
+        // Count rising zero crossings in the settled middle of the output.
+        let middle = &out[4_410..39_690];
+        let crossings = middle.windows(2).filter(|w| w[0] < 0.0 && w[1] >= 0.0).count();
+        assert!((799..=801).contains(&crossings), "{crossings} crossings");
+
+        assert_eq!(resample(&tone[..10], 48_000, 48_000).unwrap(), &tone[..10]);
+    }
+}
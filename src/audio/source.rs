@@ -0,0 +1,126 @@
+use anyhow::{Context, Result};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::temp::TempFile;
+
+/// Where the input audio comes from: a file, `-` for stdin, or an http(s) URL.
+#[derive(Debug, PartialEq)]
+pub enum AudioSource {
+    File(PathBuf),
+    Stdin,
+    Url(String),
+}
+
+impl AudioSource {
+    pub fn parse(input: &Path) -> Self {
+        match input.to_str() {
+            Some("-") => Self::Stdin,
+            Some(url) if url.starts_with("http://") || url.starts_with("https://") => {
+                Self::Url(url.to_string())
+            }
+            _ => Self::File(input.to_path_buf()),
+        }
+    }
+
+    /// Make the audio available as a local file.
+    ///
+    /// Stdin and URLs are spooled to a temporary file rather than decoded as a
+    /// stream: FFmpeg reads the input a second time to mux the soundtrack, and
+    /// a seekable file lets Symphonia probe every format it supports.
+    pub fn open(&self) -> Result<LocalAudio> {
+        match self {
+            Self::File(path) => {
+                if !path.exists() {
+                    anyhow::bail!("Input file not found: {}", path.display());
+                }
+                Ok(LocalAudio {
+                    path: path.clone(),
+                    _temporary: None,
+                })
+            }
+            Self::Stdin => {
+                let (temporary, mut file) = TempFile::create("stdin", "")?;
+                std::io::copy(&mut std::io::stdin().lock(), &mut file)
+                    .context("Failed to read audio from stdin")?;
+                file.flush()?;
+                log::info!("Read audio from stdin into {}", temporary.path().display());
+                Ok(LocalAudio::spooled(temporary))
+            }
+            Self::Url(url) => {
+                let mut resp = reqwest::blocking::get(url)
+                    .with_context(|| format!("Request failed for {url}"))?;
+                if !resp.status().is_success() {
+                    anyhow::bail!("Request to {url} failed with status {}", resp.status());
+                }
+
+                let suffix = url_extension(url).map(|extension| format!(".{extension}")).unwrap_or_default();
+                let (temporary, mut file) = TempFile::create("download", &suffix)?;
+                let bytes = resp
+                    .copy_to(&mut file)
+                    .with_context(|| format!("Failed to download audio from {url}"))?;
+                file.flush()?;
+                log::info!("Downloaded {} bytes of audio from {}", bytes, url);
+                Ok(LocalAudio::spooled(temporary))
+            }
+        }
+    }
+}
+
+/// A local copy of the input audio. Temporary copies are deleted on drop.
+pub struct LocalAudio {
+    path: PathBuf,
+    /// Deletes a spooled copy when the audio is dropped.
+    _temporary: Option<TempFile>,
+}
+
+impl LocalAudio {
+    fn spooled(temporary: TempFile) -> Self {
+        Self {
+            path: temporary.path().to_path_buf(),
+            _temporary: Some(temporary),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// File extension of a URL's path, ignoring any query or fragment, used as a
+/// format hint for the decoders.
+fn url_extension(url: &str) -> Option<&str> {
+    let url = url.split(['?', '#']).next()?;
+    let without_scheme = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let (_host, path) = without_scheme.split_once('/')?;
+    let file_name = path.rsplit('/').next()?;
+    let (_, extension) = file_name.rsplit_once('.')?;
+    (!extension.is_empty() && extension.len() <= 5 && extension.chars().all(|c| c.is_ascii_alphanumeric()))
+        .then_some(extension)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_input_sources() {
+        assert_eq!(AudioSource::parse(Path::new("-")), AudioSource::Stdin);
+        assert_eq!(
+            AudioSource::parse(Path::new("https://example.com/a.mp3")),
+            AudioSource::Url("https://example.com/a.mp3".into())
+        );
+        assert_eq!(
+            AudioSource::parse(Path::new("song.wav")),
+            AudioSource::File(PathBuf::from("song.wav"))
+        );
+    }
+
+    #[test]
+    fn extracts_url_extension() {
+        assert_eq!(url_extension("https://example.com/mix/track.flac?token=1"), Some("flac"));
+        assert_eq!(url_extension("https://example.com/stream"), None);
+        assert_eq!(url_extension("https://example.com/v1.2/stream"), None);
+        assert_eq!(url_extension("https://example.com"), None);
+    }
+}
@@ -35,7 +35,7 @@ NOTES:
   * Requires ffmpeg on PATH.
 ";
 
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Clone)]
 #[command(
     name = "sonica",
     about = "GPU-accelerated audio visualizer video generator",
@@ -48,8 +48,14 @@ NOTES:
     after_long_help = EXAMPLES,
     max_term_width = 100
 )]
+#[command(group(
+    clap::ArgGroup::new("subtitle_export")
+        .args(["write_subtitles", "export_vtt"])
+        .multiple(true)
+))]
 pub struct Cli {
-    /// Input audio file (WAV, MP3, FLAC, OGG, AAC, WebM/Opus, or any FFmpeg-supported format)
+    /// Input audio file (WAV, MP3, FLAC, OGG, AAC, WebM/Opus, or any FFmpeg-supported format),
+    /// `-` for stdin, or an http(s) URL
     pub input: Option<PathBuf>,
 
     // ---------------------------------------------------------------- Visuals
@@ -57,10 +63,41 @@ pub struct Cli {
     #[arg(short, long, default_value = "frequency_bars", help_heading = "Visuals")]
     pub template: String,
 
+    /// Render every template with its name on screen: one after another
+    /// (sequence) or all at once in a grid of tiles
+    #[arg(
+        long,
+        value_name = "LAYOUT",
+        num_args = 0..=1,
+        default_missing_value = "sequence",
+        help_heading = "Visuals"
+    )]
+    pub showcase: Option<String>,
+
     /// Post-processing effects, comma-separated; see --list-effects
     #[arg(long, value_delimiter = ',', help_heading = "Visuals")]
     pub effects: Vec<String>,
 
+    /// Rearrange effects after presets expand: the listed ones run in this
+    /// order, in the positions they already held (e.g. mirror,vignette)
+    #[arg(long, value_name = "LIST", value_delimiter = ',', help_heading = "Visuals")]
+    pub effect_order: Vec<String>,
+
+    /// Blend each finished frame with the previous one to reduce strobing,
+    /// 0.0-1.0 (adds motion blur)
+    #[arg(long, default_value_t = 0.0, value_name = "AMOUNT", help_heading = "Visuals")]
+    pub temporal_smooth: f32,
+
+    /// Check the output for rapid full-frame flashes: warn (list them at the
+    /// end) or limit (also attenuate them)
+    #[arg(long, value_name = "MODE", help_heading = "Visuals")]
+    pub flash_guard: Option<String>,
+
+    /// Use the input's embedded cover art, blurred and darkened, as the
+    /// background behind the visuals
+    #[arg(long, help_heading = "Visuals")]
+    pub background_from_art: bool,
+
     /// Template parameter overrides (key=value, comma-separated)
     #[arg(
         long = "param",
@@ -70,6 +107,16 @@ pub struct Cli {
     )]
     pub params: Vec<String>,
 
+    /// Animate a float parameter over the render (repeatable): NAME=TIME:VALUE,...
+    /// where TIME is seconds or "end", e.g. zoom=0:1,end:3
+    #[arg(long, value_name = "NAME=KEYS", help_heading = "Visuals")]
+    pub param_anim: Vec<String>,
+
+    /// JSON file of named value tracks ({"name": [[TIME, VALUE], ...]}) that
+    /// templates read per frame as automation.values[AUTO_<NAME>]
+    #[arg(long, value_name = "PATH", help_heading = "Visuals")]
+    pub automation: Option<PathBuf>,
+
     /// Video width in pixels
     #[arg(long, default_value_t = 1920, help_heading = "Visuals")]
     pub width: u32,
@@ -78,12 +125,18 @@ pub struct Cli {
     #[arg(long, default_value_t = 1080, help_heading = "Visuals")]
     pub height: u32,
 
-    /// Frames per second
-    #[arg(long, default_value_t = 30, help_heading = "Visuals")]
-    pub fps: u32,
+    /// Supersampling factor: render at 2x or 4x the resolution and downsample for smoother edges
+    #[arg(long, default_value_t = 1, value_name = "N", help_heading = "Visuals")]
+    pub ssaa: u32,
+
+    /// Frames per second: an integer, a ratio like 24000/1001, or 23.976/29.97/59.94
+    #[arg(long, default_value = "30", help_heading = "Visuals")]
+    pub fps: String,
 
     // ------------------------------------------------------ Output & encoding
-    /// Output video file
+    /// Output video file, a named pipe, or - to stream to stdout. A directory
+    /// (frames/) or a pattern (frames/%05d.png) writes one PNG per frame instead,
+    /// and a .gif path writes an animated GIF
     #[arg(
         short,
         long,
@@ -92,6 +145,16 @@ pub struct Cli {
     )]
     pub output: PathBuf,
 
+    /// Replace the output file if it already exists (by default sonica refuses)
+    #[arg(long, help_heading = "Output & Encoding")]
+    pub overwrite: bool,
+
+    /// Skip the render when the output exists and was made from the same
+    /// input audio and settings (tracked in <OUTPUT>.sonica-stamp).
+    /// --overwrite forces a render
+    #[arg(long, help_heading = "Output & Encoding")]
+    pub skip_if_unchanged: bool,
+
     /// H.264 quality (0-51, lower = better). Ignored when --bitrate is set
     #[arg(long, default_value_t = 18, help_heading = "Output & Encoding")]
     pub crf: u32,
@@ -100,6 +163,23 @@ pub struct Cli {
     #[arg(short, long, help_heading = "Output & Encoding")]
     pub bitrate: Option<String>,
 
+    /// Two-pass encode to hit --bitrate exactly (renders to a temporary lossless file first)
+    #[arg(long, requires = "bitrate", help_heading = "Output & Encoding")]
+    pub two_pass: bool,
+
+    /// Render from this many seconds into the input instead of its start
+    #[arg(long, value_name = "SECONDS", help_heading = "Output & Encoding")]
+    pub start: Option<f32>,
+
+    /// Stop rendering this many seconds into the input (past its end renders
+    /// to the end)
+    #[arg(long, value_name = "SECONDS", help_heading = "Output & Encoding")]
+    pub end: Option<f32>,
+
+    /// Make the video this long, repeating the audio (and its visuals) to fill it
+    #[arg(long, value_name = "SECONDS", help_heading = "Output & Encoding")]
+    pub loop_audio: Option<f32>,
+
     /// FFmpeg video codec
     #[arg(
         long,
@@ -108,20 +188,125 @@ pub struct Cli {
     )]
     pub codec: String,
 
+    /// Output container (mp4, mov, mkv, webm), overriding the one the -o
+    /// extension implies
+    #[arg(long, value_name = "NAME", help_heading = "Output & Encoding")]
+    pub container: Option<String>,
+
     /// FFmpeg pixel format
     #[arg(long, default_value = "yuv420p", help_heading = "Output & Encoding")]
     pub pix_fmt: String,
 
+    /// Keep the template's alpha: clear to transparent instead of black and
+    /// encode with alpha (ProRes 4444 in .mov, VP9 in .webm/.mkv)
+    #[arg(long, help_heading = "Output & Encoding")]
+    pub transparent: bool,
+
+    /// Frame rate of a .gif output; the render still runs at --fps and only
+    /// the frames that start a GIF frame are kept
+    #[arg(long, value_name = "FPS", default_value_t = 15, help_heading = "Output & Encoding")]
+    pub gif_fps: u32,
+
+    /// How many times a .gif output plays; 0 loops forever
+    #[arg(long, value_name = "N", default_value_t = 0, help_heading = "Output & Encoding")]
+    pub gif_loop_count: u16,
+
+    /// YUV color matrix to convert with and tag: auto (bt601 below 720 lines, else bt709), bt601, bt709, bt2020
+    #[arg(long, value_name = "MATRIX", default_value = "auto", help_heading = "Output & Encoding")]
+    pub color_matrix: String,
+
+    /// Render only the frame at this time to an image instead of a video
+    #[arg(long, value_name = "SECONDS", help_heading = "Output & Encoding")]
+    pub preview: Option<f32>,
+
+    /// Render a grid of frames at evenly spaced times to one image instead of
+    /// a video; GRID is COLUMNSxROWS [default: 4x4]
+    #[arg(
+        long,
+        num_args = 1..=2,
+        value_names = ["PATH", "GRID"],
+        conflicts_with = "preview",
+        help_heading = "Output & Encoding"
+    )]
+    pub contact_sheet: Option<Vec<String>>,
+
+    /// Image format for --preview and --contact-sheet: png (lossless) or jpeg
+    #[arg(long, value_name = "FORMAT", default_value = "png", help_heading = "Output & Encoding")]
+    pub image_format: String,
+
+    /// JPEG quality for --preview and --contact-sheet, 1-100 [default: 90]
+    #[arg(long, value_name = "N", help_heading = "Output & Encoding")]
+    pub image_quality: Option<u8>,
+
+    /// Shade a platform's UI-covered regions on --preview: tiktok, youtube, or instagram
+    #[arg(long, value_name = "PLATFORM", requires = "preview", help_heading = "Output & Encoding")]
+    pub safe_guides: Option<String>,
+
+    /// Also plot the track's rms over time into this PNG (or JPEG) graph
+    #[arg(long, value_name = "PATH", help_heading = "Output & Encoding")]
+    pub energy_graph: Option<PathBuf>,
+
+    /// Add bass, mid and high lines to --energy-graph
+    #[arg(long, requires = "energy_graph", help_heading = "Output & Encoding")]
+    pub energy_graph_bands: bool,
+
+    /// Overlay a coordinate grid every N pixels with UV/pixel labels at the corners and center, on --preview
+    #[arg(
+        long,
+        value_name = "PIXELS",
+        num_args = 0..=1,
+        default_missing_value = "100",
+        help_heading = "Output & Encoding"
+    )]
+    pub debug_grid: Option<u32>,
+
+    /// Draw --debug-grid into every frame of the video or contact sheet too
+    #[arg(long, requires = "debug_grid", help_heading = "Output & Encoding")]
+    pub keep_debug_grid: bool,
+
     // ----------------------------------------------------------- Text overlay
     /// Title text drawn in the corner
     #[arg(long, help_heading = "Text Overlay")]
     pub title: Option<String>,
 
+    /// Use the input's artist/title tags as the title when --title is unset
+    #[arg(long, help_heading = "Text Overlay")]
+    pub title_from_tags: bool,
+
+    /// CUE sheet or "mm:ss Title" tracklist of a mix: the title follows the song playing
+    #[arg(long, value_name = "PATH", help_heading = "Text Overlay")]
+    pub cue_sheet: Option<PathBuf>,
+
+    /// Dip to black for this many seconds at each --cue-sheet track change (0 = off)
+    #[arg(long, default_value_t = 0.0, value_name = "SECONDS", requires = "cue_sheet", help_heading = "Text Overlay")]
+    pub track_separator: f32,
+
     /// Show elapsed time overlay
     #[arg(long, help_heading = "Text Overlay")]
     pub show_time: bool,
 
-    /// Font file for title/time overlay (TTF/OTF path)
+    /// Show the note of the loudest frequency (e.g. "A4") in the top left
+    #[arg(long, help_heading = "Text Overlay")]
+    pub show_note: bool,
+
+    /// Pick black or white title/time text per frame from the brightness behind it
+    #[arg(long, help_heading = "Text Overlay")]
+    pub auto_contrast: bool,
+
+    /// JSON file of timed text overlays ({text, start, end, x, y, size, color, fade})
+    #[arg(long, value_name = "PATH", help_heading = "Text Overlay")]
+    pub overlay_script: Option<PathBuf>,
+
+    /// Overlay a diagnostic panel with the spectrum, levels, tempo and beats
+    #[arg(long, help_heading = "Text Overlay")]
+    pub debug_hud: bool,
+
+    /// Draw the whole track's waveform along the bottom with a moving playhead
+    #[arg(long, help_heading = "Text Overlay")]
+    pub waveform_strip: bool,
+
+    /// Font file for title/time overlay (TTF/OTF path), or the name of an
+    /// installed family (see --list-fonts)
     #[arg(long, value_name = "PATH", help_heading = "Text Overlay")]
     pub font: Option<PathBuf>,
 
@@ -138,22 +323,42 @@ pub struct Cli {
     #[arg(long, help_heading = "Subtitles")]
     pub subtitles: bool,
 
-    /// Burn in subtitles from an existing SRT instead of transcribing
+    /// Burn in subtitles from an existing SRT or WebVTT file instead of transcribing
     #[arg(long, value_name = "PATH", help_heading = "Subtitles")]
     pub subtitle_file: Option<PathBuf>,
 
+    /// burn (draw into the frames), soft (mux a selectable text track), or both
+    #[arg(long, value_name = "MODE", default_value = "burn", help_heading = "Subtitles")]
+    pub subtitle_mode: String,
+
     /// Save the generated subtitles as an editable SRT
     #[arg(long, value_name = "PATH", help_heading = "Subtitles")]
     pub write_subtitles: Option<PathBuf>,
 
+    /// Also write the subtitles as WebVTT for players that load external captions
+    #[arg(long, value_name = "PATH", help_heading = "Subtitles")]
+    pub export_vtt: Option<PathBuf>,
+
+    /// Tag each word in --export-vtt with its start time for karaoke-capable players
+    #[arg(long, requires = "export_vtt", help_heading = "Subtitles")]
+    pub vtt_word_timestamps: bool,
+
     /// Transcribe only, skipping the video render
-    #[arg(long, requires = "write_subtitles", help_heading = "Subtitles")]
+    #[arg(long, requires = "subtitle_export", help_heading = "Subtitles")]
     pub transcribe_only: bool,
 
     /// Whisper model: tiny/base/small/medium/large (+ .en variants), or a ggml path
     #[arg(long, default_value = "base", help_heading = "Subtitles")]
     pub whisper_model: String,
 
+    /// HuggingFace repo (owner/name) that Whisper models are downloaded from
+    #[arg(long, value_name = "REPO", default_value = "ggerganov/whisper.cpp", help_heading = "Subtitles")]
+    pub model_repo: String,
+
+    /// Discard the cached Whisper model and download it again
+    #[arg(long, help_heading = "Subtitles")]
+    pub redownload_model: bool,
+
     /// Language as ISO 639-1 (e.g. "en", "ko", "zh"). Auto-detects if unset
     #[arg(long, help_heading = "Subtitles")]
     pub subtitle_lang: Option<String>,
@@ -162,7 +367,8 @@ pub struct Cli {
     #[arg(long, default_value_t = 48.0, help_heading = "Subtitles")]
     pub subtitle_font_size: f32,
 
-    /// Subtitle font file; needed for non-Latin scripts (TTF/OTF/TTC path)
+    /// Subtitle font file; needed for non-Latin scripts (TTF/OTF/TTC path,
+    /// or an installed family name)
     #[arg(long, value_name = "PATH", help_heading = "Subtitles")]
     pub subtitle_font: Option<PathBuf>,
 
@@ -178,6 +384,10 @@ pub struct Cli {
     #[arg(long, default_value_t = 42, help_heading = "Subtitles")]
     pub subtitle_max_chars: usize,
 
+    /// Maximum lines per subtitle cue; longer phrases are split into more cues
+    #[arg(long, default_value_t = 2, help_heading = "Subtitles")]
+    pub subtitle_max_lines: usize,
+
     /// Subtitle background opacity (0.0-1.0)
     #[arg(long, default_value_t = 0.55, help_heading = "Subtitles")]
     pub subtitle_background_opacity: f32,
@@ -210,21 +420,212 @@ pub struct Cli {
     #[arg(long, help_heading = "Subtitles")]
     pub no_subtitle_karaoke: bool,
 
+    /// Karaoke highlight style: sweep, pop, or fill
+    #[arg(long, default_value = "sweep", help_heading = "Subtitles")]
+    pub karaoke_style: String,
+
     // --------------------------------------------------------- Audio analysis
     /// Smoothing factor for audio analysis (0.0-1.0; higher = calmer motion)
     #[arg(long, default_value_t = 0.85, help_heading = "Audio Analysis")]
     pub smoothing: f32,
 
+    /// Smoothing for the FFT bins (spectrum bars) only [default: --smoothing]
+    #[arg(long, value_name = "F", help_heading = "Audio Analysis")]
+    pub smoothing_bins: Option<f32>,
+
+    /// Smoothing for the bass/mid/high bands only [default: --smoothing]
+    #[arg(long, value_name = "F", help_heading = "Audio Analysis")]
+    pub smoothing_bands: Option<f32>,
+
+    /// Smoothing for rms (overall loudness) only [default: --smoothing]
+    #[arg(long, value_name = "F", help_heading = "Audio Analysis")]
+    pub smoothing_rms: Option<f32>,
+
+    /// How stereo/multichannel audio is folded to mono: average (ITU-R BS.775
+    /// for surround), rms, mid, left, or right
+    #[arg(long, default_value = "average", help_heading = "Audio Analysis")]
+    pub downmix: String,
+
+    /// Channel layout of the input when the file's is missing or wrong:
+    /// stereo, 5.1, 5.1(side), 7.1... or names in order (FL,FR,FC,LFE,SL,SR)
+    #[arg(long, value_name = "LAYOUT", help_heading = "Audio Analysis")]
+    pub channel_layout: Option<String>,
+
+    /// Analyze only these channels, by name (L,R,C,LFE,SL,SR...) or 1-based index
+    #[arg(long, value_name = "LIST", value_delimiter = ',', help_heading = "Audio Analysis")]
+    pub channels: Vec<String>,
+
+    /// Read the input as headerless interleaved PCM: f32le or s16le.
+    /// Needs --sample-rate; see --raw-channels
+    #[arg(long, value_name = "FORMAT", requires = "sample_rate", help_heading = "Audio Analysis")]
+    pub raw_input: Option<String>,
+
+    /// Sample rate of --raw-input PCM, in Hz
+    #[arg(long, value_name = "HZ", requires = "raw_input", help_heading = "Audio Analysis")]
+    pub sample_rate: Option<u32>,
+
+    /// Channel count of --raw-input PCM
+    #[arg(long, value_name = "N", default_value_t = 1, help_heading = "Audio Analysis")]
+    pub raw_channels: usize,
+
+    /// Scale the analyzed audio by its ReplayGain tags: track (default) or album
+    #[arg(
+        long,
+        value_name = "MODE",
+        num_args = 0..=1,
+        default_missing_value = "track",
+        help_heading = "Audio Analysis"
+    )]
+    pub apply_replaygain: Option<String>,
+
+    /// Bring the track to this integrated loudness (LUFS, e.g. -14) before
+    /// analysis, and the soundtrack with it
+    #[arg(long, value_name = "LUFS", allow_negative_numbers = true, help_heading = "Audio Analysis")]
+    pub normalize_lufs: Option<f32>,
+
+    /// Remove DC offset from each frame's waveform (center), or also scale
+    /// its peak to AMPLITUDE (0-1) so quiet passages fill oscilloscope templates
+    #[arg(
+        long,
+        value_name = "AMPLITUDE",
+        num_args = 0..=1,
+        default_missing_value = "center",
+        help_heading = "Audio Analysis"
+    )]
+    pub waveform_normalize: Option<String>,
+
+    /// Reduce each frame's waveform to min/max envelopes, or point-sample it for a thin oscilloscope trace
+    #[arg(long, default_value = "envelope", value_name = "MODE", help_heading = "Audio Analysis")]
+    pub waveform_sampling: String,
+
+    /// Measure sub-bass and bass with a longer 8192-point FFT for finer low-end resolution
+    #[arg(long, help_heading = "Audio Analysis")]
+    pub dual_fft: bool,
+
+    /// Samples per FFT window, a power of two from 256: larger resolves low notes, smaller
+    /// follows fast percussion
+    #[arg(long, value_name = "N", default_value_t = 2048, help_heading = "Audio Analysis")]
+    pub fft_size: usize,
+
+    /// Samples between the beat detector's FFT windows, a power of two up to --fft-size
+    /// [default: half of --fft-size]
+    #[arg(long, value_name = "N", help_heading = "Audio Analysis")]
+    pub hop_size: Option<usize>,
+
+    /// Also measure the seven bands of the left and right channels, for stereo-split visualizers
+    #[arg(long, help_heading = "Audio Analysis")]
+    pub stereo_bands: bool,
+
+    /// Analyze left and right separately: per-channel waveforms (`waveform` and `waveform_r` in
+    /// shaders) and band energies (implies --stereo-bands)
+    #[arg(long, help_heading = "Audio Analysis")]
+    pub stereo: bool,
+
+    /// Resample to this rate (e.g. 44100) before analysis, so 44.1kHz and 48kHz copies of a mix animate alike
+    #[arg(long, value_name = "HZ", help_heading = "Audio Analysis")]
+    pub analysis_rate: Option<u32>,
+
+    /// Memory budget in MB for per-frame analysis data; long, high-fps
+    /// renders that would exceed it keep fewer FFT bins per frame
+    #[arg(long, value_name = "MB", help_heading = "Audio Analysis")]
+    pub max_memory: Option<u64>,
+
+    /// Half-life in seconds of each FFT bin's normalization peak (0 = lifetime peak)
+    #[arg(long, default_value_t = 0.0, value_name = "SECONDS", help_heading = "Audio Analysis")]
+    pub bin_peak_decay: f32,
+
+    /// Noise floor for FFT bin normalization, in dB below the loudest bin
+    #[arg(long, default_value_t = -60.0, value_name = "DB", allow_hyphen_values = true, help_heading = "Audio Analysis")]
+    pub bin_noise_floor: f32,
+
+    /// Boost FFT bins by this many dB per octave above 1kHz (cut below) to flatten the spectrum
+    #[arg(long, default_value_t = 0.0, value_name = "DB", allow_hyphen_values = true, help_heading = "Audio Analysis")]
+    pub spectral_tilt: f32,
+
+    /// Lowest frequency in Hz of the spectrum sent to templates
+    #[arg(long, value_name = "HZ", help_heading = "Audio Analysis")]
+    pub freq_min: Option<f32>,
+
+    /// Highest frequency in Hz of the spectrum sent to templates
+    #[arg(long, value_name = "HZ", help_heading = "Audio Analysis")]
+    pub freq_max: Option<f32>,
+
+    /// Spacing of the spectrum's bins: linear (even in Hz), log (even per octave) or mel
+    #[arg(long, value_name = "SCALE", default_value = "linear", help_heading = "Audio Analysis")]
+    pub freq_scale: String,
+
+    /// Milliseconds beat_intensity holds at 1.0 after a beat before decaying
+    #[arg(long, default_value_t = 0.0, value_name = "MS", help_heading = "Audio Analysis")]
+    pub beat_hold: f32,
+
+    /// Shape of the beat_intensity fall-off: exponential or linear
+    #[arg(long, default_value = "exponential", value_name = "SHAPE", help_heading = "Audio Analysis")]
+    pub beat_decay: String,
+
+    /// Beat times to use instead of detection: one time in seconds per line, a
+    /// JSON array of seconds, or a MIDI tempo map
+    #[arg(long, visible_alias = "beats", value_name = "PATH", help_heading = "Audio Analysis")]
+    pub beat_grid: Option<PathBuf>,
+
     // --------------------------------------------------- Discovery and config
     /// List available templates and exit
     #[arg(long, help_heading = "Discovery & Config")]
     pub list_templates: bool,
 
+    /// Copy a template's manifest and shaders into a new directory to start a
+    /// custom template, then exit
+    #[arg(
+        long,
+        num_args = 2,
+        value_names = ["EXISTING", "NEW_DIR"],
+        help_heading = "Discovery & Config"
+    )]
+    pub copy_template: Option<Vec<PathBuf>>,
+
     /// List available post-processing effects and exit
     #[arg(long, help_heading = "Discovery & Config")]
     pub list_effects: bool,
 
+    /// List the installed font families --font and --font-family accept and exit
+    #[arg(long, help_heading = "Discovery & Config")]
+    pub list_fonts: bool,
+
+    /// List the codecs and containers your FFmpeg supports and exit
+    #[arg(long, help_heading = "Discovery & Config")]
+    pub list_formats: bool,
+
     /// Config file path (defaults to ./sonica.toml if present)
     #[arg(long, value_name = "PATH", help_heading = "Discovery & Config")]
     pub config: Option<PathBuf>,
+
+    /// Write a JSON record of the render (settings, input hash, GPU, timing)
+    #[arg(long, value_name = "PATH", help_heading = "Discovery & Config")]
+    pub report: Option<PathBuf>,
+
+    /// Write the audio analysis (tempo, beats, and per-frame levels) as JSON.
+    /// Skips the render unless -o is given
+    #[arg(long, value_name = "PATH", help_heading = "Discovery & Config")]
+    pub dump_analysis: Option<PathBuf>,
+
+    /// Include each frame's FFT bins and waveform in --dump-analysis
+    #[arg(long, requires = "dump_analysis", help_heading = "Discovery & Config")]
+    pub dump_fft: bool,
+
+    /// Print how long each stage took (decode, analysis passes, pipeline
+    /// build, render, encode) when the run finishes
+    #[arg(long, help_heading = "Discovery & Config")]
+    pub benchmark: bool,
+
+    /// Only log errors and hide progress bars
+    #[arg(short, long, help_heading = "Discovery & Config")]
+    pub quiet: bool,
+
+    /// Fail the run (non-zero exit) if anything logged a warning, e.g. an
+    /// unknown effect or parameter, or a font that failed to load
+    #[arg(long, help_heading = "Discovery & Config")]
+    pub strict: bool,
+
+    /// Log line format: text or json (one JSON object per line)
+    #[arg(long, value_name = "FORMAT", default_value = "text", help_heading = "Discovery & Config")]
+    pub log_format: String,
 }
@@ -1,4 +1,5 @@
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::path::PathBuf;
 
 #[derive(Debug, Default, Deserialize)]
@@ -10,7 +11,23 @@ pub struct Config {
     #[serde(default)]
     pub effects: Vec<String>,
     #[serde(default)]
+    pub effect_order: Vec<String>,
+    #[serde(default)]
     pub subtitle: SubtitleConfig,
+    /// `[templates.<name>]` sections, used when that template is selected.
+    #[serde(default)]
+    pub templates: HashMap<String, TemplateConfig>,
+}
+
+/// Per-template defaults. Each value beats the global one for its template
+/// and loses to the command line.
+#[derive(Debug, Default, Deserialize)]
+pub struct TemplateConfig {
+    pub effects: Option<Vec<String>>,
+    pub smoothing: Option<f32>,
+    /// Same values as `--param`; colors may be `[r, g, b]` arrays.
+    #[serde(default, deserialize_with = "deserialize_params")]
+    pub params: HashMap<String, String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -19,12 +36,21 @@ pub struct OutputConfig {
     pub width: u32,
     #[serde(default = "default_height")]
     pub height: u32,
-    #[serde(default = "default_fps")]
-    pub fps: u32,
+    #[serde(default = "default_ssaa")]
+    pub ssaa: u32,
+    /// An integer (`fps = 30`) or a string ratio (`fps = "24000/1001"`).
+    #[serde(default = "default_fps", deserialize_with = "deserialize_fps")]
+    pub fps: String,
     #[serde(default = "default_crf")]
     pub crf: u32,
     #[serde(default = "default_codec")]
     pub codec: String,
+    #[serde(default)]
+    pub container: Option<String>,
+    #[serde(default = "default_color_matrix")]
+    pub color_matrix: String,
+    #[serde(default)]
+    pub transparent: bool,
     pub font: Option<PathBuf>,
     pub font_url: Option<String>,
     pub font_family: Option<String>,
@@ -34,6 +60,52 @@ pub struct OutputConfig {
 pub struct AudioConfig {
     #[serde(default = "default_smoothing")]
     pub smoothing: f32,
+    #[serde(default)]
+    pub smoothing_bins: Option<f32>,
+    #[serde(default)]
+    pub smoothing_bands: Option<f32>,
+    #[serde(default)]
+    pub smoothing_rms: Option<f32>,
+    #[serde(default = "default_downmix")]
+    pub downmix: String,
+    #[serde(default)]
+    pub channel_layout: Option<String>,
+    #[serde(default)]
+    pub channels: Vec<String>,
+    #[serde(default)]
+    pub dual_fft: bool,
+    #[serde(default = "default_fft_size")]
+    pub fft_size: usize,
+    #[serde(default)]
+    pub hop_size: Option<usize>,
+    #[serde(default)]
+    pub stereo_bands: bool,
+    #[serde(default)]
+    pub stereo: bool,
+    #[serde(default)]
+    pub analysis_rate: Option<u32>,
+    #[serde(default)]
+    pub max_memory: Option<u64>,
+    #[serde(default)]
+    pub waveform_normalize: Option<String>,
+    #[serde(default = "default_waveform_sampling")]
+    pub waveform_sampling: String,
+    #[serde(default)]
+    pub bin_peak_decay: f32,
+    #[serde(default = "default_bin_noise_floor")]
+    pub bin_noise_floor: f32,
+    #[serde(default)]
+    pub spectral_tilt: f32,
+    #[serde(default)]
+    pub freq_min: Option<f32>,
+    #[serde(default)]
+    pub freq_max: Option<f32>,
+    #[serde(default = "default_freq_scale")]
+    pub freq_scale: String,
+    #[serde(default)]
+    pub beat_hold: f32,
+    #[serde(default = "default_beat_decay")]
+    pub beat_decay: String,
 }
 
 impl Default for OutputConfig {
@@ -41,9 +113,13 @@ impl Default for OutputConfig {
         Self {
             width: default_width(),
             height: default_height(),
+            ssaa: default_ssaa(),
             fps: default_fps(),
             crf: default_crf(),
             codec: default_codec(),
+            container: None,
+            color_matrix: default_color_matrix(),
+            transparent: false,
             font: None,
             font_url: None,
             font_family: None,
@@ -55,26 +131,103 @@ impl Default for AudioConfig {
     fn default() -> Self {
         Self {
             smoothing: default_smoothing(),
+            smoothing_bins: None,
+            smoothing_bands: None,
+            smoothing_rms: None,
+            downmix: default_downmix(),
+            channel_layout: None,
+            channels: Vec::new(),
+            dual_fft: false,
+            fft_size: default_fft_size(),
+            hop_size: None,
+            stereo_bands: false,
+            stereo: false,
+            analysis_rate: None,
+            max_memory: None,
+            waveform_normalize: None,
+            waveform_sampling: default_waveform_sampling(),
+            bin_peak_decay: 0.0,
+            bin_noise_floor: default_bin_noise_floor(),
+            spectral_tilt: 0.0,
+            freq_min: None,
+            freq_max: None,
+            freq_scale: default_freq_scale(),
+            beat_hold: 0.0,
+            beat_decay: default_beat_decay(),
         }
     }
 }
 
 fn default_width() -> u32 { 1920 }
 fn default_height() -> u32 { 1080 }
-fn default_fps() -> u32 { 30 }
+fn default_ssaa() -> u32 { 1 }
+fn default_fps() -> String { "30".into() }
 fn default_crf() -> u32 { 18 }
 fn default_codec() -> String { "libx264".into() }
+fn default_color_matrix() -> String { "auto".into() }
 fn default_smoothing() -> f32 { 0.85 }
+fn default_downmix() -> String { "average".into() }
+fn default_bin_noise_floor() -> f32 { -60.0 }
+fn default_beat_decay() -> String { "exponential".into() }
+fn default_waveform_sampling() -> String { "envelope".into() }
+fn default_freq_scale() -> String { "linear".into() }
+fn default_fft_size() -> usize { 2048 }
+
+fn deserialize_params<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<HashMap<String, String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Param {
+        Integer(i64),
+        Float(f64),
+        Bool(bool),
+        Text(String),
+        Color(Vec<f64>),
+    }
+    Ok(HashMap::<String, Param>::deserialize(deserializer)?
+        .into_iter()
+        .map(|(name, value)| {
+            let value = match value {
+                Param::Integer(v) => v.to_string(),
+                Param::Float(v) => v.to_string(),
+                Param::Bool(v) => v.to_string(),
+                Param::Text(v) => v,
+                Param::Color(rgb) => rgb.iter().map(f64::to_string).collect::<Vec<_>>().join(":"),
+            };
+            (name, value)
+        })
+        .collect())
+}
+
+fn deserialize_fps<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Fps {
+        Integer(u32),
+        Text(String),
+    }
+    Ok(match Fps::deserialize(deserializer)? {
+        Fps::Integer(fps) => fps.to_string(),
+        Fps::Text(fps) => fps,
+    })
+}
 
 #[derive(Debug, Deserialize)]
 pub struct SubtitleConfig {
     #[serde(default = "default_whisper_model")]
     pub whisper_model: String,
+    #[serde(default = "default_model_repo")]
+    pub model_repo: String,
+    #[serde(default = "default_subtitle_mode")]
+    pub mode: String,
     pub language: Option<String>,
     #[serde(default = "default_subtitle_font_size")]
     pub font_size: f32,
     #[serde(default = "default_subtitle_max_chars")]
     pub max_chars_per_line: usize,
+    #[serde(default = "default_subtitle_max_lines")]
+    pub max_lines: usize,
     pub font: Option<PathBuf>,
     pub font_url: Option<String>,
     pub font_family: Option<String>,
@@ -94,15 +247,20 @@ pub struct SubtitleConfig {
     pub margin_bottom: f32,
     #[serde(default = "default_subtitle_karaoke")]
     pub karaoke: bool,
+    #[serde(default = "default_karaoke_style")]
+    pub karaoke_style: String,
 }
 
 impl Default for SubtitleConfig {
     fn default() -> Self {
         Self {
             whisper_model: default_whisper_model(),
+            model_repo: default_model_repo(),
+            mode: default_subtitle_mode(),
             language: None,
             font_size: default_subtitle_font_size(),
             max_chars_per_line: default_subtitle_max_chars(),
+            max_lines: default_subtitle_max_lines(),
             font: None,
             font_url: None,
             font_family: None,
@@ -114,13 +272,17 @@ impl Default for SubtitleConfig {
             outline_width: default_subtitle_outline_width(),
             margin_bottom: default_subtitle_margin_bottom(),
             karaoke: default_subtitle_karaoke(),
+            karaoke_style: default_karaoke_style(),
         }
     }
 }
 
 fn default_whisper_model() -> String { "base".into() }
+fn default_model_repo() -> String { "ggerganov/whisper.cpp".into() }
+fn default_subtitle_mode() -> String { "burn".into() }
 fn default_subtitle_font_size() -> f32 { 48.0 }
 fn default_subtitle_max_chars() -> usize { 42 }
+fn default_subtitle_max_lines() -> usize { 2 }
 fn default_subtitle_background_opacity() -> f32 { 0.55 }
 fn default_subtitle_dim_opacity() -> f32 { 0.75 }
 fn default_subtitle_text_color() -> String { "#FFFFFF".into() }
@@ -129,6 +291,7 @@ fn default_subtitle_outline_color() -> String { "#000000".into() }
 fn default_subtitle_outline_width() -> u32 { 2 }
 fn default_subtitle_margin_bottom() -> f32 { 0.08 }
 fn default_subtitle_karaoke() -> bool { true }
+fn default_karaoke_style() -> String { "sweep".into() }
 
 pub fn load_config(path: &PathBuf) -> Option<Config> {
     let content = std::fs::read_to_string(path).ok()?;
@@ -0,0 +1,133 @@
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::process::Command;
+
+/// Video encoders sonica can drive through `--codec`, with how rate control
+/// behaves for each. sonica passes `-crf`/`-preset` unless `--bitrate` is set.
+pub const VIDEO_ENCODERS: &[(&str, &str)] = &[
+    ("libx264", "H.264 (default); --crf"),
+    ("libx265", "HEVC; --crf"),
+    ("libsvtav1", "AV1; --crf"),
+    ("libaom-av1", "AV1; --crf, very slow"),
+    ("libvpx-vp9", "VP9 for .webm; needs --bitrate (CRF is capped by its default bitrate)"),
+    ("h264_videotoolbox", "macOS hardware H.264; ignores --crf, use --bitrate"),
+    ("hevc_videotoolbox", "macOS hardware HEVC; ignores --crf, use --bitrate"),
+    ("h264_nvenc", "NVIDIA hardware H.264; ignores --crf, use --bitrate"),
+    ("hevc_nvenc", "NVIDIA hardware HEVC; ignores --crf, use --bitrate"),
+    ("h264_qsv", "Intel Quick Sync H.264; ignores --crf, use --bitrate"),
+    ("hevc_qsv", "Intel Quick Sync HEVC; ignores --crf, use --bitrate"),
+    ("prores_ks", "ProRes for .mov editing; ignores --crf and --bitrate"),
+];
+
+/// Audio encoders used for the muxed soundtrack.
+pub const AUDIO_ENCODERS: &[(&str, &str)] = &[
+    ("aac", "soundtrack codec (required)"),
+    ("libopus", "soundtrack codec for .webm"),
+];
+
+/// Output containers sonica can write, by FFmpeg muxer name.
+pub const CONTAINERS: &[(&str, &str)] = &[
+    ("mp4", ".mp4"),
+    ("mov", ".mov"),
+    ("matroska", ".mkv"),
+    ("webm", ".webm"),
+];
+
+/// What the local FFmpeg build supports, from `ffmpeg -encoders` and
+/// `ffmpeg -formats`.
+pub struct Capabilities {
+    pub encoders: HashSet<String>,
+    pub muxers: HashSet<String>,
+}
+
+impl Capabilities {
+    pub fn probe() -> Result<Self> {
+        Ok(Self {
+            encoders: parse_encoders(&run_ffmpeg("-encoders")?),
+            muxers: parse_muxers(&run_ffmpeg("-formats")?),
+        })
+    }
+}
+
+fn run_ffmpeg(flag: &str) -> Result<String> {
+    let output = Command::new("ffmpeg")
+        .args(["-hide_banner", flag])
+        .output()
+        .context("Failed to run ffmpeg. Is ffmpeg installed?")?;
+    if !output.status.success() {
+        anyhow::bail!("ffmpeg {} exited with an error", flag);
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Parse `ffmpeg -encoders`: a legend, a ` ------` separator, then one
+/// `<flags> <name> <description>` line per encoder.
+fn parse_encoders(listing: &str) -> HashSet<String> {
+    listing
+        .lines()
+        .skip_while(|line| !line.trim_start().starts_with("---"))
+        .skip(1)
+        .filter_map(|line| line.split_whitespace().nth(1))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Parse `ffmpeg -formats`: after the ` --` separator each line is
+/// `<D|E flags> <name[,alias...]> <description>`; `E` marks a muxer.
+fn parse_muxers(listing: &str) -> HashSet<String> {
+    listing
+        .lines()
+        .skip_while(|line| !line.trim_start().starts_with("--"))
+        .skip(1)
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let flags = fields.next()?;
+            let names = fields.next()?;
+            flags.contains('E').then_some(names)
+        })
+        .flat_map(|names| names.split(','))
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_encoder_listing() {
+        let listing = "Encoders:\n \
+            V..... = Video\n \
+            A..... = Audio\n \
+            ------\n \
+            V....D libx264              libx264 H.264 / AVC\n \
+            V....D h264_videotoolbox    VideoToolbox H.264 Encoder\n \
+            A....D aac                  AAC (Advanced Audio Coding)\n";
+
+        let encoders = parse_encoders(listing);
+
+        assert!(encoders.contains("libx264"));
+        assert!(encoders.contains("h264_videotoolbox"));
+        assert!(encoders.contains("aac"));
+        assert!(!encoders.contains("="));
+        assert_eq!(encoders.len(), 3);
+    }
+
+    #[test]
+    fn parses_muxers_from_format_listing() {
+        let listing = "File formats:\n \
+            D. = Demuxing supported\n \
+            .E = Muxing supported\n \
+            --\n \
+            D  aac             raw ADTS AAC\n \
+            DE matroska,webm   Matroska / WebM\n  \
+            E mp4             MP4 (MPEG-4 Part 14)\n";
+
+        let muxers = parse_muxers(listing);
+
+        assert!(muxers.contains("mp4"));
+        assert!(muxers.contains("matroska"));
+        assert!(muxers.contains("webm"));
+        assert!(!muxers.contains("aac"));
+    }
+}
@@ -1,13 +1,23 @@
 use anyhow::{Context, Result};
 use std::ffi::OsString;
 use std::io::{Read, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::{Child, Command, Stdio};
 use std::thread::JoinHandle;
 
+use crate::audio::decode::RawPcm;
+use crate::error::SonicaError;
+use crate::framerate::FrameRate;
+use crate::temp::TempFile;
+
+use super::FrameWriter;
+
+/// Frames go to FFmpeg's stdin from a [`FrameWriter`] thread.
 pub struct FfmpegEncoder {
     child: Child,
+    writer: FrameWriter<()>,
     stderr_reader: Option<JoinHandle<std::io::Result<Vec<u8>>>>,
+    two_pass: Option<Box<TwoPass>>,
 }
 
 impl FfmpegEncoder {
@@ -17,31 +27,62 @@ impl FfmpegEncoder {
         input_audio: &Path,
         width: u32,
         height: u32,
-        fps: u32,
+        fps: FrameRate,
         codec: &str,
         pix_fmt: &str,
         crf: u32,
         bitrate: Option<&str>,
-    ) -> Result<Self> {
-        let args = build_args(
-            output_path,
-            input_audio,
-            width,
-            height,
-            fps,
-            codec,
-            pix_fmt,
-            crf,
-            bitrate,
-        );
+        two_pass: bool,
+        options: EncodeOptions,
+    ) -> crate::error::Result<Self> {
+        if let Some(resolved) = Container::resolve(output_path, options.container) {
+            resolved.check_codec(codec)?;
+        }
+        let (args, two_pass) = match (two_pass, bitrate) {
+            (true, _) if is_stream(output_path) => {
+                return Err(anyhow::anyhow!(
+                    "Two-pass encoding can't stream to {}: the output is only written after the first pass",
+                    output_path.display()
+                )
+                .into())
+            }
+            (true, Some(bitrate)) => {
+                let plan = TwoPass {
+                    intermediate: TempFile::claim(sibling_path(output_path, "sonica-intermediate.mkv")),
+                    passlog: TempFile::claim_prefix(sibling_path(output_path, "sonica-2pass")),
+                    output: output_path.to_path_buf(),
+                    input_audio: input_audio.to_path_buf(),
+                    codec: codec.to_string(),
+                    pix_fmt: pix_fmt.to_string(),
+                    bitrate: bitrate.to_string(),
+                    options: options.clone(),
+                };
+                log::info!(
+                    "Two-pass encode: rendering to lossless intermediate {}",
+                    plan.intermediate.path().display()
+                );
+                (
+                    build_intermediate_args(plan.intermediate.path(), width, height, fps),
+                    Some(Box::new(plan)),
+                )
+            }
+            (true, None) => return Err(anyhow::anyhow!("Two-pass encoding needs a target bitrate").into()),
+            (false, _) => (
+                build_args(output_path, input_audio, width, height, fps, codec, pix_fmt, crf, bitrate, &options),
+                None,
+            ),
+        };
 
-        let mut child = Command::new("ffmpeg")
+        let mut command = Command::new("ffmpeg");
+        command
             .args(&args)
             .stdin(Stdio::piped())
-            .stdout(Stdio::null())
-            .stderr(Stdio::piped())
-            .spawn()
-            .context("Failed to spawn ffmpeg. Is ffmpeg installed?")?;
+            // `-o -`: FFmpeg writes the stream straight to our stdout, which
+            // nothing else in sonica writes to while rendering.
+            .stdout(if is_stdout(output_path) { Stdio::inherit() } else { Stdio::null() })
+            .stderr(Stdio::piped());
+        detach_from_terminal_signals(&mut command);
+        let mut child = command.spawn().map_err(SonicaError::FfmpegMissing)?;
 
         let mut stderr = child.stderr.take().context("FFmpeg stderr not available")?;
         let stderr_reader = std::thread::spawn(move || {
@@ -50,23 +91,45 @@ impl FfmpegEncoder {
             Ok(output)
         });
 
-        log::info!("FFmpeg encoder started: {}x{} @ {}fps, codec={}", width, height, fps, codec);
+        let mut stdin = child.stdin.take().context("FFmpeg stdin not available")?;
+        let writer = FrameWriter::spawn("FFmpeg", width, height, move |queued| {
+            for ((), pixels) in queued {
+                stdin.write_all(&pixels).context("Failed to write frame to ffmpeg")?;
+            }
+            // Dropping stdin closes the pipe, signalling EOF.
+            Ok(())
+        });
+
+        log::info!(
+            "FFmpeg encoder started: {}x{} @ {}fps, codec={}, color={}",
+            width,
+            height,
+            fps,
+            codec,
+            options.color_matrix.name()
+        );
 
         Ok(Self {
             child,
+            writer,
             stderr_reader: Some(stderr_reader),
+            two_pass,
         })
     }
 
-    pub fn write_frame(&mut self, rgba_pixels: &[u8]) -> Result<()> {
-        let stdin = self.child.stdin.as_mut().context("FFmpeg stdin not available")?;
-        stdin.write_all(rgba_pixels).context("Failed to write frame to ffmpeg")?;
-        Ok(())
+    pub fn write_frame(&mut self, rgba_pixels: &[u8]) -> crate::error::Result<()> {
+        self.send_frame(rgba_pixels.to_vec())
     }
 
-    pub fn finish(mut self) -> Result<()> {
-        // Close stdin to signal EOF
-        drop(self.child.stdin.take());
+    /// Queue a frame for the writer thread without copying it, blocking only
+    /// while the queue is full.
+    pub fn send_frame(&mut self, rgba_pixels: Vec<u8>) -> crate::error::Result<()> {
+        Ok(self.writer.send((), rgba_pixels)?)
+    }
+
+    pub fn finish(mut self) -> crate::error::Result<()> {
+        // The writer drains the queue and closes stdin.
+        let written = self.writer.finish();
 
         let status = self.child.wait().context("Failed to wait for ffmpeg")?;
         let stderr = self
@@ -78,8 +141,13 @@ impl FfmpegEncoder {
             .context("Failed to read FFmpeg stderr")?;
 
         if !status.success() {
-            let stderr = String::from_utf8_lossy(&stderr);
-            anyhow::bail!("FFmpeg exited with error:\n{}", stderr);
+            let stderr = String::from_utf8_lossy(&stderr).into_owned();
+            return Err(SonicaError::Ffmpeg { stderr });
+        }
+        written?;
+
+        if let Some(plan) = self.two_pass.take() {
+            plan.encode()?;
         }
 
         log::info!("FFmpeg encoding complete");
@@ -87,17 +155,535 @@ impl FfmpegEncoder {
     }
 }
 
+/// Two-pass ABR: frames arrive once through a pipe, so they are first
+/// written to a lossless FFV1 intermediate beside the output, which both
+/// passes then read. The intermediate can take 1-3 GB per minute of 1080p30;
+/// it and the passlogs are deleted when the plan drops, however the encode
+/// ends.
+struct TwoPass {
+    intermediate: TempFile,
+    passlog: TempFile,
+    output: PathBuf,
+    input_audio: PathBuf,
+    codec: String,
+    pix_fmt: String,
+    bitrate: String,
+    options: EncodeOptions,
+}
+
+impl TwoPass {
+    fn encode(&self) -> crate::error::Result<()> {
+        for pass in [1, 2] {
+            log::info!("Two-pass encode: pass {}/2 at {}...", pass, self.bitrate);
+            let mut command = Command::new("ffmpeg");
+            command.args(self.pass_args(pass)).stdin(Stdio::null());
+            detach_from_terminal_signals(&mut command);
+            let output = command.output().map_err(SonicaError::FfmpegMissing)?;
+            if !output.status.success() {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                return Err(SonicaError::Ffmpeg { stderr: format!("(pass {})\n{}", pass, stderr) });
+            }
+        }
+        Ok(())
+    }
+
+    fn pass_args(&self, pass: u32) -> Vec<OsString> {
+        let mut args: Vec<OsString> = vec![
+            "-hide_banner".into(),
+            "-loglevel".into(),
+            "error".into(),
+            "-nostats".into(),
+            "-y".into(),
+            "-i".into(),
+            self.intermediate.path().as_os_str().to_owned(),
+        ];
+        let options = &self.options;
+        if pass == 2 {
+            args.extend(audio_input_args(&self.input_audio, options));
+            match &options.subtitles {
+                Some(subtitles) => {
+                    args.extend(["-i".into(), subtitles.as_os_str().to_owned()]);
+                    args.extend(subtitle_track_args(&self.output, options.container));
+                }
+                None => args.extend(["-map".into(), "0:v".into(), "-map".into(), "1:a".into()]),
+            }
+        }
+        args.extend([
+            OsString::from("-c:v"),
+            OsString::from(&self.codec),
+            OsString::from("-pix_fmt"),
+            OsString::from(&self.pix_fmt),
+        ]);
+        args.extend(options.color_matrix.args());
+        args.extend([
+            OsString::from("-b:v"),
+            OsString::from(&self.bitrate),
+            OsString::from("-pass"),
+            OsString::from(pass.to_string()),
+            OsString::from("-passlogfile"),
+            self.passlog.path().as_os_str().to_owned(),
+        ]);
+        if pass == 1 {
+            args.extend(["-an".into(), "-f".into(), "null".into(), "-".into()]);
+        } else {
+            args.extend(output_audio_args(&self.output, options));
+            args.push(self.output.as_os_str().to_owned());
+        }
+        args
+    }
+}
+
+/// Maps video, audio and a third subtitle input into the output, with a
+/// text codec the container accepts.
+fn subtitle_track_args(output_path: &Path, container: Option<Container>) -> Vec<OsString> {
+    let codec = Container::resolve(output_path, container).map_or("mov_text", Container::subtitle_codec);
+    ["-map", "0:v", "-map", "1:a", "-map", "2:s", "-c:s", codec]
+        .map(OsString::from)
+        .to_vec()
+}
+
+/// The soundtrack encoder for the container, and `-shortest` so the video
+/// ends with whichever stream ends first.
+fn audio_codec_args(output_path: &Path, container: Option<Container>) -> Vec<OsString> {
+    let codec = Container::resolve(output_path, container).map_or("aac", Container::audio_codec);
+    ["-c:a", codec, "-b:a", "192k", "-shortest"].map(OsString::from).to_vec()
+}
+
+/// `-f` for a forced `--container`, or for stdout, which has no extension
+/// to go by. MP4 and MOV normally write their index at the end by seeking
+/// back, so a stream gets fragments that each carry their own.
+fn container_args(output_path: &Path, container: Option<Container>) -> Vec<OsString> {
+    let mut args: Vec<OsString> = Vec::new();
+    let resolved = Container::resolve(output_path, container);
+    if let Some(resolved) = resolved.filter(|_| container.is_some() || is_stdout(output_path)) {
+        args.extend(["-f".into(), resolved.name().into()]);
+    }
+    if is_stream(output_path) && matches!(resolved, Some(Container::Mp4 | Container::Mov)) {
+        args.extend(["-movflags".into(), "frag_keyframe+empty_moov".into()]);
+    }
+    args
+}
+
+/// `-o -`: write the encoded stream to stdout.
+pub fn is_stdout(output_path: &Path) -> bool {
+    output_path.as_os_str() == "-"
+}
+
+/// An output FFmpeg can't seek in: stdout or a named pipe (FIFO), e.g. one
+/// a streamer reads from.
+pub fn is_stream(output_path: &Path) -> bool {
+    is_stdout(output_path) || is_fifo(output_path)
+}
+
+#[cfg(unix)]
+fn is_fifo(path: &Path) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    std::fs::metadata(path).is_ok_and(|metadata| metadata.file_type().is_fifo())
+}
+
+#[cfg(not(unix))]
+fn is_fifo(_path: &Path) -> bool {
+    false
+}
+
+/// FFmpeg's name for the output: `pipe:1` for stdout.
+fn output_arg(output_path: &Path) -> OsString {
+    if is_stdout(output_path) {
+        "pipe:1".into()
+    } else {
+        output_path.as_os_str().to_owned()
+    }
+}
+
+/// `<output>.<suffix>` in the output's directory.
+fn sibling_path(output_path: &Path, suffix: &str) -> PathBuf {
+    let mut name = output_path.file_name().unwrap_or_default().to_owned();
+    name.push(".");
+    name.push(suffix);
+    output_path.with_file_name(name)
+}
+
+/// The muxer an output is written with: `--container`, or the one its
+/// extension implies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Container {
+    Mp4,
+    Mov,
+    Matroska,
+    Webm,
+}
+
+/// Video encoders by the format they produce, to match against what each
+/// container can hold. Encoders not listed are passed through unchecked.
+const ENCODER_FORMATS: &[(&str, &str)] = &[
+    ("libx264", "h264"),
+    ("h264_videotoolbox", "h264"),
+    ("h264_nvenc", "h264"),
+    ("h264_qsv", "h264"),
+    ("libx265", "hevc"),
+    ("hevc_videotoolbox", "hevc"),
+    ("hevc_nvenc", "hevc"),
+    ("hevc_qsv", "hevc"),
+    ("libsvtav1", "av1"),
+    ("libaom-av1", "av1"),
+    ("libvpx-vp9", "vp9"),
+    ("prores_ks", "prores"),
+];
+
+impl Container {
+    const ALL: [Self; 4] = [Self::Mp4, Self::Mov, Self::Matroska, Self::Webm];
+
+    /// A muxer name or the extension it writes (`mkv` for Matroska).
+    pub fn parse(name: &str) -> Result<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "mp4" => Ok(Self::Mp4),
+            "mov" => Ok(Self::Mov),
+            "matroska" | "mkv" => Ok(Self::Matroska),
+            "webm" => Ok(Self::Webm),
+            other => anyhow::bail!("Unknown container '{}'. Valid containers: mp4, mov, mkv, webm", other),
+        }
+    }
+
+    pub fn from_extension(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?;
+        Self::ALL.into_iter().find(|container| container.extension().eq_ignore_ascii_case(ext))
+    }
+
+    /// `forced` wins over the extension; `None` for an extension sonica
+    /// doesn't know, which is left to FFmpeg. Stdout defaults to Matroska,
+    /// which streams without seeking.
+    pub fn resolve(output_path: &Path, forced: Option<Self>) -> Option<Self> {
+        forced
+            .or_else(|| Self::from_extension(output_path))
+            .or_else(|| is_stdout(output_path).then_some(Self::Matroska))
+    }
+
+    /// FFmpeg's muxer name, for `-f`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Mp4 => "mp4",
+            Self::Mov => "mov",
+            Self::Matroska => "matroska",
+            Self::Webm => "webm",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Matroska => "mkv",
+            other => other.name(),
+        }
+    }
+
+    fn holds(self, format: &str) -> bool {
+        match self {
+            Self::Mp4 => matches!(format, "h264" | "hevc" | "av1" | "vp9"),
+            Self::Mov => matches!(format, "h264" | "hevc" | "prores"),
+            Self::Matroska => true,
+            Self::Webm => matches!(format, "av1" | "vp9"),
+        }
+    }
+
+    /// `--transparent`'s codec and pixel format: ProRes 4444 in MOV, which
+    /// editors import with its alpha, and VP9 with alpha in WebM and
+    /// Matroska. MP4 has no widely supported codec with alpha.
+    pub fn alpha_codec(self) -> Option<(&'static str, &'static str)> {
+        match self {
+            Self::Mov => Some(("prores_ks", "yuva444p10le")),
+            Self::Matroska | Self::Webm => Some(("libvpx-vp9", "yuva420p")),
+            Self::Mp4 => None,
+        }
+    }
+
+    /// Fail before encoding starts if `codec` can't be muxed into this
+    /// container, naming containers and codecs that would work.
+    pub fn check_codec(self, codec: &str) -> Result<()> {
+        let Some(&(_, format)) = ENCODER_FORMATS.iter().find(|(name, _)| *name == codec) else {
+            return Ok(());
+        };
+        if self.holds(format) {
+            return Ok(());
+        }
+        let containers: Vec<String> = Self::ALL
+            .into_iter()
+            .filter(|container| container.holds(format))
+            .map(|container| format!(".{}", container.extension()))
+            .collect();
+        // Hardware encoders are left out of the suggestions: they may not
+        // be available here.
+        let codecs: Vec<&str> = ENCODER_FORMATS
+            .iter()
+            .filter(|(name, format)| {
+                self.holds(format) && !["_videotoolbox", "_nvenc", "_qsv"].iter().any(|hw| name.ends_with(hw))
+            })
+            .map(|(name, _)| *name)
+            .collect();
+        anyhow::bail!(
+            "--codec {} ({}) can't be written to a .{} ({}) file. Write to {} instead, or pick a codec {} can hold: {}",
+            codec,
+            format,
+            self.extension(),
+            self.name(),
+            containers.join(" or "),
+            self.name(),
+            codecs.join(", ")
+        )
+    }
+
+    /// The soundtrack encoder: WebM only takes Opus or Vorbis.
+    fn audio_codec(self) -> &'static str {
+        match self {
+            Self::Webm => "libopus",
+            _ => "aac",
+        }
+    }
+
+    fn subtitle_codec(self) -> &'static str {
+        match self {
+            Self::Matroska => "srt",
+            Self::Webm => "webvtt",
+            Self::Mp4 | Self::Mov => "mov_text",
+        }
+    }
+}
+
+/// Whether FFmpeg pixel format `pix_fmt` has an alpha channel.
+pub fn has_alpha(pix_fmt: &str) -> bool {
+    pix_fmt.starts_with("yuva")
+        || pix_fmt.starts_with("gbrap")
+        || pix_fmt.starts_with("ya")
+        || ["rgba", "bgra", "argb", "abgr", "rgb32", "bgr32"]
+            .iter()
+            .any(|format| pix_fmt.starts_with(format))
+}
+
+/// `--color-matrix`: the YUV matrix that converts the rendered RGB frames,
+/// and the color metadata the stream is tagged with so players decode it
+/// with the same one instead of guessing from the resolution.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorMatrix {
+    Bt601,
+    #[default]
+    Bt709,
+    /// BT.2020 non-constant luminance; the sRGB frames are also converted
+    /// to the BT.2020 primaries.
+    Bt2020,
+}
+
+impl ColorMatrix {
+    /// `auto` follows the broadcast convention: BT.601 for SD (below 720
+    /// lines), BT.709 from HD up.
+    pub fn parse(name: &str, height: u32) -> Result<Self> {
+        match name {
+            "auto" if height < 720 => Ok(Self::Bt601),
+            "auto" | "bt709" => Ok(Self::Bt709),
+            "bt601" => Ok(Self::Bt601),
+            "bt2020" => Ok(Self::Bt2020),
+            other => anyhow::bail!(
+                "Unknown color matrix '{}'. Valid matrices: auto, bt601, bt709, bt2020",
+                other
+            ),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Bt601 => "bt601",
+            Self::Bt709 => "bt709",
+            Self::Bt2020 => "bt2020",
+        }
+    }
+
+    /// Conversion filter and stream tags: matrix, primaries, transfer.
+    fn args(self) -> Vec<OsString> {
+        let (filter, colorspace, primaries, transfer) = match self {
+            Self::Bt601 => ("scale=out_color_matrix=bt601:out_range=tv", "smpte170m", "smpte170m", "smpte170m"),
+            Self::Bt709 => ("scale=out_color_matrix=bt709:out_range=tv", "bt709", "bt709", "bt709"),
+            Self::Bt2020 => (
+                "scale=out_color_matrix=bt709:out_range=tv,colorspace=all=bt2020:iall=bt709",
+                "bt2020nc",
+                "bt2020",
+                "bt2020-10",
+            ),
+        };
+        ["-vf", filter, "-colorspace", colorspace, "-color_primaries", primaries, "-color_trc", transfer]
+            .map(OsString::from)
+            .to_vec()
+    }
+}
+
+/// Still image format for `--preview`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImageFormat {
+    /// Lossless; the default.
+    Png,
+    /// Lossy at a quality from 1 (smallest) to 100 (best).
+    Jpeg { quality: u8 },
+}
+
+impl ImageFormat {
+    pub const DEFAULT_JPEG_QUALITY: u8 = 90;
+
+    /// Parse `--image-format` with the optional `--image-quality`, which only
+    /// JPEG uses.
+    pub fn parse(name: &str, quality: Option<u8>) -> Result<Self> {
+        if quality.is_some_and(|q| !(1..=100).contains(&q)) {
+            anyhow::bail!("--image-quality must be between 1 and 100");
+        }
+        match name {
+            "png" => {
+                if quality.is_some() {
+                    log::warn!("--image-quality is ignored for PNG, which is lossless");
+                }
+                Ok(Self::Png)
+            }
+            "jpeg" | "jpg" => Ok(Self::Jpeg {
+                quality: quality.unwrap_or(Self::DEFAULT_JPEG_QUALITY),
+            }),
+            other => anyhow::bail!("Unknown image format '{}'. Valid formats: png, jpeg", other),
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Jpeg { .. } => "jpg",
+        }
+    }
+
+    /// Whether `path` already has an extension for this format.
+    pub fn matches_extension(self, path: &Path) -> bool {
+        let Some(ext) = path.extension().and_then(|ext| ext.to_str()) else {
+            return false;
+        };
+        match self {
+            Self::Png => ext.eq_ignore_ascii_case("png"),
+            Self::Jpeg { .. } => ext.eq_ignore_ascii_case("jpg") || ext.eq_ignore_ascii_case("jpeg"),
+        }
+    }
+
+    fn codec_args(self) -> Vec<OsString> {
+        match self {
+            Self::Png => vec!["-c:v".into(), "png".into()],
+            Self::Jpeg { quality } => {
+                // FFmpeg's MJPEG scale runs from 2 (best) to 31 (worst).
+                let qscale = 31 - (quality.clamp(1, 100) as u32 - 1) * 29 / 99;
+                vec!["-c:v".into(), "mjpeg".into(), "-q:v".into(), qscale.to_string().into()]
+            }
+        }
+    }
+}
+
+/// Encode a single RGBA frame as a still image.
+pub fn write_still(
+    output_path: &Path,
+    width: u32,
+    height: u32,
+    rgba_pixels: &[u8],
+    format: ImageFormat,
+) -> crate::error::Result<()> {
+    let mut child = Command::new("ffmpeg")
+        .args(build_still_args(output_path, width, height, format))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(SonicaError::FfmpegMissing)?;
+
+    child
+        .stdin
+        .take()
+        .context("FFmpeg stdin not available")?
+        .write_all(rgba_pixels)
+        .context("Failed to write frame to ffmpeg")?;
+
+    let output = child.wait_with_output().context("Failed to wait for ffmpeg")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+        return Err(SonicaError::Ffmpeg { stderr });
+    }
+    Ok(())
+}
+
+fn build_still_args(output_path: &Path, width: u32, height: u32, format: ImageFormat) -> Vec<OsString> {
+    let mut args: Vec<OsString> = vec![
+        "-hide_banner".into(),
+        "-loglevel".into(),
+        "error".into(),
+        "-y".into(),
+        "-f".into(),
+        "rawvideo".into(),
+        "-pixel_format".into(),
+        "rgba".into(),
+        "-video_size".into(),
+        format!("{}x{}", width, height).into(),
+        "-i".into(),
+        "pipe:0".into(),
+        "-frames:v".into(),
+        "1".into(),
+    ];
+    args.extend(format.codec_args());
+    // Write exactly this path rather than treating it as a sequence pattern.
+    args.extend(["-f".into(), "image2".into(), "-update".into(), "1".into()]);
+    args.push(output_path.as_os_str().to_owned());
+    args
+}
+
+/// Run FFmpeg in its own process group so a terminal Ctrl-C reaches only
+/// sonica, which then closes the pipe and lets FFmpeg finalize the file.
+#[cfg(unix)]
+fn detach_from_terminal_signals(command: &mut Command) {
+    use std::os::unix::process::CommandExt;
+    command.process_group(0);
+}
+
+#[cfg(windows)]
+fn detach_from_terminal_signals(command: &mut Command) {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+    command.creation_flags(CREATE_NEW_PROCESS_GROUP);
+}
+
+#[cfg(not(any(unix, windows)))]
+fn detach_from_terminal_signals(_command: &mut Command) {}
+
+fn build_intermediate_args(intermediate: &Path, width: u32, height: u32, fps: FrameRate) -> Vec<OsString> {
+    vec![
+        "-hide_banner".into(),
+        "-loglevel".into(),
+        "error".into(),
+        "-nostats".into(),
+        "-y".into(),
+        "-f".into(),
+        "rawvideo".into(),
+        "-pixel_format".into(),
+        "rgba".into(),
+        "-video_size".into(),
+        format!("{}x{}", width, height).into(),
+        "-framerate".into(),
+        fps.to_string().into(),
+        "-i".into(),
+        "pipe:0".into(),
+        "-c:v".into(),
+        "ffv1".into(),
+        "-level".into(),
+        "3".into(),
+        intermediate.as_os_str().to_owned(),
+    ]
+}
+
 #[allow(clippy::too_many_arguments)]
 fn build_args(
     output_path: &Path,
     input_audio: &Path,
     width: u32,
     height: u32,
-    fps: u32,
+    fps: FrameRate,
     codec: &str,
     pix_fmt: &str,
     crf: u32,
     bitrate: Option<&str>,
+    options: &EncodeOptions,
 ) -> Vec<OsString> {
     let mut args: Vec<OsString> = vec![
         "-hide_banner".into(),
@@ -115,13 +701,14 @@ fn build_args(
         fps.to_string().into(),
         "-i".into(),
         "pipe:0".into(),
-        "-i".into(),
-        input_audio.as_os_str().to_owned(),
-        "-c:v".into(),
-        codec.into(),
-        "-pix_fmt".into(),
-        pix_fmt.into(),
     ];
+    args.extend(audio_input_args(input_audio, options));
+    if let Some(subtitles) = &options.subtitles {
+        args.extend(["-i".into(), subtitles.as_os_str().to_owned()]);
+        args.extend(subtitle_track_args(output_path, options.container));
+    }
+    args.extend(["-c:v".into(), codec.into(), "-pix_fmt".into(), pix_fmt.into()]);
+    args.extend(options.color_matrix.args());
 
     if let Some(br) = bitrate {
         args.extend([OsString::from("-b:v"), OsString::from(br)]);
@@ -130,18 +717,88 @@ fn build_args(
         args.extend([OsString::from("-preset"), OsString::from("medium")]);
     }
 
-    args.extend([
-        "-c:a".into(),
-        "aac".into(),
-        "-b:a".into(),
-        "192k".into(),
-        "-shortest".into(),
-        output_path.as_os_str().to_owned(),
-    ]);
+    args.extend(output_audio_args(output_path, options));
+    args.push(output_arg(output_path));
+
+    args
+}
 
+/// Everything after the video codec that the final output needs: the
+/// soundtrack's gain and codec, the `--loop-audio` cut, and the muxer.
+fn output_audio_args(output_path: &Path, options: &EncodeOptions) -> Vec<OsString> {
+    let mut args = audio_gain_args(options.audio_gain_db);
+    args.extend(audio_codec_args(output_path, options.container));
+    args.extend(loop_length_args(options.loop_audio));
+    args.extend(container_args(output_path, options.container));
+    args
+}
+
+/// The optional parts of a video encode, shared by the single-pass command
+/// and both passes of a two-pass one. `Default` is a plain encode of the
+/// whole input with a BT.709 matrix and the container the extension implies.
+#[derive(Debug, Clone, Default)]
+pub struct EncodeOptions {
+    /// Soft subtitle track, muxed as a third input.
+    pub subtitles: Option<PathBuf>,
+    /// `--loop-audio`: repeat the soundtrack and cut the output at this many
+    /// seconds.
+    pub loop_audio: Option<f32>,
+    pub raw_input: Option<RawPcm>,
+    pub clip: Option<AudioClip>,
+    /// `--normalize-lufs`'s gain for the soundtrack.
+    pub audio_gain_db: Option<f32>,
+    /// `--container`, forced with `-f`.
+    pub container: Option<Container>,
+    pub color_matrix: ColorMatrix,
+}
+
+/// `--start`/`--end`: the part of the input audio that was rendered, in
+/// seconds, so the muxed soundtrack lines up with the frames.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AudioClip {
+    pub start: f32,
+    pub duration: f32,
+}
+
+/// The audio input, repeated endlessly with `--loop-audio`, read as
+/// headerless PCM with `--raw-input`, and cut to `--start`/`--end`.
+fn audio_input_args(input_audio: &Path, options: &EncodeOptions) -> Vec<OsString> {
+    let mut args: Vec<OsString> = Vec::new();
+    if options.loop_audio.is_some() {
+        args.extend(["-stream_loop".into(), "-1".into()]);
+    }
+    if let Some(raw) = options.raw_input {
+        args.extend(raw.ffmpeg_input_args().map(OsString::from));
+    }
+    if let Some(clip) = options.clip {
+        args.extend([
+            "-ss".into(),
+            format!("{:.3}", clip.start).into(),
+            "-t".into(),
+            format!("{:.3}", clip.duration).into(),
+        ]);
+    }
+    args.extend(["-i".into(), input_audio.as_os_str().to_owned()]);
     args
 }
 
+/// `--normalize-lufs`: the gain the analyzed samples got, applied to the
+/// soundtrack so it sounds as loud as it was visualized.
+fn audio_gain_args(gain_db: Option<f32>) -> Vec<OsString> {
+    match gain_db {
+        Some(gain_db) => vec!["-af".into(), format!("volume={:.2}dB", gain_db).into()],
+        None => Vec::new(),
+    }
+}
+
+/// Cut the output at the `--loop-audio` length.
+fn loop_length_args(loop_audio: Option<f32>) -> Vec<OsString> {
+    match loop_audio {
+        Some(seconds) => vec!["-t".into(), format!("{:.3}", seconds).into()],
+        None => Vec::new(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -150,11 +807,199 @@ mod tests {
     fn disables_progress_logs_and_preserves_paths() {
         let input = Path::new("audio input.wav");
         let output = Path::new("video output.mp4");
-        let args = build_args(output, input, 1280, 720, 30, "libx264", "yuv420p", 18, None);
+        let args = build_args(output, input, 1280, 720, FrameRate::integer(30), "libx264", "yuv420p", 18, None, &EncodeOptions::default());
 
         assert!(args.windows(2).any(|pair| pair == ["-loglevel", "error"]));
         assert!(args.iter().any(|arg| arg == "-nostats"));
         assert!(args.iter().any(|arg| arg == input.as_os_str()));
         assert_eq!(args.last().unwrap(), output.as_os_str());
     }
+
+    #[test]
+    fn passes_fractional_rates_as_exact_ratios() {
+        let fps = FrameRate::parse("23.976").unwrap();
+        let args = build_args(Path::new("out.mov"), Path::new("in.wav"), 1920, 1080, fps, "prores_ks", "yuv422p10le", 18, None, &EncodeOptions::default());
+        assert!(args.windows(2).any(|pair| pair == ["-framerate", "24000/1001"]));
+
+        let intermediate = build_intermediate_args(Path::new("out.mkv"), 1920, 1080, fps);
+        assert!(intermediate.windows(2).any(|pair| pair == ["-framerate", "24000/1001"]));
+    }
+
+    #[test]
+    fn two_pass_reads_the_intermediate_twice() {
+        let plan = TwoPass {
+            intermediate: TempFile::claim(sibling_path(Path::new("out/video.mp4"), "sonica-intermediate.mkv")),
+            passlog: TempFile::claim_prefix(sibling_path(Path::new("out/video.mp4"), "sonica-2pass")),
+            output: PathBuf::from("out/video.mp4"),
+            input_audio: PathBuf::from("song.flac"),
+            codec: "libx264".into(),
+            pix_fmt: "yuv420p".into(),
+            bitrate: "4M".into(),
+            options: EncodeOptions::default(),
+        };
+        assert_eq!(plan.intermediate.path(), Path::new("out/video.mp4.sonica-intermediate.mkv"));
+
+        let first = plan.pass_args(1);
+        assert!(first.windows(2).any(|pair| pair == ["-pass", "1"]));
+        assert!(first.windows(2).any(|pair| pair == ["-f", "null"]));
+        assert!(!first.iter().any(|arg| arg == "song.flac"));
+
+        let second = plan.pass_args(2);
+        assert!(second.windows(2).any(|pair| pair == ["-pass", "2"]));
+        assert!(second.windows(2).any(|pair| pair == ["-b:v", "4M"]));
+        assert!(second.iter().any(|arg| arg == "song.flac"));
+        assert_eq!(second.last().unwrap(), plan.output.as_os_str());
+    }
+
+    #[test]
+    fn soft_subtitles_are_muxed_as_a_third_input() {
+        let subtitles = Path::new("talk.srt");
+        let args = build_args(Path::new("talk.mp4"), Path::new("talk.wav"), 1280, 720, FrameRate::integer(30), "libx264", "yuv420p", 18, None, &EncodeOptions { subtitles: Some(subtitles.to_path_buf()), ..Default::default() });
+        let inputs: Vec<_> = args.windows(2).filter(|pair| pair[0] == "-i").map(|pair| &pair[1]).collect();
+        assert_eq!(inputs, ["pipe:0", "talk.wav", "talk.srt"]);
+        assert!(args.windows(2).any(|pair| pair == ["-map", "2:s"]));
+        assert!(args.windows(2).any(|pair| pair == ["-c:s", "mov_text"]));
+
+        let mkv = build_args(Path::new("talk.mkv"), Path::new("talk.wav"), 1280, 720, FrameRate::integer(30), "libx264", "yuv420p", 18, None, &EncodeOptions { subtitles: Some(subtitles.to_path_buf()), ..Default::default() });
+        assert!(mkv.windows(2).any(|pair| pair == ["-c:s", "srt"]));
+
+        let plan = TwoPass {
+            intermediate: TempFile::claim(PathBuf::from("talk.mp4.sonica-intermediate.mkv")),
+            passlog: TempFile::claim_prefix(PathBuf::from("talk.mp4.sonica-2pass")),
+            output: PathBuf::from("talk.mp4"),
+            input_audio: PathBuf::from("talk.wav"),
+            codec: "libx264".into(),
+            pix_fmt: "yuv420p".into(),
+            bitrate: "4M".into(),
+            options: EncodeOptions { subtitles: Some(subtitles.to_path_buf()), ..Default::default() },
+        };
+        assert!(!plan.pass_args(1).iter().any(|arg| arg == "talk.srt"));
+        assert!(plan.pass_args(2).windows(2).any(|pair| pair == ["-i", "talk.srt"]));
+    }
+
+    #[test]
+    fn looped_audio_repeats_the_input_and_cuts_at_the_target() {
+        let args = build_args(Path::new("loop.mp4"), Path::new("loop.wav"), 1280, 720, FrameRate::integer(30), "libx264", "yuv420p", 18, None, &EncodeOptions { loop_audio: Some(90.0), ..Default::default() });
+        let audio = args.iter().position(|arg| arg == "loop.wav").unwrap();
+        assert_eq!(args[audio - 3..audio], ["-stream_loop", "-1", "-i"]);
+        assert!(args.windows(2).any(|pair| pair == ["-t", "90.000"]));
+        assert_eq!(args.last().unwrap(), "loop.mp4");
+    }
+
+    #[test]
+    fn clipped_audio_is_seeked_and_cut_on_input() {
+        let clip = AudioClip { start: 62.5, duration: 30.0 };
+        let args = build_args(Path::new("chorus.mp4"), Path::new("song.flac"), 1280, 720, FrameRate::integer(30), "libx264", "yuv420p", 18, None, &EncodeOptions { clip: Some(clip), ..Default::default() });
+        let audio = args.iter().position(|arg| arg == "song.flac").unwrap();
+        assert_eq!(args[audio - 5..audio], ["-ss", "62.500", "-t", "30.000", "-i"]);
+    }
+
+    #[test]
+    fn normalized_audio_gets_the_same_gain() {
+        let args = build_args(Path::new("quiet.mp4"), Path::new("quiet.wav"), 1280, 720, FrameRate::integer(30), "libx264", "yuv420p", 18, None, &EncodeOptions { audio_gain_db: Some(7.25), ..Default::default() });
+        assert!(args.windows(2).any(|pair| pair == ["-af", "volume=7.25dB"]));
+        let plain = build_args(Path::new("quiet.mp4"), Path::new("quiet.wav"), 1280, 720, FrameRate::integer(30), "libx264", "yuv420p", 18, None, &EncodeOptions::default());
+        assert!(!plain.iter().any(|arg| arg == "-af"));
+    }
+
+    #[test]
+    fn raw_audio_is_muxed_with_its_format() {
+        let raw = RawPcm { format: crate::audio::decode::RawSampleFormat::S16le, sample_rate: 22_050, channels: 2 };
+        let args = build_args(Path::new("raw.mp4"), Path::new("dump.pcm"), 1280, 720, FrameRate::integer(30), "libx264", "yuv420p", 18, None, &EncodeOptions { raw_input: Some(raw), ..Default::default() });
+        let audio = args.iter().position(|arg| arg == "dump.pcm").unwrap();
+        assert_eq!(args[audio - 7..audio], ["-f", "s16le", "-ar", "22050", "-ac", "2", "-i"]);
+    }
+
+    #[test]
+    fn codecs_are_checked_against_the_container() {
+        assert_eq!(Container::resolve(Path::new("out.MKV"), None), Some(Container::Matroska));
+        assert_eq!(Container::resolve(Path::new("out.mp4"), Some(Container::Webm)), Some(Container::Webm));
+        assert_eq!(Container::resolve(Path::new("out.avi"), None), None);
+        assert_eq!(Container::parse("mkv").unwrap(), Container::Matroska);
+        assert!(Container::parse("avi").is_err());
+
+        assert!(Container::Mov.check_codec("prores_ks").is_ok());
+        assert!(Container::Matroska.check_codec("prores_ks").is_ok());
+        assert!(Container::Mp4.check_codec("some_new_encoder").is_ok());
+        let err = Container::Mp4.check_codec("prores_ks").unwrap_err().to_string();
+        assert!(err.contains(".mov or .mkv") && err.contains("libx264") && !err.contains("nvenc"), "{err}");
+        assert!(Container::Webm.check_codec("libx264").is_err());
+
+        let args = build_args(Path::new("out.mp4"), Path::new("in.wav"), 1280, 720, FrameRate::integer(30), "libvpx-vp9", "yuv420p", 18, Some("4M"), &EncodeOptions { container: Some(Container::Webm), ..Default::default() });
+        assert!(args.windows(2).any(|pair| pair == ["-c:a", "libopus"]));
+        assert_eq!(args[args.len() - 3..], ["-f", "webm", "out.mp4"]);
+    }
+
+    #[test]
+    fn transparent_output_picks_a_codec_the_container_holds_with_alpha() {
+        for container in Container::ALL {
+            let Some((codec, pix_fmt)) = container.alpha_codec() else {
+                assert_eq!(container, Container::Mp4);
+                continue;
+            };
+            assert!(container.check_codec(codec).is_ok(), "{codec} in {}", container.name());
+            assert!(has_alpha(pix_fmt), "{pix_fmt}");
+        }
+        assert!(!has_alpha("yuv420p"));
+        assert!(!has_alpha("yuv444p10le"));
+        assert!(has_alpha("rgba"));
+    }
+
+    #[test]
+    fn stdout_streams_matroska_or_fragmented_mp4() {
+        let stdout = Path::new("-");
+        let args = build_args(stdout, Path::new("in.wav"), 1280, 720, FrameRate::integer(30), "libx264", "yuv420p", 18, None, &EncodeOptions::default());
+        assert_eq!(args[args.len() - 3..], ["-f", "matroska", "pipe:1"]);
+
+        let mp4 = build_args(stdout, Path::new("in.wav"), 1280, 720, FrameRate::integer(30), "libx264", "yuv420p", 18, None, &EncodeOptions { container: Some(Container::Mp4), ..Default::default() });
+        assert_eq!(mp4[mp4.len() - 5..], ["-f", "mp4", "-movflags", "frag_keyframe+empty_moov", "pipe:1"]);
+
+        // A regular file keeps its seekable layout.
+        let file = build_args(Path::new("out.mp4"), Path::new("in.wav"), 1280, 720, FrameRate::integer(30), "libx264", "yuv420p", 18, None, &EncodeOptions::default());
+        assert!(!file.iter().any(|arg| arg == "-movflags" || arg == "pipe:1"));
+        assert!(is_stream(stdout) && !is_stream(Path::new("out.mp4")));
+    }
+
+    #[test]
+    fn color_matrix_converts_and_tags_the_stream() {
+        assert_eq!(ColorMatrix::parse("auto", 480).unwrap(), ColorMatrix::Bt601);
+        assert_eq!(ColorMatrix::parse("auto", 1080).unwrap(), ColorMatrix::Bt709);
+        assert!(ColorMatrix::parse("srgb", 1080).is_err());
+
+        let args = build_args(Path::new("sd.mp4"), Path::new("sd.wav"), 640, 480, FrameRate::integer(30), "libx264", "yuv420p", 18, None, &EncodeOptions { color_matrix: ColorMatrix::Bt601, ..Default::default() });
+        assert!(args.windows(2).any(|pair| pair == ["-vf", "scale=out_color_matrix=bt601:out_range=tv"]));
+        assert!(args.windows(2).any(|pair| pair == ["-colorspace", "smpte170m"]));
+        let hdr = ColorMatrix::Bt2020.args();
+        assert!(hdr.windows(2).any(|pair| pair == ["-color_primaries", "bt2020"]));
+    }
+
+    #[test]
+    fn still_args_write_one_frame() {
+        let output = Path::new("preview frame.png");
+        let args = build_still_args(output, 1080, 1920, ImageFormat::Png);
+
+        assert!(args.windows(2).any(|pair| pair == ["-video_size", "1080x1920"]));
+        assert!(args.windows(2).any(|pair| pair == ["-frames:v", "1"]));
+        assert!(args.windows(2).any(|pair| pair == ["-c:v", "png"]));
+        assert_eq!(args.last().unwrap(), output.as_os_str());
+    }
+
+    #[test]
+    fn jpeg_quality_maps_onto_the_mjpeg_scale() {
+        let qscale = |quality| {
+            let args = build_still_args(Path::new("f.jpg"), 64, 64, ImageFormat::Jpeg { quality });
+            assert!(args.windows(2).any(|pair| pair == ["-c:v", "mjpeg"]));
+            let at = args.iter().position(|arg| arg == "-q:v").unwrap();
+            args[at + 1].to_str().unwrap().parse::<u32>().unwrap()
+        };
+        assert_eq!(qscale(100), 2);
+        assert_eq!(qscale(1), 31);
+        assert!(qscale(90) < qscale(50));
+
+        assert_eq!(ImageFormat::parse("jpeg", None).unwrap(), ImageFormat::Jpeg { quality: 90 });
+        assert_eq!(ImageFormat::parse("png", None).unwrap(), ImageFormat::Png);
+        assert!(ImageFormat::parse("jpeg", Some(0)).is_err());
+        assert!(ImageFormat::parse("webp", None).is_err());
+        assert!(ImageFormat::Jpeg { quality: 90 }.matches_extension(Path::new("a.JPEG")));
+    }
 }
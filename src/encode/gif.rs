@@ -0,0 +1,189 @@
+use crate::framerate::FrameRate;
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+use super::FrameWriter;
+
+/// NeuQuant sampling speed, 1 (best palette) to 30 (fastest). 10 is the
+/// `gif` crate's own recommendation.
+const QUANTIZE_SPEED: i32 = 10;
+
+/// Whether `-o` asks for an animated GIF rather than a video.
+pub fn is_gif(output: &Path) -> bool {
+    output
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("gif"))
+}
+
+/// Writes an animated GIF with the `gif` crate instead of encoding a video,
+/// for short loops to share directly. The render runs at `--fps`; only the
+/// frames that start a new GIF frame at `--gif-fps` are kept, and each is
+/// quantized to its own 256-color palette with NeuQuant on a [`FrameWriter`]
+/// thread. There is no audio.
+pub struct GifEncoder {
+    /// Each kept frame travels with the GIF frame it starts.
+    writer: FrameWriter<usize>,
+    render_fps: FrameRate,
+    gif_fps: u32,
+    /// Render frames received so far.
+    next_index: usize,
+    /// GIF frame the next kept render frame must reach.
+    next_gif_frame: usize,
+}
+
+impl GifEncoder {
+    /// `loop_count` is how many times the animation plays; 0 loops forever.
+    pub fn new(
+        output: &Path,
+        width: u32,
+        height: u32,
+        render_fps: FrameRate,
+        gif_fps: u32,
+        loop_count: u16,
+    ) -> crate::error::Result<Self> {
+        if gif_fps == 0 {
+            return Err(anyhow::anyhow!("--gif-fps must be greater than zero").into());
+        }
+        let (Ok(gif_width), Ok(gif_height)) = (u16::try_from(width), u16::try_from(height)) else {
+            return Err(anyhow::anyhow!("A GIF is at most 65535x65535; {}x{} is too large", width, height).into());
+        };
+
+        let file = File::create(output).with_context(|| format!("Failed to create GIF: {}", output.display()))?;
+        let mut gif = gif::Encoder::new(BufWriter::new(file), gif_width, gif_height, &[])
+            .with_context(|| format!("Failed to write GIF header: {}", output.display()))?;
+        // Without the NETSCAPE extension a GIF plays once, and a count of 0 in
+        // it means forever, so N plays is N - 1 repeats.
+        match loop_count {
+            0 => gif.set_repeat(gif::Repeat::Infinite),
+            1 => Ok(()),
+            plays => gif.set_repeat(gif::Repeat::Finite(plays - 1)),
+        }
+        .context("Failed to write GIF loop count")?;
+
+        let path = output.to_path_buf();
+        let writer = FrameWriter::spawn("GIF", width, height, move |queued| {
+            // A frame's delay runs until the next kept frame starts, so each
+            // one is written when its successor arrives.
+            let mut pending: Option<(usize, Vec<u8>)> = None;
+            for (gif_frame, pixels) in queued {
+                if let Some((start, mut previous)) = pending.replace((gif_frame, pixels)) {
+                    write_gif_frame(&mut gif, &mut previous, gif_width, gif_height, delay(start, gif_frame, gif_fps))?;
+                }
+            }
+            if let Some((start, mut last)) = pending {
+                write_gif_frame(&mut gif, &mut last, gif_width, gif_height, delay(start, start + 1, gif_fps))?;
+            }
+            gif.into_inner()
+                .and_then(|mut file| std::io::Write::flush(&mut file))
+                .with_context(|| format!("Failed to write GIF: {}", path.display()))
+        });
+
+        log::info!(
+            "Writing {}x{} GIF at {}fps to {}",
+            width,
+            height,
+            gif_fps,
+            output.display()
+        );
+        Ok(Self {
+            writer,
+            render_fps,
+            gif_fps,
+            next_index: 0,
+            next_gif_frame: 0,
+        })
+    }
+
+    pub fn write_frame(&mut self, rgba_pixels: &[u8]) -> crate::error::Result<()> {
+        self.send_frame(rgba_pixels.to_vec())
+    }
+
+    /// Take the next render frame's tightly packed RGBA, queueing it for the
+    /// writer thread if it starts a new GIF frame and dropping it otherwise.
+    pub fn send_frame(&mut self, rgba_pixels: Vec<u8>) -> crate::error::Result<()> {
+        // Dropped frames are checked too, so a wrong size fails at once.
+        self.writer.check(&rgba_pixels)?;
+        // The GIF frame on screen when this render frame starts, counted
+        // exactly in the GIF's rate the way audio samples are.
+        let gif_frame = self.render_fps.frame_sample(self.next_index, self.gif_fps);
+        self.next_index += 1;
+        if gif_frame < self.next_gif_frame {
+            return Ok(());
+        }
+        self.next_gif_frame = gif_frame + 1;
+        Ok(self.writer.send(gif_frame, rgba_pixels)?)
+    }
+
+    /// Write the queued frames and the GIF trailer.
+    pub fn finish(mut self) -> crate::error::Result<()> {
+        self.writer.finish()?;
+        log::info!("Wrote {} GIF frames", self.next_gif_frame);
+        Ok(())
+    }
+}
+
+/// Delay in hundredths of a second, GIF's unit, from GIF frame `start` to
+/// `end`. Rounding each frame's start time rather than its length keeps a
+/// 15fps GIF at 7, 6, 7, ... instead of drifting at 7 every frame.
+fn delay(start: usize, end: usize, gif_fps: u32) -> u16 {
+    let centiseconds = |frame: usize| (frame as f64 * 100.0 / gif_fps as f64).round();
+    (centiseconds(end) - centiseconds(start)).min(u16::MAX as f64) as u16
+}
+
+fn write_gif_frame(
+    gif: &mut gif::Encoder<BufWriter<File>>,
+    rgba_pixels: &mut [u8],
+    width: u16,
+    height: u16,
+    delay: u16,
+) -> Result<()> {
+    let mut frame = gif::Frame::from_rgba_speed(width, height, rgba_pixels, QUANTIZE_SPEED);
+    frame.delay = delay;
+    // Every frame covers the whole canvas; clearing keeps the previous one
+    // from showing through transparent pixels of a --transparent render.
+    frame.dispose = gif::DisposalMethod::Background;
+    gif.write_frame(&frame).context("Failed to write GIF frame")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delays_round_start_times_so_they_do_not_drift() {
+        let delays: Vec<u16> = (0..6).map(|frame| delay(frame, frame + 1, 15)).collect();
+        assert_eq!(delays, [7, 6, 7, 7, 6, 7]);
+        assert_eq!(delays.iter().map(|&d| d as u32).sum::<u32>(), 40);
+        assert_eq!(delay(0, 3, 30), 10);
+    }
+
+    #[test]
+    fn keeps_one_render_frame_per_gif_frame_and_loops() {
+        let path = std::env::temp_dir().join(format!("sonica-gif-test-{}.gif", std::process::id()));
+        let (width, height) = (4, 3);
+        let mut encoder = GifEncoder::new(&path, width, height, FrameRate::integer(30), 15, 0).unwrap();
+        for frame in 0..5u8 {
+            encoder.send_frame(vec![frame * 50; (width * height * 4) as usize]).unwrap();
+        }
+        assert!(encoder.send_frame(vec![0; 4]).is_err(), "wrong frame size");
+        encoder.finish().unwrap();
+
+        let mut options = gif::DecodeOptions::new();
+        options.set_color_output(gif::ColorOutput::RGBA);
+        let mut decoder = options.read_info(File::open(&path).unwrap()).unwrap();
+        let mut frames = Vec::new();
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            frames.push((frame.delay, frame.buffer[0]));
+        }
+        assert_eq!(decoder.repeat(), gif::Repeat::Infinite);
+        // Render frames 0, 2 and 4 of 30fps start GIF frames 0, 1 and 2.
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames.iter().map(|&(delay, _)| delay).collect::<Vec<_>>(), [7, 6, 7]);
+        for (&(_, red), expected) in frames.iter().zip([0u8, 100, 200]) {
+            assert!(red.abs_diff(expected) <= 8, "{red} vs {expected}");
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+}
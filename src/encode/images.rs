@@ -0,0 +1,202 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+use super::FrameWriter;
+
+/// Digits in frame numbers when `-o` names a directory rather than a pattern.
+const DEFAULT_DIGITS: usize = 5;
+
+/// Where each frame of a PNG sequence goes: `-o frames/` numbers them
+/// `frames/00000.png`, `frames/00001.png`, ...; `-o frames/shot_%04d.png`
+/// follows the printf-style pattern, as FFmpeg's image2 muxer does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FramePattern {
+    dir: PathBuf,
+    prefix: String,
+    /// Zero-padded width of the frame number; 0 for `%d`, which doesn't pad.
+    digits: usize,
+    suffix: String,
+}
+
+impl FramePattern {
+    /// The pattern `output` names, or `None` for a video file or stream. A
+    /// directory is one that exists or a path ending in `/`; a file name is a
+    /// pattern when it ends in `.png` or holds a `%d`-style number, so
+    /// `-o 100%.mp4` stays a video.
+    pub fn parse(output: &Path) -> Result<Option<Self>> {
+        let text = output.to_string_lossy();
+        if text.ends_with('/') || text.ends_with(std::path::MAIN_SEPARATOR) || output.is_dir() {
+            return Ok(Some(Self {
+                dir: output.to_path_buf(),
+                prefix: String::new(),
+                digits: DEFAULT_DIGITS,
+                suffix: ".png".into(),
+            }));
+        }
+
+        let Some(name) = output.file_name().and_then(|name| name.to_str()) else {
+            return Ok(None);
+        };
+        let Some((prefix, spec)) = name.split_once('%') else {
+            return Ok(None);
+        };
+        let is_png = Path::new(name)
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("png"));
+        if !is_png && !spec.trim_start_matches(|c: char| c.is_ascii_digit()).starts_with('d') {
+            return Ok(None);
+        }
+        let end = spec
+            .find('d')
+            .with_context(|| format!("Invalid frame pattern '{}': use %d or %05d for the frame number", name))?;
+        let (width, suffix) = (&spec[..end], &spec[end + 1..]);
+        let digits = match width {
+            "" => 0,
+            width if width.starts_with('0') => width[1..]
+                .parse()
+                .with_context(|| format!("Invalid frame pattern '{}': use %d or %05d for the frame number", name))?,
+            _ => anyhow::bail!("Invalid frame pattern '{}': pad the frame number with zeros, as in %05d", name),
+        };
+        if suffix.contains('%') {
+            anyhow::bail!("Invalid frame pattern '{}': it may hold only one frame number", name);
+        }
+        if !is_png {
+            anyhow::bail!("Image sequences are written as PNG: end the pattern in .png, as in frames/%05d.png");
+        }
+
+        Ok(Some(Self {
+            dir: output.parent().map(Path::to_path_buf).unwrap_or_default(),
+            prefix: prefix.to_string(),
+            digits,
+            suffix: suffix.to_string(),
+        }))
+    }
+
+    /// The file for frame `index`.
+    pub fn path(&self, index: usize) -> PathBuf {
+        self.dir
+            .join(format!("{}{:0width$}{}", self.prefix, index, self.suffix, width = self.digits))
+    }
+}
+
+/// Writes each frame as a PNG file instead of encoding a video, for
+/// frame-accurate work in other tools. Frames are numbered from 0, so file N
+/// is frame N of the render. PNG encoding runs on a [`FrameWriter`] thread.
+/// There is no audio.
+pub struct ImageSequenceEncoder {
+    writer: FrameWriter<PathBuf>,
+    pattern: FramePattern,
+    next_index: usize,
+}
+
+impl ImageSequenceEncoder {
+    /// Creates the pattern's directory if needed.
+    pub fn new(pattern: FramePattern, width: u32, height: u32) -> crate::error::Result<Self> {
+        if !pattern.dir.as_os_str().is_empty() {
+            std::fs::create_dir_all(&pattern.dir)
+                .with_context(|| format!("Failed to create frame directory: {}", pattern.dir.display()))?;
+        }
+
+        let writer = FrameWriter::<PathBuf>::spawn("PNG", width, height, move |queued| {
+            for (path, pixels) in queued {
+                write_png(&path, &pixels, width, height)?;
+            }
+            Ok(())
+        });
+
+        log::info!(
+            "Writing {}x{} PNG frames to {}",
+            width,
+            height,
+            pattern.path(0).display()
+        );
+        Ok(Self {
+            writer,
+            pattern,
+            next_index: 0,
+        })
+    }
+
+    pub fn write_frame(&mut self, rgba_pixels: &[u8]) -> crate::error::Result<()> {
+        self.send_frame(rgba_pixels.to_vec())
+    }
+
+    /// Queue the next frame's tightly packed RGBA for the writer thread.
+    pub fn send_frame(&mut self, rgba_pixels: Vec<u8>) -> crate::error::Result<()> {
+        let path = self.pattern.path(self.next_index);
+        self.writer.send(path, rgba_pixels)?;
+        self.next_index += 1;
+        Ok(())
+    }
+
+    /// Wait for the queued frames to be written.
+    pub fn finish(mut self) -> crate::error::Result<()> {
+        self.writer.finish()?;
+        log::info!(
+            "Wrote {} PNG frames to {}",
+            self.next_index,
+            self.pattern.dir.display()
+        );
+        Ok(())
+    }
+}
+
+fn write_png(path: &Path, rgba_pixels: &[u8], width: u32, height: u32) -> Result<()> {
+    image::save_buffer_with_format(
+        path,
+        rgba_pixels,
+        width,
+        height,
+        image::ExtendedColorType::Rgba8,
+        image::ImageFormat::Png,
+    )
+    .with_context(|| format!("Failed to write PNG frame: {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn directories_and_patterns_number_frames_from_zero() {
+        let dir = FramePattern::parse(Path::new("frames/")).unwrap().unwrap();
+        assert_eq!(dir.path(0), Path::new("frames/00000.png"));
+        assert_eq!(dir.path(123), Path::new("frames/00123.png"));
+
+        let pattern = FramePattern::parse(Path::new("out/shot_%04d.png")).unwrap().unwrap();
+        assert_eq!(pattern.path(7), Path::new("out/shot_0007.png"));
+        let unpadded = FramePattern::parse(Path::new("f%d.PNG")).unwrap().unwrap();
+        assert_eq!(unpadded.path(42), Path::new("f42.PNG"));
+
+        assert_eq!(FramePattern::parse(Path::new("out.mp4")).unwrap(), None);
+        assert_eq!(FramePattern::parse(Path::new("-")).unwrap(), None);
+        assert_eq!(FramePattern::parse(Path::new("50%.mp4")).unwrap(), None);
+        assert_eq!(FramePattern::parse(Path::new("mix_50%_done.mp4")).unwrap(), None);
+        assert!(FramePattern::parse(Path::new("frames/%05d.jpg")).is_err());
+        assert!(FramePattern::parse(Path::new("frames/%5d.png")).is_err());
+        assert!(FramePattern::parse(Path::new("frames/%05d_%d.png")).is_err());
+    }
+
+    #[test]
+    fn writes_each_frame_as_an_rgba_png() {
+        let dir = std::env::temp_dir().join(format!("sonica-images-test-{}", std::process::id()));
+        let pattern = FramePattern::parse(&dir.join("%03d.png")).unwrap().unwrap();
+        let (width, height) = (3, 2);
+        let mut encoder = ImageSequenceEncoder::new(pattern, width, height).unwrap();
+        for frame in 0..2u8 {
+            let pixels = (0..width * height)
+                .flat_map(|i| [i as u8 * 40, frame * 100, 7, 255 - i as u8])
+                .collect::<Vec<_>>();
+            encoder.send_frame(pixels).unwrap();
+        }
+        assert!(encoder.send_frame(vec![0; 4]).is_err(), "wrong frame size");
+        encoder.finish().unwrap();
+
+        let image = image::open(dir.join("001.png")).unwrap().into_rgba8();
+        assert_eq!(image.dimensions(), (width, height));
+        // Row 1, column 2 is pixel 5.
+        assert_eq!(image.get_pixel(2, 1).0, [200, 100, 7, 250]);
+        assert!(dir.join("000.png").exists() && !dir.join("002.png").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1 +1,162 @@
+pub mod capabilities;
 pub mod ffmpeg;
+pub mod gif;
+pub mod images;
+
+use anyhow::{Context, Result};
+use std::sync::mpsc::{Receiver, SyncSender};
+use std::thread::JoinHandle;
+
+/// Frames queued for a writer thread beyond the one it is writing. Each is a
+/// full RGBA frame, so this stays small.
+const QUEUED_FRAMES: usize = 2;
+
+/// Where rendered frames go: the FFmpeg encoder, or any closure that takes
+/// each frame's tightly packed RGBA bytes.
+pub trait FrameSink {
+    fn write_frame(&mut self, rgba_pixels: &[u8]) -> Result<()>;
+}
+
+impl FrameSink for ffmpeg::FfmpegEncoder {
+    fn write_frame(&mut self, rgba_pixels: &[u8]) -> Result<()> {
+        Ok(ffmpeg::FfmpegEncoder::write_frame(self, rgba_pixels)?)
+    }
+}
+
+impl FrameSink for images::ImageSequenceEncoder {
+    fn write_frame(&mut self, rgba_pixels: &[u8]) -> Result<()> {
+        Ok(images::ImageSequenceEncoder::write_frame(self, rgba_pixels)?)
+    }
+}
+
+impl FrameSink for gif::GifEncoder {
+    fn write_frame(&mut self, rgba_pixels: &[u8]) -> Result<()> {
+        Ok(gif::GifEncoder::write_frame(self, rgba_pixels)?)
+    }
+}
+
+/// The CLI's video output: FFmpeg, a PNG sequence when `-o` names a
+/// directory or frame pattern, or an animated GIF for `-o *.gif`.
+pub enum Encoder {
+    Ffmpeg(ffmpeg::FfmpegEncoder),
+    Images(images::ImageSequenceEncoder),
+    Gif(gif::GifEncoder),
+}
+
+impl Encoder {
+    pub fn send_frame(&mut self, rgba_pixels: Vec<u8>) -> crate::error::Result<()> {
+        match self {
+            Self::Ffmpeg(encoder) => encoder.send_frame(rgba_pixels),
+            Self::Images(encoder) => encoder.send_frame(rgba_pixels),
+            Self::Gif(encoder) => encoder.send_frame(rgba_pixels),
+        }
+    }
+
+    pub fn finish(self) -> crate::error::Result<()> {
+        match self {
+            Self::Ffmpeg(encoder) => encoder.finish(),
+            Self::Images(encoder) => encoder.finish(),
+            Self::Gif(encoder) => encoder.finish(),
+        }
+    }
+}
+
+impl<F: FnMut(&[u8]) -> Result<()>> FrameSink for F {
+    fn write_frame(&mut self, rgba_pixels: &[u8]) -> Result<()> {
+        self(rgba_pixels)
+    }
+}
+
+/// The writer thread behind each encoder, so the render loop only waits on
+/// it when it falls `QUEUED_FRAMES` behind. Each frame's tightly packed RGBA
+/// travels with a `T` the thread needs for it (its file, its GIF frame).
+pub(crate) struct FrameWriter<T> {
+    /// What is written, for errors: "FFmpeg", "PNG", "GIF".
+    name: &'static str,
+    width: u32,
+    height: u32,
+    frames: Option<SyncSender<(T, Vec<u8>)>>,
+    writer: Option<JoinHandle<Result<()>>>,
+}
+
+impl<T: Send + 'static> FrameWriter<T> {
+    /// Run `write` on a new thread, fed by the queue until [`finish`](Self::finish).
+    pub(crate) fn spawn(
+        name: &'static str,
+        width: u32,
+        height: u32,
+        write: impl FnOnce(Receiver<(T, Vec<u8>)>) -> Result<()> + Send + 'static,
+    ) -> Self {
+        let (frames, queued) = std::sync::mpsc::sync_channel(QUEUED_FRAMES);
+        Self {
+            name,
+            width,
+            height,
+            frames: Some(frames),
+            writer: Some(std::thread::spawn(move || write(queued))),
+        }
+    }
+
+    /// Fail unless `rgba_pixels` is one `width`x`height` RGBA frame.
+    pub(crate) fn check(&self, rgba_pixels: &[u8]) -> Result<()> {
+        let expected = self.width as usize * self.height as usize * 4;
+        if rgba_pixels.len() != expected {
+            anyhow::bail!(
+                "Frame is {} bytes, but a {}x{} RGBA frame is {}",
+                rgba_pixels.len(),
+                self.width,
+                self.height,
+                expected
+            );
+        }
+        Ok(())
+    }
+
+    /// Queue a frame without copying it, blocking only while the queue is
+    /// full.
+    pub(crate) fn send(&mut self, tag: T, rgba_pixels: Vec<u8>) -> Result<()> {
+        self.check(&rgba_pixels)?;
+        let frames = self.frames.as_ref().with_context(|| format!("{} writer not available", self.name))?;
+        if frames.send((tag, rgba_pixels)).is_err() {
+            // The writer only hangs up after a failed write.
+            self.frames = None;
+            self.join()?;
+            anyhow::bail!("Failed to write {} frame: the writer stopped", self.name);
+        }
+        Ok(())
+    }
+
+    /// Hang up so the thread drains the queue and returns, and wait for it.
+    pub(crate) fn finish(&mut self) -> Result<()> {
+        drop(self.frames.take());
+        self.join()
+    }
+
+    fn join(&mut self) -> Result<()> {
+        let Some(writer) = self.writer.take() else {
+            return Ok(());
+        };
+        writer
+            .join()
+            .map_err(|_| anyhow::anyhow!("{} writer thread panicked", self.name))?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_failed_write_surfaces_the_writer_error() {
+        let mut writer = FrameWriter::<()>::spawn("test", 2, 1, |queued| {
+            queued.recv()?;
+            anyhow::bail!("disk full")
+        });
+        assert!(writer.send((), vec![0; 4]).is_err(), "wrong frame size");
+        writer.send((), vec![0; 8]).unwrap();
+        // The writer hangs up once its first write fails, within a queue's length.
+        let error = (0..=QUEUED_FRAMES + 1).find_map(|_| writer.send((), vec![0; 8]).err()).unwrap();
+        assert!(format!("{error:#}").contains("disk full"), "{error:#}");
+        assert!(writer.finish().is_ok(), "already joined");
+    }
+}
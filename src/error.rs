@@ -0,0 +1,46 @@
+use std::path::PathBuf;
+
+/// Failures at the library boundary: decoding, analysis, GPU setup, shader
+/// pipelines, and encoding return this, so callers can tell a missing FFmpeg
+/// from a broken shader without matching on message text. Everything below
+/// that boundary uses `anyhow` and surfaces as [`SonicaError::Other`].
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum SonicaError {
+    /// Neither Symphonia nor the FFmpeg fallback could decode the input, or
+    /// `--raw-input` PCM could not be read.
+    #[error("Failed to decode audio: {}", path.display())]
+    Decode {
+        path: PathBuf,
+        #[source]
+        source: anyhow::Error,
+    },
+
+    /// No Metal, Vulkan, or DX12 adapter is available.
+    #[error("Failed to find a suitable GPU adapter")]
+    NoGpu(#[source] wgpu::RequestAdapterError),
+
+    #[error("Failed to create GPU device")]
+    GpuDevice(#[source] wgpu::RequestDeviceError),
+
+    /// The frame size is beyond what the adapter supports.
+    #[error("{0}")]
+    GpuLimits(String),
+
+    /// A template or effect shader failed validation or pipeline creation.
+    #[error("Shader '{label}' failed to compile:\n{message}")]
+    ShaderCompile { label: String, message: String },
+
+    /// The `ffmpeg` executable could not be started.
+    #[error("Failed to spawn ffmpeg. Is ffmpeg installed?")]
+    FfmpegMissing(#[source] std::io::Error),
+
+    /// FFmpeg ran but exited unsuccessfully.
+    #[error("FFmpeg exited with error:\n{stderr}")]
+    Ffmpeg { stderr: String },
+
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+pub type Result<T, E = SonicaError> = std::result::Result<T, E>;
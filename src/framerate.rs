@@ -0,0 +1,138 @@
+use anyhow::{Context, Result};
+use std::fmt;
+
+/// An exact frame rate `num/den`, so NTSC rates like 24000/1001 (23.976) keep
+/// frames on the audio and the editing timeline instead of drifting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameRate {
+    num: u32,
+    den: u32,
+}
+
+/// Decimal spellings of the NTSC rates, which are really `N*1000/1001`.
+const NTSC_RATES: &[(&str, u32)] = &[
+    ("23.976", 24_000),
+    ("23.98", 24_000),
+    ("29.97", 30_000),
+    ("47.952", 48_000),
+    ("59.94", 60_000),
+    ("119.88", 120_000),
+];
+
+impl FrameRate {
+    pub const fn integer(fps: u32) -> Self {
+        Self { num: fps, den: 1 }
+    }
+
+    /// Parse `--fps`: an integer (`30`), a ratio (`24000/1001`), or a decimal
+    /// (`12.5`). The usual NTSC decimals (`23.976`, `29.97`, `59.94`) map to
+    /// their exact `/1001` ratios.
+    pub fn parse(spec: &str) -> Result<Self> {
+        let spec = spec.trim();
+        let invalid = || format!("Invalid frame rate '{spec}': use e.g. 30, 24000/1001 or 29.97");
+
+        let (num, den) = if let Some((num, den)) = spec.split_once('/') {
+            let num: u32 = num.trim().parse().ok().with_context(invalid)?;
+            let den: u32 = den.trim().parse().ok().with_context(invalid)?;
+            (num, den)
+        } else if let Some(&(_, num)) = NTSC_RATES.iter().find(|(decimal, _)| *decimal == spec) {
+            (num, 1001)
+        } else if let Some((whole, fraction)) = spec.split_once('.') {
+            if fraction.is_empty() || fraction.len() > 3 || !fraction.chars().all(|c| c.is_ascii_digit()) {
+                anyhow::bail!(invalid());
+            }
+            let den = 10u32.pow(fraction.len() as u32);
+            let whole: u32 = whole.parse().ok().with_context(invalid)?;
+            let fraction: u32 = fraction.parse().ok().with_context(invalid)?;
+            let num = whole.checked_mul(den).and_then(|n| n.checked_add(fraction)).with_context(invalid)?;
+            (num, den)
+        } else {
+            (spec.parse().ok().with_context(invalid)?, 1)
+        };
+
+        if num == 0 || den == 0 {
+            anyhow::bail!("Invalid frame rate '{spec}': must be greater than zero");
+        }
+        let divisor = gcd(num, den);
+        Ok(Self {
+            num: num / divisor,
+            den: den / divisor,
+        })
+    }
+
+    pub fn as_f32(self) -> f32 {
+        self.num as f32 / self.den as f32
+    }
+
+    /// Number of frames covering `duration` seconds.
+    pub fn frames_in(self, duration: f32) -> usize {
+        (duration as f64 * self.num as f64 / self.den as f64).ceil() as usize
+    }
+
+    /// Presentation time of frame `frame_idx` in seconds.
+    pub fn frame_time(self, frame_idx: usize) -> f32 {
+        (frame_idx as f64 * self.den as f64 / self.num as f64) as f32
+    }
+
+    /// Index of the frame shown at `time` seconds.
+    pub fn frame_at(self, time: f32) -> usize {
+        (time as f64 * self.num as f64 / self.den as f64) as usize
+    }
+
+    /// Sample position of frame `frame_idx`, computed as the exact rational
+    /// `frame_idx * sample_rate * den / num` so long renders don't drift from
+    /// the audio the way accumulating a float samples-per-frame does.
+    pub fn frame_sample(self, frame_idx: usize, sample_rate: u32) -> usize {
+        (frame_idx as u64 * sample_rate as u64 * self.den as u64 / self.num as u64) as usize
+    }
+}
+
+/// `30` or `24000/1001`, the form FFmpeg's `-framerate` accepts.
+impl fmt::Display for FrameRate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.den == 1 {
+            write!(f, "{}", self.num)
+        } else {
+            write!(f, "{}/{}", self.num, self.den)
+        }
+    }
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_integer_ratio_and_decimal_rates() {
+        assert_eq!(FrameRate::parse("30").unwrap(), FrameRate::integer(30));
+        assert_eq!(FrameRate::parse("24000/1001").unwrap().to_string(), "24000/1001");
+        assert_eq!(FrameRate::parse("23.976").unwrap().to_string(), "24000/1001");
+        assert_eq!(FrameRate::parse("59.94").unwrap().to_string(), "60000/1001");
+        assert_eq!(FrameRate::parse("12.5").unwrap().to_string(), "25/2");
+        assert_eq!(FrameRate::parse("60/2").unwrap().to_string(), "30");
+
+        assert!(FrameRate::parse("0").is_err());
+        assert!(FrameRate::parse("30/0").is_err());
+        assert!(FrameRate::parse("fast").is_err());
+        assert!(FrameRate::parse("29.").is_err());
+    }
+
+    #[test]
+    fn frame_timing_uses_the_exact_ratio() {
+        let ntsc = FrameRate::parse("30000/1001").unwrap();
+
+        // One hour of 29.97 is 107892.108 frames, not 108000.
+        assert_eq!(ntsc.frames_in(3600.0), 107_893);
+        assert_eq!(ntsc.frame_sample(30, 48_000), 48_048);
+        assert!((ntsc.frame_time(30) - 1.001).abs() < 1e-6);
+        assert_eq!(ntsc.frame_at(1.001), 30);
+    }
+}
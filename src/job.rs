@@ -0,0 +1,306 @@
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::audio::analysis::{self, AnalysisOptions};
+use crate::audio::channels::ChannelSelection;
+use crate::audio::decode::{self, AudioData, Downmix, RawPcm};
+use crate::audio::features::{GlobalAnalysis, SmoothedFrame};
+use crate::encode::FrameSink;
+use crate::error::{self, SonicaError};
+use crate::framerate::FrameRate;
+use crate::render::postprocess;
+use crate::render::renderer::Renderer;
+use crate::templates::animation::ParamCurve;
+use crate::templates::automation::Automation;
+use crate::templates::loader;
+use crate::templates::manifest::DefaultEffect;
+
+/// One render, configured with the setters and run with [`RenderJob::run`],
+/// which goes decode → analyze → render and hands every frame to a
+/// [`FrameSink`]. The stages are also public for callers that need to step in
+/// between them (the CLI transcribes after decoding, for one).
+#[derive(Debug, Clone)]
+pub struct RenderJob {
+    input: PathBuf,
+    templates: Vec<String>,
+    width: u32,
+    height: u32,
+    supersample: u32,
+    transparent: bool,
+    fps: FrameRate,
+    effects: Option<Vec<String>>,
+    effect_order: Vec<String>,
+    params: HashMap<String, String>,
+    param_curves: Vec<ParamCurve>,
+    automation: Automation,
+    downmix: Downmix,
+    channels: ChannelSelection,
+    raw_input: Option<RawPcm>,
+    keep_sides: bool,
+    /// `--start`/`--end` in seconds of the input.
+    time_range: Option<(f32, Option<f32>)>,
+    analysis: AnalysisOptions,
+}
+
+/// Per-frame analysis of the whole track.
+pub struct Analysis {
+    pub global: GlobalAnalysis,
+    pub frames: Vec<SmoothedFrame>,
+    /// Wall-clock time of the global, per-frame FFT, and smoothing passes.
+    pub pass_times: [Duration; 3],
+}
+
+impl RenderJob {
+    /// A 1920x1080, 30fps `frequency_bars` render of a local audio file.
+    pub fn new(input: impl Into<PathBuf>) -> Self {
+        Self {
+            input: input.into(),
+            templates: vec!["frequency_bars".into()],
+            width: 1920,
+            height: 1080,
+            supersample: 1,
+            transparent: false,
+            fps: FrameRate::integer(30),
+            effects: None,
+            effect_order: Vec::new(),
+            params: HashMap::new(),
+            param_curves: Vec::new(),
+            automation: Automation::default(),
+            downmix: Downmix::Average,
+            channels: ChannelSelection::default(),
+            raw_input: None,
+            keep_sides: false,
+            time_range: None,
+            analysis: AnalysisOptions::default(),
+        }
+    }
+
+    pub fn template(self, name: impl Into<String>) -> Self {
+        self.templates(vec![name.into()])
+    }
+
+    /// Several templates split the video into equal consecutive sections.
+    pub fn templates(mut self, names: Vec<String>) -> Self {
+        self.templates = names;
+        self
+    }
+
+    pub fn resolution(mut self, width: u32, height: u32) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    /// Render and post-process at `factor` times the resolution, then
+    /// box-filter down to it. 1 disables supersampling.
+    pub fn supersample(mut self, factor: u32) -> Self {
+        self.supersample = factor.max(1);
+        self
+    }
+
+    /// Clear each frame to transparent instead of opaque black, so the alpha
+    /// a template writes reaches the sink. Needs an encoder that keeps alpha.
+    pub fn transparent(mut self, transparent: bool) -> Self {
+        self.transparent = transparent;
+        self
+    }
+
+    pub fn fps(mut self, fps: FrameRate) -> Self {
+        self.fps = fps;
+        self
+    }
+
+    /// Post-processing effects. Without this the first template's defaults
+    /// apply; an empty list disables post-processing.
+    pub fn effects(mut self, effects: Vec<String>) -> Self {
+        self.effects = Some(effects);
+        self
+    }
+
+    /// Rearrange effects after presets are expanded; see
+    /// [`postprocess::reorder_effects`].
+    pub fn effect_order(mut self, order: Vec<String>) -> Self {
+        self.effect_order = order;
+        self
+    }
+
+    pub fn param(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.params.insert(name.into(), value.into());
+        self
+    }
+
+    pub fn param_anim(mut self, curve: ParamCurve) -> Self {
+        self.param_curves.push(curve);
+        self
+    }
+
+    /// External per-frame values for `automation.values`.
+    pub fn automation(mut self, automation: Automation) -> Self {
+        self.automation = automation;
+        self
+    }
+
+    pub fn downmix(mut self, downmix: Downmix) -> Self {
+        self.downmix = downmix;
+        self
+    }
+
+    /// Override the input's channel layout or analyze a subset of channels.
+    pub fn channels(mut self, selection: ChannelSelection) -> Self {
+        self.channels = selection;
+        self
+    }
+
+    /// Read the input as headerless PCM instead of probing its format.
+    pub fn raw_input(mut self, raw: RawPcm) -> Self {
+        self.raw_input = Some(raw);
+        self
+    }
+
+    /// Decode the left and right channels into `AudioData::sides` even when
+    /// the analysis doesn't read them, e.g. to measure loudness as the
+    /// soundtrack plays it.
+    pub fn keep_sides(mut self, keep: bool) -> Self {
+        self.keep_sides = keep;
+        self
+    }
+
+    /// Render only `start..end` seconds of the input (to its end without
+    /// `end`). The clip is analyzed on its own, so frame and beat times count
+    /// from its start; a beat grid is shifted to match.
+    pub fn time_range(mut self, start: f32, end: Option<f32>) -> Self {
+        self.time_range = Some((start, end));
+        self
+    }
+
+    pub fn analysis_options(mut self, options: AnalysisOptions) -> Self {
+        self.analysis = options;
+        self
+    }
+
+    pub fn input(&self) -> &Path {
+        &self.input
+    }
+
+    pub fn template_names(&self) -> &[String] {
+        &self.templates
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn supersample_factor(&self) -> u32 {
+        self.supersample
+    }
+
+    pub fn is_transparent(&self) -> bool {
+        self.transparent
+    }
+
+    pub fn frame_rate(&self) -> FrameRate {
+        self.fps
+    }
+
+    pub fn params(&self) -> &HashMap<String, String> {
+        &self.params
+    }
+
+    pub fn param_curves(&self) -> &[ParamCurve] {
+        &self.param_curves
+    }
+
+    pub fn automation_tracks(&self) -> &Automation {
+        &self.automation
+    }
+
+    /// The effects to run, in order: the configured list or the first
+    /// template's defaults, with presets expanded and `effect_order` applied.
+    pub fn resolved_effects(&self) -> Result<Vec<String>> {
+        let effects = match self.effects {
+            Some(ref effects) => effects.clone(),
+            None => {
+                let Some(first) = self.templates.first() else {
+                    anyhow::bail!("No templates found");
+                };
+                let effects: Vec<String> = loader::load_template(first)?
+                    .manifest
+                    .default_effects
+                    .iter()
+                    .map(DefaultEffect::spec)
+                    .collect();
+                postprocess::validate_effects(&effects)
+                    .with_context(|| format!("Template '{}' has invalid default_effects", first))?;
+                effects
+            }
+        };
+        postprocess::reorder_effects(postprocess::expand_effects(&effects), &self.effect_order)
+    }
+
+    pub fn decode(&self) -> error::Result<AudioData> {
+        let keep_sides = self.keep_sides || self.analysis.needs_sides();
+        let mut audio = match self.raw_input {
+            Some(raw) => decode::decode_raw(&self.input, raw, self.downmix, &self.channels, keep_sides),
+            None => decode::decode_audio(&self.input, self.downmix, &self.channels, keep_sides),
+        }?;
+        if let Some((start, end)) = self.time_range {
+            audio.clip(start, end)?;
+        }
+        Ok(audio)
+    }
+
+    pub fn analyze(&self, audio: &AudioData) -> error::Result<Analysis> {
+        let clipped;
+        let options = match (self.time_range, &self.analysis.beat_grid) {
+            (Some((start, _)), Some(grid)) => {
+                clipped = AnalysisOptions { beat_grid: Some(grid.clone().starting_at(start)), ..self.analysis.clone() };
+                &clipped
+            }
+            _ => &self.analysis,
+        };
+        let (global, frames, pass_times) = analysis::analyze_timed(audio, self.fps, options)?;
+        Ok(Analysis { global, frames, pass_times })
+    }
+
+    /// Initialize the GPU and build the template and effect pipelines.
+    pub fn renderer(&self, analysis: &Analysis) -> error::Result<Renderer> {
+        Renderer::new(self, analysis)
+    }
+
+    /// Run every stage, writing each RGBA frame to `sink` and reporting
+    /// `(frames done, total frames)` to `progress`. Returns the frame count;
+    /// an error from `sink` comes back as [`SonicaError::Other`].
+    pub fn run(
+        &self,
+        sink: &mut dyn FrameSink,
+        mut progress: impl FnMut(usize, usize),
+    ) -> error::Result<usize> {
+        let audio = self.decode()?;
+        let analysis = self.analyze(&audio)?;
+        let mut renderer = self.renderer(&analysis)?;
+
+        let total = analysis.frames.len();
+        let mut frames = analysis.frames.iter().enumerate().peekable();
+        let mut submitted = None;
+        while let Some((frame_idx, frame)) = frames.next() {
+            let current = match submitted.take() {
+                Some(current) => current,
+                None => renderer.submit_frame(frame_idx, frame)?,
+            };
+            // Submit the next frame first so it renders while this one is read back.
+            if let Some(&(next_idx, next)) = frames.peek() {
+                submitted = Some(renderer.submit_frame(next_idx, next)?);
+            }
+            let pixels = renderer.finish_frame(current)?;
+            sink.write_frame(&pixels).map_err(SonicaError::Other)?;
+            progress(frame_idx + 1, total);
+        }
+        Ok(total)
+    }
+}
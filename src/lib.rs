@@ -0,0 +1,39 @@
+//! sonica renders audio-reactive videos from WGSL templates on the GPU.
+//!
+//! The `sonica` binary is a CLI over this library. To embed the pipeline,
+//! describe a render with [`RenderJob`] and run it into a [`FrameSink`]: the
+//! FFmpeg encoder, or a closure that receives each frame as RGBA bytes.
+//!
+//! ```no_run
+//! use sonica::RenderJob;
+//!
+//! let job = RenderJob::new("track.wav")
+//!     .template("circular_spectrum")
+//!     .resolution(1280, 720)
+//!     .effects(vec!["bloom".into()]);
+//!
+//! let mut sink = |rgba: &[u8]| -> anyhow::Result<()> {
+//!     assert_eq!(rgba.len(), 1280 * 720 * 4);
+//!     Ok(())
+//! };
+//! job.run(&mut sink, |done, total| eprintln!("{done}/{total}"))?;
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+pub mod audio;
+pub mod encode;
+pub mod error;
+pub mod framerate;
+pub mod job;
+pub mod render;
+pub mod report;
+#[cfg(feature = "subtitles")]
+pub mod subtitle;
+pub mod temp;
+pub mod templates;
+
+pub use encode::FrameSink;
+pub use error::SonicaError;
+pub use framerate::FrameRate;
+pub use job::{Analysis, RenderJob};
+pub use render::renderer::Renderer;
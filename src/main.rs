@@ -1,25 +1,35 @@
 mod cli;
 mod config;
-mod audio;
-mod render;
-mod templates;
-mod encode;
-#[cfg(feature = "subtitles")]
-mod subtitle;
 
 use anyhow::{Context, Result};
 use clap::{CommandFactory, FromArgMatches};
-use indicatif::{ProgressBar, ProgressStyle};
-use std::collections::HashMap;
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
 
 use cli::Cli;
-use render::gpu::GpuContext;
-use render::pipeline::{ComputePipelineWrapper, FrameUniforms, RenderPipeline};
-use render::frame::{FrameRenderer, TEXTURE_FORMAT};
-use render::postprocess::PostProcessChain;
+use sonica::{audio, encode, render, report, templates, FrameRate, RenderJob};
+#[cfg(feature = "subtitles")]
+use sonica::subtitle;
+use render::background::Background;
+use render::contact_sheet::ContactSheet;
+use render::flash::{FlashGuard, FlashGuardMode};
+use render::debug_grid::DebugGrid;
+use render::energy_graph::EnergyGraph;
+use render::guides::SafeGuides;
+use render::hud::DebugHud;
+use render::script::ScriptedOverlay;
+use render::showcase::{FrameSource, ShowcaseGrid, ShowcaseLabels, ShowcaseLayout};
+use render::temporal::TemporalSmooth;
 use render::text::{load_font_from_url, TextOverlay};
+use render::waveform_strip::WaveformStrip;
 use encode::ffmpeg::FfmpegEncoder;
-use audio::features::SmoothedFrame;
+use encode::gif::GifEncoder;
+use encode::images::ImageSequenceEncoder;
+use encode::Encoder;
+use templates::animation::ParamCurve;
+use templates::automation::Automation;
 use templates::loader;
 
 /// Template name paired with its manifest description, falling back to an empty
@@ -66,33 +76,45 @@ fn effects_long_help() -> String {
     for (name, expansion) in render::postprocess::EFFECT_PRESETS {
         help.push_str(&format!("  {name:<22} {expansion}\n"));
     }
-    help.push_str("\nExample: --effects bloom,vignette");
+    help.push_str(
+        "\nAppend =INTENSITY (0.0-2.0, default 1.0) to weaken or strengthen an effect.\n\
+         Append @SCALE to run an effect at a fraction of the output resolution;\n\
+         the next pass upsamples it (the last effect always runs at full size).\n",
+    );
+    help.push_str("\nExample: --effects bloom@0.5,vignette=0.6");
     help
 }
 
-struct TemplateSlot {
-    pipeline: RenderPipeline,
-    bind_group: wgpu::BindGroup,
-    compute_pipeline: Option<ComputePipelineWrapper>,
-    name: String,
-    end_frame: usize,
-}
-
 fn main() -> Result<()> {
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
-        .format_timestamp_millis()
-        .init();
-
     // Attach the runtime-generated value lists before parsing so `--help`
     // documents the templates and effects this binary actually supports.
     let command = Cli::command()
         .mut_arg("template", |arg| arg.long_help(template_long_help()))
         .mut_arg("effects", |arg| arg.long_help(effects_long_help()));
-    let mut cli = match Cli::from_arg_matches(&command.get_matches()) {
+    let matches = command.get_matches();
+    // --dump-analysis renders only when -o is given, not from its default.
+    let output_given = matches.value_source("output") == Some(clap::parser::ValueSource::CommandLine);
+    let cli = match Cli::from_arg_matches(&matches) {
         Ok(cli) => cli,
         Err(err) => err.exit(),
     };
+    let run_started = Instant::now();
 
+    init_logging(cli.quiet, &cli.log_format)?;
+
+    let strict = cli.strict;
+    match run(cli, output_given, run_started) {
+        // Exit only once `run` has returned and dropped its temporary files.
+        Err(err) if err.is::<Interrupted>() => std::process::exit(130),
+        result => result?,
+    }
+    if strict {
+        check_no_warnings()?;
+    }
+    Ok(())
+}
+
+fn run(mut cli: Cli, output_given: bool, run_started: Instant) -> Result<()> {
     // Load config: explicit --config path, or auto-detect sonica.toml / global config
     let config_path = cli.config.clone().or_else(|| {
         let local = std::path::PathBuf::from("sonica.toml");
@@ -116,15 +138,62 @@ fn main() -> Result<()> {
     if let Some(ref path) = config_path {
         if let Some(cfg) = config::load_config(path) {
             log::info!("Loaded config from {}", path.display());
-            // Merge: config values apply only when CLI is at its default
+            // Merge: config values apply only when CLI is at its default. A
+            // [templates.<name>] section for the selected template comes first.
+            let section = cfg.templates.get(&cli.template);
+            if let Some(section) = section {
+                log::info!("Using [templates.{}] config section", cli.template);
+                // Listed first so the same --param on the command line wins.
+                let mut params: Vec<String> =
+                    section.params.iter().map(|(name, value)| format!("{name}={value}")).collect();
+                params.sort();
+                params.append(&mut cli.params);
+                cli.params = params;
+            }
             if cli.width == 1920 { cli.width = cfg.output.width; }
             if cli.height == 1080 { cli.height = cfg.output.height; }
-            if cli.fps == 30 { cli.fps = cfg.output.fps; }
+            if cli.ssaa == 1 { cli.ssaa = cfg.output.ssaa; }
+            if cli.fps == "30" { cli.fps = cfg.output.fps; }
             if cli.crf == 18 { cli.crf = cfg.output.crf; }
             if cli.codec == "libx264" { cli.codec = cfg.output.codec; }
-            if cli.smoothing == 0.85 { cli.smoothing = cfg.audio.smoothing; }
-            if cli.effects.is_empty() && !cfg.effects.is_empty() {
-                cli.effects = cfg.effects;
+            if cli.container.is_none() { cli.container = cfg.output.container; }
+            if cli.color_matrix == "auto" { cli.color_matrix = cfg.output.color_matrix; }
+            if !cli.transparent { cli.transparent = cfg.output.transparent; }
+            if cli.smoothing == 0.85 {
+                cli.smoothing = section.and_then(|s| s.smoothing).unwrap_or(cfg.audio.smoothing);
+            }
+            if cli.smoothing_bins.is_none() { cli.smoothing_bins = cfg.audio.smoothing_bins; }
+            if cli.smoothing_bands.is_none() { cli.smoothing_bands = cfg.audio.smoothing_bands; }
+            if cli.smoothing_rms.is_none() { cli.smoothing_rms = cfg.audio.smoothing_rms; }
+            if cli.downmix == "average" { cli.downmix = cfg.audio.downmix.clone(); }
+            if cli.channel_layout.is_none() { cli.channel_layout = cfg.audio.channel_layout.clone(); }
+            if cli.channels.is_empty() { cli.channels = cfg.audio.channels.clone(); }
+            if !cli.dual_fft { cli.dual_fft = cfg.audio.dual_fft; }
+            if cli.fft_size == 2048 { cli.fft_size = cfg.audio.fft_size; }
+            if cli.hop_size.is_none() { cli.hop_size = cfg.audio.hop_size; }
+            if !cli.stereo_bands { cli.stereo_bands = cfg.audio.stereo_bands; }
+            if !cli.stereo { cli.stereo = cfg.audio.stereo; }
+            if cli.analysis_rate.is_none() { cli.analysis_rate = cfg.audio.analysis_rate; }
+            if cli.max_memory.is_none() { cli.max_memory = cfg.audio.max_memory; }
+            if cli.waveform_normalize.is_none() { cli.waveform_normalize = cfg.audio.waveform_normalize.clone(); }
+            if cli.waveform_sampling == "envelope" { cli.waveform_sampling = cfg.audio.waveform_sampling.clone(); }
+            if cli.bin_peak_decay == 0.0 { cli.bin_peak_decay = cfg.audio.bin_peak_decay; }
+            if cli.bin_noise_floor == -60.0 { cli.bin_noise_floor = cfg.audio.bin_noise_floor; }
+            if cli.spectral_tilt == 0.0 { cli.spectral_tilt = cfg.audio.spectral_tilt; }
+            if cli.freq_min.is_none() { cli.freq_min = cfg.audio.freq_min; }
+            if cli.freq_max.is_none() { cli.freq_max = cfg.audio.freq_max; }
+            if cli.freq_scale == "linear" { cli.freq_scale = cfg.audio.freq_scale.clone(); }
+            if cli.beat_hold == 0.0 { cli.beat_hold = cfg.audio.beat_hold; }
+            if cli.beat_decay == "exponential" { cli.beat_decay = cfg.audio.beat_decay.clone(); }
+            if cli.effects.is_empty() {
+                if let Some(effects) = section.and_then(|s| s.effects.clone()) {
+                    cli.effects = effects;
+                } else if !cfg.effects.is_empty() {
+                    cli.effects = cfg.effects;
+                }
+            }
+            if cli.effect_order.is_empty() {
+                cli.effect_order = cfg.effect_order;
             }
             if cli.font.is_none() {
                 cli.font = cfg.output.font;
@@ -138,6 +207,9 @@ fn main() -> Result<()> {
             if cli.whisper_model == "base" {
                 cli.whisper_model = cfg.subtitle.whisper_model;
             }
+            if cli.model_repo == "ggerganov/whisper.cpp" {
+                cli.model_repo = cfg.subtitle.model_repo;
+            }
             if cli.subtitle_lang.is_none() {
                 cli.subtitle_lang = cfg.subtitle.language;
             }
@@ -147,6 +219,9 @@ fn main() -> Result<()> {
             if cli.subtitle_max_chars == 42 {
                 cli.subtitle_max_chars = cfg.subtitle.max_chars_per_line;
             }
+            if cli.subtitle_max_lines == 2 {
+                cli.subtitle_max_lines = cfg.subtitle.max_lines;
+            }
             if cli.subtitle_font.is_none() {
                 cli.subtitle_font = cfg.subtitle.font;
             }
@@ -180,6 +255,12 @@ fn main() -> Result<()> {
             if !cli.no_subtitle_karaoke && !cfg.subtitle.karaoke {
                 cli.no_subtitle_karaoke = true;
             }
+            if cli.karaoke_style == "sweep" {
+                cli.karaoke_style = cfg.subtitle.karaoke_style;
+            }
+            if cli.subtitle_mode == "burn" {
+                cli.subtitle_mode = cfg.subtitle.mode;
+            }
         } else {
             log::warn!("Failed to load config from {}", path.display());
         }
@@ -195,6 +276,18 @@ fn main() -> Result<()> {
         return Ok(());
     }
 
+    // Scaffold a custom template from an existing one
+    if let Some(ref args) = cli.copy_template {
+        let (existing, dest) = (&args[0], &args[1]);
+        let existing = existing.to_str().context("Template name must be valid UTF-8")?;
+        let name = loader::copy_template(existing, dest)?;
+        println!("Copied '{existing}' to {} as template '{name}'", dest.display());
+        if !loader::is_template_search_dir(dest.parent().unwrap_or(std::path::Path::new(""))) {
+            println!("Move it into ~/.config/sonica/templates/ to use it with -t {name}");
+        }
+        return Ok(());
+    }
+
     // List effects mode
     if cli.list_effects {
         println!("Available effects (pass with --effects, comma-separated):");
@@ -208,14 +301,48 @@ fn main() -> Result<()> {
         return Ok(());
     }
 
+    // List fonts mode: the installed families --font-family can name
+    if cli.list_fonts {
+        let families = render::text::installed_font_families();
+        if families.is_empty() {
+            println!("No installed fonts found; text uses the embedded LiberationMono");
+        } else {
+            println!("Installed font families (pass with --font or --font-family):");
+            for family in families {
+                println!("  {family}");
+            }
+        }
+        return Ok(());
+    }
+
+    // List formats mode: what the local FFmpeg build can encode
+    if cli.list_formats {
+        let caps = encode::capabilities::Capabilities::probe()?;
+        let mark = |available: bool| if available { "yes" } else { "no " };
+        println!("Video encoders (pass with --codec):");
+        for (name, note) in encode::capabilities::VIDEO_ENCODERS {
+            println!("  {} {name:<20} {note}", mark(caps.encoders.contains(*name)));
+        }
+        println!("\nAudio encoders:");
+        for (name, note) in encode::capabilities::AUDIO_ENCODERS {
+            println!("  {} {name:<20} {note}", mark(caps.encoders.contains(*name)));
+        }
+        println!("\nContainers (chosen by the -o extension, or --container):");
+        for (name, extension) in encode::capabilities::CONTAINERS {
+            println!("  {} {extension:<20} {name}", mark(caps.muxers.contains(*name)));
+        }
+        return Ok(());
+    }
+
     // Fail on unknown effect names now rather than warning mid-render and
     // producing a video that is silently missing the effect.
     render::postprocess::validate_effects(&cli.effects)?;
 
     let input = cli.input.as_ref().context("Input audio file is required")?;
-    if !input.exists() {
-        anyhow::bail!("Input file not found: {}", input.display());
-    }
+    let source = audio::source::AudioSource::parse(input);
+
+    // Only write the --dump-analysis JSON, without a video.
+    let analysis_only = cli.dump_analysis.is_some() && !output_given;
 
     if cli.subtitle_file.is_some()
         && (cli.subtitles || cli.write_subtitles.is_some() || cli.transcribe_only)
@@ -225,6 +352,19 @@ fn main() -> Result<()> {
         );
     }
 
+    if cli.font_family.is_none() {
+        if let Some(family) = font_path_as_family(cli.font.as_deref()) {
+            cli.font = None;
+            cli.font_family = Some(family);
+        }
+    }
+    if cli.subtitle_font_family.is_none() {
+        if let Some(family) = font_path_as_family(cli.subtitle_font.as_deref()) {
+            cli.subtitle_font = None;
+            cli.subtitle_font_family = Some(family);
+        }
+    }
+
     let title_font_sources = [
         cli.font.is_some(),
         cli.font_url.is_some(),
@@ -250,49 +390,504 @@ fn main() -> Result<()> {
         );
     }
 
+    if cli.subtitle_max_chars == 0 || cli.subtitle_max_lines == 0 {
+        anyhow::bail!("--subtitle-max-chars and --subtitle-max-lines must be at least 1");
+    }
+
+    if cli.preview.is_some_and(|time| !(time.is_finite() && time >= 0.0)) {
+        anyhow::bail!("--preview must be a non-negative time in seconds");
+    }
+    let contact_sheet_spec = cli
+        .contact_sheet
+        .as_deref()
+        .map(|args| -> Result<_> {
+            let grid = match args.get(1) {
+                Some(spec) => ContactSheet::parse_grid(spec)?,
+                None => ContactSheet::DEFAULT_GRID,
+            };
+            Ok((std::path::PathBuf::from(&args[0]), grid))
+        })
+        .transpose()?;
+    // A contact sheet is written to exactly the path given, so its
+    // extension picks the format.
+    let sheet_extension = contact_sheet_spec
+        .as_ref()
+        .and_then(|(path, _)| path.extension()?.to_str())
+        .map(str::to_ascii_lowercase);
+    let image_format_name = match sheet_extension.as_deref() {
+        Some("jpg" | "jpeg") => "jpeg",
+        Some("png") => "png",
+        _ => cli.image_format.as_str(),
+    };
+    let image_format = encode::ffmpeg::ImageFormat::parse(image_format_name, cli.image_quality)?;
+    let color_matrix = encode::ffmpeg::ColorMatrix::parse(&cli.color_matrix, cli.height)?;
+    let container = cli.container.as_deref().map(encode::ffmpeg::Container::parse).transpose()?;
+    // `-o frames/` or `-o frames/%05d.png` writes PNGs instead of a video.
+    let frame_pattern = encode::images::FramePattern::parse(&cli.output)?;
+    let gif_output = encode::gif::is_gif(&cli.output);
+    if (frame_pattern.is_some() || gif_output) && cli.two_pass {
+        anyhow::bail!("--two-pass is an FFmpeg option; image sequence and GIF output are written without FFmpeg");
+    }
+    if gif_output && cli.gif_fps == 0 {
+        anyhow::bail!("--gif-fps must be greater than zero");
+    }
+    // The encoder checks this too, but only after decode and analysis.
+    if contact_sheet_spec.is_none()
+        && cli.preview.is_none()
+        && !cli.transcribe_only
+        && !analysis_only
+        && frame_pattern.is_none()
+        && !gif_output
+    {
+        let resolved = encode::ffmpeg::Container::resolve(&cli.output, container);
+        if cli.transparent && cli.codec == "libx264" && cli.pix_fmt == "yuv420p" {
+            let (codec, pix_fmt) = resolved.and_then(encode::ffmpeg::Container::alpha_codec).context(
+                "--transparent needs a container that can hold alpha: write to .mov, .webm, or .mkv, \
+                 or pick an alpha-capable --codec and --pix-fmt",
+            )?;
+            log::info!("Transparent output: encoding with {} ({})", codec, pix_fmt);
+            cli.codec = codec.to_string();
+            cli.pix_fmt = pix_fmt.to_string();
+        } else if cli.transparent && !encode::ffmpeg::has_alpha(&cli.pix_fmt) {
+            anyhow::bail!(
+                "--transparent needs a pixel format with alpha, such as yuva420p; --pix-fmt {} would drop it",
+                cli.pix_fmt
+            );
+        }
+        if let Some(resolved) = resolved {
+            resolved.check_codec(&cli.codec)?;
+        }
+    }
+
+    // Checked up front so a long render can't end by clobbering, or failing
+    // to replace, an earlier result. A stamped output is ours to replace
+    // with --skip-if-unchanged.
+    let output_path = match (&contact_sheet_spec, cli.preview) {
+        (Some((path, _)), _) => path.clone(),
+        (None, Some(_)) => preview_path(&cli.output, image_format),
+        (None, None) => match frame_pattern {
+            Some(ref pattern) => pattern.path(0),
+            None => cli.output.clone(),
+        },
+    };
+    let skip_if_unchanged = cli.skip_if_unchanged && !cli.transcribe_only && !analysis_only;
+    let streams = encode::ffmpeg::is_stream(&output_path);
+    if encode::ffmpeg::is_stdout(&cli.output) && contact_sheet_spec.is_none() && cli.preview.is_some() {
+        anyhow::bail!("-o - streams a video to stdout; --preview writes an image file, so give it a path");
+    }
+    if skip_if_unchanged && streams {
+        anyhow::bail!("--skip-if-unchanged needs an output file to compare against, not a stream");
+    }
+    // A named pipe already exists by design: a reader is waiting on it.
+    if !cli.overwrite && !cli.transcribe_only && !analysis_only && !streams {
+        let stamped = skip_if_unchanged && report::RenderStamp::sidecar(&output_path).exists();
+        if output_path.exists() && !stamped {
+            anyhow::bail!(
+                "Output file already exists: {}. Pass --overwrite to replace it",
+                output_path.display()
+            );
+        }
+    }
+    let downmix = audio::decode::Downmix::parse(&cli.downmix)?;
+    let raw_input = cli
+        .raw_input
+        .as_deref()
+        .map(|format| -> Result<_> {
+            let sample_rate = cli.sample_rate.context("--raw-input needs --sample-rate")?;
+            if !(1000..=768_000).contains(&sample_rate) {
+                anyhow::bail!("--sample-rate must be between 1000 and 768000 Hz");
+            }
+            if !(1..=32).contains(&cli.raw_channels) {
+                anyhow::bail!("--raw-channels must be between 1 and 32");
+            }
+            Ok(audio::decode::RawPcm {
+                format: audio::decode::RawSampleFormat::parse(format)?,
+                sample_rate,
+                channels: cli.raw_channels,
+            })
+        })
+        .transpose()?;
+    let channels = audio::channels::ChannelSelection::parse(cli.channel_layout.as_deref(), &cli.channels)?;
+    if let Some(ref mode) = cli.apply_replaygain {
+        if mode != "track" && mode != "album" {
+            anyhow::bail!("Unknown ReplayGain mode '{}'. Valid modes: track, album", mode);
+        }
+    }
+    let waveform_normalize = cli
+        .waveform_normalize
+        .as_deref()
+        .map(audio::analysis::WaveformNormalize::parse)
+        .transpose()?;
+    let waveform_sampling = audio::analysis::WaveformSampling::parse(&cli.waveform_sampling)?;
+    let freq_scale = audio::analysis::FreqScale::parse(&cli.freq_scale)?;
+
+    let param_curves = cli
+        .param_anim
+        .iter()
+        .map(|spec| ParamCurve::parse(spec))
+        .collect::<Result<Vec<_>>>()?;
+    let automation = cli.automation.as_deref().map(Automation::load).transpose()?;
+    let safe_guides = cli.safe_guides.as_deref().map(SafeGuides::parse).transpose()?;
+    let showcase = cli.showcase.as_deref().map(ShowcaseLayout::parse).transpose()?;
+    if cli.debug_grid == Some(0) {
+        anyhow::bail!("--debug-grid spacing must be at least 1 pixel");
+    }
+    if cli.debug_grid.is_some() && cli.preview.is_none() && !cli.keep_debug_grid {
+        log::warn!("--debug-grid is only drawn on --preview; add --keep-debug-grid to draw it into the output");
+    }
+
+    for (flag, smoothing) in [
+        ("--smoothing-bins", cli.smoothing_bins),
+        ("--smoothing-bands", cli.smoothing_bands),
+        ("--smoothing-rms", cli.smoothing_rms),
+    ] {
+        if smoothing.is_some_and(|value| !(0.0..=1.0).contains(&value)) {
+            anyhow::bail!("{} must be between 0.0 and 1.0", flag);
+        }
+    }
+    if !(cli.bin_peak_decay.is_finite() && cli.bin_peak_decay >= 0.0) {
+        anyhow::bail!("--bin-peak-decay must be a non-negative number of seconds");
+    }
+    if !(cli.bin_noise_floor.is_finite() && cli.bin_noise_floor <= 0.0) {
+        anyhow::bail!("--bin-noise-floor must be 0 dB or below");
+    }
+    if !(cli.spectral_tilt.is_finite() && cli.spectral_tilt.abs() <= 12.0) {
+        anyhow::bail!("--spectral-tilt must be between -12 and 12 dB per octave");
+    }
+    for (flag, hz) in [("--freq-min", cli.freq_min), ("--freq-max", cli.freq_max)] {
+        if hz.is_some_and(|hz| !(hz.is_finite() && hz >= 0.0)) {
+            anyhow::bail!("{} must be a non-negative frequency in Hz", flag);
+        }
+    }
+    if let (Some(min), Some(max)) = (cli.freq_min, cli.freq_max) {
+        if min >= max {
+            anyhow::bail!("--freq-min ({} Hz) must be below --freq-max ({} Hz)", min, max);
+        }
+    }
+    let hop_size = cli.hop_size.unwrap_or(cli.fft_size / 2);
+    audio::analysis::check_fft_sizes(cli.fft_size, hop_size).context("Invalid --fft-size or --hop-size")?;
+    if cli.analysis_rate.is_some_and(|rate| !(8000..=192_000).contains(&rate)) {
+        anyhow::bail!("--analysis-rate must be between 8000 and 192000 Hz");
+    }
+    if cli.loop_audio.is_some_and(|seconds| !(seconds.is_finite() && seconds > 0.0)) {
+        anyhow::bail!("--loop-audio must be a positive number of seconds");
+    }
+    if cli.start.is_some_and(|seconds| !(seconds.is_finite() && seconds >= 0.0)) {
+        anyhow::bail!("--start must be a non-negative number of seconds");
+    }
+    if cli.end.is_some_and(|seconds| !(seconds.is_finite() && seconds > 0.0)) {
+        anyhow::bail!("--end must be a positive number of seconds");
+    }
+    // `--start`/`--end` in seconds of the input; frame times count from start.
+    let time_range = (cli.start.is_some() || cli.end.is_some()).then(|| (cli.start.unwrap_or(0.0), cli.end));
+    if let Some((start, Some(end))) = time_range {
+        if end <= start {
+            anyhow::bail!("--end ({}s) must be after --start ({}s)", end, start);
+        }
+    }
+    if cli.normalize_lufs.is_some_and(|lufs| !(lufs.is_finite() && (-70.0..=0.0).contains(&lufs))) {
+        anyhow::bail!("--normalize-lufs must be between -70 and 0 LUFS, such as -14");
+    }
+    if cli.normalize_lufs.is_some() && cli.apply_replaygain.is_some() {
+        anyhow::bail!("--normalize-lufs and --apply-replaygain both set the track's level; use one");
+    }
+    if time_range.is_some() && cli.loop_audio.is_some() {
+        anyhow::bail!("--loop-audio repeats the whole input; it can't be combined with --start or --end");
+    }
+    if !(0.0..1.0).contains(&cli.temporal_smooth) {
+        anyhow::bail!("--temporal-smooth must be at least 0.0 and below 1.0");
+    }
+    let flash_guard_mode = cli.flash_guard.as_deref().map(FlashGuardMode::parse).transpose()?;
+    if !(cli.beat_hold.is_finite() && cli.beat_hold >= 0.0) {
+        anyhow::bail!("--beat-hold must be a non-negative number of milliseconds");
+    }
+    let beat_decay = audio::analysis::BeatDecay::parse(&cli.beat_decay)?;
+    let beat_grid = cli.beat_grid.as_deref().map(audio::beatgrid::BeatGrid::load).transpose()?;
+    let mut cue_sheet = cli.cue_sheet.as_deref().map(audio::cuesheet::CueSheet::load).transpose()?;
+    if let Some(ref sheet) = cue_sheet {
+        log::info!("Cue sheet: {} tracks", sheet.track_count());
+    }
+    if !(cli.track_separator.is_finite() && cli.track_separator >= 0.0) {
+        anyhow::bail!("--track-separator must be a non-negative number of seconds");
+    }
+
+    let fps = FrameRate::parse(&cli.fps).context("Invalid --fps")?;
+
     log::info!("sonica - GPU-accelerated audio visualizer");
     log::info!("Input: {}", input.display());
     log::info!("Output: {}", cli.output.display());
     log::info!("Template: {}", cli.template);
-    log::info!("Resolution: {}x{} @ {}fps", cli.width, cli.height, cli.fps);
+    log::info!("Resolution: {}x{} @ {}fps", cli.width, cli.height, fps);
+    if cli.width == 0 || cli.height == 0 {
+        anyhow::bail!("--width and --height must be at least 1");
+    }
+    if ![1, 2, 4].contains(&cli.ssaa) {
+        anyhow::bail!("--ssaa must be 1, 2 or 4");
+    }
+    let (render_width, render_height) = (cli.width.saturating_mul(cli.ssaa), cli.height.saturating_mul(cli.ssaa));
+    // An analysis dump on its own never touches the GPU.
+    if !analysis_only {
+        render::gpu::GpuContext::check_resolution(render_width, render_height).with_context(|| {
+            if cli.ssaa > 1 {
+                format!("--ssaa {} renders at {}x{}", cli.ssaa, render_width, render_height)
+            } else {
+                "Unsupported resolution".to_string()
+            }
+        })?;
+    }
+
+    // Resolve template names and effects: "none" disables all, CLI > template defaults
+    let template_names: Vec<String> = if cli.template == "all" || showcase.is_some() {
+        loader::list_templates()?
+    } else {
+        vec![cli.template.clone()]
+    };
+    if template_names.is_empty() {
+        anyhow::bail!("No templates found");
+    }
+
+    let local_input = source.open()?;
+    let render_stamp = if skip_if_unchanged {
+        let stamp = report::RenderStamp::new(&output_path, local_input.path(), &settings_fingerprint(&cli))?;
+        if !cli.overwrite && stamp.is_current(&output_path) {
+            log::info!(
+                "{} is up to date with the input and settings; skipping (pass --overwrite to render anyway)",
+                output_path.display()
+            );
+            return Ok(());
+        }
+        stamp.clear()?;
+        Some(stamp)
+    } else {
+        None
+    };
+    let mut analysis_options = audio::analysis::AnalysisOptions {
+        smoothing: cli.smoothing,
+        smoothing_bins: cli.smoothing_bins,
+        smoothing_bands: cli.smoothing_bands,
+        smoothing_rms: cli.smoothing_rms,
+        bin_peak_decay: cli.bin_peak_decay,
+        bin_noise_floor_db: cli.bin_noise_floor,
+        spectral_tilt: cli.spectral_tilt,
+        freq_min: cli.freq_min,
+        freq_max: cli.freq_max,
+        dual_fft: cli.dual_fft,
+        fft_size: cli.fft_size,
+        hop_size,
+        stereo_bands: cli.stereo_bands || cli.stereo,
+        stereo_waveform: cli.stereo,
+        beat_hold: cli.beat_hold / 1000.0,
+        beat_decay,
+        beat_grid,
+        analysis_rate: cli.analysis_rate,
+        fft_bins: None,
+        waveform_normalize,
+        waveform_sampling,
+        freq_scale,
+    };
+    let mut job = RenderJob::new(local_input.path())
+        .templates(template_names)
+        .resolution(cli.width, cli.height)
+        .supersample(cli.ssaa)
+        .transparent(cli.transparent)
+        .fps(fps)
+        .downmix(downmix)
+        .channels(channels)
+        .keep_sides(cli.normalize_lufs.is_some())
+        .analysis_options(analysis_options.clone());
+    if cli.effects.iter().any(|e| e == "none") {
+        job = job.effects(Vec::new());
+    } else if !cli.effects.is_empty() {
+        job = job.effects(cli.effects.clone());
+    }
+    if !cli.effect_order.is_empty() {
+        job = job.effect_order(cli.effect_order.clone());
+    }
+    for (key, value) in cli.params.iter().filter_map(|s| s.split_once('=')) {
+        job = job.param(key, value);
+    }
+    for curve in param_curves {
+        job = job.param_anim(curve);
+    }
+    if let Some(automation) = automation {
+        job = job.automation(automation);
+    }
+    if let Some(raw) = raw_input {
+        job = job.raw_input(raw);
+    }
+    if let Some((start, end)) = time_range {
+        job = job.time_range(start, end);
+        cue_sheet = cue_sheet.map(|sheet| sheet.starting_at(start));
+    }
+
+    // Stage wall-clock times for --benchmark; cheap enough to always collect.
+    let mut timings = report::StageTimings::default();
 
     // 1. Decode audio
     log::info!("Decoding audio...");
-    let audio_data = audio::decode::decode_audio(input)?;
+    let decode_started = Instant::now();
+    let mut audio_data = job.decode()?;
+    timings.record("decode", decode_started.elapsed());
+    let audio_clip = time_range.map(|(start, _)| {
+        let duration = audio_data.samples.len() as f32 / audio_data.sample_rate as f32;
+        log::info!("Rendering {:.1}s of audio from {:.1}s", duration, start);
+        encode::ffmpeg::AudioClip { start, duration }
+    });
+
+    if let Some(ref mode) = cli.apply_replaygain {
+        match audio_data.tags.replay_gain.linear_gain(mode) {
+            Some(gain) => {
+                log::info!("Applying {} ReplayGain: {:+.2} dB", mode, 20.0 * gain.log10());
+                for sample in &mut audio_data.samples {
+                    *sample *= gain;
+                }
+            }
+            None => log::warn!("--apply-replaygain: input has no ReplayGain tags"),
+        }
+    }
+
+    // One gain for the whole track, so levels are comparable across quiet
+    // and loud masters; FFmpeg applies the same gain to the soundtrack, so
+    // loudness and peaks are measured on its channels, not the mono mix.
+    let mut audio_gain_db = None;
+    if let Some(target) = cli.normalize_lufs {
+        match audio::loudness::integrated_loudness(&audio_data.channels(), audio_data.sample_rate) {
+            Some(loudness) => {
+                let gain = audio::loudness::gain_to(loudness, target);
+                log::info!(
+                    "Loudness {:.1} LUFS; applying {:+.2} dB to reach {:.1} LUFS",
+                    loudness,
+                    20.0 * gain.log10(),
+                    target
+                );
+                audio_data.apply_gain(gain);
+                let peak = audio_data
+                    .channels()
+                    .iter()
+                    .flat_map(|channel| channel.iter())
+                    .fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+                if peak > 1.0 {
+                    log::warn!(
+                        "--normalize-lufs {}: peaks reach {:+.1} dBFS and will clip in the soundtrack; try a lower target",
+                        target,
+                        20.0 * peak.log10()
+                    );
+                }
+                audio_gain_db = Some(20.0 * gain.log10());
+            }
+            None => log::warn!("--normalize-lufs: the audio is too short or too quiet to measure; leaving its level"),
+        }
+    }
+
+    // Loop before transcription and analysis so subtitles, beats, and every
+    // visual repeat in step with the audio FFmpeg loops.
+    if let Some(seconds) = cli.loop_audio {
+        let length = audio_data.samples.len() as f32 / audio_data.sample_rate as f32;
+        log::info!("Repeating {:.1}s of audio to fill {:.1}s", length, seconds);
+        audio_data.repeat_to(seconds);
+    }
+
+    // The frame count is known once the audio is decoded (and looped); keep
+    // the analysis inside --max-memory by storing fewer FFT bins per frame.
+    if let Some(megabytes) = cli.max_memory {
+        let length = audio_data.samples.len() as f32 / audio_data.sample_rate as f32;
+        let frames = fps.frames_in(length);
+        let budget = megabytes.saturating_mul(1024 * 1024);
+        let full_bins = analysis_options.fft_size / 2;
+        let full = audio::analysis::analysis_memory(frames, full_bins, &analysis_options);
+        match audio::analysis::bins_within(frames, full_bins, budget, &analysis_options) {
+            Some(bins) if bins == full_bins => {}
+            Some(bins) => {
+                log::warn!(
+                    "--max-memory: {} frames need ~{} MB of analysis data at {} FFT bins; using {} bins instead",
+                    frames,
+                    full.div_ceil(1024 * 1024),
+                    full_bins,
+                    bins
+                );
+                analysis_options.fft_bins = Some(bins);
+                job = job.analysis_options(analysis_options);
+            }
+            None => anyhow::bail!(
+                "--max-memory {} MB is too small for {} frames even with reduced FFT bins; \
+                 raise it, lower --fps, or render a shorter section",
+                megabytes,
+                frames
+            ),
+        }
+    }
+
+    let title = cli.title.clone().or_else(|| {
+        if !cli.title_from_tags {
+            return None;
+        }
+        let title = audio_data.tags.display_title();
+        match &title {
+            Some(title) => log::info!("Title from tags: {}", title),
+            None => log::warn!("--title-from-tags: input has no title or artist tags"),
+        }
+        title
+    });
 
     // 1b. Transcribe audio (if subtitles enabled)
     #[cfg(feature = "subtitles")]
+    let subtitle_mode = subtitle::SubtitleMode::parse(&cli.subtitle_mode)?;
+    #[cfg(feature = "subtitles")]
     let subtitle_cues = if let Some(ref subtitle_path) = cli.subtitle_file {
-        let cues = subtitle::srt::read_srt(subtitle_path)?;
+        let mut cues = subtitle::import::read_subtitles(subtitle_path)?;
+        if let Some((start, end)) = time_range {
+            cues = subtitle::import::clip_cues(cues, start, end);
+        }
         log::info!(
             "Loaded {} subtitle cues from {}",
             cues.len(),
             subtitle_path.display()
         );
+        if let Some(ref vtt_path) = cli.export_vtt {
+            subtitle::vtt::write_vtt(vtt_path, &cues, cli.vtt_word_timestamps)?;
+            log::info!("Wrote WebVTT subtitles to {}", vtt_path.display());
+        }
         Some(cues)
-    } else if cli.subtitles || cli.write_subtitles.is_some() {
+    } else if cli.subtitles || cli.write_subtitles.is_some() || cli.export_vtt.is_some() {
         log::info!("Transcribing audio for subtitles...");
-        let model_path = subtitle::model::resolve_model_path(&cli.whisper_model)?;
+        let model_path = subtitle::model::resolve_model_path(
+            &cli.whisper_model,
+            &cli.model_repo,
+            cli.redownload_model,
+        )?;
         let transcriber = subtitle::transcribe::WhisperTranscriber::new(
             &model_path,
             cli.subtitle_lang.as_deref(),
         )?;
+        let transcribe_started = Instant::now();
         let words = transcriber.transcribe(&audio_data.samples, audio_data.sample_rate)?;
-        log::info!("Whisper returned {} word segments:", words.len());
+        timings.record("transcription", transcribe_started.elapsed());
+        log::info!("Whisper returned {} word segments", words.len());
         for (i, w) in words.iter().enumerate() {
-            log::info!("  [{:3}] {:.2}s - {:.2}s  {:?}", i, w.start_time, w.end_time, w.text);
+            log::debug!("  [{:3}] {:.2}s - {:.2}s  {:?}", i, w.start_time, w.end_time, w.text);
         }
-        let cues = subtitle::cue::group_words(words, cli.subtitle_max_chars);
-        log::info!("Grouped into {} subtitle cues:", cues.len());
+        let cues = subtitle::cue::group_words(
+            words,
+            cli.subtitle_max_chars,
+            cli.subtitle_max_lines,
+        );
+        log::info!("Grouped into {} subtitle cues", cues.len());
         for (i, c) in cues.iter().enumerate() {
-            log::info!("  [{:3}] {:.2}s - {:.2}s  {:?}", i, c.start_time, c.end_time, c.text);
+            log::debug!("  [{:3}] {:.2}s - {:.2}s  {:?}", i, c.start_time, c.end_time, c.text);
         }
         if let Some(ref subtitle_path) = cli.write_subtitles {
             subtitle::srt::write_srt(subtitle_path, &cues)?;
             log::info!("Wrote subtitles to {}", subtitle_path.display());
         }
+        if let Some(ref vtt_path) = cli.export_vtt {
+            subtitle::vtt::write_vtt(vtt_path, &cues, cli.vtt_word_timestamps)?;
+            log::info!("Wrote WebVTT subtitles to {}", vtt_path.display());
+        }
         if cli.transcribe_only {
             log::info!("Transcription complete; skipping video render");
+            if cli.benchmark {
+                eprint!("{}", timings.table(run_started.elapsed()));
+            }
             return Ok(());
         }
         Some(cues)
@@ -304,7 +899,9 @@ fn main() -> Result<()> {
     if cli.subtitles
         || cli.subtitle_file.is_some()
         || cli.write_subtitles.is_some()
+        || cli.export_vtt.is_some()
         || cli.transcribe_only
+        || cli.subtitle_mode != "burn"
     {
         anyhow::bail!(
             "Subtitle support requires the 'subtitles' feature. \
@@ -314,152 +911,170 @@ fn main() -> Result<()> {
 
     // 2. Analyze audio (3-pass pipeline)
     log::info!("Analyzing audio...");
-    let (global, frames) = audio::analysis::analyze(&audio_data, cli.fps, cli.smoothing)?;
+    let analysis_started = Instant::now();
+    let analysis = job.analyze(&audio_data)?;
+    let (global, frames) = (&analysis.global, &analysis.frames);
 
+    let analysis_seconds = analysis_started.elapsed().as_secs_f64();
+    for (pass, time) in ["global", "per-frame FFT", "smoothing"].iter().zip(analysis.pass_times) {
+        timings.record(format!("analysis: {pass}"), time);
+    }
     let total_frames = frames.len();
     log::info!("Total frames: {}, Duration: {:.1}s", total_frames, global.duration);
 
-    // 3. Resolve template names
-    let template_names: Vec<String> = if cli.template == "all" {
-        loader::list_templates()?
-    } else {
-        vec![cli.template.clone()]
-    };
-
-    if template_names.is_empty() {
-        anyhow::bail!("No templates found");
+    if let Some(ref path) = cli.dump_analysis {
+        audio::export::write_analysis(path, fps, global, frames, cli.dump_fft)?;
+        log::info!("Wrote analysis of {} frames to {}", total_frames, path.display());
+        if analysis_only {
+            log::info!("Analysis written; skipping video render (pass -o to render as well)");
+            if cli.benchmark {
+                eprint!("{}", timings.table(run_started.elapsed()));
+            }
+            return Ok(());
+        }
     }
 
-    // Determine effects: "none" disables all, CLI > template defaults
-    let first_template = loader::load_template(&template_names[0])?;
-    let effects = if cli.effects.iter().any(|e| e == "none") {
-        Vec::new()
-    } else if cli.effects.is_empty() {
-        first_template.manifest.default_effects.clone()
-    } else {
-        cli.effects.clone()
+    let preview_frame = match cli.preview {
+        Some(time) => {
+            let frame_idx = fps.frame_at(time);
+            if frame_idx >= total_frames {
+                anyhow::bail!(
+                    "--preview {:.2}s is past the end of the audio ({:.2}s)",
+                    time,
+                    global.duration
+                );
+            }
+            Some(frame_idx)
+        }
+        None => None,
     };
-    drop(first_template);
-
-    // 4. Initialize GPU
-    log::info!("Initializing GPU...");
-    let gpu = GpuContext::new()?;
-    let frame_renderer = FrameRenderer::new(&gpu, cli.width, cli.height);
-
-    // 5. Create shared GPU buffers
-    let uniform_buffer = gpu.device.create_buffer(&wgpu::BufferDescriptor {
-        label: Some("uniform_buffer"),
-        size: std::mem::size_of::<FrameUniforms>() as u64,
-        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-        mapped_at_creation: false,
-    });
-
-    let num_fft_bins = if frames.is_empty() { 1024 } else { frames[0].fft_bins.len() };
-    let fft_buffer = gpu.device.create_buffer(&wgpu::BufferDescriptor {
-        label: Some("fft_buffer"),
-        size: (num_fft_bins * std::mem::size_of::<f32>()) as u64,
-        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
-        mapped_at_creation: false,
-    });
 
-    let num_waveform = if frames.is_empty() { 512 } else { frames[0].waveform.len() };
-    let waveform_buffer = gpu.device.create_buffer(&wgpu::BufferDescriptor {
-        label: Some("waveform_buffer"),
-        size: (num_waveform * std::mem::size_of::<f32>()) as u64,
-        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
-        mapped_at_creation: false,
-    });
+    // 3. Initialize the GPU and build the template and effect pipelines
+    let pipeline_started = Instant::now();
+    let mut renderer = match showcase {
+        Some(ShowcaseLayout::Grid) => FrameSource::Grid(ShowcaseGrid::new(&job, &analysis)?),
+        _ => FrameSource::Single(Box::new(job.renderer(&analysis)?)),
+    };
+    timings.record("pipeline build", pipeline_started.elapsed());
+    if cli.transparent && !renderer.effects().is_empty() {
+        log::warn!(
+            "--transparent: effects ({}) output opaque frames, so the video will have no transparency; \
+             pass --effects none to keep the template's alpha",
+            renderer.effects().join(", ")
+        );
+    }
 
-    // 6. Parse template parameter overrides
-    let param_overrides: HashMap<String, String> = cli
-        .params
-        .iter()
-        .filter_map(|s| {
-            let mut parts = s.splitn(2, '=');
-            let key = parts.next()?.to_string();
-            let val = parts.next()?.to_string();
-            Some((key, val))
-        })
-        .collect();
-
-    // 7. Build per-template pipelines and bind groups, assign frame ranges
-    let num_templates = template_names.len();
-    let frames_per_template = total_frames / num_templates;
-    let mut slots: Vec<TemplateSlot> = Vec::with_capacity(num_templates);
-
-    for (i, name) in template_names.iter().enumerate() {
-        let tmpl = loader::load_template(name)?;
-        let shader_src = loader::inject_params(&tmpl.fragment_shader, &tmpl.manifest, &param_overrides);
-        let pipeline = RenderPipeline::new(&gpu.device, &shader_src, TEXTURE_FORMAT)?;
-
-        let bind_group = gpu.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("main_bind_group"),
-            layout: &pipeline.bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: uniform_buffer.as_entire_binding(),
+    let render_report = cli
+        .report
+        .clone()
+        .map(|path| -> Result<_> {
+            let report = report::RenderReport {
+                sonica_version: env!("CARGO_PKG_VERSION"),
+                input: report::InputReport {
+                    path: input.display().to_string(),
+                    sha256: report::sha256_file(local_input.path())?,
+                    sample_rate: audio_data.sample_rate,
+                    duration_seconds: global.duration,
                 },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: fft_buffer.as_entire_binding(),
+                output: cli.output.display().to_string(),
+                video: report::VideoReport {
+                    width: cli.width,
+                    height: cli.height,
+                    fps: fps.to_string(),
+                    codec: cli.codec.clone(),
+                    pix_fmt: cli.pix_fmt.clone(),
+                    color_matrix: color_matrix.name().to_string(),
+                    crf: cli.bitrate.is_none().then_some(cli.crf),
+                    bitrate: cli.bitrate.clone(),
                 },
-                wgpu::BindGroupEntry {
-                    binding: 2,
-                    resource: waveform_buffer.as_entire_binding(),
+                templates: job.template_names().to_vec(),
+                effects: renderer.effects().to_vec(),
+                params: job
+                    .params()
+                    .iter()
+                    .map(|(key, value)| (key.clone(), value.clone()))
+                    .collect(),
+                param_anim: cli.param_anim.clone(),
+                analysis: report::AnalysisReport {
+                    tempo_bpm: global.tempo_bpm,
+                    beats: global.beat_times.len(),
+                    peak_rms: global.peak_rms,
+                    smoothing: cli.smoothing,
+                    downmix: cli.downmix.clone(),
                 },
-            ],
-        });
-
-        let compute_pipeline = if let Some(ref compute_src) = tmpl.compute_shader {
-            let compute_src = loader::inject_params(compute_src, &tmpl.manifest, &param_overrides);
-            Some(ComputePipelineWrapper::new(&gpu.device, &compute_src)?)
-        } else {
-            None
-        };
+                gpu: report::GpuReport::from_adapter(renderer.adapter_info()),
+                timing: report::TimingReport::default(),
+            };
+            Ok((path, report))
+        })
+        .transpose()?;
 
-        let start_frame = i * frames_per_template;
-        let end_frame = if i == num_templates - 1 {
-            total_frames
-        } else {
-            (i + 1) * frames_per_template
-        };
+    // 3b. Soft subtitle track: an existing SRT is muxed as is; transcribed
+    // cues go through a temporary SRT unless --write-subtitles saved one.
+    let writes_video = preview_frame.is_none() && contact_sheet_spec.is_none();
+    #[cfg(feature = "subtitles")]
+    let (soft_subtitles, temporary_subtitles) = match subtitle_cues {
+        Some(_) if subtitle_mode.muxes() && writes_video && (frame_pattern.is_some() || gif_output) => {
+            log::warn!("--subtitle-mode {}: image sequences and GIFs have no text track to mux into", cli.subtitle_mode);
+            (None, None)
+        }
+        Some(ref cues) if subtitle_mode.muxes() && writes_video => {
+            // A clipped --subtitle-file no longer matches the video's times.
+            let subtitle_file = cli.subtitle_file.clone().filter(|_| time_range.is_none());
+            if let Some(path) = subtitle_file.or_else(|| cli.write_subtitles.clone()) {
+                (Some(path), None)
+            } else {
+                let (temporary, _) = sonica::temp::TempFile::create("subtitles", ".srt")?;
+                subtitle::srt::write_srt(temporary.path(), cues)?;
+                (Some(temporary.path().to_path_buf()), Some(temporary))
+            }
+        }
+        _ => (None, None),
+    };
+    #[cfg(not(feature = "subtitles"))]
+    let (soft_subtitles, temporary_subtitles): (Option<std::path::PathBuf>, Option<sonica::temp::TempFile>) = (None, None);
 
-        log::info!(
-            "Template [{}]: {} (frames {}-{})",
-            i, tmpl.manifest.display_name, start_frame, end_frame - 1
-        );
+    // 4. Start FFmpeg encoder (a preview or contact sheet writes an image
+    // instead, and an image sequence or GIF is written without FFmpeg)
+    let mut encoder = if !writes_video {
+        None
+    } else if let Some(pattern) = frame_pattern {
+        Some(Encoder::Images(ImageSequenceEncoder::new(pattern, cli.width, cli.height)?))
+    } else if gif_output {
+        Some(Encoder::Gif(GifEncoder::new(
+            &cli.output,
+            cli.width,
+            cli.height,
+            fps,
+            cli.gif_fps,
+            cli.gif_loop_count,
+        )?))
+    } else {
+        log::info!("Starting FFmpeg encoder...");
+        Some(Encoder::Ffmpeg(FfmpegEncoder::new(
+            &cli.output,
+            local_input.path(),
+            cli.width,
+            cli.height,
+            fps,
+            &cli.codec,
+            &cli.pix_fmt,
+            cli.crf,
+            cli.bitrate.as_deref(),
+            cli.two_pass,
+            encode::ffmpeg::EncodeOptions {
+                subtitles: soft_subtitles,
+                loop_audio: cli.loop_audio,
+                raw_input,
+                clip: audio_clip,
+                audio_gain_db,
+                container,
+                color_matrix,
+            },
+        )?))
+    };
 
-        slots.push(TemplateSlot {
-            pipeline,
-            bind_group,
-            compute_pipeline,
-            name: tmpl.manifest.display_name.clone(),
-            end_frame,
-        });
-    }
-
-    // 7b. Post-processing chain
-    let pp_chain = PostProcessChain::new(&gpu.device, cli.width, cli.height, &effects)?;
-    if pp_chain.has_effects() {
-        log::info!("Post-processing effects: {:?}", effects);
-    }
-
-    // 8. Start FFmpeg encoder
-    log::info!("Starting FFmpeg encoder...");
-    let mut encoder = FfmpegEncoder::new(
-        &cli.output,
-        input,
-        cli.width,
-        cli.height,
-        cli.fps,
-        &cli.codec,
-        &cli.pix_fmt,
-        cli.crf,
-        cli.bitrate.as_deref(),
-    )?;
-
-    // 8. Text overlay
+    // 5. Text overlay
     let font_bytes = if let Some(ref font_url) = cli.font_url {
         match load_font_from_url(font_url) {
             Ok(bytes) => Some(bytes),
@@ -472,6 +1087,18 @@ fn main() -> Result<()> {
         None
     };
 
+    if let Some(ref path) = cli.energy_graph {
+        let graph = EnergyGraph::new(cli.font.as_deref(), font_bytes.as_deref(), cli.font_family.as_deref());
+        let pixels = graph.render(frames, global.duration, cli.energy_graph_bands);
+        let format = if encode::ffmpeg::ImageFormat::Png.matches_extension(path) {
+            encode::ffmpeg::ImageFormat::Png
+        } else {
+            encode::ffmpeg::ImageFormat::parse("jpeg", cli.image_quality)?
+        };
+        encode::ffmpeg::write_still(path, EnergyGraph::WIDTH, EnergyGraph::HEIGHT, &pixels, format)?;
+        log::info!("Energy graph written to {}", path.display());
+    }
+
     #[cfg(feature = "subtitles")]
     let subtitle_font_bytes = if let Some(ref font_url) = cli.subtitle_font_url {
         match load_font_from_url(font_url) {
@@ -485,7 +1112,7 @@ fn main() -> Result<()> {
         None
     };
 
-    let text_overlay = if cli.title.is_some() || cli.show_time {
+    let text_overlay = if title.is_some() || cue_sheet.is_some() || cli.show_time || cli.show_note {
         let shorter = cli.width.min(cli.height) as f32;
         let font_size = (shorter * 0.046).max(24.0);
         Some(TextOverlay::new(
@@ -498,9 +1125,101 @@ fn main() -> Result<()> {
         None
     };
 
-    // 8b. Subtitle renderer
+    let scripted_overlay = cli
+        .overlay_script
+        .as_deref()
+        .map(|path| {
+            let shorter = cli.width.min(cli.height) as f32;
+            ScriptedOverlay::load(
+                path,
+                (shorter * 0.046).max(24.0),
+                cli.font.as_deref(),
+                font_bytes.as_deref(),
+                cli.font_family.as_deref(),
+            )
+        })
+        .transpose()?;
+
+    let mut contact_sheet = contact_sheet_spec.map(|(path, grid)| {
+        let sheet = ContactSheet::new(
+            grid,
+            cli.width,
+            cli.height,
+            cli.font.as_deref(),
+            font_bytes.as_deref(),
+            cli.font_family.as_deref(),
+        );
+        (path, sheet)
+    });
+    let sheet_frames = contact_sheet
+        .as_ref()
+        .map(|(_, sheet)| sheet.frame_indices(total_frames));
+
+    let background = if cli.background_from_art {
+        match audio_data.tags.cover_art.as_deref() {
+            Some(bytes) => match render::texture::decode_image(bytes) {
+                Ok(image) => {
+                    log::info!("Using {}x{} cover art as the background", image.width, image.height);
+                    Some(Background::new(&image, cli.width, cli.height))
+                }
+                Err(err) => {
+                    log::warn!("--background-from-art: failed to decode the cover art: {:#}", err);
+                    None
+                }
+            },
+            None => {
+                log::warn!("--background-from-art: input has no embedded cover art");
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    let waveform_strip = cli
+        .waveform_strip
+        .then(|| WaveformStrip::new(&audio_data.samples, audio_data.sample_rate, cli.width, cli.height));
+
+    let mut temporal_smooth = (cli.temporal_smooth > 0.0).then(|| TemporalSmooth::new(cli.temporal_smooth));
+    // Only a video has consecutive frames to compare.
+    let mut flash_guard = flash_guard_mode.filter(|_| writes_video).map(FlashGuard::new);
+
+    let debug_hud = cli.debug_hud.then(|| {
+        DebugHud::new(
+            cli.height,
+            global.tempo_bpm,
+            cli.font.as_deref(),
+            font_bytes.as_deref(),
+            cli.font_family.as_deref(),
+        )
+    });
+
+    let showcase_labels = showcase.map(|layout| {
+        let label_height = match layout {
+            ShowcaseLayout::Sequence => cli.height,
+            ShowcaseLayout::Grid => cli.height / render::showcase::grid_size(job.template_names().len()).1,
+        };
+        ShowcaseLabels::new(
+            label_height,
+            cli.font.as_deref(),
+            font_bytes.as_deref(),
+            cli.font_family.as_deref(),
+        )
+    });
+
+    let debug_grid = cli.debug_grid.map(|spacing| {
+        DebugGrid::new(
+            spacing,
+            cli.height,
+            cli.font.as_deref(),
+            font_bytes.as_deref(),
+            cli.font_family.as_deref(),
+        )
+    });
+
+    // 5b. Subtitle renderer
     #[cfg(feature = "subtitles")]
-    let subtitle_renderer = subtitle_cues.map(|cues| -> Result<_> {
+    let subtitle_renderer = subtitle_cues.filter(|_| subtitle_mode.burns()).map(|cues| -> Result<_> {
         let has_subtitle_font = cli.subtitle_font.is_some()
             || cli.subtitle_font_url.is_some()
             || cli.subtitle_font_family.is_some();
@@ -534,16 +1253,18 @@ fn main() -> Result<()> {
             cli.subtitle_outline_width,
             cli.subtitle_margin_bottom,
             !cli.no_subtitle_karaoke,
+            &cli.karaoke_style,
         )?;
         Ok(subtitle::render::SubtitleRenderer::new(
             cues,
             sub_overlay,
             cli.subtitle_max_chars,
+            cli.subtitle_max_lines,
             style,
         ))
     }).transpose()?;
 
-    // 9. Render loop
+    // 6. Render loop
     let pb = ProgressBar::new(total_frames as u64);
     pb.set_style(
         ProgressStyle::default_bar()
@@ -551,44 +1272,88 @@ fn main() -> Result<()> {
             .unwrap()
             .progress_chars("=>-"),
     );
+    if cli.quiet || preview_frame.is_some() || contact_sheet.is_some() {
+        pb.set_draw_target(ProgressDrawTarget::hidden());
+    }
+
+    // Ctrl-C stops the loop and finalizes what has been encoded so far; a
+    // second Ctrl-C exits immediately, which drops nothing, so it removes the
+    // temporary files itself.
+    let interrupted = Arc::new(AtomicBool::new(false));
+    {
+        let interrupted = Arc::clone(&interrupted);
+        ctrlc::set_handler(move || {
+            if interrupted.swap(true, Ordering::SeqCst) {
+                sonica::temp::remove_all();
+                std::process::exit(130);
+            }
+        })
+        .context("Failed to install Ctrl-C handler")?;
+    }
+
+    let render_started = Instant::now();
+    let mut frames_written = 0;
+    // FFmpeg encodes in its own process, fed by a writer thread; this is how
+    // long the render loop waited on it (a full frame queue, stills, and
+    // finalizing).
+    let mut encode_time = std::time::Duration::ZERO;
+
+    let mut targets = frames
+        .iter()
+        .enumerate()
+        .filter(|&(frame_idx, _)| {
+            preview_frame.is_none_or(|target| frame_idx == target)
+                && sheet_frames.as_ref().is_none_or(|targets| targets.contains(&frame_idx))
+        })
+        .peekable();
+    // The next frame is submitted before this one is read back, so the GPU
+    // renders it while this one is composited and handed to the encoder.
+    let mut submitted = None;
 
-    let mut current_slot_idx = 0;
-
-    for (frame_idx, frame) in frames.iter().enumerate() {
-        // Advance to the correct template slot
-        while current_slot_idx + 1 < slots.len()
-            && frame_idx >= slots[current_slot_idx].end_frame
-        {
-            current_slot_idx += 1;
-            log::info!("Switching to template: {}", slots[current_slot_idx].name);
-        }
-        let slot = &slots[current_slot_idx];
-
-        // Update uniforms
-        let uniforms = build_uniforms(frame, frame_idx as u32, cli.width, cli.height, cli.fps, global.duration);
-        gpu.queue.write_buffer(&uniform_buffer, 0, bytemuck::bytes_of(&uniforms));
-        gpu.queue.write_buffer(&fft_buffer, 0, bytemuck::cast_slice(&frame.fft_bins));
-        gpu.queue.write_buffer(&waveform_buffer, 0, bytemuck::cast_slice(&frame.waveform));
-
-        // Compute dispatch (if template has a compute shader)
-        if let Some(ref _compute) = slot.compute_pipeline {
-            // TODO: create compute bind group, dispatch, and submit
-            // Requires output buffer binding and workgroup size configuration
-        }
-
-        // Render
-        let mut pixels = if pp_chain.has_effects() {
-            frame_renderer.render_and_readback(&gpu, &slot.pipeline.pipeline, &slot.bind_group)?;
-            let final_texture = pp_chain.run(
-                &gpu.device,
-                &gpu.queue,
-                &frame_renderer.render_texture,
-                frame.time,
+    while let Some((frame_idx, frame)) = targets.next() {
+        if interrupted.load(Ordering::SeqCst) {
+            pb.abandon();
+            log::warn!(
+                "Interrupted at frame {} of {}; finalizing partial video",
+                frame_idx,
+                total_frames
             );
-            frame_renderer.readback_texture(&gpu, final_texture)?
-        } else {
-            frame_renderer.render_and_readback(&gpu, &slot.pipeline.pipeline, &slot.bind_group)?
+            break;
+        }
+
+        let current = match submitted.take() {
+            Some(current) => current,
+            None => renderer.submit_frame(frame_idx, frame)?,
         };
+        if let Some(&(next_idx, next)) = targets.peek() {
+            submitted = Some(renderer.submit_frame(next_idx, next)?);
+        }
+        let mut pixels = renderer.finish_frame(current)?;
+
+        if let Some(ref background) = background {
+            background.render_frame(&mut pixels);
+        }
+
+        // Track separators dim the visuals only; the new title shows through.
+        if let Some(ref sheet) = cue_sheet {
+            let gain = sheet.separator_gain(frame.time, cli.track_separator);
+            if gain < 1.0 {
+                for pixel in pixels.chunks_exact_mut(4) {
+                    for channel in &mut pixel[..3] {
+                        *channel = (*channel as f32 * gain).round() as u8;
+                    }
+                }
+            }
+        }
+
+        if let Some(ref labels) = showcase_labels {
+            labels.draw(&mut pixels, cli.width, cli.height, &renderer.labels(frame_idx));
+        }
+
+        // Drawn first so text at the bottom edge stays on top of it.
+        if let Some(ref strip) = waveform_strip {
+            strip.render_frame(&mut pixels, cli.width, cli.height, frame.time);
+        }
 
         // Text overlay compositing
         if let Some(ref overlay) = text_overlay {
@@ -596,10 +1361,21 @@ fn main() -> Result<()> {
             let shorter = cli.width.min(cli.height) as f32;
             let margin = (shorter * 0.07) as u32;
 
-            if let Some(ref title) = cli.title {
+            let color_for = |pixels: &[u8], x: u32, y: u32, w: u32| {
+                if cli.auto_contrast {
+                    TextOverlay::contrast_color(pixels, cli.width, cli.height, x, y, w, overlay.line_height(), color[3])
+                } else {
+                    color
+                }
+            };
+
+            // The cue sheet's song title, falling back to --title before the first song.
+            let title = cue_sheet.as_ref().and_then(|sheet| sheet.title_at(frame.time)).or(title.as_deref());
+            if let Some(title) = title {
                 let tw = overlay.measure_width(title);
                 let tx = cli.width - margin - tw;
                 let ty = margin;
+                let color = color_for(&pixels, tx, ty, tw);
                 overlay.composite(&mut pixels, cli.width, cli.height, title, tx, ty, color);
             }
 
@@ -614,8 +1390,21 @@ fn main() -> Result<()> {
                 let tw = overlay.measure_width(&time_str);
                 let tx = cli.width - margin - tw;
                 let ty = cli.height - margin - overlay.line_height();
+                let color = color_for(&pixels, tx, ty, tw);
                 overlay.composite(&mut pixels, cli.width, cli.height, &time_str, tx, ty, color);
             }
+
+            if cli.show_note {
+                if let Some(note) = audio::pitch::note_name(frame.dominant_frequency_hz) {
+                    let tw = overlay.measure_width(&note);
+                    let color = color_for(&pixels, margin, margin, tw);
+                    overlay.composite(&mut pixels, cli.width, cli.height, &note, margin, margin, color);
+                }
+            }
+        }
+
+        if let Some(ref script) = scripted_overlay {
+            script.render_frame(&mut pixels, cli.width, cli.height, frame.time);
         }
 
         // Subtitle overlay
@@ -624,43 +1413,272 @@ fn main() -> Result<()> {
             sub.render_frame(&mut pixels, cli.width, cli.height, frame.time);
         }
 
-        encoder.write_frame(&pixels)?;
+        if let Some(ref hud) = debug_hud {
+            hud.render_frame(&mut pixels, cli.width, cli.height, frame);
+        }
+        if let Some(grid) = debug_grid.as_ref().filter(|_| cli.keep_debug_grid) {
+            grid.draw(&mut pixels, cli.width, cli.height);
+        }
+
+        // Last, so overlays are smoothed along with the visuals.
+        if let Some(ref mut smooth) = temporal_smooth {
+            smooth.apply(frame_idx, &mut pixels);
+        }
+        if let Some(ref mut guard) = flash_guard {
+            guard.check(frame.time, &mut pixels);
+        }
+
+        if let (Some((_, ref mut sheet)), Some(ref targets)) = (&mut contact_sheet, &sheet_frames) {
+            // Short tracks can put the same frame in several cells.
+            for (cell, _) in targets.iter().enumerate().filter(|(_, &target)| target == frame_idx) {
+                sheet.place(cell, &pixels, cli.width, cli.height, frame.time);
+            }
+            frames_written += 1;
+            continue;
+        }
+
+        let Some(ref mut encoder) = encoder else {
+            // Guides are drawn on the preview only, never into the video.
+            if let Some(ref guides) = safe_guides {
+                guides.draw(&mut pixels, cli.width, cli.height);
+            }
+            if let Some(grid) = debug_grid.as_ref().filter(|_| !cli.keep_debug_grid) {
+                grid.draw(&mut pixels, cli.width, cli.height);
+            }
+            let path = preview_path(&cli.output, image_format);
+            let encode_started = Instant::now();
+            encode::ffmpeg::write_still(&path, cli.width, cli.height, &pixels, image_format)?;
+            encode_time += encode_started.elapsed();
+            timings.record("render", render_started.elapsed() - encode_time);
+            timings.record("encode", encode_time);
+            log::info!("Preview at {:.2}s written to {}", frame.time, path.display());
+            if let Some(ref stamp) = render_stamp {
+                stamp.write()?;
+            }
+            if let Some((report_path, report)) = render_report {
+                report.finish(
+                    &report_path,
+                    report::TimingReport {
+                        analysis_seconds,
+                        render_seconds: render_started.elapsed().as_secs_f64(),
+                        total_seconds: run_started.elapsed().as_secs_f64(),
+                        frames_rendered: 1,
+                        frames_total: total_frames,
+                        interrupted: false,
+                    },
+                )?;
+            }
+            if cli.benchmark {
+                eprint!("{}", timings.table(run_started.elapsed()));
+            }
+            return Ok(());
+        };
+
+        let encode_started = Instant::now();
+        encoder.send_frame(pixels)?;
+        encode_time += encode_started.elapsed();
+        frames_written += 1;
         pb.set_position(frame_idx as u64 + 1);
     }
 
-    pb.finish_with_message("Rendering complete");
+    let render_time = render_started.elapsed() - encode_time;
+
+    if let Some((path, sheet)) = contact_sheet {
+        let encode_started = Instant::now();
+        encode::ffmpeg::write_still(&path, sheet.width(), sheet.height(), sheet.pixels(), image_format)?;
+        timings.record("render", render_time);
+        timings.record("encode", encode_time + encode_started.elapsed());
+        log::info!("Contact sheet of {} frames written to {}", sheet.cells(), path.display());
+        if let Some(stamp) = render_stamp.as_ref().filter(|_| !interrupted.load(Ordering::SeqCst)) {
+            stamp.write()?;
+        }
+        if let Some((report_path, report)) = render_report {
+            report.finish(
+                &report_path,
+                report::TimingReport {
+                    analysis_seconds,
+                    render_seconds: render_started.elapsed().as_secs_f64(),
+                    total_seconds: run_started.elapsed().as_secs_f64(),
+                    frames_rendered: frames_written,
+                    frames_total: total_frames,
+                    interrupted: interrupted.load(Ordering::SeqCst),
+                },
+            )?;
+        }
+        if cli.benchmark {
+            eprint!("{}", timings.table(run_started.elapsed()));
+        }
+        return Ok(());
+    }
 
-    // 10. Finish encoding
+    if let Some(ref guard) = flash_guard {
+        guard.finish();
+    }
+
+    // 7. Finish encoding
     log::info!("Finishing encoding...");
-    encoder.finish()?;
+    if let Some(encoder) = encoder {
+        let encode_started = Instant::now();
+        let finished = encoder.finish();
+        encode_time += encode_started.elapsed();
+        drop(temporary_subtitles);
+        finished?;
+    }
+
+    timings.record("render", render_time);
+    timings.record("encode", encode_time);
+
+    if let Some((report_path, report)) = render_report {
+        report.finish(
+            &report_path,
+            report::TimingReport {
+                analysis_seconds,
+                render_seconds: render_started.elapsed().as_secs_f64(),
+                total_seconds: run_started.elapsed().as_secs_f64(),
+                frames_rendered: frames_written,
+                frames_total: total_frames,
+                interrupted: interrupted.load(Ordering::SeqCst),
+            },
+        )?;
+    }
+
+    if cli.benchmark {
+        pb.suspend(|| eprint!("{}", timings.table(run_started.elapsed())));
+    }
 
+    if interrupted.load(Ordering::SeqCst) {
+        log::warn!(
+            "Render interrupted: saved {:.1}s ({} of {} frames) to {}",
+            fps.frame_time(frames_written),
+            frames_written,
+            total_frames,
+            cli.output.display()
+        );
+        return Err(Interrupted.into());
+    }
+
+    if let Some(stamp) = render_stamp {
+        stamp.write()?;
+    }
+
+    pb.finish_with_message("Rendering complete");
     log::info!("Done! Output: {}", cli.output.display());
     Ok(())
 }
 
-fn build_uniforms(
-    frame: &SmoothedFrame,
-    frame_idx: u32,
-    width: u32,
-    height: u32,
-    fps: u32,
-    duration: f32,
-) -> FrameUniforms {
-    FrameUniforms {
-        resolution: [width as f32, height as f32],
-        time: frame.time,
-        frame: frame_idx,
-        fps: fps as f32,
-        duration,
-        rms: frame.rms,
-        spectral_centroid: frame.spectral_centroid,
-        spectral_flux: frame.spectral_flux,
-        beat_intensity: frame.beat_intensity,
-        beat_phase: frame.beat_phase,
-        is_beat: if frame.is_beat { 1.0 } else { 0.0 },
-        bass: frame.bass,
-        mid: frame.mid,
-        high: frame.high,
-        _padding: 0.0,
+/// `--font "Family Name"`: a value that isn't a file but names an installed
+/// font family selects that family, as `--font-family` would.
+fn font_path_as_family(font: Option<&std::path::Path>) -> Option<String> {
+    let font = font.filter(|path| !path.exists())?.to_str()?;
+    render::text::is_installed_font_family(font).then(|| font.to_string())
+}
+
+/// What `--skip-if-unchanged` compares between runs: every setting after the
+/// config merge, minus the ones that only change how sonica reports on the
+/// run. The input is compared by content, so its path is left out.
+fn settings_fingerprint(cli: &Cli) -> String {
+    let mut cli = cli.clone();
+    cli.input = None;
+    cli.config = None;
+    cli.overwrite = false;
+    cli.skip_if_unchanged = false;
+    cli.report = None;
+    cli.benchmark = false;
+    cli.quiet = false;
+    cli.log_format = String::new();
+    format!("{cli:?}")
+}
+
+/// `--preview` writes an image: keep `-o` if it already names one of
+/// `--image-format`, otherwise swap the video extension for the format's.
+fn preview_path(output: &std::path::Path, format: encode::ffmpeg::ImageFormat) -> std::path::PathBuf {
+    if format.matches_extension(output) {
+        output.to_path_buf()
+    } else {
+        output.with_extension(format.extension())
+    }
+}
+
+/// Configure `env_logger` from `--quiet` / `--log-format`. `RUST_LOG` still
+/// takes precedence over the default level.
+fn init_logging(quiet: bool, log_format: &str) -> Result<()> {
+    let default_level = if quiet { "error" } else { "info" };
+    let mut builder =
+        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(default_level));
+
+    match log_format {
+        "text" => {
+            builder.format_timestamp_millis();
+        }
+        "json" => {
+            builder.format(|buf, record| {
+                use std::io::Write;
+                let line = serde_json::json!({
+                    "timestamp": buf.timestamp_millis().to_string(),
+                    "level": record.level().as_str(),
+                    "target": record.target(),
+                    "message": record.args().to_string(),
+                });
+                writeln!(buf, "{line}")
+            });
+        }
+        other => anyhow::bail!("Unknown log format '{}'. Valid formats: text, json", other),
+    }
+
+    // Warnings are recorded for --strict even when --quiet hides them.
+    let logger = builder.build();
+    log::set_max_level(logger.filter().max(log::LevelFilter::Warn));
+    log::set_boxed_logger(Box::new(WarningRecorder { inner: logger }))?;
+    Ok(())
+}
+
+/// Messages of every warning sonica logged during the run.
+static WARNINGS: std::sync::Mutex<Vec<String>> = std::sync::Mutex::new(Vec::new());
+
+/// Forwards records to env_logger and keeps sonica's own warnings in
+/// `WARNINGS`. Dependencies' warnings (wgpu, naga) don't count: they're
+/// about the platform, not the render.
+struct WarningRecorder {
+    inner: env_logger::Logger,
+}
+
+impl log::Log for WarningRecorder {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::Level::Warn || self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &log::Record) {
+        if record.level() == log::Level::Warn && record.target().split("::").next() == Some("sonica") {
+            if let Ok(mut warnings) = WARNINGS.lock() {
+                warnings.push(record.args().to_string());
+            }
+        }
+        if self.inner.matches(record) {
+            self.inner.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+/// A render stopped by Ctrl-C after finalizing its partial output, which
+/// `main` turns into exit status 130.
+#[derive(Debug, thiserror::Error)]
+#[error("Render interrupted")]
+struct Interrupted;
+
+/// `--strict`: the run succeeded, but fail it if it logged any warning, so a
+/// pipeline can trust that the output is exactly what was asked for.
+fn check_no_warnings() -> Result<()> {
+    let warnings = WARNINGS.lock().map_err(|_| anyhow::anyhow!("Warning log poisoned"))?;
+    if warnings.is_empty() {
+        return Ok(());
     }
+    anyhow::bail!(
+        "--strict: {} warning(s) during the run:\n  {}",
+        warnings.len(),
+        warnings.join("\n  ")
+    )
 }
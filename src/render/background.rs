@@ -0,0 +1,119 @@
+use super::texture::DecodedImage;
+
+/// Brightness the art is dimmed to, so it reads as a backdrop.
+const BRIGHTNESS: f32 = 0.35;
+/// Box blur radius as a fraction of the longer output side; three passes
+/// approximate a Gaussian.
+const BLUR_RADIUS: f32 = 1.0 / 64.0;
+const BLUR_PASSES: usize = 3;
+
+/// `--background-from-art`: the input's embedded cover art, scaled to cover
+/// the frame, blurred, and darkened once up front, then screen-blended under
+/// each rendered frame so the visuals stay bright on top of it.
+pub struct Background {
+    /// RGB, one triple per output pixel.
+    pixels: Vec<f32>,
+}
+
+impl Background {
+    pub fn new(image: &DecodedImage, width: u32, height: u32) -> Self {
+        let mut pixels = cover(image, width, height);
+        let radius = (width.max(height) as f32 * BLUR_RADIUS).round() as usize;
+        for _ in 0..BLUR_PASSES {
+            box_blur(&mut pixels, width as usize, height as usize, radius, true);
+            box_blur(&mut pixels, width as usize, height as usize, radius, false);
+        }
+        for value in &mut pixels {
+            *value *= BRIGHTNESS;
+        }
+        Self { pixels }
+    }
+
+    /// Screen blend: black visuals show the art, bright ones cover it.
+    pub fn render_frame(&self, pixels: &mut [u8]) {
+        for (pixel, art) in pixels.chunks_exact_mut(4).zip(self.pixels.chunks_exact(3)) {
+            for (channel, &art) in pixel[..3].iter_mut().zip(art) {
+                let visual = *channel as f32;
+                *channel = (visual + art - visual * art / 255.0).round().clamp(0.0, 255.0) as u8;
+            }
+        }
+    }
+}
+
+/// Scale `image` to fill `width`x`height`, cropping the overhang evenly.
+fn cover(image: &DecodedImage, width: u32, height: u32) -> Vec<f32> {
+    let scale = (width as f32 / image.width as f32).max(height as f32 / image.height as f32);
+    let offset_x = (image.width as f32 * scale - width as f32) / 2.0;
+    let offset_y = (image.height as f32 * scale - height as f32) / 2.0;
+    let mut pixels = Vec::with_capacity(width as usize * height as usize * 3);
+    for y in 0..height {
+        let sy = (((y as f32 + 0.5 + offset_y) / scale) as u32).min(image.height - 1);
+        for x in 0..width {
+            let sx = (((x as f32 + 0.5 + offset_x) / scale) as u32).min(image.width - 1);
+            let i = (sy * image.width + sx) as usize * 4;
+            pixels.extend(image.pixels[i..i + 3].iter().map(|&c| c as f32));
+        }
+    }
+    pixels
+}
+
+/// One pass of a running-sum box blur along rows (or columns), clamping at
+/// the edges.
+fn box_blur(pixels: &mut [f32], width: usize, height: usize, radius: usize, horizontal: bool) {
+    if radius == 0 {
+        return;
+    }
+    let (lines, length) = if horizontal { (height, width) } else { (width, height) };
+    let index = |line: usize, at: usize| if horizontal { line * width + at } else { at * width + line } * 3;
+    let mut source = vec![0.0f32; length * 3];
+    for line in 0..lines {
+        for at in 0..length {
+            source[at * 3..at * 3 + 3].copy_from_slice(&pixels[index(line, at)..index(line, at) + 3]);
+        }
+        let sample = |at: isize, channel: usize| source[at.clamp(0, length as isize - 1) as usize * 3 + channel];
+        let span = (2 * radius + 1) as f32;
+        for channel in 0..3 {
+            let mut sum: f32 = (-(radius as isize)..=radius as isize).map(|at| sample(at, channel)).sum();
+            for at in 0..length {
+                pixels[index(line, at) + channel] = sum / span;
+                let at = at as isize;
+                sum += sample(at + radius as isize + 1, channel) - sample(at - radius as isize, channel);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(width: u32, height: u32, color: impl Fn(u32, u32) -> [u8; 4]) -> DecodedImage {
+        let color = &color;
+        let pixels = (0..height).flat_map(|y| (0..width).flat_map(move |x| color(x, y))).collect();
+        DecodedImage { width, height, pixels }
+    }
+
+    #[test]
+    fn art_covers_the_frame_and_sits_under_the_visuals() {
+        // A square cover on a wide frame keeps its middle band.
+        let art = image(4, 4, |_, y| if y < 2 { [200, 0, 0, 255] } else { [0, 0, 200, 255] });
+        let background = Background::new(&art, 8, 4);
+
+        let mut frame = vec![0u8; 8 * 4 * 4];
+        frame[4 * 8 * 3..].fill(255);
+        background.render_frame(&mut frame);
+
+        let top_left = &frame[..4];
+        assert!(top_left[0] > top_left[2] && top_left[0] < 200, "darkened red art: {top_left:?}");
+        let bottom_right = &frame[frame.len() - 4..];
+        assert_eq!(bottom_right, [255, 255, 255, 255], "white visuals cover the art");
+    }
+
+    #[test]
+    fn blur_keeps_a_flat_image_flat() {
+        let mut pixels = vec![100.0f32; 16 * 9 * 3];
+        box_blur(&mut pixels, 16, 9, 4, true);
+        box_blur(&mut pixels, 16, 9, 4, false);
+        assert!(pixels.iter().all(|&value| (value - 100.0).abs() < 1e-3));
+    }
+}
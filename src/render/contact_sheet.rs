@@ -0,0 +1,177 @@
+use anyhow::{Context, Result};
+use std::path::Path;
+
+use super::text::TextOverlay;
+
+const LABEL_BG: [u8; 4] = [0, 0, 0, 160];
+const LABEL_COLOR: [u8; 4] = [255, 255, 255, 235];
+
+/// `--contact-sheet`: frames at evenly spaced times, scaled down and tiled
+/// into one grid image, each labelled with its timestamp.
+pub struct ContactSheet {
+    columns: u32,
+    rows: u32,
+    thumb_width: u32,
+    thumb_height: u32,
+    pixels: Vec<u8>,
+    overlay: TextOverlay,
+}
+
+impl ContactSheet {
+    pub const DEFAULT_GRID: (u32, u32) = (4, 4);
+
+    /// Parse a `COLUMNSxROWS` grid such as `4x4` or `6x3`.
+    pub fn parse_grid(spec: &str) -> Result<(u32, u32)> {
+        let invalid = || format!("Invalid contact sheet grid '{spec}': use COLUMNSxROWS, e.g. 4x4");
+        let (columns, rows) = spec
+            .to_ascii_lowercase()
+            .split_once('x')
+            .map(|(c, r)| (c.trim().parse::<u32>(), r.trim().parse::<u32>()))
+            .with_context(invalid)?;
+        match (columns, rows) {
+            (Ok(columns), Ok(rows)) if (1..=32).contains(&columns) && (1..=32).contains(&rows) => {
+                Ok((columns, rows))
+            }
+            _ => anyhow::bail!(invalid()),
+        }
+    }
+
+    /// A sheet as wide as one `width`x`height` frame, with thumbnails that
+    /// keep the frame's aspect ratio.
+    pub fn new(
+        (columns, rows): (u32, u32),
+        width: u32,
+        height: u32,
+        font_path: Option<&Path>,
+        font_bytes: Option<&[u8]>,
+        font_family: Option<&str>,
+    ) -> Self {
+        let thumb_width = (width / columns).max(1);
+        let thumb_height = (thumb_width * height / width).max(1);
+        let font_size = (thumb_height as f32 * 0.09).max(12.0);
+        Self {
+            columns,
+            rows,
+            thumb_width,
+            thumb_height,
+            pixels: [0, 0, 0, 255].repeat((columns * thumb_width * rows * thumb_height) as usize),
+            overlay: TextOverlay::new(font_size, font_path, font_bytes, font_family),
+        }
+    }
+
+    pub fn cells(&self) -> usize {
+        (self.columns * self.rows) as usize
+    }
+
+    pub fn width(&self) -> u32 {
+        self.columns * self.thumb_width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.rows * self.thumb_height
+    }
+
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Frame index for each cell: the middle of `cells` equal spans of the
+    /// track, so neither the silent first frame nor the last is picked.
+    pub fn frame_indices(&self, total_frames: usize) -> Vec<usize> {
+        let cells = self.cells();
+        (0..cells)
+            .map(|cell| ((2 * cell + 1) * total_frames / (2 * cells)).min(total_frames.saturating_sub(1)))
+            .collect()
+    }
+
+    /// Scale a rendered frame into `cell` (row-major) and label it with `time`.
+    pub fn place(&mut self, cell: usize, rgba: &[u8], width: u32, height: u32, time: f32) {
+        let (tw, th) = (self.thumb_width, self.thumb_height);
+        let sheet_width = self.width();
+        let (cell_x, cell_y) = ((cell as u32 % self.columns) * tw, (cell as u32 / self.columns) * th);
+
+        // Box filter: each thumbnail pixel averages the source pixels it covers.
+        for ty in 0..th {
+            let (y0, y1) = (ty * height / th, ((ty + 1) * height / th).max(ty * height / th + 1));
+            for tx in 0..tw {
+                let (x0, x1) = (tx * width / tw, ((tx + 1) * width / tw).max(tx * width / tw + 1));
+                let mut sum = [0u32; 4];
+                for sy in y0..y1.min(height) {
+                    for sx in x0..x1.min(width) {
+                        let i = ((sy * width + sx) * 4) as usize;
+                        for (acc, &c) in sum.iter_mut().zip(&rgba[i..i + 4]) {
+                            *acc += c as u32;
+                        }
+                    }
+                }
+                let count = (y1.min(height) - y0) * (x1.min(width) - x0);
+                let o = (((cell_y + ty) * sheet_width + cell_x + tx) * 4) as usize;
+                for (dst, acc) in self.pixels[o..o + 4].iter_mut().zip(sum) {
+                    *dst = (acc / count.max(1)) as u8;
+                }
+            }
+        }
+
+        let label = format_timestamp(time);
+        let pad = (self.overlay.line_height() / 4).max(2);
+        let label_w = self.overlay.measure_width(&label) + pad * 2;
+        let label_h = self.overlay.line_height() + pad;
+        let label_y = cell_y + th.saturating_sub(label_h);
+        let (sheet_w, sheet_h) = (self.width(), self.height());
+        TextOverlay::fill_rect(&mut self.pixels, sheet_w, sheet_h, cell_x, label_y, label_w, label_h, LABEL_BG);
+        self.overlay.composite(
+            &mut self.pixels,
+            sheet_w,
+            sheet_h,
+            &label,
+            cell_x + pad,
+            label_y + pad / 2,
+            LABEL_COLOR,
+        );
+    }
+}
+
+/// `m:ss`, or `h:mm:ss` past an hour.
+pub(super) fn format_timestamp(time: f32) -> String {
+    let secs = time.max(0.0) as u64;
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, (secs % 3600) / 60, secs % 60)
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid_parsing_and_frame_spacing() {
+        assert_eq!(ContactSheet::parse_grid("4x4").unwrap(), (4, 4));
+        assert_eq!(ContactSheet::parse_grid("6X3").unwrap(), (6, 3));
+        assert!(ContactSheet::parse_grid("0x4").is_err());
+        assert!(ContactSheet::parse_grid("4").is_err());
+
+        let sheet = ContactSheet::new((2, 2), 64, 36, None, None, None);
+        assert_eq!((sheet.width(), sheet.height()), (64, 36));
+        assert_eq!(sheet.frame_indices(100), [12, 37, 62, 87]);
+        assert_eq!(sheet.frame_indices(2), [0, 0, 1, 1]);
+        assert_eq!(format_timestamp(3725.4), "1:02:05");
+    }
+
+    #[test]
+    fn frames_land_in_their_cells() {
+        let mut sheet = ContactSheet::new((2, 1), 128, 64, None, None, None);
+        let white = vec![255u8; 128 * 64 * 4];
+
+        sheet.place(1, &white, 128, 64, 0.0);
+
+        let pixel = |x: u32, y: u32| {
+            let i = ((y * sheet.width() + x) * 4) as usize;
+            sheet.pixels()[i..i + 4].to_vec()
+        };
+        assert_eq!((sheet.width(), sheet.height()), (128, 32));
+        assert_eq!(pixel(100, 1), [255, 255, 255, 255]);
+        assert_eq!(pixel(30, 1), [0, 0, 0, 255]);
+    }
+}
@@ -0,0 +1,120 @@
+use std::path::Path;
+
+use super::text::TextOverlay;
+
+const LINE_COLOR: [u8; 4] = [255, 255, 255, 60];
+const AXIS_COLOR: [u8; 4] = [255, 220, 0, 140];
+const MARKER_COLOR: [u8; 4] = [255, 220, 0, 255];
+const LABEL_PANEL_COLOR: [u8; 4] = [0, 0, 0, 160];
+const TEXT_COLOR: [u8; 4] = [255, 255, 255, 235];
+
+/// `--debug-grid`: a light grid every `spacing` pixels with the center axes
+/// highlighted, and markers at the corners and center labeled with their UV
+/// and pixel coordinates, for lining up template elements and overlays.
+///
+/// UV follows the shaders: (0,0) is the top-left pixel and (1,1) the
+/// bottom-right, the same as `uv` in the fragment stage.
+pub struct DebugGrid {
+    overlay: TextOverlay,
+    spacing: u32,
+}
+
+impl DebugGrid {
+    pub fn new(
+        spacing: u32,
+        height: u32,
+        font_path: Option<&Path>,
+        font_bytes: Option<&[u8]>,
+        font_family: Option<&str>,
+    ) -> Self {
+        let font_size = (height as f32 * 0.016).max(11.0);
+        Self {
+            overlay: TextOverlay::new(font_size, font_path, font_bytes, font_family),
+            spacing: spacing.max(1),
+        }
+    }
+
+    pub fn draw(&self, pixels: &mut [u8], width: u32, height: u32) {
+        if width == 0 || height == 0 {
+            return;
+        }
+        for x in (self.spacing..width).step_by(self.spacing as usize) {
+            TextOverlay::fill_rect(pixels, width, height, x, 0, 1, height, LINE_COLOR);
+        }
+        for y in (self.spacing..height).step_by(self.spacing as usize) {
+            TextOverlay::fill_rect(pixels, width, height, 0, y, width, 1, LINE_COLOR);
+        }
+        TextOverlay::fill_rect(pixels, width, height, width / 2, 0, 1, height, AXIS_COLOR);
+        TextOverlay::fill_rect(pixels, width, height, 0, height / 2, width, 1, AXIS_COLOR);
+
+        for (x, y) in markers(width, height) {
+            self.draw_marker(pixels, width, height, x, y);
+        }
+    }
+
+    /// A small square on the point and a label beside it, flipped toward the
+    /// inside of the frame so corner labels stay visible.
+    fn draw_marker(&self, pixels: &mut [u8], width: u32, height: u32, x: u32, y: u32) {
+        let size = (self.overlay.line_height() / 3).max(3);
+        let left = x.saturating_sub(size / 2).min(width.saturating_sub(size));
+        let top = y.saturating_sub(size / 2).min(height.saturating_sub(size));
+        TextOverlay::fill_rect(pixels, width, height, left, top, size, size, MARKER_COLOR);
+
+        let text = label(x, y, width, height);
+        let line = self.overlay.line_height();
+        let pad = line / 4;
+        let panel_w = self.overlay.measure_width(&text) + pad * 2;
+        let panel_h = line + pad;
+        let gap = size;
+        let panel_x = if x + gap + panel_w <= width { x + gap } else { x.saturating_sub(gap + panel_w) };
+        let panel_y = if y + gap + panel_h <= height { y + gap } else { y.saturating_sub(gap + panel_h) };
+        TextOverlay::fill_rect(pixels, width, height, panel_x, panel_y, panel_w, panel_h, LABEL_PANEL_COLOR);
+        self.overlay
+            .composite(pixels, width, height, &text, panel_x + pad, panel_y + pad / 2, TEXT_COLOR);
+    }
+}
+
+/// Pixel positions of the four corners and the center.
+fn markers(width: u32, height: u32) -> [(u32, u32); 5] {
+    let (right, bottom) = (width - 1, height - 1);
+    [(0, 0), (right, 0), (0, bottom), (right, bottom), (width / 2, height / 2)]
+}
+
+/// `uv (0.50,0.50) px (960,540)`, with UV at the pixel's position in the
+/// frame so the corners read exactly 0 and 1.
+fn label(x: u32, y: u32, width: u32, height: u32) -> String {
+    let u = x as f32 / (width - 1).max(1) as f32;
+    let v = y as f32 / (height - 1).max(1) as f32;
+    format!("uv ({u:.2},{v:.2}) px ({x},{y})")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels_corners_and_center_with_uv_and_pixels() {
+        let labels: Vec<String> = markers(1920, 1080)
+            .into_iter()
+            .map(|(x, y)| label(x, y, 1920, 1080))
+            .collect();
+        assert_eq!(labels[0], "uv (0.00,0.00) px (0,0)");
+        assert_eq!(labels[3], "uv (1.00,1.00) px (1919,1079)");
+        assert_eq!(labels[4], "uv (0.50,0.50) px (960,540)");
+    }
+
+    #[test]
+    fn draws_lines_on_the_spacing_only() {
+        let (width, height) = (400, 300);
+        let mut pixels = vec![0u8; (width * height * 4) as usize];
+        let grid = DebugGrid::new(10, height, None, None, None);
+        grid.draw(&mut pixels, width, height);
+
+        // Clear of the marker labels.
+        let green = |x: u32, y: u32| pixels[((y * width + x) * 4 + 1) as usize];
+        assert!(green(110, 125) > 0, "vertical line");
+        assert!(green(115, 120) > 0, "horizontal line");
+        assert_eq!(green(115, 125), 0, "between lines");
+        assert!(green(200, 75) > green(110, 125), "center axis stands out");
+    }
+}
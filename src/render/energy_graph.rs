@@ -0,0 +1,192 @@
+use std::path::Path;
+
+use super::contact_sheet::format_timestamp;
+use super::text::TextOverlay;
+use crate::audio::features::SmoothedFrame;
+
+const BACKGROUND_COLOR: [u8; 4] = [16, 16, 22, 255];
+const GRID_COLOR: [u8; 4] = [255, 255, 255, 28];
+const LABEL_COLOR: [u8; 4] = [200, 200, 210, 255];
+const RMS_COLOR: [u8; 4] = [255, 255, 255, 255];
+const RMS_FILL_COLOR: [u8; 4] = [255, 255, 255, 40];
+const BAND_COLORS: [[u8; 4]; 3] = [[255, 90, 70, 220], [90, 220, 120, 220], [80, 150, 255, 220]];
+/// Candidate spacings in seconds between time labels, smallest first.
+const TIME_STEPS: [f32; 12] = [1.0, 2.0, 5.0, 10.0, 15.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1800.0, 3600.0];
+/// Most time labels along the axis.
+const MAX_TIME_LABELS: f32 = 12.0;
+
+/// `--energy-graph`: the track's smoothed rms over time (and optionally
+/// bass/mid/high) plotted as lines into a standalone image, from the frames
+/// the analysis already produced.
+pub struct EnergyGraph {
+    overlay: TextOverlay,
+}
+
+impl EnergyGraph {
+    pub const WIDTH: u32 = 1600;
+    pub const HEIGHT: u32 = 400;
+
+    pub fn new(font_path: Option<&Path>, font_bytes: Option<&[u8]>, font_family: Option<&str>) -> Self {
+        Self {
+            overlay: TextOverlay::new(Self::HEIGHT as f32 * 0.045, font_path, font_bytes, font_family),
+        }
+    }
+
+    /// RGBA pixels of a `WIDTH`x`HEIGHT` graph of `frames` over `duration`.
+    pub fn render(&self, frames: &[SmoothedFrame], duration: f32, bands: bool) -> Vec<u8> {
+        let (width, height) = (Self::WIDTH, Self::HEIGHT);
+        let mut pixels = BACKGROUND_COLOR.repeat((width * height) as usize);
+
+        let line = self.overlay.line_height();
+        let pad = line / 2;
+        let plot_x = pad * 2 + self.overlay.measure_width("0.5");
+        let plot_y = pad + line;
+        let plot_w = width - plot_x - pad * 2;
+        let plot_h = height - plot_y - line - pad * 2;
+        let y_of = |value: f32| plot_y + ((1.0 - value.clamp(0.0, 1.0)) * (plot_h - 1) as f32).round() as u32;
+
+        // Level grid, labelled at 0, 0.5 and 1
+        for (value, label) in [(0.0, Some("0")), (0.25, None), (0.5, Some("0.5")), (0.75, None), (1.0, Some("1"))] {
+            let y = y_of(value);
+            TextOverlay::fill_rect(&mut pixels, width, height, plot_x, y, plot_w, 1, GRID_COLOR);
+            if let Some(label) = label {
+                let x = plot_x - pad - self.overlay.measure_width(label);
+                self.overlay
+                    .composite(&mut pixels, width, height, label, x, y.saturating_sub(line / 2), LABEL_COLOR);
+            }
+        }
+
+        // Time grid
+        let step = time_step(duration);
+        let label_y = plot_y + plot_h + pad;
+        for tick in (0..).map(|i| i as f32 * step).take_while(|&time| time <= duration) {
+            let x = plot_x + (tick / duration.max(f32::EPSILON) * (plot_w - 1) as f32).round() as u32;
+            TextOverlay::fill_rect(&mut pixels, width, height, x, plot_y, 1, plot_h, GRID_COLOR);
+            let label = format_timestamp(tick);
+            let label_x = x.saturating_sub(self.overlay.measure_width(&label) / 2).max(plot_x);
+            self.overlay.composite(&mut pixels, width, height, &label, label_x, label_y, LABEL_COLOR);
+        }
+
+        let mut series: Vec<(&str, Vec<f32>, [u8; 4])> = Vec::new();
+        if bands {
+            series.push(("bass", column_values(frames, plot_w, |f| f.bass), BAND_COLORS[0]));
+            series.push(("mid", column_values(frames, plot_w, |f| f.mid), BAND_COLORS[1]));
+            series.push(("high", column_values(frames, plot_w, |f| f.high), BAND_COLORS[2]));
+        }
+        let rms = column_values(frames, plot_w, |f| f.rms);
+        for (x, &value) in rms.iter().enumerate() {
+            let top = y_of(value);
+            let fill_h = plot_y + plot_h - top;
+            TextOverlay::fill_rect(&mut pixels, width, height, plot_x + x as u32, top, 1, fill_h, RMS_FILL_COLOR);
+        }
+        series.push(("rms", rms, RMS_COLOR));
+
+        // Lines: each column spans from the previous column's level to its
+        // own, so steep changes stay connected.
+        let thickness = (height / 200).max(2);
+        for (_, values, color) in &series {
+            let mut previous = None;
+            for (x, &value) in values.iter().enumerate() {
+                let y = y_of(value);
+                let (top, bottom) = match previous {
+                    Some(prev) => (y.min(prev), y.max(prev)),
+                    None => (y, y),
+                };
+                let top = top.saturating_sub(thickness / 2);
+                let x = plot_x + x as u32;
+                TextOverlay::fill_rect(&mut pixels, width, height, x, top, 1, bottom - top + thickness, *color);
+                previous = Some(y);
+            }
+        }
+
+        // Legend, right-aligned above the plot
+        let mut legend_x = width - pad * 2;
+        for (name, _, color) in series.iter().rev() {
+            legend_x -= self.overlay.measure_width(name) + pad * 2;
+            self.overlay.composite(&mut pixels, width, height, name, legend_x, pad / 2, *color);
+        }
+
+        pixels
+    }
+}
+
+/// `value` of the frames under each of `columns` columns, averaged when
+/// several frames share a column.
+fn column_values(frames: &[SmoothedFrame], columns: u32, value: impl Fn(&SmoothedFrame) -> f32) -> Vec<f32> {
+    if frames.is_empty() {
+        return vec![0.0; columns as usize];
+    }
+    let columns = columns as usize;
+    (0..columns)
+        .map(|x| {
+            let start = (x * frames.len() / columns).min(frames.len() - 1);
+            let end = ((x + 1) * frames.len() / columns).clamp(start + 1, frames.len());
+            frames[start..end].iter().map(&value).sum::<f32>() / (end - start) as f32
+        })
+        .collect()
+}
+
+/// Spacing between time labels: the smallest of `TIME_STEPS` that keeps at
+/// most `MAX_TIME_LABELS` of them.
+fn time_step(duration: f32) -> f32 {
+    TIME_STEPS
+        .into_iter()
+        .find(|&step| duration / step <= MAX_TIME_LABELS)
+        .unwrap_or(TIME_STEPS[TIME_STEPS.len() - 1])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(rms: f32) -> SmoothedFrame {
+        SmoothedFrame {
+            fft_bins: Vec::new(),
+            bass: 0.0,
+            mid: 0.0,
+            high: 0.0,
+            rms,
+            spectral_centroid: 0.0,
+            spectral_flux: 0.0,
+            spectral_centroid_hz: 0.0,
+            spectral_flux_raw: 0.0,
+            onset_strength: 0.0,
+            dominant_frequency_hz: 0.0,
+            bands_left: [0.0; 7],
+            bands_right: [0.0; 7],
+            beat_intensity: 0.0,
+            beat_phase: 0.0,
+            is_beat: false,
+            waveform: Vec::new(),
+            waveform_left: Vec::new(),
+            waveform_right: Vec::new(),
+            time: 0.0,
+        }
+    }
+
+    #[test]
+    fn columns_average_the_frames_they_cover() {
+        let frames: Vec<SmoothedFrame> = [0.0, 1.0, 0.5, 0.5].into_iter().map(frame).collect();
+        assert_eq!(column_values(&frames, 2, |f| f.rms), [0.5, 0.5]);
+        // More columns than frames repeat each frame.
+        assert_eq!(column_values(&frames[..2], 4, |f| f.rms), [0.0, 0.0, 1.0, 1.0]);
+        assert_eq!(column_values(&[], 3, |f| f.rms), [0.0; 3]);
+
+        assert_eq!(time_step(10.0), 1.0);
+        assert_eq!(time_step(180.0), 15.0);
+        assert_eq!(time_step(3600.0), 300.0);
+    }
+
+    #[test]
+    fn a_rising_level_climbs_across_the_graph() {
+        let frames: Vec<SmoothedFrame> = (0..100).map(|i| frame(i as f32 / 99.0)).collect();
+        let pixels = EnergyGraph::new(None, None, None).render(&frames, 10.0, true);
+        let (width, height) = (EnergyGraph::WIDTH, EnergyGraph::HEIGHT);
+        assert_eq!(pixels.len(), (width * height * 4) as usize);
+
+        // Topmost white line pixel in a column.
+        let line_top = |x: u32| (0..height).find(|&y| pixels[((y * width + x) * 4) as usize..][..3] == [255; 3]);
+        let (left, right) = (line_top(width / 4).unwrap(), line_top(width * 3 / 4).unwrap());
+        assert!(right < left, "rms rises: {left} -> {right}");
+    }
+}
@@ -0,0 +1,44 @@
+use super::frame::TEXTURE_FORMAT;
+use super::gpu::GpuContext;
+
+/// Binding of the `feedback` texture; its sampler is at `FEEDBACK_BINDING + 1`.
+/// Claimed only by templates that opt in.
+pub const FEEDBACK_BINDING: u32 = 13;
+
+/// A template's `feedback` texture: the render target as the previous frame
+/// left it, before post-processing, for trails, feedback zooms and
+/// reaction-diffusion. The renderer copies each frame into it after drawing.
+/// New textures are zeroed, so a template's first frame sees black.
+pub struct FeedbackTexture {
+    pub texture: wgpu::Texture,
+    pub view: wgpu::TextureView,
+    pub sampler: wgpu::Sampler,
+}
+
+impl FeedbackTexture {
+    /// A texture matching the render target: `width`x`height` at render size.
+    pub fn new(gpu: &GpuContext, width: u32, height: u32) -> Self {
+        let texture = gpu.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("feedback_texture"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: TEXTURE_FORMAT,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = gpu.device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("feedback_sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        Self { texture, view, sampler }
+    }
+}
@@ -0,0 +1,207 @@
+use anyhow::Result;
+use std::collections::VecDeque;
+
+/// Change in mean relative luminance that counts as one half of a flash,
+/// as in the WCAG general flash threshold.
+const FLASH_DELTA: f32 = 0.1;
+/// Only swings whose darker frame is below this count as flashes.
+const DARKER_LIMIT: f32 = 0.8;
+/// More flashes than this within any one second is a violation.
+const MAX_FLASHES_PER_SECOND: usize = 3;
+
+/// What `--flash-guard` does about rapid full-frame flashes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlashGuardMode {
+    /// List the offending timestamps when the render finishes.
+    Warn,
+    /// Also pull offending frames back toward the previous frame so the
+    /// luminance swing stays under the threshold.
+    Limit,
+}
+
+impl FlashGuardMode {
+    pub fn parse(name: &str) -> Result<Self> {
+        match name {
+            "warn" => Ok(Self::Warn),
+            "limit" => Ok(Self::Limit),
+            other => anyhow::bail!("Unknown flash guard mode '{}'. Valid modes: warn, limit", other),
+        }
+    }
+}
+
+/// `--flash-guard`: tracks each output frame's mean relative luminance and
+/// counts flashes, i.e. pairs of opposing swings of at least `FLASH_DELTA`.
+pub struct FlashGuard {
+    mode: FlashGuardMode,
+    srgb_to_linear: [f32; 256],
+    previous: Option<(f32, Vec<u8>)>,
+    /// Direction of the last swing, +1 brighter or -1 darker.
+    last_swing: f32,
+    /// Times of the flashes within the last second.
+    recent: VecDeque<f32>,
+    violations: Vec<f32>,
+}
+
+impl FlashGuard {
+    pub fn new(mode: FlashGuardMode) -> Self {
+        let srgb_to_linear = std::array::from_fn(|i| {
+            let c = i as f32 / 255.0;
+            if c <= 0.04045 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        });
+        Self {
+            mode,
+            srgb_to_linear,
+            previous: None,
+            last_swing: 0.0,
+            recent: VecDeque::new(),
+            violations: Vec::new(),
+        }
+    }
+
+    /// Check the frame at `time` (and in `Limit` mode, attenuate it in place).
+    pub fn check(&mut self, time: f32, pixels: &mut [u8]) {
+        let mut luma = self.mean_luminance(pixels);
+        if let Some((prev_luma, ref previous)) = self.previous {
+            let delta = luma - prev_luma;
+            if delta.abs() >= FLASH_DELTA && luma.min(prev_luma) < DARKER_LIMIT {
+                let swing = delta.signum();
+                let is_flash = self.last_swing == -swing;
+                while self.recent.front().is_some_and(|&t| time - t >= 1.0) {
+                    self.recent.pop_front();
+                }
+                if is_flash && self.recent.len() >= MAX_FLASHES_PER_SECOND {
+                    self.violations.push(time);
+                    if self.mode == FlashGuardMode::Limit && previous.len() == pixels.len() {
+                        // Luminance isn't linear in sRGB values, so bisect
+                        // for the largest blend toward this frame that stays
+                        // under the threshold.
+                        let current = pixels.to_vec();
+                        let blend = |pixels: &mut [u8], keep: f32| {
+                            for ((out, &cur), &prev) in pixels.iter_mut().zip(&current).zip(previous) {
+                                *out = (prev as f32 + (cur as f32 - prev as f32) * keep).round() as u8;
+                            }
+                        };
+                        let (mut lo, mut hi) = (0.0f32, 1.0f32);
+                        for _ in 0..12 {
+                            let mid = (lo + hi) / 2.0;
+                            blend(pixels, mid);
+                            if (self.mean_luminance(pixels) - prev_luma).abs() < FLASH_DELTA * 0.9 {
+                                lo = mid;
+                            } else {
+                                hi = mid;
+                            }
+                        }
+                        blend(pixels, lo);
+                        luma = self.mean_luminance(pixels);
+                    } else {
+                        self.recent.push_back(time);
+                        self.last_swing = swing;
+                    }
+                } else {
+                    if is_flash {
+                        self.recent.push_back(time);
+                    }
+                    self.last_swing = swing;
+                }
+            }
+        }
+        match self.previous {
+            Some((ref mut prev_luma, ref mut buffer)) if buffer.len() == pixels.len() => {
+                *prev_luma = luma;
+                buffer.copy_from_slice(pixels);
+            }
+            _ => self.previous = Some((luma, pixels.to_vec())),
+        }
+    }
+
+    /// Warn about every second that had too many flashes.
+    pub fn finish(&self) {
+        if self.violations.is_empty() {
+            log::info!("Flash guard: no rapid flashes found");
+            return;
+        }
+        let mut seconds: Vec<u64> = self.violations.iter().map(|&t| t as u64).collect();
+        seconds.dedup();
+        let listed: Vec<String> = seconds.iter().take(20).map(|&s| format!("{}:{:02}", s / 60, s % 60)).collect();
+        let more = seconds.len().saturating_sub(listed.len());
+        let action = match self.mode {
+            FlashGuardMode::Warn => "may trigger photosensitive seizures",
+            FlashGuardMode::Limit => "were attenuated",
+        };
+        log::warn!(
+            "Flash guard: more than {} flashes per second {} at {}{}",
+            MAX_FLASHES_PER_SECOND,
+            action,
+            listed.join(", "),
+            if more > 0 { format!(" and {} more", more) } else { String::new() }
+        );
+    }
+
+    /// Times of the frames that broke the flash limit.
+    #[cfg(test)]
+    fn violations(&self) -> &[f32] {
+        &self.violations
+    }
+
+    fn mean_luminance(&self, pixels: &[u8]) -> f32 {
+        let sum: f32 = pixels
+            .chunks_exact(4)
+            .map(|p| {
+                let [r, g, b] = [p[0], p[1], p[2]].map(|c| self.srgb_to_linear[c as usize]);
+                0.2126 * r + 0.7152 * g + 0.0722 * b
+            })
+            .sum();
+        sum / (pixels.len() / 4).max(1) as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Alternate black and white frames at 30fps for two seconds, returning
+    /// the frames as they leave the guard.
+    fn strobe(guard: &mut FlashGuard) -> Vec<Vec<u8>> {
+        (0..60)
+            .map(|i| {
+                let mut pixels = vec![if i % 2 == 0 { 0 } else { 255 }; 16];
+                guard.check(i as f32 / 30.0, &mut pixels);
+                pixels
+            })
+            .collect()
+    }
+
+    #[test]
+    fn strobing_is_flagged_and_steady_frames_are_not() {
+        let mut guard = FlashGuard::new(FlashGuardMode::Warn);
+        strobe(&mut guard);
+        assert!(!guard.violations().is_empty());
+        assert!(guard.violations()[0] < 0.5);
+
+        let mut steady = FlashGuard::new(FlashGuardMode::Warn);
+        for i in 0..60 {
+            let mut pixels = vec![(i * 4) as u8; 16];
+            steady.check(i as f32 / 30.0, &mut pixels);
+        }
+        assert!(steady.violations().is_empty());
+    }
+
+    #[test]
+    fn limited_output_passes_the_check() {
+        let mut guard = FlashGuard::new(FlashGuardMode::Limit);
+        let frames = strobe(&mut guard);
+        assert!(!guard.violations().is_empty());
+
+        let mut recheck = FlashGuard::new(FlashGuardMode::Warn);
+        for (i, mut pixels) in frames.into_iter().enumerate() {
+            recheck.check(i as f32 / 30.0, &mut pixels);
+        }
+        assert!(recheck.violations().is_empty(), "{:?}", recheck.violations());
+        assert_eq!(FlashGuardMode::parse("limit").unwrap(), FlashGuardMode::Limit);
+        assert!(FlashGuardMode::parse("off").is_err());
+    }
+}
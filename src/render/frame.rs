@@ -1,20 +1,39 @@
 use anyhow::Result;
+use std::cell::Cell;
 use super::gpu::GpuContext;
 
 pub const TEXTURE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
 
+/// Readback buffers per renderer: one frame is mapped and read while the
+/// next is rendered and copied into the other.
+pub const READBACK_BUFFERS: usize = 2;
+
 pub struct FrameRenderer {
     pub render_texture: wgpu::Texture,
     pub render_texture_view: wgpu::TextureView,
-    pub output_buffer: wgpu::Buffer,
+    readback_buffers: Vec<wgpu::Buffer>,
+    next_readback: Cell<usize>,
+    /// Which readback buffers are copied into or mapped and not yet read.
+    /// wgpu has no `Buffer::map_state` to ask, so this tracks it for the
+    /// check in [`begin_readback`](Self::begin_readback).
+    in_flight: Vec<Cell<bool>>,
+    /// Opaque black, or transparent with `--transparent`.
+    clear_color: wgpu::Color,
     pub width: u32,
     pub height: u32,
     pub padded_bytes_per_row: u32,
     pub unpadded_bytes_per_row: u32,
 }
 
+/// A frame copied into one of the readback buffers, waiting to be mapped.
+pub struct Readback {
+    buffer: usize,
+    submission: wgpu::SubmissionIndex,
+    mapped: std::sync::mpsc::Receiver<std::result::Result<(), wgpu::BufferAsyncError>>,
+}
+
 impl FrameRenderer {
-    pub fn new(gpu: &GpuContext, width: u32, height: u32) -> Self {
+    pub fn new(gpu: &GpuContext, width: u32, height: u32, clear_color: wgpu::Color) -> Self {
         let render_texture = gpu.device.create_texture(&wgpu::TextureDescriptor {
             label: Some("render_target"),
             size: wgpu::Extent3d {
@@ -38,17 +57,24 @@ impl FrameRenderer {
         let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
         let padded_bytes_per_row = unpadded_bytes_per_row.div_ceil(align) * align;
 
-        let output_buffer = gpu.device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("output_buffer"),
-            size: (padded_bytes_per_row * height) as u64,
-            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
-            mapped_at_creation: false,
-        });
+        let readback_buffers = (0..READBACK_BUFFERS)
+            .map(|_| {
+                gpu.device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("output_buffer"),
+                    size: (padded_bytes_per_row * height) as u64,
+                    usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                    mapped_at_creation: false,
+                })
+            })
+            .collect();
 
         Self {
             render_texture,
             render_texture_view,
-            output_buffer,
+            readback_buffers,
+            next_readback: Cell::new(0),
+            in_flight: (0..READBACK_BUFFERS).map(|_| Cell::new(false)).collect(),
+            clear_color,
             width,
             height,
             padded_bytes_per_row,
@@ -56,12 +82,17 @@ impl FrameRenderer {
         }
     }
 
-    pub fn render_and_readback(
+    /// Draw the template over the render target, cleared to the clear color
+    /// first unless `clear` is false, and copy it into `feedback` for the
+    /// next frame.
+    pub fn render(
         &self,
         gpu: &GpuContext,
         pipeline: &wgpu::RenderPipeline,
         bind_group: &wgpu::BindGroup,
-    ) -> Result<Vec<u8>> {
+        clear: bool,
+        feedback: Option<&wgpu::Texture>,
+    ) {
         let mut encoder = gpu.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("frame_encoder"),
         });
@@ -74,7 +105,13 @@ impl FrameRenderer {
                     depth_slice: None,
                     resolve_target: None,
                     ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        // The target keeps its contents between frames, so
+                        // loading it lets accumulating templates draw trails.
+                        load: if clear {
+                            wgpu::LoadOp::Clear(self.clear_color)
+                        } else {
+                            wgpu::LoadOp::Load
+                        },
                         store: wgpu::StoreOp::Store,
                     },
                 })],
@@ -89,61 +126,36 @@ impl FrameRenderer {
             render_pass.draw(0..3, 0..1); // fullscreen triangle
         }
 
-        encoder.copy_texture_to_buffer(
-            wgpu::TexelCopyTextureInfo {
-                texture: &self.render_texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-                aspect: wgpu::TextureAspect::All,
-            },
-            wgpu::TexelCopyBufferInfo {
-                buffer: &self.output_buffer,
-                layout: wgpu::TexelCopyBufferLayout {
-                    offset: 0,
-                    bytes_per_row: Some(self.padded_bytes_per_row),
-                    rows_per_image: Some(self.height),
+        if let Some(feedback) = feedback {
+            encoder.copy_texture_to_texture(
+                self.render_texture.as_image_copy(),
+                feedback.as_image_copy(),
+                wgpu::Extent3d {
+                    width: self.width,
+                    height: self.height,
+                    depth_or_array_layers: 1,
                 },
-            },
-            wgpu::Extent3d {
-                width: self.width,
-                height: self.height,
-                depth_or_array_layers: 1,
-            },
-        );
-
-        gpu.queue.submit(std::iter::once(encoder.finish()));
-
-        // Read back
-        let buffer_slice = self.output_buffer.slice(..);
-        let (sender, receiver) = std::sync::mpsc::channel();
-        buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
-            sender.send(result).unwrap();
-        });
-        gpu.device.poll(wgpu::PollType::wait_indefinitely())?;
-        receiver.recv()??;
-
-        let data = buffer_slice.get_mapped_range()?;
-
-        // Strip row padding
-        let mut pixels = Vec::with_capacity((self.unpadded_bytes_per_row * self.height) as usize);
-        for row in 0..self.height {
-            let start = (row * self.padded_bytes_per_row) as usize;
-            let end = start + self.unpadded_bytes_per_row as usize;
-            pixels.extend_from_slice(&data[start..end]);
+            );
         }
 
-        drop(data);
-        self.output_buffer.unmap();
-
-        Ok(pixels)
+        gpu.queue.submit(std::iter::once(encoder.finish()));
     }
 
-    /// Read back pixels from an arbitrary texture (e.g. post-processing output)
-    pub fn readback_texture(
-        &self,
-        gpu: &GpuContext,
-        texture: &wgpu::Texture,
-    ) -> Result<Vec<u8>> {
+    /// Copy `texture` (the render target or post-processing output) into the
+    /// next readback buffer and start mapping it. The pixels are read by
+    /// [`finish_readback`](Self::finish_readback); until then the GPU is free
+    /// to render the next frame. At most `READBACK_BUFFERS` readbacks may be
+    /// outstanding.
+    pub fn begin_readback(&self, gpu: &GpuContext, texture: &wgpu::Texture) -> Readback {
+        let buffer = self.next_readback.get();
+        self.next_readback.set((buffer + 1) % self.readback_buffers.len());
+        let output_buffer = &self.readback_buffers[buffer];
+        let busy = self.in_flight[buffer].replace(true);
+        debug_assert!(
+            !busy,
+            "readback buffer {buffer} is still mapped: more than {READBACK_BUFFERS} readbacks outstanding"
+        );
+
         let mut encoder = gpu.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("readback_encoder"),
         });
@@ -156,7 +168,7 @@ impl FrameRenderer {
                 aspect: wgpu::TextureAspect::All,
             },
             wgpu::TexelCopyBufferInfo {
-                buffer: &self.output_buffer,
+                buffer: output_buffer,
                 layout: wgpu::TexelCopyBufferLayout {
                     offset: 0,
                     bytes_per_row: Some(self.padded_bytes_per_row),
@@ -170,17 +182,34 @@ impl FrameRenderer {
             },
         );
 
-        gpu.queue.submit(std::iter::once(encoder.finish()));
+        let submission = gpu.queue.submit(std::iter::once(encoder.finish()));
 
-        let buffer_slice = self.output_buffer.slice(..);
-        let (sender, receiver) = std::sync::mpsc::channel();
-        buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
-            sender.send(result).unwrap();
+        let (sender, mapped) = std::sync::mpsc::channel();
+        output_buffer.slice(..).map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
         });
-        gpu.device.poll(wgpu::PollType::wait_indefinitely())?;
-        receiver.recv()??;
 
+        Readback {
+            buffer,
+            submission,
+            mapped,
+        }
+    }
+
+    /// Wait for `readback`'s copy, without waiting on anything submitted
+    /// after it, and return its pixels tightly packed.
+    pub fn finish_readback(&self, gpu: &GpuContext, readback: Readback) -> Result<Vec<u8>> {
+        let output_buffer = &self.readback_buffers[readback.buffer];
+        gpu.device.poll(wgpu::PollType::Wait {
+            submission_index: Some(readback.submission),
+            timeout: None,
+        })?;
+        readback.mapped.recv()??;
+
+        let buffer_slice = output_buffer.slice(..);
         let data = buffer_slice.get_mapped_range()?;
+
+        // Strip row padding
         let mut pixels = Vec::with_capacity((self.unpadded_bytes_per_row * self.height) as usize);
         for row in 0..self.height {
             let start = (row * self.padded_bytes_per_row) as usize;
@@ -188,7 +217,8 @@ impl FrameRenderer {
             pixels.extend_from_slice(&data[start..end]);
         }
         drop(data);
-        self.output_buffer.unmap();
+        output_buffer.unmap();
+        self.in_flight[readback.buffer].set(false);
 
         Ok(pixels)
     }
@@ -1,45 +1,130 @@
-use anyhow::{Context, Result};
 use wgpu;
 
+use crate::error::{Result, SonicaError};
+
 pub struct GpuContext {
     pub device: wgpu::Device,
     pub queue: wgpu::Queue,
+    pub adapter_info: wgpu::AdapterInfo,
 }
 
 impl GpuContext {
-    pub fn new() -> Result<Self> {
-        pollster::block_on(Self::init_async())
+    /// A device with limits raised as needed for `width`x`height` frames.
+    pub fn new(width: u32, height: u32) -> Result<Self> {
+        pollster::block_on(Self::init_async(width, height))
     }
 
-    async fn init_async() -> Result<Self> {
-        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
-            backends: wgpu::Backends::METAL | wgpu::Backends::VULKAN | wgpu::Backends::DX12,
-            ..wgpu::InstanceDescriptor::new_without_display_handle()
-        });
-
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::HighPerformance,
-                compatible_surface: None,
-                force_fallback_adapter: false,
-                apply_limit_buckets: false,
-            })
-            .await
-            .context("Failed to find a suitable GPU adapter")?;
+    /// Fail early, before decoding and analysis, if the GPU can't render
+    /// `width`x`height` frames.
+    pub fn check_resolution(width: u32, height: u32) -> Result<()> {
+        let adapter = pollster::block_on(request_adapter())?;
+        limits_for(&adapter.limits(), width, height, &adapter.get_info().name)?;
+        Ok(())
+    }
+
+    async fn init_async(width: u32, height: u32) -> Result<Self> {
+        let adapter = request_adapter().await?;
 
-        log::info!("Using GPU: {}", adapter.get_info().name);
-        log::info!("Backend: {:?}", adapter.get_info().backend);
+        let adapter_info = adapter.get_info();
+        log::info!("Using GPU: {}", adapter_info.name);
+        log::info!("Backend: {:?}", adapter_info.backend);
 
+        let required_limits = limits_for(&adapter.limits(), width, height, &adapter_info.name)?;
         let (device, queue) = adapter
             .request_device(&wgpu::DeviceDescriptor {
                 label: Some("sonica_device"),
                 required_features: wgpu::Features::empty(),
-                required_limits: wgpu::Limits::default(),
+                required_limits,
                 ..Default::default()
             })
             .await
-            .context("Failed to create GPU device")?;
+            .map_err(SonicaError::GpuDevice)?;
+
+        Ok(Self {
+            device,
+            queue,
+            adapter_info,
+        })
+    }
+}
+
+async fn request_adapter() -> Result<wgpu::Adapter> {
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+        backends: wgpu::Backends::METAL | wgpu::Backends::VULKAN | wgpu::Backends::DX12,
+        ..wgpu::InstanceDescriptor::new_without_display_handle()
+    });
+
+    instance
+        .request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            compatible_surface: None,
+            force_fallback_adapter: false,
+            apply_limit_buckets: false,
+        })
+        .await
+        .map_err(SonicaError::NoGpu)
+}
+
+/// Default limits, raised where a `width`x`height` frame needs more: the
+/// render and post-processing textures need the larger side as a 2D texture
+/// dimension, and each readback buffer holds one padded RGBA frame. Errors if
+/// the adapter (named `gpu` in messages) can't go that far.
+fn limits_for(adapter: &wgpu::Limits, width: u32, height: u32, gpu: &str) -> Result<wgpu::Limits> {
+    let mut limits = wgpu::Limits::default();
+
+    let dimension = width.max(height);
+    if dimension > adapter.max_texture_dimension_2d {
+        return Err(SonicaError::GpuLimits(format!(
+            "{}x{} is larger than the maximum texture size of {}px per side on {}; \
+             lower --width/--height to {} or less",
+            width,
+            height,
+            adapter.max_texture_dimension_2d,
+            gpu,
+            adapter.max_texture_dimension_2d
+        )));
+    }
+    limits.max_texture_dimension_2d = limits.max_texture_dimension_2d.max(dimension);
+
+    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    let readback_size = ((width as u64 * 4).div_ceil(align as u64) * align as u64) * height as u64;
+    if readback_size > adapter.max_buffer_size {
+        return Err(SonicaError::GpuLimits(format!(
+            "A {}x{} frame needs a {} MiB readback buffer, more than the {} MiB limit on {}; \
+             lower --width/--height",
+            width,
+            height,
+            readback_size.div_ceil(1 << 20),
+            adapter.max_buffer_size >> 20,
+            gpu
+        )));
+    }
+    limits.max_buffer_size = limits.max_buffer_size.max(readback_size);
+
+    Ok(limits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn raises_limits_up_to_what_the_adapter_allows() {
+        let adapter = wgpu::Limits {
+            max_texture_dimension_2d: 16384,
+            max_buffer_size: 2 << 30,
+            ..wgpu::Limits::default()
+        };
+
+        let hd = limits_for(&adapter, 1920, 1080, "test").unwrap();
+        assert_eq!(hd.max_texture_dimension_2d, wgpu::Limits::default().max_texture_dimension_2d);
+
+        let wide = limits_for(&adapter, 12000, 4000, "test").unwrap();
+        assert_eq!(wide.max_texture_dimension_2d, 12000);
+        assert!(wide.max_buffer_size >= 12000 * 4 * 4000);
 
-        Ok(Self { device, queue })
+        let err = limits_for(&wgpu::Limits::default(), 16384, 1080, "test").unwrap_err();
+        assert!(matches!(err, SonicaError::GpuLimits(_)), "{err}");
+        assert!(err.to_string().contains("8192"), "{err}");
     }
 }
@@ -0,0 +1,86 @@
+use anyhow::Result;
+
+use super::text::TextOverlay;
+
+/// Translucent red used to shade the unsafe regions.
+const GUIDE_COLOR: [u8; 4] = [255, 40, 40, 90];
+
+/// Regions of a vertical video covered by a platform's UI (captions, action
+/// buttons, progress bar), as fractions of the frame. Approximate, taken
+/// from each platform's published 1080x1920 templates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SafeGuides {
+    pub top: f32,
+    pub bottom: f32,
+    pub left: f32,
+    pub right: f32,
+}
+
+impl SafeGuides {
+    pub fn parse(platform: &str) -> Result<Self> {
+        let (top, bottom, left, right) = match platform {
+            "tiktok" => (0.08, 0.20, 0.05, 0.12),
+            "youtube" => (0.08, 0.19, 0.05, 0.11),
+            "instagram" => (0.12, 0.22, 0.05, 0.10),
+            other => anyhow::bail!(
+                "Unknown safe guide platform '{}'. Valid platforms: tiktok, youtube, instagram",
+                other
+            ),
+        };
+        Ok(Self {
+            top,
+            bottom,
+            left,
+            right,
+        })
+    }
+
+    /// Shade the unsafe regions. Side bands only span the rows between the
+    /// top and bottom bands so no area is shaded twice.
+    pub fn draw(&self, pixels: &mut [u8], width: u32, height: u32) {
+        let top = (self.top * height as f32) as u32;
+        let bottom = (self.bottom * height as f32) as u32;
+        let left = (self.left * width as f32) as u32;
+        let right = (self.right * width as f32) as u32;
+        let middle = height.saturating_sub(top + bottom);
+
+        TextOverlay::fill_rect(pixels, width, height, 0, 0, width, top, GUIDE_COLOR);
+        TextOverlay::fill_rect(pixels, width, height, 0, height - bottom, width, bottom, GUIDE_COLOR);
+        TextOverlay::fill_rect(pixels, width, height, 0, top, left, middle, GUIDE_COLOR);
+        TextOverlay::fill_rect(
+            pixels,
+            width,
+            height,
+            width - right,
+            top,
+            right,
+            middle,
+            GUIDE_COLOR,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shades_only_unsafe_regions() {
+        let (width, height) = (100, 200);
+        let mut pixels = vec![0u8; (width * height * 4) as usize];
+        SafeGuides::parse("tiktok").unwrap().draw(&mut pixels, width, height);
+
+        let red = |x: u32, y: u32| pixels[((y * width + x) * 4) as usize];
+        assert!(red(50, 5) > 0, "top band");
+        assert!(red(50, 195) > 0, "bottom band");
+        assert!(red(95, 100) > 0, "right band");
+        assert_eq!(red(50, 100), 0, "safe center");
+        // Overlapping corners would be shaded twice.
+        assert_eq!(red(2, 5), red(50, 5));
+    }
+
+    #[test]
+    fn rejects_unknown_platform() {
+        assert!(SafeGuides::parse("vine").is_err());
+    }
+}
@@ -0,0 +1,134 @@
+use super::gpu::GpuContext;
+use super::pipeline::{ComputePipelineWrapper, COMPUTE_WORKGROUP_SIZE, HISTORY_FORMAT};
+use crate::templates::manifest::HistoryDef;
+
+/// A template's `history` texture: two copies the compute shader ping-pongs
+/// between, reading last frame's and writing this frame's, so the fragment
+/// shader sees a picture that builds up over time (a scrolling spectrogram).
+pub struct HistoryTexture {
+    pub binding: u32,
+    width: u32,
+    height: u32,
+    #[allow(dead_code)]
+    textures: [wgpu::Texture; 2],
+    views: [wgpu::TextureView; 2],
+    pub sampler: wgpu::Sampler,
+    /// `compute_bind_groups[i]` reads copy `i` and writes the other.
+    compute_bind_groups: [wgpu::BindGroup; 2],
+    /// The copy holding the newest frame.
+    current: usize,
+}
+
+impl HistoryTexture {
+    /// Create both copies, cleared to zero, and the compute bind groups that
+    /// pair them with the shared uniform, FFT, and waveform buffers.
+    pub fn new(
+        gpu: &GpuContext,
+        def: &HistoryDef,
+        compute: &ComputePipelineWrapper,
+        uniform_buffer: &wgpu::Buffer,
+        fft_buffer: &wgpu::Buffer,
+        waveform_buffer: &wgpu::Buffer,
+    ) -> Self {
+        let create = |label| {
+            gpu.device.create_texture(&wgpu::TextureDescriptor {
+                label: Some(label),
+                size: wgpu::Extent3d {
+                    width: def.width,
+                    height: def.height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: HISTORY_FORMAT,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::STORAGE_BINDING,
+                view_formats: &[],
+            })
+        };
+        let textures = [create("history_a"), create("history_b")];
+        let views = [0, 1].map(|i| textures[i].create_view(&wgpu::TextureViewDescriptor::default()));
+
+        let compute_bind_groups = [0, 1].map(|read| {
+            gpu.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("compute_bind_group"),
+                layout: &compute.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: uniform_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: fft_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: waveform_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: wgpu::BindingResource::TextureView(&views[read]),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 4,
+                        resource: wgpu::BindingResource::TextureView(&views[1 - read]),
+                    },
+                ],
+            })
+        });
+
+        let sampler = gpu.device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("history_sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        log::info!("Template history texture {}x{} at binding {}", def.width, def.height, def.binding);
+
+        Self {
+            binding: def.binding,
+            width: def.width,
+            height: def.height,
+            textures,
+            views,
+            sampler,
+            compute_bind_groups,
+            current: 0,
+        }
+    }
+
+    /// Both copies, for building one fragment bind group per copy.
+    pub fn views(&self) -> &[wgpu::TextureView; 2] {
+        &self.views
+    }
+
+    /// Index into [`views`](Self::views) of the copy holding the newest frame.
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    /// Run the compute shader once, writing the next frame's history from
+    /// the current one, and make that the current copy.
+    pub fn advance(&mut self, gpu: &GpuContext, compute: &ComputePipelineWrapper) {
+        let mut encoder = gpu.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("history_encoder"),
+        });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("history_pass"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&compute.pipeline);
+            pass.set_bind_group(0, &self.compute_bind_groups[self.current], &[]);
+            pass.dispatch_workgroups(
+                self.width.div_ceil(COMPUTE_WORKGROUP_SIZE),
+                self.height.div_ceil(COMPUTE_WORKGROUP_SIZE),
+                1,
+            );
+        }
+        gpu.queue.submit(std::iter::once(encoder.finish()));
+        self.current = 1 - self.current;
+    }
+}
@@ -0,0 +1,142 @@
+use std::path::Path;
+
+use super::text::TextOverlay;
+use crate::audio::features::SmoothedFrame;
+
+const BAR_COUNT: usize = 48;
+const PANEL_COLOR: [u8; 4] = [0, 0, 0, 170];
+const BAR_COLOR: [u8; 4] = [80, 220, 140, 230];
+const METER_TRACK_COLOR: [u8; 4] = [255, 255, 255, 40];
+const TEXT_COLOR: [u8; 4] = [255, 255, 255, 235];
+const BEAT_COLOR: [u8; 4] = [255, 70, 70, 255];
+
+/// `--debug-hud`: a diagnostic panel in the top-left corner showing what the
+/// analysis fed the shader this frame — a log-spaced spectrum, level meters
+/// for rms/bass/mid/high, and the tempo and beat state.
+pub struct DebugHud {
+    overlay: TextOverlay,
+    tempo_bpm: f32,
+}
+
+impl DebugHud {
+    pub fn new(
+        height: u32,
+        tempo_bpm: f32,
+        font_path: Option<&Path>,
+        font_bytes: Option<&[u8]>,
+        font_family: Option<&str>,
+    ) -> Self {
+        let font_size = (height as f32 * 0.018).max(12.0);
+        Self {
+            overlay: TextOverlay::new(font_size, font_path, font_bytes, font_family),
+            tempo_bpm,
+        }
+    }
+
+    pub fn render_frame(&self, pixels: &mut [u8], width: u32, height: u32, frame: &SmoothedFrame) {
+        let line = self.overlay.line_height();
+        let pad = line / 2;
+        let panel_w = (width / 4).max(240).min(width);
+        let graph_h = line * 4;
+        let meters = [
+            ("rms", frame.rms),
+            ("bass", frame.bass),
+            ("mid", frame.mid),
+            ("high", frame.high),
+        ];
+        let panel_h = pad * 3 + graph_h + line * (meters.len() as u32 + 1);
+        TextOverlay::fill_rect(pixels, width, height, 0, 0, panel_w, panel_h, PANEL_COLOR);
+
+        // Spectrum
+        let graph_w = panel_w - pad * 2;
+        let bar_w = (graph_w / BAR_COUNT as u32).max(1);
+        for (i, level) in spectrum_bars(&frame.fft_bins, BAR_COUNT).into_iter().enumerate() {
+            let bar_h = (level * graph_h as f32) as u32;
+            TextOverlay::fill_rect(
+                pixels,
+                width,
+                height,
+                pad + i as u32 * bar_w,
+                pad + graph_h - bar_h,
+                bar_w.saturating_sub(1).max(1),
+                bar_h,
+                BAR_COLOR,
+            );
+        }
+
+        // Level meters
+        let label_w = self.overlay.measure_width("high 0.00 ");
+        let meter_x = pad + label_w;
+        let meter_w = panel_w.saturating_sub(meter_x + pad);
+        let mut y = pad * 2 + graph_h;
+        for (name, value) in meters {
+            let text = format!("{name} {value:.2}");
+            self.overlay.composite(pixels, width, height, &text, pad, y, TEXT_COLOR);
+            let meter_y = y + line / 4;
+            let meter_h = (line / 2).max(1);
+            TextOverlay::fill_rect(pixels, width, height, meter_x, meter_y, meter_w, meter_h, METER_TRACK_COLOR);
+            let fill = (value.clamp(0.0, 1.0) * meter_w as f32) as u32;
+            TextOverlay::fill_rect(pixels, width, height, meter_x, meter_y, fill, meter_h, BAR_COLOR);
+            y += line;
+        }
+
+        // Tempo and beat state
+        let text = format!(
+            "bpm {:.1}  beat {:.2}  t {:.2}s",
+            self.tempo_bpm, frame.beat_intensity, frame.time
+        );
+        self.overlay.composite(pixels, width, height, &text, pad, y, TEXT_COLOR);
+        if frame.is_beat {
+            let size = line / 2;
+            TextOverlay::fill_rect(
+                pixels,
+                width,
+                height,
+                panel_w - pad - size,
+                y + line / 4,
+                size,
+                size,
+                BEAT_COLOR,
+            );
+        }
+    }
+}
+
+/// Collapse the FFT into `count` log-spaced bars (max of each bin range), so
+/// the low end isn't squeezed into the first bar or two.
+fn spectrum_bars(fft_bins: &[f32], count: usize) -> Vec<f32> {
+    if fft_bins.len() < 2 {
+        return vec![0.0; count];
+    }
+    let max_bin = fft_bins.len() as f32;
+    (0..count)
+        .map(|i| {
+            let start = max_bin.powf(i as f32 / count as f32) as usize;
+            let end = (max_bin.powf((i + 1) as f32 / count as f32) as usize).max(start + 1);
+            fft_bins[start.min(fft_bins.len() - 1)..end.min(fft_bins.len())]
+                .iter()
+                .copied()
+                .fold(0.0, f32::max)
+                .clamp(0.0, 1.0)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spectrum_bars_cover_every_bin_in_log_steps() {
+        let mut bins = vec![0.0f32; 1024];
+        bins[1] = 0.5;
+        bins[1023] = 1.0;
+
+        let bars = spectrum_bars(&bins, 48);
+
+        assert_eq!(bars.len(), 48);
+        assert_eq!(bars[0], 0.5, "low bins get their own bars");
+        assert_eq!(bars[24], 0.0);
+        assert_eq!(bars[47], 1.0);
+    }
+}
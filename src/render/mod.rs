@@ -1,5 +1,22 @@
+pub mod background;
+pub mod contact_sheet;
+pub mod debug_grid;
+pub mod energy_graph;
+pub mod feedback;
 pub mod gpu;
+pub mod guides;
+pub mod history;
+pub mod hud;
 pub mod pipeline;
+pub mod flash;
 pub mod frame;
 pub mod postprocess;
+#[cfg(test)]
+mod reference;
+pub mod renderer;
+pub mod script;
+pub mod showcase;
+pub mod temporal;
 pub mod text;
+pub mod texture;
+pub mod waveform_strip;
@@ -1,7 +1,13 @@
-use anyhow::Result;
 use bytemuck::{Pod, Zeroable};
 use wgpu;
 
+use crate::error::{Result, SonicaError};
+
+/// Binding of `waveform_r`, the right channel's waveform under `--stereo`
+/// (a copy of `waveform` otherwise). Kept clear of the low bindings that
+/// template textures start from.
+pub const WAVEFORM_R_BINDING: u32 = 15;
+
 #[repr(C)]
 #[derive(Clone, Copy, Debug, Pod, Zeroable)]
 pub struct FrameUniforms {
@@ -19,7 +25,19 @@ pub struct FrameUniforms {
     pub bass: f32,
     pub mid: f32,
     pub high: f32,
-    pub _padding: f32,
+    /// Spectral centroid in Hz, for thresholds in real units
+    pub spectral_centroid_hz: f32,
+    /// Spectral flux before normalization
+    pub spectral_flux_raw: f32,
+    /// Smoothed onset-strength envelope (0.0-1.0)
+    pub onset_strength: f32,
+    pub _padding: [f32; 2],
+    /// Left channel bands: sub_bass, bass, low_mid, mid
+    pub left_bands_low: [f32; 4],
+    /// Left channel bands: upper_mid, presence, brilliance, 0
+    pub left_bands_high: [f32; 4],
+    pub right_bands_low: [f32; 4],
+    pub right_bands_high: [f32; 4],
 }
 
 impl Default for FrameUniforms {
@@ -39,7 +57,14 @@ impl Default for FrameUniforms {
             bass: 0.0,
             mid: 0.0,
             high: 0.0,
-            _padding: 0.0,
+            spectral_centroid_hz: 0.0,
+            spectral_flux_raw: 0.0,
+            onset_strength: 0.0,
+            _padding: [0.0; 2],
+            left_bands_low: [0.0; 4],
+            left_bands_high: [0.0; 4],
+            right_bands_low: [0.0; 4],
+            right_bands_high: [0.0; 4],
         }
     }
 }
@@ -50,49 +75,127 @@ pub struct RenderPipeline {
 }
 
 impl RenderPipeline {
-    pub fn new(device: &wgpu::Device, shader_source: &str, texture_format: wgpu::TextureFormat) -> Result<Self> {
+    /// `texture_bindings` lists the template's own textures; each adds a
+    /// `texture_2d<f32>` at that binding and a filtering sampler at binding + 1.
+    /// `name` identifies the template in compile errors. With `accumulate`
+    /// the output is alpha-blended over the render target's contents instead
+    /// of replacing them.
+    pub fn new(
+        device: &wgpu::Device,
+        name: &str,
+        shader_source: &str,
+        texture_format: wgpu::TextureFormat,
+        texture_bindings: &[u32],
+        accumulate: bool,
+    ) -> Result<Self> {
+        capture_shader_errors(device, name, || {
+            Self::create(device, shader_source, texture_format, texture_bindings, accumulate)
+        })
+    }
+
+    fn create(
+        device: &wgpu::Device,
+        shader_source: &str,
+        texture_format: wgpu::TextureFormat,
+        texture_bindings: &[u32],
+        accumulate: bool,
+    ) -> Self {
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("template_shader"),
             source: wgpu::ShaderSource::Wgsl(shader_source.into()),
         });
 
-        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: Some("main_bind_group_layout"),
-            entries: &[
-                // @binding(0): FrameUniforms
-                wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
+        let mut entries = vec![
+            // @binding(0): FrameUniforms
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
                 },
-                // @binding(1): FFT bins (storage)
-                wgpu::BindGroupLayoutEntry {
-                    binding: 1,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
+                count: None,
+            },
+            // @binding(1): FFT bins (storage)
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Storage { read_only: true },
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
                 },
-                // @binding(2): waveform samples (storage)
-                wgpu::BindGroupLayoutEntry {
-                    binding: 2,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
+                count: None,
+            },
+            // @binding(2): waveform samples (storage)
+            wgpu::BindGroupLayoutEntry {
+                binding: 2,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Storage { read_only: true },
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
                 },
-            ],
+                count: None,
+            },
+            // @binding(3): animated parameters (`param_anim`)
+            wgpu::BindGroupLayoutEntry {
+                binding: 3,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            // @binding(4): external automation values (storage)
+            wgpu::BindGroupLayoutEntry {
+                binding: 4,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Storage { read_only: true },
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            // @binding(15): right-channel waveform samples (storage)
+            wgpu::BindGroupLayoutEntry {
+                binding: WAVEFORM_R_BINDING,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Storage { read_only: true },
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+        ];
+
+        for &binding in texture_bindings {
+            entries.push(wgpu::BindGroupLayoutEntry {
+                binding,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            });
+            entries.push(wgpu::BindGroupLayoutEntry {
+                binding: binding + 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            });
+        }
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("main_bind_group_layout"),
+            entries: &entries,
         });
 
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
@@ -115,7 +218,11 @@ impl RenderPipeline {
                 entry_point: Some("fs_main"),
                 targets: &[Some(wgpu::ColorTargetState {
                     format: texture_format,
-                    blend: Some(wgpu::BlendState::REPLACE),
+                    blend: Some(if accumulate {
+                        wgpu::BlendState::ALPHA_BLENDING
+                    } else {
+                        wgpu::BlendState::REPLACE
+                    }),
                     write_mask: wgpu::ColorWrites::ALL,
                 })],
                 compilation_options: Default::default(),
@@ -135,22 +242,53 @@ impl RenderPipeline {
             cache: None,
         });
 
-        Ok(Self {
+        Self {
             pipeline,
             bind_group_layout,
-        })
+        }
+    }
+}
+
+/// Run `create` with validation errors captured rather than passed to the
+/// device's uncaptured-error handler, which panics, and report them as a
+/// compile failure of the shader `label`.
+pub(crate) fn capture_shader_errors<T>(
+    device: &wgpu::Device,
+    label: &str,
+    create: impl FnOnce() -> T,
+) -> Result<T> {
+    let scope = device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let value = create();
+    match pollster::block_on(scope.pop()) {
+        Some(error) => Err(SonicaError::ShaderCompile {
+            label: label.to_string(),
+            message: error.to_string(),
+        }),
+        None => Ok(value),
     }
 }
 
+/// Format of a template's history texture: filterable for the fragment
+/// shader's sampler and writable as a storage texture without extra features.
+pub const HISTORY_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+/// Workgroup size compute shaders must declare, `@workgroup_size(8, 8)`:
+/// one invocation per history texel.
+pub const COMPUTE_WORKGROUP_SIZE: u32 = 8;
+
+/// A template's compute stage, run once per frame before the fragment pass
+/// to update its history texture.
 pub struct ComputePipelineWrapper {
-    #[allow(dead_code)]
     pub pipeline: wgpu::ComputePipeline,
-    #[allow(dead_code)]
     pub bind_group_layout: wgpu::BindGroupLayout,
 }
 
 impl ComputePipelineWrapper {
-    pub fn new(device: &wgpu::Device, shader_source: &str) -> Result<Self> {
+    pub fn new(device: &wgpu::Device, name: &str, shader_source: &str) -> Result<Self> {
+        capture_shader_errors(device, name, || Self::create(device, shader_source))
+    }
+
+    fn create(device: &wgpu::Device, shader_source: &str) -> Self {
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("compute_shader"),
             source: wgpu::ShaderSource::Wgsl(shader_source.into()),
@@ -192,14 +330,25 @@ impl ComputePipelineWrapper {
                     },
                     count: None,
                 },
-                // @binding(3): Output (storage, read-write)
+                // @binding(3): last frame's history (read with textureLoad)
                 wgpu::BindGroupLayoutEntry {
                     binding: 3,
                     visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: false },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                // @binding(4): this frame's history (write-only storage texture)
+                wgpu::BindGroupLayoutEntry {
+                    binding: 4,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::StorageTexture {
+                        access: wgpu::StorageTextureAccess::WriteOnly,
+                        format: HISTORY_FORMAT,
+                        view_dimension: wgpu::TextureViewDimension::D2,
                     },
                     count: None,
                 },
@@ -221,9 +370,9 @@ impl ComputePipelineWrapper {
             cache: None,
         });
 
-        Ok(Self {
+        Self {
             pipeline,
             bind_group_layout,
-        })
+        }
     }
 }
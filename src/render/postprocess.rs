@@ -1,8 +1,9 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use bytemuck::{Pod, Zeroable};
 use wgpu;
 
 use super::frame::TEXTURE_FORMAT;
+use super::pipeline::capture_shader_errors;
 
 /// Every post-processing effect, in the order the `all` preset applies them.
 /// This is the single source of truth for `--help`, `--list-effects`, and
@@ -15,6 +16,18 @@ pub const EFFECTS: &[(&str, &str)] = &[
     ("vignette", "Darkens the corners to draw the eye inward"),
     ("film_grain", "Animated sensor noise (warning: inflates file size)"),
     ("color_grading", "Cinematic contrast and colour tint"),
+    (
+        "mirror",
+        "Symmetry fold; mirror:horizontal|vertical|quad|<N> (N-fold radial)",
+    ),
+    (
+        "hue_rotate",
+        "Rotates every hue with the spectral centroid (brighter sound, further round); hue_rotate:<degrees 1-360> (default 180)",
+    ),
+    (
+        "broadcast_safe",
+        "Soft-knee limit on highlights, always applied last; broadcast_safe:<ceiling 1-255> (default 235)",
+    ),
 ];
 
 /// Shorthand names that expand to several effects (or none at all).
@@ -35,9 +48,12 @@ pub const EFFECT_PRESETS: &[(&str, &str)] = &[
 /// Unknown names used to only produce a `log::warn!` mid-render, so a typo like
 /// `vignete` yielded a successful video that was silently missing the effect.
 pub fn validate_effects(effects: &[String]) -> Result<()> {
-    for name in effects {
-        let known = EFFECTS.iter().any(|(e, _)| e == name)
-            || EFFECT_PRESETS.iter().any(|(p, _)| p == name);
+    for spec in effects {
+        effect_scale(spec)?;
+        let tuned = effect_intensity(spec)? != 1.0;
+        let (name, option) = split_effect_spec(spec);
+        let known = EFFECTS.iter().any(|(e, _)| *e == name)
+            || EFFECT_PRESETS.iter().any(|(p, _)| *p == name);
         if !known {
             let all: Vec<&str> = EFFECTS
                 .iter()
@@ -50,10 +66,160 @@ pub fn validate_effects(effects: &[String]) -> Result<()> {
                 all.join(", ")
             );
         }
+        if tuned && matches!(name, "mirror" | "broadcast_safe") {
+            anyhow::bail!("Effect '{}' has no intensity to tune (got '{}')", name, spec);
+        }
+        match (name, option) {
+            ("mirror", option) => {
+                MirrorMode::parse(option)?;
+            }
+            ("broadcast_safe", option) => {
+                broadcast_ceiling(option)?;
+            }
+            ("hue_rotate", option) => {
+                hue_range(option)?;
+            }
+            (_, Some(option)) => {
+                anyhow::bail!("Effect '{}' does not take an option (got '{}')", name, option);
+            }
+            (_, None) => {}
+        }
     }
     Ok(())
 }
 
+/// Splits `name:option` (e.g. `mirror:quad`) into its parts, ignoring any
+/// `=intensity` and `@scale` suffixes.
+fn split_effect_spec(spec: &str) -> (&str, Option<&str>) {
+    let spec = split_effect_intensity(split_effect_scale(spec).0).0;
+    match spec.split_once(':') {
+        Some((name, option)) => (name, Some(option)),
+        None => (spec, None),
+    }
+}
+
+/// Splits `spec@scale` (e.g. `bloom@0.5`) into the effect and its scale.
+fn split_effect_scale(spec: &str) -> (&str, Option<&str>) {
+    match spec.split_once('@') {
+        Some((spec, scale)) => (spec, Some(scale)),
+        None => (spec, None),
+    }
+}
+
+/// Splits `spec=intensity` (e.g. `vignette=0.5`) into the effect and its
+/// intensity.
+fn split_effect_intensity(spec: &str) -> (&str, Option<&str>) {
+    match spec.split_once('=') {
+        Some((spec, intensity)) => (spec, Some(intensity)),
+        None => (spec, None),
+    }
+}
+
+/// Strength of an effect, passed to its shader as `pp.intensity`: 1.0 is
+/// the standard look, 0.0 turns it off, and up to 2.0 exaggerates it.
+fn effect_intensity(spec: &str) -> Result<f32> {
+    match split_effect_intensity(split_effect_scale(spec).0).1 {
+        None => Ok(1.0),
+        Some(value) => match value.parse::<f32>() {
+            Ok(intensity) if (0.0..=2.0).contains(&intensity) => Ok(intensity),
+            _ => anyhow::bail!(
+                "Invalid intensity '{}' for effect '{}'. Use a value from 0.0 to 2.0 (1.0 is the standard look)",
+                value,
+                split_effect_spec(spec).0
+            ),
+        },
+    }
+}
+
+/// Fraction of the output resolution an effect renders at: `bloom@0.5` runs
+/// the bloom pass at half width and height, and the next pass upsamples it.
+fn effect_scale(spec: &str) -> Result<f32> {
+    match split_effect_scale(spec).1 {
+        None => Ok(1.0),
+        Some(value) => match value.parse::<f32>() {
+            Ok(scale) if scale > 0.0 && scale <= 1.0 => Ok(scale),
+            _ => anyhow::bail!(
+                "Invalid scale '{}' for effect '{}'. Use a fraction of the output resolution above 0 and up to 1 (e.g. bloom@0.5)",
+                value,
+                split_effect_spec(spec).0
+            ),
+        },
+    }
+}
+
+/// How the `mirror` effect folds the frame.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum MirrorMode {
+    /// Left half reflected onto the right.
+    Horizontal,
+    /// Top half reflected onto the bottom.
+    Vertical,
+    /// Top-left quadrant reflected into all four.
+    Quad,
+    /// N wedges around the centre, each a reflection of its neighbour.
+    Radial(u32),
+}
+
+impl MirrorMode {
+    fn parse(option: Option<&str>) -> Result<Self> {
+        match option {
+            None | Some("horizontal") => Ok(Self::Horizontal),
+            Some("vertical") => Ok(Self::Vertical),
+            Some("quad") => Ok(Self::Quad),
+            Some(other) => match other.parse::<u32>() {
+                Ok(segments) if segments >= 2 => Ok(Self::Radial(segments)),
+                _ => anyhow::bail!(
+                    "Invalid mirror option '{}'. Use horizontal, vertical, quad, or a segment count >= 2",
+                    other
+                ),
+            },
+        }
+    }
+
+    /// WGSL constants consumed by the `mirror` fragment shader.
+    fn wgsl_consts(self) -> String {
+        // Non-radial modes never read the segment count, but it is still
+        // const-evaluated, so keep it non-zero.
+        let (mode, segments) = match self {
+            Self::Horizontal => (0, 2),
+            Self::Vertical => (1, 2),
+            Self::Quad => (2, 2),
+            Self::Radial(segments) => (3, segments),
+        };
+        format!(
+            "const MIRROR_MODE: i32 = {};\nconst MIRROR_SEGMENTS: f32 = {}.0;\n",
+            mode, segments
+        )
+    }
+}
+
+/// Highlight ceiling for `broadcast_safe` as a fraction of full scale, from an
+/// 8-bit code value (235 is the top of limited-range video).
+fn broadcast_ceiling(option: Option<&str>) -> Result<f32> {
+    match option {
+        None => Ok(235.0 / 255.0),
+        Some(value) => match value.parse::<u32>() {
+            Ok(code) if (1..=255).contains(&code) => Ok(code as f32 / 255.0),
+            _ => anyhow::bail!(
+                "Invalid broadcast_safe ceiling '{}'. Use an 8-bit code value from 1 to 255",
+                value
+            ),
+        },
+    }
+}
+
+/// How far `hue_rotate` turns the hue at the brightest spectral centroid, as
+/// a fraction of the colour wheel, from an option in degrees.
+fn hue_range(option: Option<&str>) -> Result<f32> {
+    match option {
+        None => Ok(0.5),
+        Some(value) => match value.parse::<f32>() {
+            Ok(degrees) if degrees > 0.0 && degrees <= 360.0 => Ok(degrees / 360.0),
+            _ => anyhow::bail!("Invalid hue_rotate range '{}'. Use degrees from 1 to 360", value),
+        },
+    }
+}
+
 #[repr(C)]
 #[derive(Clone, Copy, Debug, Pod, Zeroable)]
 pub struct PostProcessUniforms {
@@ -67,16 +233,27 @@ pub struct PostProcessPass {
     bind_group_layout: wgpu::BindGroupLayout,
     sampler: wgpu::Sampler,
     uniform_buffer: wgpu::Buffer,
+    /// The effect's `=intensity`, 1.0 unless tuned.
+    intensity: f32,
+    /// `intensity` is also scaled by the frame's normalized spectral centroid.
+    centroid_intensity: bool,
     #[allow(dead_code)]
     name: String,
 }
 
+/// An intermediate texture the chain renders into.
+struct Target {
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+}
+
 pub struct PostProcessChain {
     passes: Vec<PostProcessPass>,
-    ping_texture: wgpu::Texture,
-    pong_texture: wgpu::Texture,
-    ping_view: wgpu::TextureView,
-    pong_view: wgpu::TextureView,
+    /// Target 0 is full resolution and receives the input frame; the rest
+    /// are ping/pong textures at each size the passes render at.
+    targets: Vec<Target>,
+    /// Index into `targets` that each pass renders into.
+    pass_targets: Vec<usize>,
     width: u32,
     height: u32,
 }
@@ -87,9 +264,9 @@ impl PostProcessChain {
         width: u32,
         height: u32,
         effects: &[String],
-    ) -> Result<Self> {
-        let make_texture = |label: &str| {
-            device.create_texture(&wgpu::TextureDescriptor {
+    ) -> crate::error::Result<Self> {
+        let make_target = |label: &str, (width, height): (u32, u32)| {
+            let texture = device.create_texture(&wgpu::TextureDescriptor {
                 label: Some(label),
                 size: wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
                 mip_level_count: 1,
@@ -101,34 +278,47 @@ impl PostProcessChain {
                     | wgpu::TextureUsages::COPY_SRC
                     | wgpu::TextureUsages::COPY_DST,
                 view_formats: &[],
-            })
+            });
+            let view = texture.create_view(&Default::default());
+            Target { texture, view }
         };
 
-        let ping_texture = make_texture("pp_ping");
-        let pong_texture = make_texture("pp_pong");
-        let ping_view = ping_texture.create_view(&Default::default());
-        let pong_view = pong_texture.create_view(&Default::default());
-
         let mut passes = Vec::new();
 
         // Expand presets
         let expanded = expand_effects(effects);
+        if !expanded.is_empty() {
+            log::info!("Post-processing order: {}", expanded.join(" -> "));
+        }
 
+        let mut scales = Vec::new();
         for effect_name in &expanded {
             if let Some(shader_src) = get_effect_shader(effect_name) {
                 let pass = PostProcessPass::new(device, &shader_src, effect_name)?;
                 passes.push(pass);
+                scales.push(effect_scale(effect_name)?);
             } else {
                 log::warn!("Unknown effect: {}", effect_name);
             }
         }
+        // The last pass writes the finished frame, so it always runs at full
+        // resolution.
+        if let Some(last) = scales.last_mut() {
+            *last = 1.0;
+        }
+
+        let sizes = target_sizes(width, height, &scales);
+        let targets = sizes
+            .sizes
+            .iter()
+            .enumerate()
+            .map(|(i, &size)| make_target(&format!("pp_target_{i}"), size))
+            .collect();
 
         Ok(Self {
             passes,
-            ping_texture,
-            pong_texture,
-            ping_view,
-            pong_view,
+            targets,
+            pass_targets: sizes.pass_targets,
             width,
             height,
         })
@@ -140,6 +330,7 @@ impl PostProcessChain {
 
     /// Run the post-processing chain.
     /// Input texture is copied to ping, then ping-pong through passes.
+    /// `spectral_centroid` (0.0-1.0) drives the audio-reactive effects.
     /// Returns the view of the final output texture.
     pub fn run<'a>(
         &'a self,
@@ -147,33 +338,31 @@ impl PostProcessChain {
         queue: &wgpu::Queue,
         input_texture: &'a wgpu::Texture,
         time: f32,
+        spectral_centroid: f32,
     ) -> &'a wgpu::Texture {
         if self.passes.is_empty() {
             return input_texture;
         }
 
-        // Copy input to ping
+        // Copy input to the full-resolution target
         let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("pp_copy_encoder"),
         });
         encoder.copy_texture_to_texture(
             input_texture.as_image_copy(),
-            self.ping_texture.as_image_copy(),
+            self.targets[0].texture.as_image_copy(),
             wgpu::Extent3d { width: self.width, height: self.height, depth_or_array_layers: 1 },
         );
         queue.submit(std::iter::once(encoder.finish()));
 
-        let textures = [&self.ping_texture, &self.pong_texture];
-        let views = [&self.ping_view, &self.pong_view];
-
-        for (i, pass) in self.passes.iter().enumerate() {
-            let src_idx = i % 2;
-            let dst_idx = (i + 1) % 2;
-
+        let mut src_idx = 0;
+        for (pass, &dst_idx) in self.passes.iter().zip(&self.pass_targets) {
+            // Effects are written against the output resolution; a scaled
+            // pass evaluates them at fewer pixels, not with a smaller kernel.
             let uniforms = PostProcessUniforms {
                 resolution: [self.width as f32, self.height as f32],
                 time,
-                intensity: 1.0,
+                intensity: if pass.centroid_intensity { pass.intensity * spectral_centroid } else { pass.intensity },
             };
             queue.write_buffer(&pass.uniform_buffer, 0, bytemuck::bytes_of(&uniforms));
 
@@ -187,7 +376,7 @@ impl PostProcessChain {
                     },
                     wgpu::BindGroupEntry {
                         binding: 1,
-                        resource: wgpu::BindingResource::TextureView(views[src_idx]),
+                        resource: wgpu::BindingResource::TextureView(&self.targets[src_idx].view),
                     },
                     wgpu::BindGroupEntry {
                         binding: 2,
@@ -204,7 +393,7 @@ impl PostProcessChain {
                 let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                     label: Some("pp_pass"),
                     color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                        view: views[dst_idx],
+                        view: &self.targets[dst_idx].view,
                         depth_slice: None,
                         resolve_target: None,
                         ops: wgpu::Operations {
@@ -224,16 +413,53 @@ impl PostProcessChain {
             }
 
             queue.submit(std::iter::once(encoder.finish()));
+            src_idx = dst_idx;
         }
 
         // Return the texture that has the final result
-        let final_idx = self.passes.len() % 2;
-        textures[final_idx]
+        &self.targets[src_idx].texture
+    }
+}
+
+/// Texture sizes for a chain and the one each pass renders into.
+#[derive(Debug, PartialEq)]
+struct TargetSizes {
+    sizes: Vec<(u32, u32)>,
+    pass_targets: Vec<usize>,
+}
+
+/// Plan the chain's intermediate textures: a pass renders into a texture of
+/// its own size that isn't the one it reads, so each size needs at most a
+/// ping/pong pair. The first texture is full size and holds the input.
+fn target_sizes(width: u32, height: u32, scales: &[f32]) -> TargetSizes {
+    let mut sizes = vec![(width, height)];
+    let mut pass_targets = Vec::with_capacity(scales.len());
+    let mut src = 0;
+    for &scale in scales {
+        let size = (
+            ((width as f32 * scale).round() as u32).max(1),
+            ((height as f32 * scale).round() as u32).max(1),
+        );
+        let dst = match (0..sizes.len()).find(|&i| i != src && sizes[i] == size) {
+            Some(dst) => dst,
+            None => {
+                sizes.push(size);
+                sizes.len() - 1
+            }
+        };
+        pass_targets.push(dst);
+        src = dst;
     }
+    TargetSizes { sizes, pass_targets }
 }
 
 impl PostProcessPass {
-    fn new(device: &wgpu::Device, shader_source: &str, name: &str) -> Result<Self> {
+    fn new(device: &wgpu::Device, shader_source: &str, name: &str) -> crate::error::Result<Self> {
+        let intensity = effect_intensity(name)?;
+        capture_shader_errors(device, name, || Self::create(device, shader_source, name, intensity))
+    }
+
+    fn create(device: &wgpu::Device, shader_source: &str, name: &str, intensity: f32) -> Self {
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some(name),
             source: wgpu::ShaderSource::Wgsl(shader_source.into()),
@@ -320,40 +546,82 @@ impl PostProcessPass {
             mapped_at_creation: false,
         });
 
-        Ok(Self {
+        Self {
             pipeline,
             bind_group_layout,
             sampler,
             uniform_buffer,
+            intensity,
+            centroid_intensity: split_effect_spec(name).0 == "hue_rotate",
             name: name.to_string(),
-        })
+        }
     }
 }
 
-fn expand_effects(effects: &[String]) -> Vec<String> {
+/// Expand presets into their effects, in execution order. A preset's
+/// `=intensity` and `@scale` apply to each effect it expands to.
+pub fn expand_effects(effects: &[String]) -> Vec<String> {
     let mut result = Vec::new();
     for e in effects {
-        match e.as_str() {
+        let name = split_effect_spec(e).0;
+        let suffix = &e[name.len()..];
+        let with_suffix = |effect: &str| format!("{effect}{suffix}");
+        match name {
             "none" => return Vec::new(),
             "crt" => {
-                result.extend_from_slice(&[
-                    "crt_scanlines".into(),
-                    "chromatic_aberration".into(),
-                    "vignette".into(),
-                    "film_grain".into(),
-                    "color_grading".into(),
-                ]);
+                result.extend(
+                    ["crt_scanlines", "chromatic_aberration", "vignette", "film_grain", "color_grading"]
+                        .map(with_suffix),
+                );
             }
             "all" => {
-                result.extend(EFFECTS.iter().map(|(name, _)| name.to_string()));
+                result.extend(EFFECTS.iter().map(|(name, _)| with_suffix(name)));
             }
-            other => result.push(other.to_string()),
+            _ => result.push(e.clone()),
         }
     }
+    // The limiter has to see the final image, so it runs after everything
+    // else no matter where it was listed.
+    let (limiters, mut result): (Vec<String>, Vec<String>) = result
+        .into_iter()
+        .partition(|spec| split_effect_spec(spec).0 == "broadcast_safe");
+    result.extend(limiters.into_iter().last());
     result
 }
 
-fn get_effect_shader(name: &str) -> Option<String> {
+/// Rearrange an expanded chain for `--effect-order`: the listed effects run
+/// in the listed order, taking the positions those effects already held,
+/// and every other effect stays where it was. Names match without their
+/// option, so `mirror` matches `mirror:6`.
+pub fn reorder_effects(mut effects: Vec<String>, order: &[String]) -> Result<Vec<String>> {
+    let mut slots = Vec::with_capacity(order.len());
+    for name in order {
+        if name == "broadcast_safe" {
+            anyhow::bail!("--effect-order: broadcast_safe always runs last and can't be reordered");
+        }
+        let slot = (0..effects.len())
+            .find(|i| !slots.contains(i) && split_effect_spec(&effects[*i]).0 == name)
+            .with_context(|| {
+                format!(
+                    "--effect-order lists '{}', which is not in the effect chain ({})",
+                    name,
+                    effects.join(", ")
+                )
+            })?;
+        slots.push(slot);
+    }
+
+    let picked: Vec<String> = slots.iter().map(|&i| effects[i].clone()).collect();
+    slots.sort_unstable();
+    for (slot, effect) in slots.into_iter().zip(picked) {
+        effects[slot] = effect;
+    }
+    Ok(effects)
+}
+
+fn get_effect_shader(spec: &str) -> Option<String> {
+    let (name, option) = split_effect_spec(spec);
+
     // Shared fullscreen VS + postprocess-specific uniform struct used in all effects
     let common_header = r#"
 struct PPUniforms {
@@ -380,6 +648,33 @@ fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
     out.uv = vec2<f32>((x + 1.0) * 0.5, (1.0 - y) * 0.5);
     return out;
 }
+
+// HSV with every component in 0-1; hue 1.0 is the same red as 0.0.
+fn rgb_to_hsv(c: vec3<f32>) -> vec3<f32> {
+    let max_c = max(c.r, max(c.g, c.b));
+    let delta = max_c - min(c.r, min(c.g, c.b));
+    var h = 0.0;
+    if delta > 0.0 {
+        if max_c == c.r {
+            h = (c.g - c.b) / delta;
+        } else if max_c == c.g {
+            h = (c.b - c.r) / delta + 2.0;
+        } else {
+            h = (c.r - c.g) / delta + 4.0;
+        }
+        h = fract(h / 6.0);
+    }
+    var s = 0.0;
+    if max_c > 0.0 {
+        s = delta / max_c;
+    }
+    return vec3<f32>(h, s, max_c);
+}
+
+fn hsv_to_rgb(c: vec3<f32>) -> vec3<f32> {
+    let k = fract(vec3<f32>(c.x) + vec3<f32>(1.0, 2.0 / 3.0, 1.0 / 3.0)) * 6.0 - 3.0;
+    return c.z * mix(vec3<f32>(1.0), clamp(abs(k) - 1.0, vec3<f32>(0.0), vec3<f32>(1.0)), c.y);
+}
 "#;
 
     let fragment = match name {
@@ -514,18 +809,106 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     let saturation = 1.1;
     color = mix(vec3<f32>(gray), color, saturation);
 
+    // Intensity blends between the untouched and the graded image
+    let original = textureSample(input_tex, input_sampler, in.uv).rgb;
+    color = mix(original, color, pp.intensity);
+
     // Clamp
     color = clamp(color, vec3<f32>(0.0), vec3<f32>(1.0));
 
     return vec4<f32>(color, 1.0);
 }
 "#,
+        "mirror" => {
+            let consts = MirrorMode::parse(option).ok()?.wgsl_consts();
+            return Some(format!("{}{}{}", common_header, consts, MIRROR_FRAGMENT));
+        }
+        "hue_rotate" => {
+            let range = hue_range(option).ok()?;
+            return Some(format!(
+                "{}const HUE_RANGE: f32 = {:?};\n{}",
+                common_header, range, HUE_ROTATE_FRAGMENT
+            ));
+        }
+        "broadcast_safe" => {
+            let ceiling = broadcast_ceiling(option).ok()?;
+            return Some(format!(
+                "{}const CEILING: f32 = {:?};\n{}",
+                common_header, ceiling, BROADCAST_SAFE_FRAGMENT
+            ));
+        }
         _ => return None,
     };
 
     Some(format!("{}{}", common_header, fragment))
 }
 
+/// Folds the UVs before a single sample, so the whole effect is one lookup.
+/// Radial mode works in aspect-corrected space so wedges stay equal-angled.
+const MIRROR_FRAGMENT: &str = r#"
+const TWO_PI: f32 = 6.2831853;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    var uv = in.uv;
+
+    if MIRROR_MODE == 0 || MIRROR_MODE == 2 {
+        uv.x = 0.5 - abs(uv.x - 0.5);
+    }
+    if MIRROR_MODE == 1 || MIRROR_MODE == 2 {
+        uv.y = 0.5 - abs(uv.y - 0.5);
+    }
+    if MIRROR_MODE == 3 {
+        let aspect = pp.resolution.x / pp.resolution.y;
+        var p = uv - vec2<f32>(0.5);
+        p.x *= aspect;
+        let r = length(p);
+        let wedge = TWO_PI / MIRROR_SEGMENTS;
+        var a = atan2(p.y, p.x);
+        a = a - wedge * floor(a / wedge);
+        a = abs(a - wedge * 0.5);
+        p = vec2<f32>(cos(a), sin(a)) * r;
+        p.x /= aspect;
+        uv = p + vec2<f32>(0.5);
+    }
+
+    let color = textureSample(input_tex, input_sampler, uv).rgb;
+    return vec4<f32>(color, 1.0);
+}
+"#;
+
+/// Turns the hue by `intensity` (the spectral centroid) times `HUE_RANGE`.
+/// Hue is circular, so the sum wraps with `fract`: a full 360 degree range
+/// brings the brightest sound back round to the original colours.
+const HUE_ROTATE_FRAGMENT: &str = r#"
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let hsv = rgb_to_hsv(textureSample(input_tex, input_sampler, in.uv).rgb);
+    let hue = fract(hsv.x + pp.intensity * HUE_RANGE);
+    return vec4<f32>(hsv_to_rgb(vec3<f32>(hue, hsv.y, hsv.z)), 1.0);
+}
+"#;
+
+/// Compresses the brightest channel into the ceiling with an exponential
+/// soft knee and scales the others by the same factor, so highlights roll off
+/// without clipping or shifting hue. Values below the knee pass through.
+const BROADCAST_SAFE_FRAGMENT: &str = r#"
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(input_tex, input_sampler, in.uv).rgb;
+
+    let knee_width = CEILING * 0.1;
+    let knee = CEILING - knee_width;
+    let peak = max(color.r, max(color.g, color.b));
+    if peak <= knee {
+        return vec4<f32>(color, 1.0);
+    }
+
+    let limited = CEILING - knee_width * exp(-(peak - knee) / knee_width);
+    return vec4<f32>(color * (limited / peak), 1.0);
+}
+"#;
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -554,9 +937,113 @@ mod tests {
         }
     }
 
+    #[test]
+    fn mirror_accepts_modes_and_rejects_bad_options() {
+        for spec in ["mirror", "mirror:horizontal", "mirror:vertical", "mirror:quad", "mirror:6"] {
+            assert!(validate_effects(&[spec.to_string()]).is_ok(), "{spec}");
+            assert!(get_effect_shader(spec).is_some(), "{spec}");
+        }
+        assert_eq!(MirrorMode::parse(Some("8")).unwrap(), MirrorMode::Radial(8));
+        assert!(validate_effects(&["mirror:1".to_string()]).is_err());
+        assert!(validate_effects(&["mirror:diagonal".to_string()]).is_err());
+        assert!(validate_effects(&["bloom:2".to_string()]).is_err());
+    }
+
+    #[test]
+    fn broadcast_safe_runs_last_and_validates_ceiling() {
+        let expanded = expand_effects(&[
+            "broadcast_safe:230".to_string(),
+            "bloom".to_string(),
+            "color_grading".to_string(),
+        ]);
+        assert_eq!(expanded, ["bloom", "color_grading", "broadcast_safe:230"]);
+
+        assert!(validate_effects(&["broadcast_safe".to_string()]).is_ok());
+        assert!(get_effect_shader("broadcast_safe:230").is_some());
+        assert!(validate_effects(&["broadcast_safe:0".to_string()]).is_err());
+        assert!(validate_effects(&["broadcast_safe:bright".to_string()]).is_err());
+    }
+
+    #[test]
+    fn hue_rotate_takes_a_range_in_degrees() {
+        assert!(validate_effects(&["hue_rotate".to_string(), "hue_rotate:360".to_string()]).is_ok());
+        assert_eq!(hue_range(None).unwrap(), 0.5);
+        assert_eq!(hue_range(Some("90")).unwrap(), 0.25);
+        for bad in ["hue_rotate:0", "hue_rotate:361", "hue_rotate:warm"] {
+            assert!(validate_effects(&[bad.to_string()]).is_err(), "{bad}");
+        }
+        assert!(get_effect_shader("hue_rotate:90").unwrap().contains("const HUE_RANGE: f32 = 0.25;"));
+    }
+
+    #[test]
+    fn effect_order_rearranges_named_effects_in_place() {
+        let chain = expand_effects(&["crt".to_string(), "mirror:6".to_string()]);
+        let order = ["mirror".to_string(), "vignette".to_string()];
+
+        let reordered = reorder_effects(chain, &order).unwrap();
+
+        assert_eq!(
+            reordered,
+            [
+                "crt_scanlines",
+                "chromatic_aberration",
+                "mirror:6",
+                "film_grain",
+                "color_grading",
+                "vignette",
+            ]
+        );
+        assert!(reorder_effects(reordered.clone(), &["bloom".to_string()]).is_err());
+        assert!(reorder_effects(reordered, &["broadcast_safe".to_string()]).is_err());
+    }
+
     #[test]
     fn validate_effects_rejects_typos_and_accepts_presets() {
         assert!(validate_effects(&["vignete".to_string()]).is_err());
         assert!(validate_effects(&["vignette".to_string(), "crt".to_string()]).is_ok());
     }
+
+    #[test]
+    fn scaled_effects_validate_and_keep_their_scale_through_presets() {
+        assert!(validate_effects(&["bloom@0.5".to_string(), "mirror:quad@0.25".to_string()]).is_ok());
+        assert!(get_effect_shader("mirror:quad@0.25").is_some());
+        assert_eq!(effect_scale("mirror:quad@0.25").unwrap(), 0.25);
+        assert_eq!(effect_scale("bloom").unwrap(), 1.0);
+        for bad in ["bloom@0", "bloom@1.5", "bloom@half"] {
+            assert!(validate_effects(&[bad.to_string()]).is_err(), "{bad}");
+        }
+
+        let expanded = expand_effects(&["crt@0.5".to_string(), "broadcast_safe@0.5".to_string()]);
+        assert_eq!(expanded[0], "crt_scanlines@0.5");
+        assert_eq!(expanded.last().unwrap(), "broadcast_safe@0.5");
+        let reordered = reorder_effects(expanded, &["vignette".to_string()]).unwrap();
+        assert_eq!(reordered[2], "vignette@0.5");
+    }
+
+    #[test]
+    fn tuned_intensities_validate_and_carry_through_presets() {
+        assert!(validate_effects(&["vignette=0.5".to_string(), "bloom=1.5@0.5".to_string()]).is_ok());
+        assert_eq!(effect_intensity("bloom=1.5@0.5").unwrap(), 1.5);
+        assert_eq!(effect_intensity("bloom@0.5").unwrap(), 1.0);
+        assert!(get_effect_shader("bloom=1.5@0.5").is_some());
+        for bad in ["bloom=3", "bloom=-1", "bloom=strong", "mirror:quad=0.5", "broadcast_safe=0.5"] {
+            assert!(validate_effects(&[bad.to_string()]).is_err(), "{bad}");
+        }
+        assert!(validate_effects(&["mirror=1".to_string()]).is_ok());
+
+        let expanded = expand_effects(&["crt=0.5@0.5".to_string()]);
+        assert_eq!(expanded[0], "crt_scanlines=0.5@0.5");
+    }
+
+    #[test]
+    fn scaled_passes_ping_pong_between_textures_of_their_size() {
+        let plan = target_sizes(1920, 1080, &[0.5, 0.5, 1.0, 0.5, 1.0]);
+        assert_eq!(plan.sizes, [(1920, 1080), (960, 540), (960, 540)]);
+        // Each pass reads the previous pass's target and writes a different one.
+        assert_eq!(plan.pass_targets, [1, 2, 0, 1, 0]);
+
+        let full = target_sizes(640, 360, &[1.0, 1.0, 1.0]);
+        assert_eq!(full.sizes, [(640, 360), (640, 360)]);
+        assert_eq!(full.pass_targets, [1, 0, 1]);
+    }
 }
@@ -0,0 +1,240 @@
+//! CPU ports of the simplest templates, for GPU-free tests of how analysis
+//! features turn into pixels. Each port follows its WGSL line for line at the
+//! template's default parameters; it is a reference for the data flow, not a
+//! general shader interpreter.
+
+use crate::audio::features::SmoothedFrame;
+
+/// `templates/frequency_bars/main.wgsl` with its manifest defaults.
+pub struct FrequencyBars {
+    pub bar_count: u32,
+    pub color_base: [f32; 3],
+    pub mirror: bool,
+    pub gap_ratio: f32,
+}
+
+impl Default for FrequencyBars {
+    fn default() -> Self {
+        Self {
+            bar_count: 64,
+            color_base: [0.0, 0.8, 1.0],
+            mirror: true,
+            gap_ratio: 0.2,
+        }
+    }
+}
+
+impl FrequencyBars {
+    /// Render one frame to RGBA as the GPU would: shaded at pixel centers and
+    /// stored through the sRGB render target.
+    pub fn render(&self, frame: &SmoothedFrame, width: u32, height: u32) -> Vec<u8> {
+        let mut pixels = Vec::with_capacity((width * height * 4) as usize);
+        for py in 0..height {
+            for px in 0..width {
+                let uv = [(px as f32 + 0.5) / width as f32, (py as f32 + 0.5) / height as f32];
+                let color = self.fragment(frame, uv);
+                pixels.extend(color.iter().map(|&c| (linear_to_srgb(c) * 255.0).round() as u8));
+                pixels.push(255);
+            }
+        }
+        pixels
+    }
+
+    /// Height of bar `bar_index` as a fraction of the frame, before the
+    /// fragment shader clips it to the top edge.
+    pub fn bar_height(&self, frame: &SmoothedFrame, bar_index: u32) -> f32 {
+        let t = (bar_index as f32 + 0.5) / self.bar_count as f32;
+        sample_fft_log(&frame.fft_bins, t).powf(0.7) * (1.0 + frame.beat_intensity * 0.3)
+    }
+
+    fn fragment(&self, frame: &SmoothedFrame, uv: [f32; 2]) -> [f32; 3] {
+        let bar_uv_x = if self.mirror { (uv[0] - 0.5).abs() * 2.0 } else { uv[0] };
+
+        let bar_width = 1.0 / self.bar_count as f32;
+        let bar_index = (bar_uv_x / bar_width).floor() as u32;
+        let within_bar = (bar_uv_x % bar_width) / bar_width;
+        if within_bar > 1.0 - self.gap_ratio {
+            return [0.0; 3];
+        }
+
+        let t = (bar_index as f32 + 0.5) / self.bar_count as f32;
+        let height = self.bar_height(frame, bar_index);
+
+        let y = 1.0 - uv[1];
+        if y > height {
+            return [0.02, 0.02, 0.05];
+        }
+
+        let hue = t * 0.6 + 0.55;
+        let sat = 0.8 + frame.beat_intensity * 0.2;
+        let val = 0.6 + (y / height.max(0.001)) * 0.4;
+        let rgb = hsv2rgb(hue % 1.0, sat, val);
+        let mut color = [0, 1, 2].map(|i| rgb[i] * self.color_base[i] * 2.0);
+
+        let top_dist = (y - height).abs();
+        if top_dist < 0.015 {
+            let glow = 1.0 - top_dist / 0.015;
+            color = color.map(|c| c + glow * 0.5);
+        }
+
+        let segment_y = y * 40.0;
+        let segment_line = smoothstep(0.0, 0.1, (segment_y - segment_y.round()).abs());
+        color.map(|c| c * (0.85 + 0.15 * segment_line))
+    }
+}
+
+fn sample_fft_log(fft_bins: &[f32], t: f32) -> f32 {
+    let (min_freq, max_freq) = (20.0f32, 20000.0f32);
+    let num_bins = fft_bins.len() as u32;
+    let freq = min_freq * (max_freq / min_freq).powf(t);
+    let bin_f = freq / max_freq * num_bins as f32;
+    let bin_lo = bin_f.floor() as u32;
+    if bin_lo >= num_bins {
+        return 0.0;
+    }
+    let bin_hi = (bin_lo + 1).min(num_bins - 1);
+    let frac = bin_f - bin_f.floor();
+    let (lo, hi) = (fft_bins[bin_lo as usize], fft_bins[bin_hi as usize]);
+    lo + (hi - lo) * frac
+}
+
+fn hsv2rgb(h: f32, s: f32, v: f32) -> [f32; 3] {
+    let c = v * s;
+    let hp = h * 6.0;
+    let x = c * (1.0 - (hp % 2.0 - 1.0).abs());
+    let rgb = if hp < 1.0 {
+        [c, x, 0.0]
+    } else if hp < 2.0 {
+        [x, c, 0.0]
+    } else if hp < 3.0 {
+        [0.0, c, x]
+    } else if hp < 4.0 {
+        [0.0, x, c]
+    } else if hp < 5.0 {
+        [x, 0.0, c]
+    } else {
+        [c, 0.0, x]
+    };
+    rgb.map(|channel| channel + v - c)
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+fn linear_to_srgb(c: f32) -> f32 {
+    let c = c.clamp(0.0, 1.0);
+    if c <= 0.003_130_8 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::analysis::{self, AnalysisOptions};
+    use crate::audio::decode::{AudioData, AudioTags};
+    use crate::framerate::FrameRate;
+
+    const WIDTH: u32 = 256;
+    const HEIGHT: u32 = 128;
+
+    fn tone(freq: f32) -> AudioData {
+        let sample_rate = 44_100;
+        AudioData {
+            samples: (0..sample_rate * 2)
+                .map(|i| 0.5 * (2.0 * std::f32::consts::PI * freq * i as f32 / sample_rate as f32).sin())
+                .collect(),
+            sides: None,
+            sample_rate,
+            tags: AudioTags::default(),
+        }
+    }
+
+    fn frame(fft_bins: Vec<f32>, beat_intensity: f32) -> SmoothedFrame {
+        SmoothedFrame {
+            fft_bins,
+            bass: 0.0,
+            mid: 0.0,
+            high: 0.0,
+            rms: 0.0,
+            spectral_centroid: 0.0,
+            spectral_flux: 0.0,
+            spectral_centroid_hz: 0.0,
+            spectral_flux_raw: 0.0,
+            onset_strength: 0.0,
+            dominant_frequency_hz: 0.0,
+            bands_left: [0.0; 7],
+            bands_right: [0.0; 7],
+            beat_intensity,
+            beat_phase: 0.0,
+            is_beat: false,
+            waveform: vec![0.0; 512],
+            waveform_left: Vec::new(),
+            waveform_right: Vec::new(),
+            time: 0.0,
+        }
+    }
+
+    /// Rows from the bottom that are lit in column `x`, i.e. the drawn bar.
+    fn column_fill(pixels: &[u8], x: u32) -> u32 {
+        (0..HEIGHT)
+            .rev()
+            .take_while(|&y| {
+                let i = ((y * WIDTH + x) * 4) as usize;
+                pixels[i..i + 3].iter().any(|&c| c > 100)
+            })
+            .count() as u32
+    }
+
+    /// Screen column at the center of bar `bar_index` on the right half of the
+    /// mirrored layout.
+    fn bar_column(bars: &FrequencyBars, bar_index: u32) -> u32 {
+        let bar_uv_x = (bar_index as f32 + 0.4) / bars.bar_count as f32;
+        ((0.5 + bar_uv_x / 2.0) * WIDTH as f32) as u32
+    }
+
+    #[test]
+    fn a_tone_raises_the_bars_at_its_frequency() {
+        let bars = FrequencyBars::default();
+        let options = AnalysisOptions::default();
+        let (_, frames) = analysis::analyze(&tone(440.0), FrameRate::integer(10), &options).unwrap();
+        let frame = &frames[frames.len() / 2];
+
+        // Bars are log-spaced from 20Hz to 20kHz.
+        let tone_bar = ((440.0f32 / 20.0).ln() / 1000.0f32.ln() * bars.bar_count as f32) as u32;
+        let quiet_bar = bars.bar_count - 4;
+        let pixels = bars.render(frame, WIDTH, HEIGHT);
+
+        assert_eq!(pixels.len(), (WIDTH * HEIGHT * 4) as usize);
+        assert!(bars.bar_height(frame, tone_bar) > 0.5);
+        assert!(column_fill(&pixels, bar_column(&bars, tone_bar)) > HEIGHT / 2);
+        assert!(column_fill(&pixels, bar_column(&bars, quiet_bar)) < HEIGHT / 16);
+    }
+
+    #[test]
+    fn silence_draws_only_the_background() {
+        let bars = FrequencyBars::default();
+        let pixels = bars.render(&frame(vec![0.0; 1024], 0.0), WIDTH, HEIGHT);
+
+        assert!((0..WIDTH).all(|x| column_fill(&pixels, x) == 0));
+    }
+
+    #[test]
+    fn beats_boost_bar_height() {
+        let bars = FrequencyBars::default();
+        let calm = frame(vec![0.3; 1024], 0.0);
+        let beat = frame(vec![0.3; 1024], 1.0);
+
+        assert!((bars.bar_height(&beat, 10) / bars.bar_height(&calm, 10) - 1.3).abs() < 1e-5);
+        let x = bar_column(&bars, 10);
+        let (calm_fill, beat_fill) = (
+            column_fill(&bars.render(&calm, WIDTH, HEIGHT), x),
+            column_fill(&bars.render(&beat, WIDTH, HEIGHT), x),
+        );
+        assert!(beat_fill > calm_fill, "{beat_fill} <= {calm_fill}");
+    }
+}
@@ -0,0 +1,502 @@
+use super::feedback::{FeedbackTexture, FEEDBACK_BINDING};
+use super::frame::{FrameRenderer, Readback, TEXTURE_FORMAT};
+use super::gpu::GpuContext;
+use super::history::HistoryTexture;
+use super::pipeline::{ComputePipelineWrapper, FrameUniforms, RenderPipeline, WAVEFORM_R_BINDING};
+use super::postprocess::PostProcessChain;
+use super::texture::TemplateTexture;
+use crate::audio::features::SmoothedFrame;
+use crate::error::Result;
+use crate::framerate::FrameRate;
+use crate::job::{Analysis, RenderJob};
+use crate::templates::animation::{self, AnimatedParams, ParamCurve};
+use crate::templates::automation::{self, Automation};
+use crate::templates::loader;
+
+struct TemplateSlot {
+    pipeline: RenderPipeline,
+    /// One bind group, or one per history copy when the template has a
+    /// history texture.
+    bind_groups: Vec<wgpu::BindGroup>,
+    animated_params: AnimatedParams,
+    param_anim_buffer: wgpu::Buffer,
+    compute_pipeline: Option<ComputePipelineWrapper>,
+    history: Option<HistoryTexture>,
+    feedback: Option<FeedbackTexture>,
+    /// Draw over the previous frame instead of a cleared target.
+    accumulate: bool,
+    /// Whether the slot has drawn a frame yet; its first frame always clears
+    /// whatever the previous template left behind.
+    started: bool,
+    name: String,
+    end_frame: usize,
+}
+
+/// A frame submitted with [`Renderer::submit_frame`] whose pixels have not
+/// been read back yet.
+pub struct PendingFrame(Readback);
+
+/// The GPU side of a render: turns analyzed frames into RGBA pixels through
+/// the job's template pipelines and post-processing chain.
+pub struct Renderer {
+    gpu: GpuContext,
+    frame_renderer: FrameRenderer,
+    uniform_buffer: wgpu::Buffer,
+    fft_buffer: wgpu::Buffer,
+    waveform_buffer: wgpu::Buffer,
+    waveform_r_buffer: wgpu::Buffer,
+    automation: Automation,
+    automation_buffer: wgpu::Buffer,
+    slots: Vec<TemplateSlot>,
+    current_slot: usize,
+    pp_chain: PostProcessChain,
+    effects: Vec<String>,
+    /// Render size: the output size times `supersample`.
+    width: u32,
+    height: u32,
+    supersample: u32,
+    fps: FrameRate,
+    duration: f32,
+}
+
+impl Renderer {
+    pub fn new(job: &RenderJob, analysis: &Analysis) -> Result<Self> {
+        let template_names = job.template_names();
+        if template_names.is_empty() {
+            return Err(anyhow::anyhow!("No templates found").into());
+        }
+        let effects = job.resolved_effects()?;
+        let supersample = job.supersample_factor();
+        let (width, height) = (job.width() * supersample, job.height() * supersample);
+        if supersample > 1 {
+            log::info!("Supersampling {}x: rendering at {}x{}", supersample, width, height);
+        }
+        let frames = &analysis.frames;
+        let total_frames = frames.len();
+
+        log::info!("Initializing GPU...");
+        let gpu = GpuContext::new(width, height)?;
+        let clear_color = if job.is_transparent() { wgpu::Color::TRANSPARENT } else { wgpu::Color::BLACK };
+        let frame_renderer = FrameRenderer::new(&gpu, width, height, clear_color);
+
+        // Shared GPU buffers
+        let uniform_buffer = gpu.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("uniform_buffer"),
+            size: std::mem::size_of::<FrameUniforms>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let num_fft_bins = if frames.is_empty() { 1024 } else { frames[0].fft_bins.len() };
+        let fft_buffer = gpu.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("fft_buffer"),
+            size: (num_fft_bins * std::mem::size_of::<f32>()) as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let num_waveform = if frames.is_empty() { 1024 } else { frames[0].waveform.len() };
+        let waveform_buffer = gpu.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("waveform_buffer"),
+            size: (num_waveform * std::mem::size_of::<f32>()) as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let waveform_r_buffer = gpu.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("waveform_r_buffer"),
+            size: (num_waveform * std::mem::size_of::<f32>()) as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let automation = job.automation_tracks().clone();
+        let automation_buffer = gpu.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("automation_buffer"),
+            size: (automation::AUTOMATION_SLOTS * std::mem::size_of::<f32>()) as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        // Per-template pipelines and bind groups, with their frame ranges
+        let param_overrides = job.params();
+        let num_templates = template_names.len();
+        let frames_per_template = total_frames / num_templates;
+        let mut slots: Vec<TemplateSlot> = Vec::with_capacity(num_templates);
+
+        for (i, name) in template_names.iter().enumerate() {
+            let tmpl = loader::load_template(name)?;
+            // When cycling templates, each curve applies to those that have the parameter.
+            let curves: Vec<ParamCurve> = job
+                .param_curves()
+                .iter()
+                .filter(|curve| num_templates == 1 || tmpl.manifest.parameters.contains_key(&curve.name))
+                .cloned()
+                .collect();
+            let animated_params = AnimatedParams::new(&tmpl.manifest, param_overrides, &curves)?;
+            let shader_src = format!(
+                "{}{}{}",
+                automation.wgsl_decl(),
+                animated_params.wgsl_decl(),
+                loader::inject_params(&tmpl.fragment_shader, &tmpl.manifest, param_overrides)
+            );
+            if !automation.is_empty() && !tmpl.fragment_shader.contains("automation.values") {
+                log::warn!("--automation: template '{}' doesn't read automation.values", name);
+            }
+            for unused in animated_params.unused_curves(&shader_src) {
+                log::warn!(
+                    "--param-anim {}: template '{}' reads this parameter as a constant, so it won't animate",
+                    unused,
+                    name
+                );
+            }
+            let param_anim_buffer = gpu.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("param_anim_buffer"),
+                size: animated_params.buffer_size(),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+            let textures = tmpl
+                .textures
+                .iter()
+                .map(|asset| TemplateTexture::upload(&gpu, asset))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            let mut texture_bindings: Vec<u32> = textures.iter().map(|t| t.binding).collect();
+            texture_bindings.extend(tmpl.manifest.history.as_ref().map(|h| h.binding));
+            let feedback = tmpl.manifest.feedback.then(|| FeedbackTexture::new(&gpu, width, height));
+            if feedback.is_some() {
+                texture_bindings.push(FEEDBACK_BINDING);
+            }
+            let pipeline = RenderPipeline::new(
+                &gpu.device,
+                name,
+                &shader_src,
+                TEXTURE_FORMAT,
+                &texture_bindings,
+                tmpl.manifest.accumulate,
+            )?;
+
+            let mut entries = vec![
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: fft_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: waveform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: animation::PARAM_ANIM_BINDING,
+                    resource: param_anim_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: automation::AUTOMATION_BINDING,
+                    resource: automation_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: WAVEFORM_R_BINDING,
+                    resource: waveform_r_buffer.as_entire_binding(),
+                },
+            ];
+            for texture in &textures {
+                entries.push(wgpu::BindGroupEntry {
+                    binding: texture.binding,
+                    resource: wgpu::BindingResource::TextureView(&texture.view),
+                });
+                entries.push(wgpu::BindGroupEntry {
+                    binding: texture.binding + 1,
+                    resource: wgpu::BindingResource::Sampler(&texture.sampler),
+                });
+            }
+            if let Some(feedback) = &feedback {
+                entries.push(wgpu::BindGroupEntry {
+                    binding: FEEDBACK_BINDING,
+                    resource: wgpu::BindingResource::TextureView(&feedback.view),
+                });
+                entries.push(wgpu::BindGroupEntry {
+                    binding: FEEDBACK_BINDING + 1,
+                    resource: wgpu::BindingResource::Sampler(&feedback.sampler),
+                });
+            }
+
+            let compute_pipeline = if let Some(ref compute_src) = tmpl.compute_shader {
+                let compute_src = loader::inject_params(compute_src, &tmpl.manifest, param_overrides);
+                Some(ComputePipelineWrapper::new(&gpu.device, name, &compute_src)?)
+            } else {
+                None
+            };
+            // The loader only accepts a history texture together with a compute shader.
+            let history = tmpl.manifest.history.as_ref().zip(compute_pipeline.as_ref()).map(|(def, compute)| {
+                HistoryTexture::new(&gpu, def, compute, &uniform_buffer, &fft_buffer, &waveform_buffer)
+            });
+
+            let create_bind_group = |entries: &[wgpu::BindGroupEntry]| {
+                gpu.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("main_bind_group"),
+                    layout: &pipeline.bind_group_layout,
+                    entries,
+                })
+            };
+            let bind_groups = match &history {
+                Some(history) => history
+                    .views()
+                    .iter()
+                    .map(|view| {
+                        let mut entries = entries.clone();
+                        entries.push(wgpu::BindGroupEntry {
+                            binding: history.binding,
+                            resource: wgpu::BindingResource::TextureView(view),
+                        });
+                        entries.push(wgpu::BindGroupEntry {
+                            binding: history.binding + 1,
+                            resource: wgpu::BindingResource::Sampler(&history.sampler),
+                        });
+                        create_bind_group(&entries)
+                    })
+                    .collect(),
+                None => vec![create_bind_group(&entries)],
+            };
+
+            let start_frame = i * frames_per_template;
+            let end_frame = if i == num_templates - 1 {
+                total_frames
+            } else {
+                (i + 1) * frames_per_template
+            };
+
+            log::info!(
+                "Template [{}]: {} (frames {}-{})",
+                i, tmpl.manifest.display_name, start_frame, end_frame.saturating_sub(1)
+            );
+
+            slots.push(TemplateSlot {
+                pipeline,
+                bind_groups,
+                animated_params,
+                param_anim_buffer,
+                compute_pipeline,
+                history,
+                feedback,
+                accumulate: tmpl.manifest.accumulate,
+                started: false,
+                name: tmpl.manifest.display_name.clone(),
+                end_frame,
+            });
+        }
+
+        // Post-processing chain
+        let pp_chain = PostProcessChain::new(&gpu.device, width, height, &effects)?;
+
+        Ok(Self {
+            gpu,
+            frame_renderer,
+            uniform_buffer,
+            fft_buffer,
+            waveform_buffer,
+            waveform_r_buffer,
+            automation,
+            automation_buffer,
+            slots,
+            current_slot: 0,
+            pp_chain,
+            effects,
+            width,
+            height,
+            supersample,
+            fps: job.frame_rate(),
+            duration: analysis.global.duration,
+        })
+    }
+
+    pub fn adapter_info(&self) -> &wgpu::AdapterInfo {
+        &self.gpu.adapter_info
+    }
+
+    /// The post-processing effects in the chain, after template defaults.
+    pub fn effects(&self) -> &[String] {
+        &self.effects
+    }
+
+    /// Display name of the template frame `frame_idx` comes from.
+    pub fn template_name_at(&self, frame_idx: usize) -> &str {
+        let slot = self
+            .slots
+            .iter()
+            .find(|slot| frame_idx < slot.end_frame)
+            .unwrap_or(&self.slots[self.slots.len() - 1]);
+        &slot.name
+    }
+
+    /// Render frame `frame_idx` to tightly packed RGBA at the job's output
+    /// size, `width * height * 4` bytes. Frames may be skipped but not revisited once a later template
+    /// section has started. A template's history texture advances once per
+    /// call, so it only builds up over consecutive frames.
+    pub fn render_frame(&mut self, frame_idx: usize, frame: &SmoothedFrame) -> Result<Vec<u8>> {
+        let pending = self.submit_frame(frame_idx, frame)?;
+        self.finish_frame(pending)
+    }
+
+    /// [`render_frame`](Self::render_frame) in two halves: queue the GPU work
+    /// for `frame_idx` now and read its pixels with
+    /// [`finish_frame`](Self::finish_frame) later, so the next frame can be
+    /// submitted first and render while this one is read back. At most two
+    /// frames may be pending.
+    pub fn submit_frame(&mut self, frame_idx: usize, frame: &SmoothedFrame) -> Result<PendingFrame> {
+        // Advance to the correct template slot
+        while self.current_slot + 1 < self.slots.len() && frame_idx >= self.slots[self.current_slot].end_frame {
+            self.current_slot += 1;
+            log::info!("Switching to template: {}", self.slots[self.current_slot].name);
+        }
+        let slot = &mut self.slots[self.current_slot];
+        let gpu = &self.gpu;
+
+        // Update uniforms
+        let uniforms = build_uniforms(frame, frame_idx as u32, self.width, self.height, self.fps, self.duration);
+        gpu.queue.write_buffer(&self.uniform_buffer, 0, bytemuck::bytes_of(&uniforms));
+        gpu.queue.write_buffer(&self.fft_buffer, 0, bytemuck::cast_slice(&frame.fft_bins));
+        // With stereo waveforms, `waveform` carries the left channel and
+        // `waveform_r` the right; otherwise both carry the mono waveform.
+        let (waveform, waveform_r) = if frame.waveform_right.is_empty() {
+            (&frame.waveform, &frame.waveform)
+        } else {
+            (&frame.waveform_left, &frame.waveform_right)
+        };
+        gpu.queue.write_buffer(&self.waveform_buffer, 0, bytemuck::cast_slice(waveform));
+        gpu.queue.write_buffer(&self.waveform_r_buffer, 0, bytemuck::cast_slice(waveform_r));
+        gpu.queue.write_buffer(
+            &slot.param_anim_buffer,
+            0,
+            bytemuck::cast_slice(&slot.animated_params.values_at(frame.time, self.duration)),
+        );
+        gpu.queue.write_buffer(
+            &self.automation_buffer,
+            0,
+            bytemuck::cast_slice(&self.automation.values_at(frame.time, self.duration)),
+        );
+
+        // Compute dispatch: update the history texture the fragment shader samples
+        let bind_group = match (&mut slot.history, &slot.compute_pipeline) {
+            (Some(history), Some(compute)) => {
+                history.advance(gpu, compute);
+                &slot.bind_groups[history.current()]
+            }
+            _ => &slot.bind_groups[0],
+        };
+
+        // Render
+        let clear = !(slot.accumulate && slot.started);
+        let feedback = slot.feedback.as_ref().map(|feedback| &feedback.texture);
+        slot.started = true;
+        self.frame_renderer.render(gpu, &slot.pipeline.pipeline, bind_group, clear, feedback);
+        let readback = if self.pp_chain.has_effects() {
+            let final_texture = self.pp_chain.run(
+                &gpu.device,
+                &gpu.queue,
+                &self.frame_renderer.render_texture,
+                frame.time,
+                frame.spectral_centroid,
+            );
+            self.frame_renderer.begin_readback(gpu, final_texture)
+        } else {
+            self.frame_renderer.begin_readback(gpu, &self.frame_renderer.render_texture)
+        };
+        Ok(PendingFrame(readback))
+    }
+
+    /// Wait for a frame from [`submit_frame`](Self::submit_frame) and return
+    /// its pixels, as [`render_frame`](Self::render_frame) would.
+    pub fn finish_frame(&self, pending: PendingFrame) -> Result<Vec<u8>> {
+        let pixels = self.frame_renderer.finish_readback(&self.gpu, pending.0)?;
+        if self.supersample > 1 {
+            Ok(downsample(&pixels, self.width, self.height, self.supersample))
+        } else {
+            Ok(pixels)
+        }
+    }
+}
+
+/// Box-filter RGBA `pixels` of `width`x`height` down by `factor` on each
+/// axis, averaging each `factor`x`factor` block.
+fn downsample(pixels: &[u8], width: u32, height: u32, factor: u32) -> Vec<u8> {
+    let (out_width, out_height) = ((width / factor) as usize, (height / factor) as usize);
+    let (width, factor) = (width as usize, factor as usize);
+    let block = (factor * factor) as u32;
+    let mut out = vec![0u8; out_width * out_height * 4];
+    for (y, row) in out.chunks_exact_mut(out_width * 4).enumerate() {
+        for (x, pixel) in row.chunks_exact_mut(4).enumerate() {
+            let mut sum = [0u32; 4];
+            for sy in y * factor..(y + 1) * factor {
+                let start = (sy * width + x * factor) * 4;
+                for source in pixels[start..start + factor * 4].chunks_exact(4) {
+                    for (total, &value) in sum.iter_mut().zip(source) {
+                        *total += value as u32;
+                    }
+                }
+            }
+            for (out, total) in pixel.iter_mut().zip(sum) {
+                *out = ((total + block / 2) / block) as u8;
+            }
+        }
+    }
+    out
+}
+
+fn build_uniforms(
+    frame: &SmoothedFrame,
+    frame_idx: u32,
+    width: u32,
+    height: u32,
+    fps: FrameRate,
+    duration: f32,
+) -> FrameUniforms {
+    FrameUniforms {
+        resolution: [width as f32, height as f32],
+        time: frame.time,
+        frame: frame_idx,
+        fps: fps.as_f32(),
+        duration,
+        rms: frame.rms,
+        spectral_centroid: frame.spectral_centroid,
+        spectral_flux: frame.spectral_flux,
+        beat_intensity: frame.beat_intensity,
+        beat_phase: frame.beat_phase,
+        is_beat: if frame.is_beat { 1.0 } else { 0.0 },
+        bass: frame.bass,
+        mid: frame.mid,
+        high: frame.high,
+        spectral_centroid_hz: frame.spectral_centroid_hz,
+        spectral_flux_raw: frame.spectral_flux_raw,
+        onset_strength: frame.onset_strength,
+        _padding: [0.0; 2],
+        left_bands_low: low_bands(&frame.bands_left),
+        left_bands_high: high_bands(&frame.bands_left),
+        right_bands_low: low_bands(&frame.bands_right),
+        right_bands_high: high_bands(&frame.bands_right),
+    }
+}
+
+fn low_bands(bands: &[f32; 7]) -> [f32; 4] {
+    [bands[0], bands[1], bands[2], bands[3]]
+}
+
+fn high_bands(bands: &[f32; 7]) -> [f32; 4] {
+    [bands[4], bands[5], bands[6], 0.0]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn downsample_averages_each_block() {
+        // 4x2 → 2x1: a black/white block and a solid grey block.
+        let mut pixels = Vec::new();
+        for _ in 0..2 {
+            pixels.extend([0, 0, 0, 255, 255, 255, 255, 255, 100, 100, 100, 255, 100, 100, 100, 255]);
+        }
+        assert_eq!(downsample(&pixels, 4, 2, 2), vec![128, 128, 128, 255, 100, 100, 100, 255]);
+    }
+}
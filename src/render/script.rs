@@ -0,0 +1,196 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::path::Path;
+
+use super::text::{parse_color, TextOverlay};
+
+/// One timed text entry from an `--overlay-script` file.
+///
+/// `x` and `y` place the text's top-left corner as fractions of the frame,
+/// `size` is in pixels (defaults to the title size), and `fade` is the
+/// fade-in/fade-out duration in seconds.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScriptEntry {
+    pub text: String,
+    pub start: f32,
+    pub end: f32,
+    #[serde(default)]
+    pub x: f32,
+    #[serde(default)]
+    pub y: f32,
+    pub size: Option<f32>,
+    #[serde(default = "default_color")]
+    pub color: String,
+    #[serde(default)]
+    pub fade: f32,
+}
+
+fn default_color() -> String {
+    "#FFFFFF".into()
+}
+
+struct ScriptedText {
+    text: String,
+    start: f32,
+    end: f32,
+    x: f32,
+    y: f32,
+    color: [u8; 4],
+    fade: f32,
+    /// Index into `ScriptedOverlay::overlays`.
+    overlay: usize,
+}
+
+/// Timed text overlays (lower-thirds, callouts) loaded from a JSON script and
+/// composited each frame with `TextOverlay`.
+pub struct ScriptedOverlay {
+    entries: Vec<ScriptedText>,
+    /// One `TextOverlay` per distinct font size in the script.
+    overlays: Vec<(f32, TextOverlay)>,
+}
+
+impl ScriptedOverlay {
+    pub fn load(
+        path: &Path,
+        default_size: f32,
+        font_path: Option<&Path>,
+        font_bytes: Option<&[u8]>,
+        font_family: Option<&str>,
+    ) -> Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read overlay script: {}", path.display()))?;
+        let entries = parse_script(&json)
+            .with_context(|| format!("Invalid overlay script: {}", path.display()))?;
+
+        let mut overlays: Vec<(f32, TextOverlay)> = Vec::new();
+        let mut scripted = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let size = entry.size.unwrap_or(default_size);
+            let overlay = match overlays.iter().position(|(s, _)| *s == size) {
+                Some(index) => index,
+                None => {
+                    overlays.push((size, TextOverlay::new(size, font_path, font_bytes, font_family)));
+                    overlays.len() - 1
+                }
+            };
+            scripted.push(ScriptedText {
+                color: parse_color(&entry.color)?,
+                text: entry.text,
+                start: entry.start,
+                end: entry.end,
+                x: entry.x,
+                y: entry.y,
+                fade: entry.fade,
+                overlay,
+            });
+        }
+
+        log::info!(
+            "Loaded {} scripted overlays from {}",
+            scripted.len(),
+            path.display()
+        );
+
+        Ok(Self {
+            entries: scripted,
+            overlays,
+        })
+    }
+
+    /// Composite every entry active at `time` onto the frame.
+    pub fn render_frame(&self, pixels: &mut [u8], width: u32, height: u32, time: f32) {
+        for entry in &self.entries {
+            let opacity = fade_opacity(entry.start, entry.end, entry.fade, time);
+            if opacity <= 0.0 {
+                continue;
+            }
+
+            let mut color = entry.color;
+            color[3] = (color[3] as f32 * opacity).round() as u8;
+            let x = (entry.x * width as f32) as u32;
+            let y = (entry.y * height as f32) as u32;
+            self.overlays[entry.overlay]
+                .1
+                .composite(pixels, width, height, &entry.text, x, y, color);
+        }
+    }
+}
+
+/// Parse and validate the script JSON: a top-level array of entries.
+fn parse_script(json: &str) -> Result<Vec<ScriptEntry>> {
+    let entries: Vec<ScriptEntry> = serde_json::from_str(json)?;
+
+    for (i, entry) in entries.iter().enumerate() {
+        let context = || format!("overlay entry {} ({:?})", i, entry.text);
+        if !entry.start.is_finite() || !entry.end.is_finite() || entry.start < 0.0 {
+            anyhow::bail!("{}: start and end must be non-negative seconds", context());
+        }
+        if entry.end <= entry.start {
+            anyhow::bail!("{}: end must be after start", context());
+        }
+        if !(0.0..=1.0).contains(&entry.x) || !(0.0..=1.0).contains(&entry.y) {
+            anyhow::bail!("{}: x and y must be between 0.0 and 1.0", context());
+        }
+        if entry.size.is_some_and(|size| !(size.is_finite() && size > 0.0)) {
+            anyhow::bail!("{}: size must be a positive pixel size", context());
+        }
+        if !(entry.fade.is_finite() && entry.fade >= 0.0) {
+            anyhow::bail!("{}: fade must be non-negative seconds", context());
+        }
+        parse_color(&entry.color).with_context(context)?;
+    }
+
+    Ok(entries)
+}
+
+/// Opacity of an entry at `time`: ramps up over `fade` seconds after `start`
+/// and down over the `fade` seconds before `end`.
+fn fade_opacity(start: f32, end: f32, fade: f32, time: f32) -> f32 {
+    if time < start || time > end {
+        return 0.0;
+    }
+    if fade <= 0.0 {
+        return 1.0;
+    }
+    ((time - start) / fade).min((end - time) / fade).clamp(0.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_script_entries() {
+        let entries = parse_script(
+            r##"[
+                {"text": "Live at the Roxy", "start": 1.0, "end": 5.0, "x": 0.05, "y": 0.8,
+                 "size": 40, "color": "#FFCC00", "fade": 0.5},
+                {"text": "Drop", "start": 30, "end": 32}
+            ]"##,
+        )
+        .unwrap();
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].size, Some(40.0));
+        assert_eq!(entries[1].color, "#FFFFFF");
+        assert_eq!(entries[1].fade, 0.0);
+    }
+
+    #[test]
+    fn rejects_invalid_entries() {
+        assert!(parse_script(r#"[{"text": "a", "start": 2, "end": 1}]"#).is_err());
+        assert!(parse_script(r#"[{"text": "a", "start": 0, "end": 1, "x": 1.5}]"#).is_err());
+        assert!(parse_script(r#"[{"text": "a", "start": 0, "end": 1, "color": "red"}]"#).is_err());
+        assert!(parse_script(r##"[{"text": "a", "start": 0, "end": 1, "colour": "#FFF"}]"##).is_err());
+    }
+
+    #[test]
+    fn fades_in_and_out() {
+        assert_eq!(fade_opacity(1.0, 5.0, 1.0, 0.5), 0.0);
+        assert_eq!(fade_opacity(1.0, 5.0, 1.0, 1.5), 0.5);
+        assert_eq!(fade_opacity(1.0, 5.0, 1.0, 3.0), 1.0);
+        assert_eq!(fade_opacity(1.0, 5.0, 1.0, 4.75), 0.25);
+        assert_eq!(fade_opacity(1.0, 5.0, 0.0, 1.0), 1.0);
+    }
+}
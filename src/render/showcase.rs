@@ -0,0 +1,268 @@
+use anyhow::Result;
+use std::path::Path;
+
+use super::renderer::{PendingFrame, Renderer};
+use super::text::TextOverlay;
+use crate::audio::features::SmoothedFrame;
+use crate::job::{Analysis, RenderJob};
+
+const LABEL_PANEL_COLOR: [u8; 4] = [0, 0, 0, 170];
+const LABEL_COLOR: [u8; 4] = [255, 255, 255, 240];
+
+/// How `--showcase` puts the templates side by side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShowcaseLayout {
+    /// One template after another, equal duration each, like `-t all`.
+    Sequence,
+    /// Every template at once, each in its own tile of the frame.
+    Grid,
+}
+
+impl ShowcaseLayout {
+    pub fn parse(name: &str) -> Result<Self> {
+        match name {
+            "sequence" => Ok(Self::Sequence),
+            "grid" => Ok(Self::Grid),
+            other => anyhow::bail!("Unknown showcase layout '{}'. Valid layouts: sequence, grid", other),
+        }
+    }
+}
+
+/// Columns and rows of a grid holding `count` tiles: as square as possible,
+/// with the spare cells in the last row.
+pub fn grid_size(count: usize) -> (u32, u32) {
+    let count = count.max(1) as u32;
+    let columns = (count as f32).sqrt().ceil() as u32;
+    (columns, count.div_ceil(columns))
+}
+
+/// `--showcase grid`: one renderer per template at tile size, composited
+/// into a single frame. Tiles fill the grid row by row; the frame's
+/// leftover pixels and spare cells stay black.
+pub struct ShowcaseGrid {
+    tiles: Vec<Renderer>,
+    columns: u32,
+    tile_width: u32,
+    tile_height: u32,
+    width: u32,
+    height: u32,
+}
+
+impl ShowcaseGrid {
+    /// Build a renderer per template of `job`, each rendering only its own
+    /// template at the tile size.
+    pub fn new(job: &RenderJob, analysis: &Analysis) -> Result<Self> {
+        let names = job.template_names();
+        let (columns, rows) = grid_size(names.len());
+        let (width, height) = (job.width(), job.height());
+        let (tile_width, tile_height) = (width / columns, height / rows);
+        if tile_width == 0 || tile_height == 0 {
+            anyhow::bail!(
+                "{}x{} is too small for a {}x{} showcase grid",
+                width,
+                height,
+                columns,
+                rows
+            );
+        }
+        log::info!(
+            "Showcase grid: {} templates in {}x{} tiles of {}x{}",
+            names.len(),
+            columns,
+            rows,
+            tile_width,
+            tile_height
+        );
+
+        let tiles = names
+            .iter()
+            .map(|name| {
+                job.clone()
+                    .templates(vec![name.clone()])
+                    .resolution(tile_width, tile_height)
+                    .renderer(analysis)
+            })
+            .collect::<crate::error::Result<Vec<_>>>()?;
+        Ok(Self {
+            tiles,
+            columns,
+            tile_width,
+            tile_height,
+            width,
+            height,
+        })
+    }
+
+    pub fn adapter_info(&self) -> &wgpu::AdapterInfo {
+        self.tiles[0].adapter_info()
+    }
+
+    pub fn effects(&self) -> &[String] {
+        self.tiles[0].effects()
+    }
+
+    /// Submit frame `frame_idx` to every tile.
+    pub fn submit_frame(&mut self, frame_idx: usize, frame: &SmoothedFrame) -> Result<Vec<PendingFrame>> {
+        let pending = self
+            .tiles
+            .iter_mut()
+            .map(|tile| tile.submit_frame(frame_idx, frame))
+            .collect::<crate::error::Result<_>>()?;
+        Ok(pending)
+    }
+
+    /// Read back the tiles of a submitted frame and composite them into RGBA
+    /// at the job's output size.
+    pub fn finish_frame(&self, pending: Vec<PendingFrame>) -> Result<Vec<u8>> {
+        let mut pixels = vec![0u8; (self.width * self.height * 4) as usize];
+        for (index, (tile, pending)) in self.tiles.iter().zip(pending).enumerate() {
+            let tile_pixels = tile.finish_frame(pending)?;
+            let (x, y) = tile_origin(index, self.columns, self.tile_width, self.tile_height);
+            blit(&mut pixels, self.width, &tile_pixels, self.tile_width, self.tile_height, x, y);
+        }
+        Ok(pixels)
+    }
+
+    /// Each tile's template display name and top-left corner.
+    pub fn labels(&self, frame_idx: usize) -> Vec<(&str, u32, u32)> {
+        self.tiles
+            .iter()
+            .enumerate()
+            .map(|(index, tile)| {
+                let (x, y) = tile_origin(index, self.columns, self.tile_width, self.tile_height);
+                (tile.template_name_at(frame_idx), x, y)
+            })
+            .collect()
+    }
+}
+
+/// A frame submitted to a [`FrameSource`], to be read back with
+/// [`FrameSource::finish_frame`].
+pub enum SubmittedFrame {
+    Single(PendingFrame),
+    Grid(Vec<PendingFrame>),
+}
+
+/// What the render loop draws from: the job's renderer, or a showcase grid.
+pub enum FrameSource {
+    Single(Box<Renderer>),
+    Grid(ShowcaseGrid),
+}
+
+impl FrameSource {
+    pub fn adapter_info(&self) -> &wgpu::AdapterInfo {
+        match self {
+            Self::Single(renderer) => renderer.adapter_info(),
+            Self::Grid(grid) => grid.adapter_info(),
+        }
+    }
+
+    pub fn effects(&self) -> &[String] {
+        match self {
+            Self::Single(renderer) => renderer.effects(),
+            Self::Grid(grid) => grid.effects(),
+        }
+    }
+
+    /// Queue frame `frame_idx` on the GPU; see [`Renderer::submit_frame`].
+    pub fn submit_frame(&mut self, frame_idx: usize, frame: &SmoothedFrame) -> Result<SubmittedFrame> {
+        match self {
+            Self::Single(renderer) => Ok(SubmittedFrame::Single(renderer.submit_frame(frame_idx, frame)?)),
+            Self::Grid(grid) => Ok(SubmittedFrame::Grid(grid.submit_frame(frame_idx, frame)?)),
+        }
+    }
+
+    /// Wait for a submitted frame and return its RGBA pixels.
+    pub fn finish_frame(&self, submitted: SubmittedFrame) -> Result<Vec<u8>> {
+        match (self, submitted) {
+            (Self::Single(renderer), SubmittedFrame::Single(pending)) => Ok(renderer.finish_frame(pending)?),
+            (Self::Grid(grid), SubmittedFrame::Grid(pending)) => grid.finish_frame(pending),
+            _ => anyhow::bail!("Frame was submitted to a different frame source"),
+        }
+    }
+
+    /// Template names to label and the corner of the area each one covers,
+    /// for frame `frame_idx`.
+    pub fn labels(&self, frame_idx: usize) -> Vec<(&str, u32, u32)> {
+        match self {
+            Self::Single(renderer) => vec![(renderer.template_name_at(frame_idx), 0, 0)],
+            Self::Grid(grid) => grid.labels(frame_idx),
+        }
+    }
+}
+
+/// `--showcase` name cards: each template's name on a dark panel in the top
+/// left of the area it covers.
+pub struct ShowcaseLabels {
+    overlay: TextOverlay,
+}
+
+impl ShowcaseLabels {
+    /// `height` is the height of one labelled area: the frame in a sequence,
+    /// a tile in a grid.
+    pub fn new(height: u32, font_path: Option<&Path>, font_bytes: Option<&[u8]>, font_family: Option<&str>) -> Self {
+        let font_size = (height as f32 * 0.035).max(11.0);
+        Self {
+            overlay: TextOverlay::new(font_size, font_path, font_bytes, font_family),
+        }
+    }
+
+    pub fn draw(&self, pixels: &mut [u8], width: u32, height: u32, labels: &[(&str, u32, u32)]) {
+        let line = self.overlay.line_height();
+        let pad = line / 3;
+        for &(name, x, y) in labels {
+            let (panel_x, panel_y) = (x + pad, y + pad);
+            let panel_w = self.overlay.measure_width(name) + pad * 2;
+            TextOverlay::fill_rect(pixels, width, height, panel_x, panel_y, panel_w, line + pad, LABEL_PANEL_COLOR);
+            self.overlay
+                .composite(pixels, width, height, name, panel_x + pad, panel_y + pad / 2, LABEL_COLOR);
+        }
+    }
+}
+
+/// Top-left pixel of tile `index`, filling the grid row by row.
+fn tile_origin(index: usize, columns: u32, tile_width: u32, tile_height: u32) -> (u32, u32) {
+    let index = index as u32;
+    ((index % columns) * tile_width, (index / columns) * tile_height)
+}
+
+/// Copy a `tile_width`x`tile_height` RGBA tile into `canvas` at (`x`, `y`).
+fn blit(canvas: &mut [u8], canvas_width: u32, tile: &[u8], tile_width: u32, tile_height: u32, x: u32, y: u32) {
+    let row_bytes = (tile_width * 4) as usize;
+    for row in 0..tile_height {
+        let src = (row * tile_width * 4) as usize;
+        let dst = (((y + row) * canvas_width + x) * 4) as usize;
+        canvas[dst..dst + row_bytes].copy_from_slice(&tile[src..src + row_bytes]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid_is_as_square_as_possible() {
+        assert_eq!(grid_size(1), (1, 1));
+        assert_eq!(grid_size(2), (2, 1));
+        assert_eq!(grid_size(4), (2, 2));
+        assert_eq!(grid_size(5), (3, 2));
+        assert_eq!(grid_size(7), (3, 3));
+        assert_eq!(grid_size(10), (4, 3));
+
+        assert_eq!(tile_origin(0, 3, 640, 540), (0, 0));
+        assert_eq!(tile_origin(4, 3, 640, 540), (640, 540));
+    }
+
+    #[test]
+    fn tiles_land_in_their_cells() {
+        let (width, height) = (8, 4);
+        let mut canvas = vec![0u8; (width * height * 4) as usize];
+        for (index, value) in [10u8, 20, 30].into_iter().enumerate() {
+            let (x, y) = tile_origin(index, 2, 4, 2);
+            blit(&mut canvas, width, &[value; 4 * 2 * 4], 4, 2, x, y);
+        }
+        let at = |x: u32, y: u32| canvas[((y * width + x) * 4) as usize];
+        assert_eq!([at(0, 0), at(3, 1), at(4, 0), at(7, 1), at(0, 2), at(3, 3)], [10, 10, 20, 20, 30, 30]);
+        assert_eq!(at(4, 2), 0, "spare cell stays black");
+    }
+}
@@ -0,0 +1,60 @@
+/// `--temporal-smooth`: blends each finished frame with the previous output
+/// to tame strobing shaders. The previous output (not input) is kept, so the
+/// blend is an exponential moving average: higher amounts calm flicker more
+/// and add more motion blur.
+pub struct TemporalSmooth {
+    /// Weight of the previous output, 0.0 (off) to below 1.0.
+    amount: f32,
+    /// Last output frame and its index; a skipped frame restarts the average.
+    previous: Option<(usize, Vec<u8>)>,
+}
+
+impl TemporalSmooth {
+    pub fn new(amount: f32) -> Self {
+        Self { amount, previous: None }
+    }
+
+    /// Blend `pixels` in place with the previous output and keep the result.
+    pub fn apply(&mut self, frame_idx: usize, pixels: &mut [u8]) {
+        if let Some((prev_idx, ref previous)) = self.previous {
+            if prev_idx + 1 == frame_idx && previous.len() == pixels.len() {
+                let keep = 1.0 - self.amount;
+                for (out, &prev) in pixels.iter_mut().zip(previous) {
+                    *out = (*out as f32 * keep + prev as f32 * self.amount).round() as u8;
+                }
+            }
+        }
+        match self.previous {
+            Some((ref mut idx, ref mut buffer)) if buffer.len() == pixels.len() => {
+                *idx = frame_idx;
+                buffer.copy_from_slice(pixels);
+            }
+            _ => self.previous = Some((frame_idx, pixels.to_vec())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blends_consecutive_frames_and_restarts_after_a_gap() {
+        let mut smooth = TemporalSmooth::new(0.5);
+        let mut first = vec![200u8; 4];
+        smooth.apply(0, &mut first);
+        assert_eq!(first, [200; 4], "nothing to blend with yet");
+
+        let mut second = vec![0u8; 4];
+        smooth.apply(1, &mut second);
+        assert_eq!(second, [100; 4]);
+
+        let mut third = vec![0u8; 4];
+        smooth.apply(2, &mut third);
+        assert_eq!(third, [50; 4], "blends with the previous output");
+
+        let mut after_gap = vec![10u8; 4];
+        smooth.apply(7, &mut after_gap);
+        assert_eq!(after_gap, [10; 4]);
+    }
+}
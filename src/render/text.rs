@@ -3,6 +3,7 @@ use fontdue::{Font, FontSettings, Metrics};
 use log::warn;
 use std::{
     borrow::Cow,
+    collections::BTreeSet,
     io::{Cursor, Read},
     fs,
     path::{Path, PathBuf},
@@ -76,6 +77,23 @@ impl TextOverlay {
         x: u32,
         y: u32,
         color: [u8; 4],
+    ) {
+        self.composite_shaded(pixels, width, height, text, x, y, |_, _| color);
+    }
+
+    /// Composite text with a color chosen per pixel by `color_at(px, py)`, so
+    /// differently colored parts of a line are drawn in one pass instead of
+    /// overdrawing each other.
+    #[allow(clippy::too_many_arguments)]
+    pub fn composite_shaded(
+        &self,
+        pixels: &mut [u8],
+        width: u32,
+        height: u32,
+        text: &str,
+        x: u32,
+        y: u32,
+        color_at: impl Fn(u32, u32) -> [u8; 4],
     ) {
         let mut cursor_x = x as f32;
         for ch in text.chars() {
@@ -107,12 +125,9 @@ impl TextOverlay {
                         continue;
                     }
 
+                    let color = color_at(px as u32, py as u32);
                     let a = alpha as f32 / 255.0 * (color[3] as f32 / 255.0);
-                    let inv_a = 1.0 - a;
-                    pixels[idx] = (color[0] as f32 * a + pixels[idx] as f32 * inv_a) as u8;
-                    pixels[idx + 1] = (color[1] as f32 * a + pixels[idx + 1] as f32 * inv_a) as u8;
-                    pixels[idx + 2] = (color[2] as f32 * a + pixels[idx + 2] as f32 * inv_a) as u8;
-                    pixels[idx + 3] = 255;
+                    blend_pixel(&mut pixels[idx..idx + 4], color, a);
                 }
             }
 
@@ -133,6 +148,35 @@ impl TextOverlay {
         color: [u8; 4],
         outline_color: [u8; 4],
         outline_width: u32,
+    ) {
+        self.composite_outlined_shaded(
+            pixels,
+            width,
+            height,
+            text,
+            x,
+            y,
+            |_, _| color,
+            outline_color,
+            outline_width,
+        );
+    }
+
+    /// [`composite_outlined`](Self::composite_outlined) with the fill color
+    /// chosen per pixel, as in [`composite_shaded`](Self::composite_shaded).
+    #[cfg(feature = "subtitles")]
+    #[allow(clippy::too_many_arguments)]
+    pub fn composite_outlined_shaded(
+        &self,
+        pixels: &mut [u8],
+        width: u32,
+        height: u32,
+        text: &str,
+        x: u32,
+        y: u32,
+        color_at: impl Fn(u32, u32) -> [u8; 4],
+        outline_color: [u8; 4],
+        outline_width: u32,
     ) {
         let radius = outline_width as i32;
         if radius > 0 && outline_color[3] > 0 {
@@ -161,82 +205,47 @@ impl TextOverlay {
             }
         }
 
-        self.composite(pixels, width, height, text, x, y, color);
+        self.composite_shaded(pixels, width, height, text, x, y, color_at);
     }
 
-    /// Composite text onto an RGBA pixel buffer, clipping horizontally at `max_x`.
+    /// Composite a transparent RGBA layer, such as one built up with the
+    /// `composite*` methods on a zeroed buffer, at (`lx`, `ly`).
     ///
-    /// Identical to `composite()` but pixels beyond `max_x` are not drawn.
-    /// Used for karaoke-style partial word highlighting.
+    /// Drawing onto a transparent buffer leaves its colors premultiplied by
+    /// their alpha, so the layer is blended with a single premultiplied
+    /// "over" per pixel.
     #[cfg(feature = "subtitles")]
     #[allow(clippy::too_many_arguments)]
-    pub fn composite_clipped(
-        &self,
+    pub fn composite_layer(
         pixels: &mut [u8],
         width: u32,
         height: u32,
-        text: &str,
-        x: u32,
-        y: u32,
-        color: [u8; 4],
-        max_x: u32,
+        layer: &[u8],
+        lx: u32,
+        ly: u32,
+        lw: u32,
+        lh: u32,
     ) {
-        let mut cursor_x = x as f32;
-        for ch in text.chars() {
-            let (metrics, bitmap) = self.rasterize_with_fallback(ch);
-            if metrics.width == 0 || metrics.height == 0 || bitmap.is_empty() {
-                cursor_x += metrics.advance_width;
-                continue;
-            }
-
-            let glyph_x = cursor_x.round() as i32;
-
-            // Early exit: entire glyph is past the clip boundary
-            if glyph_x >= max_x as i32 {
-                break;
-            }
-
-            let glyph_y = y as i32 + self.font_size as i32 - metrics.height as i32 - metrics.ymin;
-
-            for gy in 0..metrics.height {
-                for gx in 0..metrics.width {
-                    let alpha = bitmap[gy * metrics.width + gx];
-                    if alpha == 0 {
-                        continue;
-                    }
-
-                    let px = glyph_x + gx as i32;
-                    let py = glyph_y + gy as i32;
-
-                    if px < 0 || py < 0 || px >= width as i32 || py >= height as i32 {
-                        continue;
-                    }
-
-                    // Clip at max_x
-                    if px >= max_x as i32 {
-                        continue;
-                    }
-
-                    let idx = ((py as u32 * width + px as u32) * 4) as usize;
-                    if idx + 3 >= pixels.len() {
-                        continue;
-                    }
-
-                    let a = alpha as f32 / 255.0 * (color[3] as f32 / 255.0);
-                    let inv_a = 1.0 - a;
-                    pixels[idx] = (color[0] as f32 * a + pixels[idx] as f32 * inv_a) as u8;
-                    pixels[idx + 1] = (color[1] as f32 * a + pixels[idx + 1] as f32 * inv_a) as u8;
-                    pixels[idx + 2] = (color[2] as f32 * a + pixels[idx + 2] as f32 * inv_a) as u8;
-                    pixels[idx + 3] = 255;
+        let x_end = (lx + lw).min(width);
+        let y_end = (ly + lh).min(height);
+        for py in ly..y_end {
+            for px in lx..x_end {
+                let src_idx = (((py - ly) * lw + (px - lx)) * 4) as usize;
+                let Some(src) = layer.get(src_idx..src_idx + 4) else { continue };
+                if src[3] == 0 {
+                    continue;
+                }
+                let idx = ((py * width + px) * 4) as usize;
+                let Some(dst) = pixels.get_mut(idx..idx + 4) else { continue };
+                let inv_a = 1.0 - src[3] as f32 / 255.0;
+                for c in 0..4 {
+                    dst[c] = (src[c] as f32 + dst[c] as f32 * inv_a).round().min(255.0) as u8;
                 }
             }
-
-            cursor_x += metrics.advance_width;
         }
     }
 
     /// Fill a rectangle on the pixel buffer with the given RGBA color (alpha-blended).
-    #[cfg(feature = "subtitles")]
     #[allow(clippy::too_many_arguments)]
     pub fn fill_rect(
         pixels: &mut [u8],
@@ -252,7 +261,6 @@ impl TextOverlay {
         if a == 0.0 {
             return;
         }
-        let inv_a = 1.0 - a;
         let x_end = (rx + rw).min(width);
         let y_end = (ry + rh).min(height);
         for py in ry..y_end {
@@ -261,12 +269,40 @@ impl TextOverlay {
                 if idx + 3 >= pixels.len() {
                     continue;
                 }
-                pixels[idx] = (color[0] as f32 * a + pixels[idx] as f32 * inv_a) as u8;
-                pixels[idx + 1] = (color[1] as f32 * a + pixels[idx + 1] as f32 * inv_a) as u8;
-                pixels[idx + 2] = (color[2] as f32 * a + pixels[idx + 2] as f32 * inv_a) as u8;
-                pixels[idx + 3] = ((color[3] as f32 * a + pixels[idx + 3] as f32 * inv_a) as u8).max(pixels[idx + 3]);
+                blend_pixel(&mut pixels[idx..idx + 4], color, a);
+            }
+        }
+    }
+
+    /// Black or white, whichever reads better over the average luminance of
+    /// the given rectangle (sampled before the text is drawn), with `alpha`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn contrast_color(
+        pixels: &[u8],
+        width: u32,
+        height: u32,
+        rx: u32,
+        ry: u32,
+        rw: u32,
+        rh: u32,
+        alpha: u8,
+    ) -> [u8; 4] {
+        let x_end = (rx + rw).min(width);
+        let y_end = (ry + rh).min(height);
+        let (mut sum, mut count) = (0.0f32, 0u32);
+        for py in ry..y_end {
+            for px in rx..x_end {
+                let idx = ((py * width + px) * 4) as usize;
+                let Some(p) = pixels.get(idx..idx + 3) else { continue };
+                sum += 0.2126 * p[0] as f32 + 0.7152 * p[1] as f32 + 0.0722 * p[2] as f32;
+                count += 1;
             }
         }
+        if count > 0 && sum / count as f32 > 128.0 {
+            [0, 0, 0, alpha]
+        } else {
+            [255, 255, 255, alpha]
+        }
     }
 
     /// The font size used for rendering, in pixels.
@@ -320,6 +356,33 @@ impl TextOverlay {
     }
 }
 
+/// Blend `color` over one RGBA pixel with coverage `a`. Alpha composites as
+/// `a + dst * (1 - a)` so overlays on a transparent frame stay as transparent
+/// as the overlay itself.
+fn blend_pixel(pixel: &mut [u8], color: [u8; 4], a: f32) {
+    let inv_a = 1.0 - a;
+    pixel[0] = (color[0] as f32 * a + pixel[0] as f32 * inv_a) as u8;
+    pixel[1] = (color[1] as f32 * a + pixel[1] as f32 * inv_a) as u8;
+    pixel[2] = (color[2] as f32 * a + pixel[2] as f32 * inv_a) as u8;
+    pixel[3] = (255.0 * a + pixel[3] as f32 * inv_a).round() as u8;
+}
+
+/// Parse `#RRGGBB` or `#RRGGBBAA`; the `#` is optional.
+pub fn parse_color(value: &str) -> Result<[u8; 4]> {
+    let hex = value.strip_prefix('#').unwrap_or(value);
+    if !(hex.len() == 6 || hex.len() == 8) || !hex.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        anyhow::bail!("expected #RRGGBB or #RRGGBBAA, got '{value}'");
+    }
+
+    let channel = |i: usize| u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16);
+    Ok([
+        channel(0)?,
+        channel(1)?,
+        channel(2)?,
+        if hex.len() == 8 { channel(3)? } else { 255 },
+    ])
+}
+
 #[cfg(feature = "subtitles")]
 fn offset_coordinate(value: u32, offset: i32) -> u32 {
     if offset < 0 {
@@ -817,13 +880,13 @@ fn load_font_from_bytes_at_index(bytes: &[u8], collection_index: u32) -> Option<
     }
 }
 
-fn load_system_font_family(family: &str) -> Option<Font> {
-    use fontdb::{Database, Family, Query, Stretch, Style, Weight};
+/// The fonts installed in the OS font directories, scanned on first use.
+fn system_font_database() -> &'static fontdb::Database {
     use std::sync::OnceLock;
 
-    static DATABASE: OnceLock<Database> = OnceLock::new();
-    let database = DATABASE.get_or_init(|| {
-        let mut database = Database::new();
+    static DATABASE: OnceLock<fontdb::Database> = OnceLock::new();
+    DATABASE.get_or_init(|| {
+        let mut database = fontdb::Database::new();
         database.load_system_fonts();
 
         #[cfg(target_os = "macos")]
@@ -833,14 +896,38 @@ fn load_system_font_family(family: &str) -> Option<Font> {
         }
 
         database
-    });
+    })
+}
 
-    let id = database.query(&Query {
+fn query_system_font_family(family: &str) -> Option<fontdb::ID> {
+    use fontdb::{Family, Query, Stretch, Style, Weight};
+
+    system_font_database().query(&Query {
         families: &[Family::Name(family)],
         weight: Weight::NORMAL,
         stretch: Stretch::Normal,
         style: Style::Normal,
-    })?;
+    })
+}
+
+/// Family names of the installed fonts, sorted and deduplicated, as
+/// `--font-family` accepts them.
+pub fn installed_font_families() -> Vec<String> {
+    let families: BTreeSet<String> = system_font_database()
+        .faces()
+        .flat_map(|face| face.families.iter().map(|(name, _)| name.clone()))
+        .collect();
+    families.into_iter().collect()
+}
+
+/// Whether `family` names an installed font.
+pub fn is_installed_font_family(family: &str) -> bool {
+    query_system_font_family(family).is_some()
+}
+
+fn load_system_font_family(family: &str) -> Option<Font> {
+    let database = system_font_database();
+    let id = query_system_font_family(family)?;
 
     log::info!("Using installed font family '{}'", family);
 
@@ -875,3 +962,78 @@ fn find_system_font() -> Option<PathBuf> {
         .find(|p| p.exists())
         .map(Path::to_path_buf)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_colors_with_alpha() {
+        assert_eq!(parse_color("#102030").unwrap(), [16, 32, 48, 255]);
+        assert_eq!(parse_color("10203080").unwrap(), [16, 32, 48, 128]);
+        assert!(parse_color("#FFFF").is_err());
+        assert!(parse_color("#GG0000").is_err());
+    }
+
+    #[test]
+    fn listed_font_families_resolve_by_name() {
+        let families = installed_font_families();
+        assert!(families.windows(2).all(|pair| pair[0] < pair[1]));
+        for family in families.iter().take(5) {
+            assert!(is_installed_font_family(family), "{family}");
+        }
+        assert!(!is_installed_font_family("No Such Font Family 7f3a"));
+    }
+
+    #[test]
+    fn overlays_keep_a_transparent_background_transparent() {
+        let (width, height) = (64, 32);
+        let mut pixels = vec![0u8; (width * height * 4) as usize];
+        let overlay = TextOverlay::new(24.0, None, None, None);
+
+        overlay.composite(&mut pixels, width, height, "Hi", 2, 2, [255, 255, 255, 128]);
+        TextOverlay::fill_rect(&mut pixels, width, height, 40, 0, 8, 8, [0, 0, 0, 64]);
+
+        let text_alpha = (0..32 * 32)
+            .map(|i| pixels[(i / 32 * width + i % 32) as usize * 4 + 3])
+            .max()
+            .unwrap();
+        assert!(text_alpha > 100 && text_alpha <= 128, "text alpha {text_alpha}");
+        assert_eq!(pixels[(40 * 4 + 3) as usize], 64);
+        assert_eq!(pixels[(60 * 4 + 3) as usize], 0);
+    }
+
+    #[cfg(feature = "subtitles")]
+    #[test]
+    fn layer_composites_like_drawing_directly() {
+        let (width, height) = (64, 32);
+        let background = [40u8, 80, 120, 255];
+        let color = [250, 200, 10, 180];
+        let overlay = TextOverlay::new(24.0, None, None, None);
+
+        let mut direct: Vec<u8> = background.repeat((width * height) as usize);
+        overlay.composite(&mut direct, width, height, "Hi", 6, 4, color);
+
+        let (lw, lh) = (40, 28);
+        let mut layer = vec![0u8; (lw * lh * 4) as usize];
+        overlay.composite(&mut layer, lw, lh, "Hi", 2, 2, color);
+        let mut layered: Vec<u8> = background.repeat((width * height) as usize);
+        TextOverlay::composite_layer(&mut layered, width, height, &layer, 4, 2, lw, lh);
+
+        let max_diff = direct.iter().zip(&layered).map(|(a, b)| a.abs_diff(*b)).max().unwrap();
+        assert!(max_diff <= 2, "max channel difference {max_diff}");
+    }
+
+    #[test]
+    fn contrast_color_flips_over_bright_regions() {
+        let (width, height) = (8, 4);
+        // Left half white, right half black.
+        let pixels: Vec<u8> = (0..width * height)
+            .flat_map(|i| if i % width < 4 { [255, 255, 255, 255] } else { [0, 0, 0, 255] })
+            .collect();
+
+        assert_eq!(TextOverlay::contrast_color(&pixels, width, height, 0, 0, 4, 4, 220), [0, 0, 0, 220]);
+        assert_eq!(TextOverlay::contrast_color(&pixels, width, height, 4, 0, 4, 4, 220), [255, 255, 255, 220]);
+        assert_eq!(TextOverlay::contrast_color(&pixels, width, height, 20, 20, 4, 4, 220), [255, 255, 255, 220]);
+    }
+}
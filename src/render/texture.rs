@@ -0,0 +1,212 @@
+use anyhow::{Context, Result};
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+
+use super::gpu::GpuContext;
+use crate::templates::loader::TextureAsset;
+
+/// An RGBA8 image decoded from a template's texture file.
+pub struct DecodedImage {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+/// A template texture uploaded to the GPU, bound at `binding` with its sampler
+/// at `binding + 1`.
+pub struct TemplateTexture {
+    pub binding: u32,
+    #[allow(dead_code)]
+    pub texture: wgpu::Texture,
+    pub view: wgpu::TextureView,
+    pub sampler: wgpu::Sampler,
+}
+
+impl TemplateTexture {
+    pub fn upload(gpu: &GpuContext, asset: &TextureAsset) -> Result<Self> {
+        let image = decode_image(&asset.bytes)
+            .with_context(|| format!("Failed to decode template texture '{}'", asset.file))?;
+
+        let format = if asset.linear {
+            wgpu::TextureFormat::Rgba8Unorm
+        } else {
+            wgpu::TextureFormat::Rgba8UnormSrgb
+        };
+        let size = wgpu::Extent3d {
+            width: image.width,
+            height: image.height,
+            depth_or_array_layers: 1,
+        };
+
+        let texture = gpu.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(&asset.file),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+
+        gpu.queue.write_texture(
+            texture.as_image_copy(),
+            &image.pixels,
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(image.width * 4),
+                rows_per_image: Some(image.height),
+            },
+            size,
+        );
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = gpu.device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("template_texture_sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        log::info!(
+            "Loaded template texture '{}' ({}x{}) at binding {}",
+            asset.file,
+            image.width,
+            image.height,
+            asset.binding
+        );
+
+        Ok(Self {
+            binding: asset.binding,
+            texture,
+            view,
+            sampler,
+        })
+    }
+}
+
+/// Decode any image FFmpeg understands (PNG, JPEG, ...) into RGBA8.
+///
+/// FFmpeg is already required for encoding, so this avoids pulling in an image
+/// decoding crate. The bytes are piped in and a single PAM frame is read back,
+/// whose header carries the dimensions.
+pub fn decode_image(bytes: &[u8]) -> Result<DecodedImage> {
+    let mut child = Command::new("ffmpeg")
+        .args([
+            "-hide_banner",
+            "-loglevel",
+            "error",
+            "-i",
+            "pipe:0",
+            "-frames:v",
+            "1",
+            "-f",
+            "image2pipe",
+            "-c:v",
+            "pam",
+            "-pix_fmt",
+            "rgba",
+            "pipe:1",
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to run FFmpeg image decoder. Is ffmpeg installed?")?;
+
+    // Feed stdin from a separate thread so a large image cannot deadlock
+    // against FFmpeg filling its stdout pipe.
+    let mut stdin = child.stdin.take().context("FFmpeg stdin not available")?;
+    let input = bytes.to_vec();
+    let writer = std::thread::spawn(move || stdin.write_all(&input));
+
+    let mut stdout = Vec::new();
+    child
+        .stdout
+        .take()
+        .context("FFmpeg stdout not available")?
+        .read_to_end(&mut stdout)
+        .context("Failed to read FFmpeg image output")?;
+    let output = child.wait_with_output().context("Failed to wait for FFmpeg")?;
+    // A broken pipe here just means FFmpeg stopped reading early; its exit
+    // status below is the authoritative error.
+    let _ = writer.join();
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("FFmpeg image decoder exited with an error:\n{stderr}");
+    }
+
+    parse_pam(&stdout)
+}
+
+/// Parse a binary PAM (`P7`) image with an RGB_ALPHA tuple type.
+fn parse_pam(bytes: &[u8]) -> Result<DecodedImage> {
+    let header_end = bytes
+        .windows(b"ENDHDR\n".len())
+        .position(|window| window == b"ENDHDR\n")
+        .context("PAM header is missing ENDHDR")?;
+    let header = std::str::from_utf8(&bytes[..header_end]).context("PAM header is not UTF-8")?;
+
+    let mut lines = header.lines();
+    if lines.next() != Some("P7") {
+        anyhow::bail!("Not a PAM image");
+    }
+
+    let (mut width, mut height, mut depth, mut maxval) = (0u32, 0u32, 0u32, 0u32);
+    for line in lines {
+        let Some((key, value)) = line.split_once(' ') else {
+            continue;
+        };
+        match key {
+            "WIDTH" => width = value.trim().parse()?,
+            "HEIGHT" => height = value.trim().parse()?,
+            "DEPTH" => depth = value.trim().parse()?,
+            "MAXVAL" => maxval = value.trim().parse()?,
+            _ => {}
+        }
+    }
+
+    if width == 0 || height == 0 {
+        anyhow::bail!("PAM image has no dimensions");
+    }
+    if depth != 4 || maxval != 255 {
+        anyhow::bail!("Expected 8-bit RGBA PAM, got depth {depth} maxval {maxval}");
+    }
+
+    let data = &bytes[header_end + b"ENDHDR\n".len()..];
+    let expected = (width * height * 4) as usize;
+    if data.len() < expected {
+        anyhow::bail!("PAM image is truncated: {} of {} bytes", data.len(), expected);
+    }
+
+    Ok(DecodedImage {
+        width,
+        height,
+        pixels: data[..expected].to_vec(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_rgba_pam() {
+        let mut bytes =
+            b"P7\nWIDTH 2\nHEIGHT 1\nDEPTH 4\nMAXVAL 255\nTUPLTYPE RGB_ALPHA\nENDHDR\n".to_vec();
+        bytes.extend_from_slice(&[255, 0, 0, 255, 0, 0, 255, 128]);
+
+        let image = parse_pam(&bytes).unwrap();
+
+        assert_eq!((image.width, image.height), (2, 1));
+        assert_eq!(image.pixels, [255, 0, 0, 255, 0, 0, 255, 128]);
+    }
+
+    #[test]
+    fn rejects_truncated_pam() {
+        let mut bytes = b"P7\nWIDTH 2\nHEIGHT 2\nDEPTH 4\nMAXVAL 255\nENDHDR\n".to_vec();
+        bytes.extend_from_slice(&[0; 4]);
+        assert!(parse_pam(&bytes).is_err());
+    }
+}
@@ -0,0 +1,98 @@
+use super::text::TextOverlay;
+
+const BACKGROUND_COLOR: [u8; 4] = [0, 0, 0, 120];
+const PLAYED_COLOR: [u8; 4] = [255, 255, 255, 220];
+const UPCOMING_COLOR: [u8; 4] = [255, 255, 255, 90];
+const PLAYHEAD_COLOR: [u8; 4] = [255, 70, 70, 255];
+
+/// `--waveform-strip`: the whole track's waveform along the bottom of the
+/// frame with a playhead at the current time. The min/max envelope is
+/// computed once from the decoded samples, one column per pixel.
+pub struct WaveformStrip {
+    /// Per-column (min, max), scaled so the track's peak fills the strip.
+    columns: Vec<(f32, f32)>,
+    strip_height: u32,
+    duration: f32,
+}
+
+impl WaveformStrip {
+    pub fn new(samples: &[f32], sample_rate: u32, width: u32, height: u32) -> Self {
+        let peak = samples.iter().fold(0.0f32, |peak, s| peak.max(s.abs())).max(1e-6);
+        let columns = (0..width as usize)
+            .map(|x| {
+                let start = x * samples.len() / width as usize;
+                let end = ((x + 1) * samples.len() / width as usize).max(start + 1).min(samples.len());
+                samples
+                    .get(start..end)
+                    .unwrap_or_default()
+                    .iter()
+                    .fold((0.0f32, 0.0f32), |(min, max), &s| (min.min(s / peak), max.max(s / peak)))
+            })
+            .collect();
+        Self {
+            columns,
+            strip_height: (height as f32 * 0.1).round().max(8.0).min(height as f32) as u32,
+            duration: samples.len() as f32 / sample_rate as f32,
+        }
+    }
+
+    pub fn render_frame(&self, pixels: &mut [u8], width: u32, height: u32, time: f32) {
+        let top = height - self.strip_height;
+        TextOverlay::fill_rect(pixels, width, height, 0, top, width, self.strip_height, BACKGROUND_COLOR);
+
+        let playhead = playhead_x(time, self.duration, width);
+        let center = top as f32 + self.strip_height as f32 / 2.0;
+        let half = (self.strip_height as f32 / 2.0 - 1.0).max(1.0);
+        for (x, &(min, max)) in self.columns.iter().enumerate() {
+            let y0 = (center - max * half).floor() as u32;
+            let y1 = (center - min * half).ceil() as u32;
+            let color = if (x as u32) < playhead { PLAYED_COLOR } else { UPCOMING_COLOR };
+            TextOverlay::fill_rect(pixels, width, height, x as u32, y0, 1, (y1 - y0).max(1), color);
+        }
+
+        let line_width = (width / 640).max(2);
+        TextOverlay::fill_rect(
+            pixels,
+            width,
+            height,
+            playhead.saturating_sub(line_width / 2),
+            top,
+            line_width,
+            self.strip_height,
+            PLAYHEAD_COLOR,
+        );
+    }
+}
+
+/// Column of the playhead at `time`.
+fn playhead_x(time: f32, duration: f32, width: u32) -> u32 {
+    if duration <= 0.0 {
+        return 0;
+    }
+    ((time / duration).clamp(0.0, 1.0) * width as f32) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn envelope_and_playhead_follow_the_track() {
+        // One second of silence, then one second at half scale.
+        let mut samples = vec![0.0f32; 200];
+        samples.extend((0..200).map(|i| if i % 2 == 0 { 0.5 } else { -0.5 }));
+        let strip = WaveformStrip::new(&samples, 200, 8, 100);
+
+        assert_eq!(strip.columns[0], (0.0, 0.0));
+        assert_eq!(strip.columns[7], (-1.0, 1.0), "the peak fills the strip");
+        assert_eq!(strip.strip_height, 10);
+        assert_eq!(playhead_x(1.0, strip.duration, 8), 4);
+        assert_eq!(playhead_x(5.0, strip.duration, 8), 8);
+
+        let mut pixels = vec![0u8; 8 * 100 * 4];
+        strip.render_frame(&mut pixels, 8, 100, 0.0);
+        let alpha = |x: u32, y: u32| pixels[((y * 8 + x) * 4 + 3) as usize];
+        assert_eq!(alpha(6, 50), 0, "nothing above the strip");
+        assert!(alpha(6, 91) > alpha(2, 91), "loud columns reach the edge of the strip");
+    }
+}
@@ -0,0 +1,257 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Machine-readable record of a render, written by `--report` for
+/// reproducibility and bug reports.
+#[derive(Debug, Serialize)]
+pub struct RenderReport {
+    pub sonica_version: &'static str,
+    pub input: InputReport,
+    pub output: String,
+    pub video: VideoReport,
+    pub templates: Vec<String>,
+    pub effects: Vec<String>,
+    pub params: BTreeMap<String, String>,
+    pub param_anim: Vec<String>,
+    pub analysis: AnalysisReport,
+    pub gpu: GpuReport,
+    pub timing: TimingReport,
+}
+
+#[derive(Debug, Serialize)]
+pub struct InputReport {
+    pub path: String,
+    pub sha256: String,
+    pub sample_rate: u32,
+    pub duration_seconds: f32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VideoReport {
+    pub width: u32,
+    pub height: u32,
+    pub fps: String,
+    pub codec: String,
+    pub pix_fmt: String,
+    pub color_matrix: String,
+    pub crf: Option<u32>,
+    pub bitrate: Option<String>,
+}
+
+/// What analysis found and how it was set up. There is no musical key:
+/// sonica doesn't detect one.
+#[derive(Debug, Serialize)]
+pub struct AnalysisReport {
+    pub tempo_bpm: f32,
+    pub beats: usize,
+    pub peak_rms: f32,
+    pub smoothing: f32,
+    pub downmix: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GpuReport {
+    pub name: String,
+    pub backend: String,
+    pub driver: String,
+}
+
+impl GpuReport {
+    pub fn from_adapter(info: &wgpu::AdapterInfo) -> Self {
+        Self {
+            name: info.name.clone(),
+            backend: format!("{:?}", info.backend),
+            driver: format!("{} {}", info.driver, info.driver_info).trim().to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct TimingReport {
+    pub analysis_seconds: f64,
+    pub render_seconds: f64,
+    pub total_seconds: f64,
+    pub frames_rendered: usize,
+    pub frames_total: usize,
+    pub interrupted: bool,
+}
+
+/// Wall-clock time spent in each stage of a run, printed by `--benchmark`.
+#[derive(Debug, Default)]
+pub struct StageTimings {
+    stages: Vec<(String, Duration)>,
+}
+
+impl StageTimings {
+    pub fn record(&mut self, stage: impl Into<String>, time: Duration) {
+        self.stages.push((stage.into(), time));
+    }
+
+    /// A table of every stage in the order recorded, with its share of `total`.
+    pub fn table(&self, total: Duration) -> String {
+        let width = self
+            .stages
+            .iter()
+            .map(|(stage, _)| stage.chars().count())
+            .chain(["total".len()])
+            .max()
+            .unwrap_or_default();
+        let row = |stage: &str, time: Duration| {
+            let share = 100.0 * time.as_secs_f64() / total.as_secs_f64().max(f64::EPSILON);
+            format!("{:<width$}  {:>9.3}s  {:>5.1}%\n", stage, time.as_secs_f64(), share)
+        };
+        let mut table = String::new();
+        for (stage, time) in &self.stages {
+            table += &row(stage, *time);
+        }
+        table + &row("total", total)
+    }
+}
+
+impl RenderReport {
+    /// Record the run's timing and write the report as pretty-printed JSON.
+    pub fn finish(mut self, path: &Path, timing: TimingReport) -> Result<()> {
+        self.timing = timing;
+        self.write(path)
+    }
+
+    fn write(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json + "\n")
+            .with_context(|| format!("Failed to write report: {}", path.display()))?;
+        log::info!("Wrote render report to {}", path.display());
+        Ok(())
+    }
+}
+
+/// `--skip-if-unchanged`: a digest of the input audio and the effective
+/// settings, kept in a sidecar next to the output. A re-run whose digest
+/// matches the sidecar has nothing new to render.
+#[derive(Debug)]
+pub struct RenderStamp {
+    path: PathBuf,
+    digest: String,
+}
+
+impl RenderStamp {
+    /// `settings` is any text that changes whenever a setting that affects
+    /// the output does.
+    pub fn new(output: &Path, input: &Path, settings: &str) -> Result<Self> {
+        let mut hasher = Sha256::new();
+        hasher.update(format!("sonica {}\n", env!("CARGO_PKG_VERSION")));
+        hasher.update(sha256_file(input)? + "\n");
+        hasher.update(settings);
+        Ok(Self {
+            path: Self::sidecar(output),
+            digest: hex(&hasher.finalize()),
+        })
+    }
+
+    /// `out.mp4` keeps its stamp in `out.mp4.sonica-stamp`.
+    pub fn sidecar(output: &Path) -> PathBuf {
+        let mut name = output.as_os_str().to_owned();
+        name.push(".sonica-stamp");
+        PathBuf::from(name)
+    }
+
+    /// Whether `output` exists and was rendered from the same input and settings.
+    pub fn is_current(&self, output: &Path) -> bool {
+        output.exists()
+            && std::fs::read_to_string(&self.path).is_ok_and(|stamp| stamp.trim() == self.digest)
+    }
+
+    /// Drop the old stamp before rendering, so an interrupted or failed run
+    /// can't leave a partial output that looks up to date.
+    pub fn clear(&self) -> Result<()> {
+        match std::fs::remove_file(&self.path) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err)
+                .with_context(|| format!("Failed to remove stamp: {}", self.path.display())),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn write(&self) -> Result<()> {
+        std::fs::write(&self.path, format!("{}\n", self.digest))
+            .with_context(|| format!("Failed to write stamp: {}", self.path.display()))
+    }
+}
+
+/// Hex SHA-256 of a file, read in chunks so large files aren't loaded whole.
+pub fn sha256_file(path: &Path) -> Result<String> {
+    let mut file =
+        std::fs::File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 1 << 20];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hex(&hasher.finalize()))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hashes_files_as_hex_sha256() {
+        let path = std::env::temp_dir().join(format!("sonica-report-test-{}", std::process::id()));
+        std::fs::write(&path, b"abc").unwrap();
+
+        let digest = sha256_file(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            digest.unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn stamps_match_only_the_same_input_and_settings() {
+        let dir = std::env::temp_dir().join(format!("sonica-stamp-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (input, output) = (dir.join("in.wav"), dir.join("out.mp4"));
+        std::fs::write(&input, b"audio").unwrap();
+        std::fs::write(&output, b"video").unwrap();
+        assert_eq!(RenderStamp::sidecar(&output), dir.join("out.mp4.sonica-stamp"));
+
+        let stamp = RenderStamp::new(&output, &input, "crf=18").unwrap();
+        assert!(!stamp.is_current(&output));
+        stamp.write().unwrap();
+        assert!(stamp.is_current(&output));
+        assert!(!RenderStamp::new(&output, &input, "crf=20").unwrap().is_current(&output));
+        std::fs::write(&input, b"other audio").unwrap();
+        assert!(!RenderStamp::new(&output, &input, "crf=18").unwrap().is_current(&output));
+        stamp.clear().unwrap();
+        stamp.clear().unwrap();
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn stage_table_lists_each_stage_and_its_share() {
+        let mut timings = StageTimings::default();
+        timings.record("decode", Duration::from_millis(250));
+        timings.record("analysis pass 2", Duration::from_millis(750));
+
+        assert_eq!(
+            timings.table(Duration::from_secs(2)),
+            "decode               0.250s   12.5%\n\
+             analysis pass 2      0.750s   37.5%\n\
+             total                2.000s  100.0%\n"
+        );
+    }
+}
@@ -14,13 +14,33 @@ pub struct SubtitleCue {
     pub words: Vec<TimedWord>,
 }
 
+/// Number of lines `words` occupy when wrapped greedily at `max_chars`,
+/// matching the renderer's line splitting.
+pub(super) fn line_count<'a>(words: impl IntoIterator<Item = &'a str>, max_chars: usize) -> usize {
+    let mut lines = 0;
+    let mut line_len = 0;
+    for word in words {
+        let word_len = character_count(word);
+        if lines == 0 || line_len + 1 + word_len > max_chars {
+            lines += 1;
+            line_len = word_len;
+        } else {
+            line_len += 1 + word_len;
+        }
+    }
+    lines
+}
+
 /// Group word-level segments into subtitle cues based on timing gaps,
-/// punctuation boundaries, and maximum character count.
-pub fn group_words(words: Vec<TimedWord>, max_chars: usize) -> Vec<SubtitleCue> {
+/// punctuation boundaries, and a budget of `max_lines` lines of
+/// `max_chars` characters each.
+pub fn group_words(words: Vec<TimedWord>, max_chars: usize, max_lines: usize) -> Vec<SubtitleCue> {
     if words.is_empty() {
         return Vec::new();
     }
 
+    let words = hard_break_long_words(words, max_chars);
+
     let mut cues: Vec<SubtitleCue> = Vec::new();
     let mut current_words: Vec<TimedWord> = Vec::new();
     let mut current_text = String::new();
@@ -28,16 +48,18 @@ pub fn group_words(words: Vec<TimedWord>, max_chars: usize) -> Vec<SubtitleCue>
     let mut current_end = words[0].end_time;
 
     for word in &words {
-        let would_be = if current_text.is_empty() {
-            character_count(&word.text)
-        } else {
-            character_count(&current_text) + 1 + character_count(&word.text)
-        };
+        let would_be = line_count(
+            current_words
+                .iter()
+                .chain(std::iter::once(word))
+                .map(|w| w.text.as_str()),
+            max_chars,
+        );
 
         let timing_gap = word.start_time - current_end;
         let should_break = !current_text.is_empty()
             && (timing_gap > 0.5
-                || would_be > max_chars
+                || would_be > max_lines
                 || ends_with_sentence_punct(&current_text));
 
         if should_break {
@@ -53,12 +75,11 @@ pub fn group_words(words: Vec<TimedWord>, max_chars: usize) -> Vec<SubtitleCue>
         }
 
         if current_text.is_empty() {
-            current_text.push_str(&word.text);
             current_start = word.start_time;
-        } else {
+        } else if !word.joins_previous {
             current_text.push(' ');
-            current_text.push_str(&word.text);
         }
+        current_text.push_str(&word.text);
         current_end = word.end_time;
         current_words.push(word.clone());
     }
@@ -74,11 +95,40 @@ pub fn group_words(words: Vec<TimedWord>, max_chars: usize) -> Vec<SubtitleCue>
     }
 
     // Merge short cues (<800ms) with the next cue
-    merge_short_cues(&mut cues, 0.8);
+    merge_short_cues(&mut cues, 0.8, max_chars, max_lines);
 
     cues
 }
 
+/// Split any word longer than a whole line into line-sized pieces, dividing
+/// its time span by character count so karaoke timing stays continuous.
+fn hard_break_long_words(words: Vec<TimedWord>, max_chars: usize) -> Vec<TimedWord> {
+    let max_chars = max_chars.max(1);
+    let mut result = Vec::with_capacity(words.len());
+
+    for word in words {
+        let graphemes: Vec<&str> = UnicodeSegmentation::graphemes(word.text.as_str(), true).collect();
+        if graphemes.len() <= max_chars {
+            result.push(word);
+            continue;
+        }
+
+        let duration = word.end_time - word.start_time;
+        let total = graphemes.len() as f32;
+        for (i, chunk) in graphemes.chunks(max_chars).enumerate() {
+            let offset = (i * max_chars) as f32;
+            result.push(TimedWord {
+                text: chunk.concat(),
+                start_time: word.start_time + duration * offset / total,
+                end_time: word.start_time + duration * (offset + chunk.len() as f32) / total,
+                joins_previous: i > 0,
+            });
+        }
+    }
+
+    result
+}
+
 fn ends_with_sentence_punct(text: &str) -> bool {
     let trimmed = text.trim_end();
     trimmed.ends_with('.')
@@ -89,13 +139,28 @@ fn ends_with_sentence_punct(text: &str) -> bool {
         || trimmed.ends_with('！')
 }
 
-fn merge_short_cues(cues: &mut Vec<SubtitleCue>, min_duration: f32) {
+fn merge_short_cues(
+    cues: &mut Vec<SubtitleCue>,
+    min_duration: f32,
+    max_chars: usize,
+    max_lines: usize,
+) {
     let mut i = 0;
     while i + 1 < cues.len() {
         let duration = cues[i].end_time - cues[i].start_time;
-        if duration < min_duration {
+        let merged_lines = line_count(
+            cues[i]
+                .words
+                .iter()
+                .chain(&cues[i + 1].words)
+                .map(|w| w.text.as_str()),
+            max_chars,
+        );
+        if duration < min_duration && merged_lines <= max_lines {
             let next = cues.remove(i + 1);
-            cues[i].text.push(' ');
+            if !next.words.first().is_some_and(|word| word.joins_previous) {
+                cues[i].text.push(' ');
+            }
             cues[i].text.push_str(&next.text);
             cues[i].end_time = next.end_time;
             cues[i].words.extend(next.words);
@@ -115,6 +180,7 @@ mod tests {
             text: text.to_string(),
             start_time: start,
             end_time: end,
+            joins_previous: false,
         }
     }
 
@@ -128,7 +194,7 @@ mod tests {
             word("a", 1.8, 1.9),
             word("test", 1.9, 2.5),
         ];
-        let cues = group_words(words, 12);
+        let cues = group_words(words, 12, 1);
         assert!(cues.len() >= 2);
         for cue in &cues {
             assert!(!cue.text.is_empty());
@@ -144,13 +210,50 @@ mod tests {
             word("손상되었기", 2.0, 3.0),
         ];
 
-        let cues = group_words(words, 13);
+        let cues = group_words(words, 13, 1);
 
         assert_eq!(cues.len(), 2);
         assert_eq!(cues[0].text, "암세포는 미토콘드리아가");
         assert_eq!(cues[1].text, "손상되었기");
     }
 
+    #[test]
+    fn splits_cues_at_line_budget() {
+        let words = vec![
+            word("alpha", 0.0, 0.5),
+            word("beta", 0.5, 1.0),
+            word("gamma", 1.0, 1.5),
+            word("delta", 1.5, 2.0),
+            word("epsilon", 2.0, 2.5),
+        ];
+
+        let cues = group_words(words, 11, 2);
+
+        assert_eq!(cues.len(), 2);
+        assert_eq!(cues[0].text, "alpha beta gamma delta");
+        assert_eq!(cues[1].text, "epsilon");
+        for cue in &cues {
+            assert!(line_count(cue.words.iter().map(|w| w.text.as_str()), 11) <= 2);
+        }
+    }
+
+    #[test]
+    fn hard_breaks_words_longer_than_a_line() {
+        let words = vec![word("abcdefghij", 0.0, 1.0)];
+
+        let cues = group_words(words, 4, 2);
+
+        let pieces: Vec<&str> = cues
+            .iter()
+            .flat_map(|cue| cue.words.iter().map(|w| w.text.as_str()))
+            .collect();
+        assert_eq!(pieces, ["abcd", "efgh", "ij"]);
+        let text: String = cues.iter().map(|cue| cue.text.as_str()).collect();
+        assert_eq!(text, "abcdefghij", "pieces of a word are not joined with spaces");
+        assert_eq!(cues[0].words[1].start_time, 0.4);
+        assert_eq!(cues.last().unwrap().end_time, 1.0);
+    }
+
     #[test]
     fn counts_combining_sequence_as_one_character() {
         assert_eq!(character_count("e\u{301}"), 1);
@@ -164,7 +267,7 @@ mod tests {
             word("New", 2.0, 2.5), // 1.0s gap
             word("sentence", 2.5, 3.0),
         ];
-        let cues = group_words(words, 100);
+        let cues = group_words(words, 100, 2);
         assert_eq!(cues.len(), 2);
         assert_eq!(cues[0].text, "Hello world.");
         assert_eq!(cues[0].words.len(), 2);
@@ -178,7 +281,7 @@ mod tests {
             word("Hello.", 0.0, 1.5),
             word("World", 1.5, 3.0),
         ];
-        let cues = group_words(words, 100);
+        let cues = group_words(words, 100, 2);
         assert_eq!(cues.len(), 2);
         assert_eq!(cues[0].text, "Hello.");
         assert_eq!(cues[1].text, "World");
@@ -190,7 +293,7 @@ mod tests {
             word("Hi", 0.0, 0.3), // very short
             word("there", 0.3, 1.0),
         ];
-        let cues = group_words(words, 100);
+        let cues = group_words(words, 100, 2);
         assert_eq!(cues.len(), 1);
         assert_eq!(cues[0].text, "Hi there");
         assert_eq!(cues[0].words.len(), 2);
@@ -198,7 +301,7 @@ mod tests {
 
     #[test]
     fn empty_input() {
-        let cues = group_words(vec![], 42);
+        let cues = group_words(vec![], 42, 2);
         assert!(cues.is_empty());
     }
 
@@ -209,7 +312,7 @@ mod tests {
             word("two", 0.6, 1.0),
             word("three", 1.1, 1.8),
         ];
-        let cues = group_words(words, 100);
+        let cues = group_words(words, 100, 2);
         assert_eq!(cues.len(), 1);
         assert_eq!(cues[0].words.len(), 3);
         assert_eq!(cues[0].words[0].text, "one");
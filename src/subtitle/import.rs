@@ -0,0 +1,92 @@
+use super::cue::SubtitleCue;
+use super::{srt, vtt};
+use anyhow::{Context, Result};
+use std::path::Path;
+
+/// Read `--subtitle-file` cues, picking SRT or WebVTT by extension. Cues
+/// must be in start-time order; overlapping cues are allowed. Imported cues
+/// carry no word timing, so they render without karaoke highlighting.
+pub fn read_subtitles(path: &Path) -> Result<Vec<SubtitleCue>> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase);
+    let cues = match extension.as_deref() {
+        Some("srt") => srt::read_srt(path)?,
+        Some("vtt") => vtt::read_vtt(path)?,
+        _ => anyhow::bail!(
+            "Unsupported subtitle file: {}. Use a .srt or .vtt file",
+            path.display()
+        ),
+    };
+    check_order(&cues)
+        .with_context(|| format!("Failed to parse subtitle file: {}", path.display()))?;
+    Ok(cues)
+}
+
+/// Cues for a `--start`/`--end` clip of the track: times count from
+/// `start`, cues entirely outside the clip are dropped, and ones that cross
+/// its edges are cut to it.
+pub fn clip_cues(cues: Vec<SubtitleCue>, start: f32, end: Option<f32>) -> Vec<SubtitleCue> {
+    let end = end.unwrap_or(f32::INFINITY);
+    cues.into_iter()
+        .filter(|cue| cue.end_time > start && cue.start_time < end)
+        .map(|mut cue| {
+            cue.start_time = cue.start_time.max(start) - start;
+            cue.end_time = cue.end_time.min(end) - start;
+            for word in &mut cue.words {
+                word.start_time -= start;
+                word.end_time -= start;
+            }
+            cue
+        })
+        .collect()
+}
+
+fn check_order(cues: &[SubtitleCue]) -> Result<()> {
+    for (index, pair) in cues.windows(2).enumerate() {
+        if pair[1].start_time < pair[0].start_time {
+            anyhow::bail!(
+                "Subtitle cue {} starts at {} but the cue before it starts at {}; cues must be in time order",
+                index + 2,
+                srt::format_clock(pair[1].start_time, ','),
+                srt::format_clock(pair[0].start_time, ',')
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cue(start_time: f32, end_time: f32) -> SubtitleCue {
+        SubtitleCue {
+            text: "cue".to_string(),
+            start_time,
+            end_time,
+            words: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn accepts_overlapping_cues_but_not_out_of_order_ones() {
+        assert!(check_order(&[cue(0.0, 2.0), cue(1.5, 3.0), cue(1.5, 4.0)]).is_ok());
+        assert!(check_order(&[cue(0.0, 2.0), cue(3.0, 4.0), cue(2.5, 5.0)]).is_err());
+    }
+
+    #[test]
+    fn clipping_rebases_cues_to_the_clip_start() {
+        let cues = clip_cues(vec![cue(0.0, 2.0), cue(9.0, 11.0), cue(12.0, 14.0), cue(19.0, 21.0)], 10.0, Some(20.0));
+        let times: Vec<_> = cues.iter().map(|cue| (cue.start_time, cue.end_time)).collect();
+        assert_eq!(times, [(0.0, 1.0), (2.0, 4.0), (9.0, 10.0)]);
+        assert_eq!(clip_cues(vec![cue(30.0, 31.0)], 10.0, None)[0].start_time, 20.0);
+    }
+
+    #[test]
+    fn rejects_unknown_extensions() {
+        let error = read_subtitles(Path::new("captions.ass")).unwrap_err();
+        assert!(error.to_string().contains(".srt or .vtt"));
+    }
+}
@@ -1,5 +1,36 @@
 pub mod cue;
+pub mod import;
 pub mod model;
 pub mod render;
 pub mod srt;
 pub mod transcribe;
+pub mod vtt;
+
+/// `--subtitle-mode`: where the cues end up in the output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubtitleMode {
+    /// Drawn into the frames.
+    Burn,
+    /// Muxed as a selectable text track that viewers can toggle.
+    Soft,
+    Both,
+}
+
+impl SubtitleMode {
+    pub fn parse(name: &str) -> anyhow::Result<Self> {
+        match name {
+            "burn" => Ok(Self::Burn),
+            "soft" => Ok(Self::Soft),
+            "both" => Ok(Self::Both),
+            other => anyhow::bail!("Unknown subtitle mode '{}'. Valid modes: burn, soft, both", other),
+        }
+    }
+
+    pub fn burns(self) -> bool {
+        matches!(self, Self::Burn | Self::Both)
+    }
+
+    pub fn muxes(self) -> bool {
+        matches!(self, Self::Soft | Self::Both)
+    }
+}
@@ -1,6 +1,8 @@
 use anyhow::{Context, Result};
 use std::path::{Path, PathBuf};
 
+use crate::report::sha256_file;
+
 /// Known Whisper model sizes and their HuggingFace filenames.
 const KNOWN_MODELS: &[(&str, &str)] = &[
     ("tiny", "ggml-tiny.bin"),
@@ -14,12 +16,18 @@ const KNOWN_MODELS: &[(&str, &str)] = &[
     ("large", "ggml-large-v3-turbo.bin"),
 ];
 
+/// Environment variable naming a directory of pre-downloaded ggml models.
+/// When set, known model names resolve there and nothing is fetched.
+pub const MODEL_DIR_ENV: &str = "SONICA_MODEL_DIR";
+
 /// Resolve a model input string to an actual file path.
 ///
 /// - If `input` is an existing file path, return it directly.
-/// - If `input` is a known model name (tiny/base/small/medium/large),
-///   check the cache directory and download from HuggingFace if missing.
-pub fn resolve_model_path(input: &str) -> Result<PathBuf> {
+/// - If `input` is a known model name (tiny/base/small/medium/large) and
+///   `SONICA_MODEL_DIR` is set, look up its filename in that directory.
+/// - Otherwise check the cache directory and download from the HuggingFace
+///   `repo` (`owner/name`) if missing, truncated, or `redownload` is set.
+pub fn resolve_model_path(input: &str, repo: &str, redownload: bool) -> Result<PathBuf> {
     let as_path = Path::new(input);
     if as_path.exists() {
         log::info!("Using Whisper model from path: {}", as_path.display());
@@ -42,28 +50,188 @@ pub fn resolve_model_path(input: &str) -> Result<PathBuf> {
             )
         })?;
 
-    let cache_dir = model_cache_dir()?;
-    let cached_path = cache_dir.join(filename);
-
-    if cached_path.exists() {
+    if let Some(dir) = std::env::var_os(MODEL_DIR_ENV) {
+        let local_path = Path::new(&dir).join(filename);
+        if !local_path.is_file() {
+            anyhow::bail!(
+                "Whisper model '{}' not found at {} ({} is set, so nothing is downloaded). \
+                 Fetch it from https://huggingface.co/{}/resolve/main/{}",
+                model_name,
+                local_path.display(),
+                MODEL_DIR_ENV,
+                repo,
+                filename
+            );
+        }
         log::info!(
-            "Using cached Whisper model '{}': {}",
+            "Using Whisper model '{}' from {}: {}",
             model_name,
-            cached_path.display()
+            MODEL_DIR_ENV,
+            local_path.display()
         );
-        return Ok(cached_path);
+        return Ok(local_path);
+    }
+
+    let repo = HubRepo::parse(repo)?;
+    let cache_dir = model_cache_dir()?;
+    let cached_path = cache_dir.join(filename);
+
+    if cached_path.exists() && !redownload {
+        match cached_model_is_intact(&repo, filename, &cached_path) {
+            Ok(true) => {
+                log::info!(
+                    "Using cached Whisper model '{}': {}",
+                    model_name,
+                    cached_path.display()
+                );
+                return Ok(cached_path);
+            }
+            Ok(false) => {
+                log::warn!(
+                    "Cached Whisper model {} is incomplete or corrupt; downloading it again",
+                    cached_path.display()
+                );
+            }
+            Err(err) => {
+                // Offline with a model from before verification existed: trust it
+                // rather than refusing to run.
+                log::warn!(
+                    "Could not verify cached Whisper model {} ({:#}); using it anyway",
+                    cached_path.display(),
+                    err
+                );
+                return Ok(cached_path);
+            }
+        }
     }
 
     log::info!(
-        "Downloading Whisper model '{}' from HuggingFace...",
-        model_name
+        "Downloading Whisper model '{}' from HuggingFace ({}/{})...",
+        model_name,
+        repo.owner,
+        repo.name
     );
-    download_model(filename, &cached_path)?;
+    download_model(&repo, filename, &cached_path)?;
     log::info!("Model saved to {}", cached_path.display());
 
     Ok(cached_path)
 }
 
+/// A HuggingFace model repository id, split into owner and name.
+#[derive(Debug, PartialEq)]
+struct HubRepo<'a> {
+    owner: &'a str,
+    name: &'a str,
+}
+
+impl<'a> HubRepo<'a> {
+    fn parse(repo: &'a str) -> Result<Self> {
+        match repo.split_once('/') {
+            Some((owner, name)) if !owner.is_empty() && !name.is_empty() && !name.contains('/') => {
+                Ok(Self { owner, name })
+            }
+            _ => anyhow::bail!("Invalid model repo '{}'. Expected 'owner/name'", repo),
+        }
+    }
+}
+
+/// Size and SHA-256 of a model file, as published by the Hub and as recorded
+/// in the `<model>.verified` sidecar once a download has been checked.
+#[derive(Debug, PartialEq)]
+struct ModelDigest {
+    sha256: String,
+    size: u64,
+}
+
+impl ModelDigest {
+    fn parse(sidecar: &str) -> Option<Self> {
+        let (sha256, size) = sidecar.trim().split_once(' ')?;
+        Some(Self {
+            sha256: sha256.to_string(),
+            size: size.trim().parse().ok()?,
+        })
+    }
+
+    fn format(&self) -> String {
+        format!("{} {}\n", self.sha256, self.size)
+    }
+}
+
+fn sidecar_path(model_path: &Path) -> PathBuf {
+    let mut name = model_path.as_os_str().to_owned();
+    name.push(".verified");
+    PathBuf::from(name)
+}
+
+/// A cached model with a sidecar only needs its size compared, which is instant
+/// and works offline. Without one (a download from an older sonica, or one that
+/// was interrupted) the file is hashed once against the Hub's metadata.
+fn cached_model_is_intact(repo: &HubRepo, filename: &str, path: &Path) -> Result<bool> {
+    let size = std::fs::metadata(path)?.len();
+    if let Some(recorded) = std::fs::read_to_string(sidecar_path(path))
+        .ok()
+        .as_deref()
+        .and_then(ModelDigest::parse)
+    {
+        return Ok(recorded.size == size);
+    }
+
+    let expected = remote_digest(repo, filename)?;
+    if expected.size != size {
+        return Ok(false);
+    }
+    log::info!("Verifying cached Whisper model {}...", path.display());
+    if sha256_file(path)? != expected.sha256 {
+        return Ok(false);
+    }
+    write_sidecar(path, &expected);
+    Ok(true)
+}
+
+fn write_sidecar(path: &Path, digest: &ModelDigest) {
+    let sidecar = sidecar_path(path);
+    if let Err(err) = std::fs::write(&sidecar, digest.format()) {
+        log::warn!(
+            "Failed to record model checksum at {}: {}",
+            sidecar.display(),
+            err
+        );
+    }
+}
+
+fn remote_digest(repo: &HubRepo, filename: &str) -> Result<ModelDigest> {
+    use hf_hub::repository::RepoTreeEntry;
+    use hf_hub::HFClientSync;
+
+    let client = HFClientSync::new().context("Failed to initialize HuggingFace Hub client")?;
+    let entries = client
+        .model(repo.owner, repo.name)
+        .get_paths_info()
+        .paths(vec![filename.to_string()])
+        .send()
+        .with_context(|| {
+            format!(
+                "Failed to fetch metadata for '{}' from HuggingFace",
+                filename
+            )
+        })?;
+
+    entries
+        .into_iter()
+        .find_map(|entry| match entry {
+            RepoTreeEntry::File {
+                path,
+                lfs: Some(lfs),
+                ..
+            } if path == filename => Some(ModelDigest {
+                sha256: lfs.sha256?,
+                size: lfs.size?,
+            }),
+            _ => None,
+        })
+        .with_context(|| format!("HuggingFace did not publish a checksum for '{}'", filename))
+}
+
 fn model_cache_dir() -> Result<PathBuf> {
     let base = dirs::cache_dir()
         .or_else(dirs::home_dir)
@@ -74,27 +242,136 @@ fn model_cache_dir() -> Result<PathBuf> {
     Ok(dir)
 }
 
-fn download_model(filename: &str, dest: &Path) -> Result<()> {
+fn download_model(repo: &HubRepo, filename: &str, dest: &Path) -> Result<()> {
     use hf_hub::HFClientSync;
 
+    let expected = remote_digest(repo, filename)?;
     let client = HFClientSync::new().context("Failed to initialize HuggingFace Hub client")?;
-    let repo = client.model("ggerganov", "whisper.cpp");
-    let downloaded = repo
-        .download_file()
-        .filename(filename)
-        .send()
-        .with_context(|| format!("Failed to download model file '{}' from HuggingFace", filename))?;
+    let hub_repo = client.model(repo.owner, repo.name);
 
-    // hf-hub downloads to its own cache; copy to our cache location
-    if downloaded != dest {
-        std::fs::copy(&downloaded, dest).with_context(|| {
-            format!(
-                "Failed to copy model from {} to {}",
-                downloaded.display(),
-                dest.display()
-            )
-        })?;
+    // hf-hub keeps its own cache, which may hold the same truncated file that
+    // sent us here, so a failed check retries once with a forced download.
+    for attempt in 0..2 {
+        let progress = DownloadProgress::new(expected.size);
+        let downloaded = hub_repo
+            .download_file()
+            .filename(filename)
+            .force_download(attempt > 0)
+            .progress(progress.clone())
+            .send()
+            .with_context(|| {
+                format!(
+                    "Failed to download model file '{}' from HuggingFace",
+                    filename
+                )
+            })?;
+        progress.finish();
+
+        // hf-hub downloads to its own cache; copy to our cache location
+        if downloaded != dest {
+            std::fs::copy(&downloaded, dest).with_context(|| {
+                format!(
+                    "Failed to copy model from {} to {}",
+                    downloaded.display(),
+                    dest.display()
+                )
+            })?;
+        }
+
+        let size = std::fs::metadata(dest)?.len();
+        if size == expected.size && sha256_file(dest)? == expected.sha256 {
+            write_sidecar(dest, &expected);
+            return Ok(());
+        }
+        log::warn!(
+            "Downloaded model {} failed its integrity check ({} of {} bytes)",
+            filename,
+            size,
+            expected.size
+        );
     }
 
-    Ok(())
+    let _ = std::fs::remove_file(dest);
+    anyhow::bail!(
+        "Downloaded Whisper model '{}' is corrupt after retrying. Check your connection and run again with --redownload-model",
+        filename
+    )
+}
+
+/// Terminal progress bar fed by hf-hub's download events.
+#[derive(Clone)]
+struct DownloadProgress(indicatif::ProgressBar);
+
+impl DownloadProgress {
+    fn new(total_bytes: u64) -> Self {
+        let bar = indicatif::ProgressBar::new(total_bytes);
+        bar.set_style(
+            indicatif::ProgressStyle::default_bar()
+                .template("[{elapsed_precise}] {bar:40.cyan/blue} {bytes}/{total_bytes} ({eta} remaining)")
+                .unwrap()
+                .progress_chars("=>-"),
+        );
+        if !log::log_enabled!(log::Level::Info) {
+            bar.set_draw_target(indicatif::ProgressDrawTarget::hidden());
+        }
+        Self(bar)
+    }
+
+    fn finish(&self) {
+        self.0.finish_and_clear();
+    }
+}
+
+impl hf_hub::progress::ProgressHandler for DownloadProgress {
+    fn on_progress(&self, event: &hf_hub::progress::ProgressEvent) {
+        use hf_hub::progress::{DownloadEvent, ProgressEvent};
+
+        match event {
+            ProgressEvent::Download(DownloadEvent::Progress { files }) => {
+                for file in files {
+                    self.0.set_position(file.bytes_completed);
+                }
+            }
+            ProgressEvent::Download(DownloadEvent::AggregateProgress {
+                bytes_completed, ..
+            }) => {
+                self.0.set_position(*bytes_completed);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sidecar_round_trips() {
+        let digest = ModelDigest {
+            sha256: "ab12".to_string(),
+            size: 147_951_465,
+        };
+
+        assert_eq!(ModelDigest::parse(&digest.format()), Some(digest));
+        assert_eq!(ModelDigest::parse("garbage"), None);
+        assert_eq!(
+            sidecar_path(Path::new("/cache/ggml-base.bin")),
+            Path::new("/cache/ggml-base.bin.verified")
+        );
+    }
+
+    #[test]
+    fn parses_hub_repo_ids() {
+        assert_eq!(
+            HubRepo::parse("ggerganov/whisper.cpp").unwrap(),
+            HubRepo {
+                owner: "ggerganov",
+                name: "whisper.cpp"
+            }
+        );
+        assert!(HubRepo::parse("whisper.cpp").is_err());
+        assert!(HubRepo::parse("/whisper.cpp").is_err());
+        assert!(HubRepo::parse("a/b/c").is_err());
+    }
 }
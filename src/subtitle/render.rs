@@ -1,7 +1,33 @@
 use super::cue::{character_count, SubtitleCue};
-use crate::render::text::TextOverlay;
+use crate::render::text::{parse_color, TextOverlay};
 use anyhow::{Context, Result};
 
+/// How the karaoke highlight reveals the word being spoken.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum KaraokeStyle {
+    /// Left-to-right wipe across the word.
+    #[default]
+    Sweep,
+    /// The word lights up at once with a brief lift and alpha bump.
+    Pop,
+    /// Bottom-up fill across the word's height.
+    Fill,
+}
+
+impl KaraokeStyle {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value {
+            "sweep" => Ok(Self::Sweep),
+            "pop" => Ok(Self::Pop),
+            "fill" => Ok(Self::Fill),
+            other => anyhow::bail!("Unknown karaoke style '{other}'. Valid styles: sweep, pop, fill"),
+        }
+    }
+}
+
+/// How long the pop animation lasts after a word starts, in seconds.
+const POP_DURATION: f32 = 0.18;
+
 #[derive(Clone, Debug)]
 pub struct SubtitleStyle {
     background_color: [u8; 4],
@@ -12,6 +38,7 @@ pub struct SubtitleStyle {
     outline_width: u32,
     margin_bottom: f32,
     karaoke: bool,
+    karaoke_style: KaraokeStyle,
 }
 
 impl SubtitleStyle {
@@ -25,6 +52,7 @@ impl SubtitleStyle {
         outline_width: u32,
         margin_bottom: f32,
         karaoke: bool,
+        karaoke_style: &str,
     ) -> Result<Self> {
         validate_fraction("subtitle background opacity", background_opacity, 1.0)?;
         validate_fraction("subtitle dim opacity", dim_opacity, 1.0)?;
@@ -46,13 +74,14 @@ impl SubtitleStyle {
             outline_width,
             margin_bottom,
             karaoke,
+            karaoke_style: KaraokeStyle::parse(karaoke_style)?,
         })
     }
 }
 
 impl Default for SubtitleStyle {
     fn default() -> Self {
-        Self::from_options(0.55, 0.75, "#FFFFFF", "#FFFFFF", "#000000", 2, 0.08, true)
+        Self::from_options(0.55, 0.75, "#FFFFFF", "#FFFFFF", "#000000", 2, 0.08, true, "sweep")
             .expect("default subtitle style is valid")
     }
 }
@@ -61,6 +90,7 @@ pub struct SubtitleRenderer {
     cues: Vec<SubtitleCue>,
     overlay: TextOverlay,
     max_chars_per_line: usize,
+    max_lines: usize,
     style: SubtitleStyle,
 }
 
@@ -69,12 +99,14 @@ impl SubtitleRenderer {
         cues: Vec<SubtitleCue>,
         overlay: TextOverlay,
         max_chars_per_line: usize,
+        max_lines: usize,
         style: SubtitleStyle,
     ) -> Self {
         Self {
             cues,
             overlay,
             max_chars_per_line,
+            max_lines,
             style,
         }
     }
@@ -104,7 +136,8 @@ impl SubtitleRenderer {
         self.render_karaoke(pixels, width, height, cue, time);
     }
 
-    /// Karaoke-style rendering: dim base layer + bright overlay for spoken words.
+    /// Karaoke-style rendering: spoken words bright, the rest dim, composited
+    /// over the background box in a single pass.
     fn render_karaoke(
         &self,
         pixels: &mut [u8],
@@ -116,8 +149,11 @@ impl SubtitleRenderer {
         let font_size = self.overlay.font_size() as u32;
         let line_spacing = (font_size as f32 * 0.2) as u32;
 
-        // Split cue words into lines by max_chars
-        let lines = self.split_words_into_lines(cue);
+        // Split cue words into lines by max_chars. Grouping already keeps cues
+        // within max_lines; the cap guards hand-made SRT cues so the box
+        // never grows off-screen.
+        let mut lines = self.split_words_into_lines(cue);
+        lines.truncate(self.max_lines);
 
         let total_text_height = lines.len() as u32 * font_size
             + (lines.len().saturating_sub(1)) as u32 * line_spacing;
@@ -153,47 +189,29 @@ impl SubtitleRenderer {
             bg_h,
             self.style.background_color,
         );
-        let text_y = bg_y + pad_top;
 
+        // Build the text in a transparent layer the size of the box, each
+        // glyph pixel drawn once in either the bright or the dim color, then
+        // blend the layer over the box in one step. Overdrawing a dim line
+        // with bright words on the frame blends the edges twice and muddies
+        // them against the box.
+        let mut layer = vec![0u8; (bg_w * bg_h * 4) as usize];
         for (i, words) in lines.iter().enumerate() {
             let line_text: String = words.iter().map(|w| w.text.as_str()).collect::<Vec<_>>().join(" ");
             let tw = self.overlay.measure_width(&line_text);
             let line_x = if tw < width { (width - tw) / 2 } else { 0 };
-            let y = text_y + i as u32 * (font_size + line_spacing);
-
-            // Pass 1: Draw entire line in dim color
-            self.overlay.composite_outlined(
-                pixels,
-                width,
-                height,
-                &line_text,
-                line_x,
-                y,
-                self.style.dim_color,
-                self.style.outline_color,
-                self.style.outline_width,
-            );
-
-            // Pass 2: Overdraw spoken portion in bright color
-            self.render_karaoke_highlight(
-                pixels,
-                width,
-                height,
-                words,
-                &line_text,
-                line_x,
-                y,
-                time,
-                self.style.highlight_color,
-            );
+            let x = line_x.saturating_sub(bg_x);
+            let y = pad_top + i as u32 * (font_size + line_spacing);
+            self.render_karaoke_line(&mut layer, bg_w, bg_h, words, &line_text, x, y, time);
         }
+        TextOverlay::composite_layer(pixels, width, height, &layer, bg_x, bg_y, bg_w, bg_h);
     }
 
-    /// Render the bright highlight over spoken words in a single line.
+    /// Draw one line into the karaoke layer: spoken text bright, the rest dim.
     #[allow(clippy::too_many_arguments)]
-    fn render_karaoke_highlight(
+    fn render_karaoke_line(
         &self,
-        pixels: &mut [u8],
+        layer: &mut [u8],
         width: u32,
         height: u32,
         words: &[super::transcribe::TimedWord],
@@ -201,7 +219,6 @@ impl SubtitleRenderer {
         line_x: u32,
         y: u32,
         time: f32,
-        bright_color: [u8; 4],
     ) {
         // Calculate per-word x positions within the line
         let mut word_x_positions: Vec<u32> = Vec::with_capacity(words.len());
@@ -211,30 +228,39 @@ impl SubtitleRenderer {
             // Measure x offset of this word within line_text
             let prefix = &line_text[..cursor];
             let x_offset = self.overlay.measure_width(prefix);
-            word_x_positions.push(x_offset);
+            word_x_positions.push(line_x + x_offset);
             cursor += word.text.len();
             if wi + 1 < words.len() {
                 cursor += 1; // space between words
             }
         }
 
+        // The highlight always covers a prefix of the line: everything left
+        // of `lit_end`, plus the rising part of a word being filled.
+        let mut lit_end = line_x;
+        let mut fill: Option<(u32, u32, u32)> = None;
+        let mut pop: Option<(usize, f32)> = None;
+
         for (wi, word) in words.iter().enumerate() {
             if time < word.start_time {
                 // This word hasn't started yet — stop highlighting
                 break;
             }
 
-            let word_x = line_x + word_x_positions[wi];
+            let word_x = word_x_positions[wi];
             let word_width = self.overlay.measure_width(&word.text);
 
             if time >= word.end_time {
-                // Word fully spoken — render entirely in bright
-                self.overlay.composite(pixels, width, height, &word.text, word_x, y, bright_color);
-
-                // Also highlight the trailing space if not the last word
+                // Word fully spoken
                 if wi + 1 < words.len() {
+                    // The trailing space lasts until the next word starts, so the
+                    // highlight eases across it rather than jumping on silence
                     let space_x = word_x + word_width;
-                    self.overlay.composite(pixels, width, height, " ", space_x, y, bright_color);
+                    let next_x = word_x_positions[wi + 1].max(space_x);
+                    let progress = gap_progress(word.end_time, words[wi + 1].start_time, time);
+                    lit_end = space_x + ((next_x - space_x) as f32 * progress).round() as u32;
+                } else {
+                    lit_end = u32::MAX;
                 }
             } else {
                 // Word is currently being spoken — partial highlight
@@ -245,16 +271,56 @@ impl SubtitleRenderer {
                     1.0
                 };
 
-                let clip_x = word_x + (word_width as f32 * progress).round() as u32;
-                self.overlay.composite_clipped(
-                    pixels, width, height,
-                    &word.text, word_x, y,
-                    bright_color, clip_x,
-                );
+                lit_end = word_x;
+                match self.style.karaoke_style {
+                    KaraokeStyle::Sweep => {
+                        lit_end = word_x + (word_width as f32 * progress).round() as u32;
+                    }
+                    KaraokeStyle::Fill => {
+                        let line_height = self.overlay.line_height();
+                        let min_y = y + (line_height as f32 * (1.0 - progress)).round() as u32;
+                        fill = Some((word_x, word_x + word_width, min_y));
+                    }
+                    KaraokeStyle::Pop => pop = Some((wi, pop_envelope(time - word.start_time))),
+                }
                 // Current word is partially done — no more words to highlight
                 break;
             }
         }
+
+        let (bright, dim) = (self.style.highlight_color, self.style.dim_color);
+        let lit = |px: u32, py: u32| {
+            px < lit_end || fill.is_some_and(|(x0, x1, min_y)| (x0..x1).contains(&px) && py >= min_y)
+        };
+        self.overlay.composite_outlined_shaded(
+            layer,
+            width,
+            height,
+            line_text,
+            line_x,
+            y,
+            |px, py| if lit(px, py) { bright } else { dim },
+            self.style.outline_color,
+            self.style.outline_width,
+        );
+
+        if let Some((wi, pop)) = pop {
+            let lift = (self.overlay.font_size() * 0.12 * pop).round() as u32;
+            let mut color = bright;
+            color[3] = (color[3] as f32 + (255.0 - color[3] as f32) * pop) as u8;
+            // Outlined so the lifted word masks the dim copy beneath it
+            self.overlay.composite_outlined(
+                layer,
+                width,
+                height,
+                &words[wi].text,
+                word_x_positions[wi],
+                y.saturating_sub(lift),
+                color,
+                self.style.outline_color,
+                self.style.outline_width,
+            );
+        }
     }
 
     /// Split cue words into lines respecting max_chars_per_line.
@@ -299,7 +365,8 @@ impl SubtitleRenderer {
         height: u32,
         cue: &SubtitleCue,
     ) {
-        let lines = wrap_text(&cue.text, self.max_chars_per_line);
+        let mut lines = wrap_text(&cue.text, self.max_chars_per_line);
+        lines.truncate(self.max_lines);
 
         let font_size = self.overlay.font_size() as u32;
         let line_spacing = (font_size as f32 * 0.2) as u32;
@@ -372,6 +439,25 @@ impl SubtitleRenderer {
     }
 }
 
+/// Eased progress through the silence between a word ending at `gap_start`
+/// and the next one starting at `gap_end`. Overlapping or touching words
+/// have no gap and count as already crossed.
+fn gap_progress(gap_start: f32, gap_end: f32, time: f32) -> f32 {
+    let gap = gap_end - gap_start;
+    if gap <= 0.0 {
+        return 1.0;
+    }
+    let t = ((time - gap_start) / gap).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Strength of the pop animation `elapsed` seconds after a word starts:
+/// 1.0 at onset, easing out to 0.0 by `POP_DURATION`.
+fn pop_envelope(elapsed: f32) -> f32 {
+    let t = (elapsed / POP_DURATION).clamp(0.0, 1.0);
+    (1.0 - t) * (1.0 - t)
+}
+
 fn validate_fraction(name: &str, value: f32, maximum: f32) -> Result<()> {
     if value.is_finite() && (0.0..=maximum).contains(&value) {
         Ok(())
@@ -380,17 +466,14 @@ fn validate_fraction(name: &str, value: f32, maximum: f32) -> Result<()> {
     }
 }
 
+/// `#RRGGBB`: subtitle opacity has its own settings, so an alpha byte is
+/// refused rather than ignored.
 fn parse_rgb(value: &str) -> Result<[u8; 3]> {
-    let value = value.strip_prefix('#').unwrap_or(value);
-    if value.len() != 6 || !value.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+    if value.strip_prefix('#').unwrap_or(value).len() != 6 {
         anyhow::bail!("expected #RRGGBB, got '{value}'");
     }
-
-    Ok([
-        u8::from_str_radix(&value[0..2], 16)?,
-        u8::from_str_radix(&value[2..4], 16)?,
-        u8::from_str_radix(&value[4..6], 16)?,
-    ])
+    let [r, g, b, _] = parse_color(value)?;
+    Ok([r, g, b])
 }
 
 fn with_alpha(color: [u8; 3], opacity: f32) -> [u8; 4] {
@@ -447,6 +530,7 @@ mod tests {
             3,
             0.12,
             false,
+            "pop",
         )
         .unwrap();
 
@@ -456,6 +540,7 @@ mod tests {
         assert_eq!(style.highlight_color, [0, 255, 170, 255]);
         assert_eq!(style.outline_width, 3);
         assert!(!style.karaoke);
+        assert_eq!(style.karaoke_style, KaraokeStyle::Pop);
     }
 
     #[test]
@@ -469,12 +554,33 @@ mod tests {
             2,
             0.08,
             true,
+            "sweep",
         )
         .is_err());
+        assert!(KaraokeStyle::parse("bounce").is_err());
         assert!(parse_rgb("#FFFF").is_err());
+        assert!(parse_rgb("#FFFFFF80").is_err());
         assert!(validate_fraction("margin", 0.6, 0.5).is_err());
     }
 
+    #[test]
+    fn pop_envelope_decays_after_word_onset() {
+        assert_eq!(pop_envelope(0.0), 1.0);
+        assert!(pop_envelope(POP_DURATION * 0.5) < 1.0);
+        assert_eq!(pop_envelope(POP_DURATION), 0.0);
+        assert_eq!(pop_envelope(1.0), 0.0);
+    }
+
+    #[test]
+    fn gap_progress_eases_across_silence() {
+        assert_eq!(gap_progress(1.0, 2.0, 1.0), 0.0);
+        assert_eq!(gap_progress(1.0, 2.0, 1.5), 0.5);
+        assert!(gap_progress(1.0, 2.0, 1.1) < 0.1);
+        assert_eq!(gap_progress(1.0, 2.0, 3.0), 1.0);
+        assert_eq!(gap_progress(1.0, 1.0, 1.0), 1.0);
+        assert_eq!(gap_progress(1.0, 0.9, 1.0), 1.0);
+    }
+
     #[test]
     fn wrap_short_text() {
         let lines = wrap_text("Hello world", 42);
@@ -514,6 +620,7 @@ mod tests {
             text: text.to_string(),
             start_time: start,
             end_time: end,
+            joins_previous: false,
         }
     }
 
@@ -525,7 +632,7 @@ mod tests {
         ];
 
         let overlay = TextOverlay::new(24.0, None, None, None);
-        let renderer = SubtitleRenderer::new(cues, overlay, 42, SubtitleStyle::default());
+        let renderer = SubtitleRenderer::new(cues, overlay, 42, 2, SubtitleStyle::default());
 
         assert!(renderer.find_active_cue(0.5).is_none());
         assert_eq!(renderer.find_active_cue(2.0).unwrap().text, "Hello");
@@ -534,6 +641,26 @@ mod tests {
         assert!(renderer.find_active_cue(7.0).is_none());
     }
 
+    #[test]
+    fn spoken_karaoke_line_matches_plain_text() {
+        // Once every word is spoken the dim pass must not show through: the
+        // karaoke line should look like the plain line in the bright color.
+        let cue = make_cue("Hi there", 0.0, 2.0, vec![tw("Hi", 0.0, 0.5), tw("there", 0.6, 1.0)]);
+        let style = SubtitleStyle::from_options(0.55, 0.4, "#FFFFFF", "#FFFFFF", "#000000", 2, 0.08, true, "sweep").unwrap();
+        let renderer = SubtitleRenderer::new(vec![], TextOverlay::new(24.0, None, None, None), 42, 2, style);
+
+        let (width, height) = (200, 80);
+        let frame: Vec<u8> = [30u8, 90, 160, 255].repeat((width * height) as usize);
+        let mut plain = frame.clone();
+        renderer.render_plain(&mut plain, width, height, &cue);
+        let mut karaoke = frame.clone();
+        renderer.render_karaoke(&mut karaoke, width, height, &cue, 1.5);
+
+        assert_ne!(karaoke, frame);
+        let max_diff = plain.iter().zip(&karaoke).map(|(a, b)| a.abs_diff(*b)).max().unwrap();
+        assert!(max_diff <= 2, "max channel difference {max_diff}");
+    }
+
     #[test]
     fn split_words_into_lines_respects_max_chars() {
         let cue = make_cue(
@@ -549,7 +676,7 @@ mod tests {
         );
 
         let overlay = TextOverlay::new(24.0, None, None, None);
-        let renderer = SubtitleRenderer::new(vec![], overlay, 12, SubtitleStyle::default());
+        let renderer = SubtitleRenderer::new(vec![], overlay, 12, 2, SubtitleStyle::default());
 
         let lines = renderer.split_words_into_lines(&cue);
         assert!(lines.len() >= 2);
@@ -573,7 +700,7 @@ mod tests {
             ],
         );
         let overlay = TextOverlay::new(24.0, None, None, None);
-        let renderer = SubtitleRenderer::new(vec![], overlay, 13, SubtitleStyle::default());
+        let renderer = SubtitleRenderer::new(vec![], overlay, 13, 2, SubtitleStyle::default());
 
         let lines = renderer.split_words_into_lines(&cue);
 
@@ -63,8 +63,6 @@ fn parse_srt(content: &str) -> Result<Vec<SubtitleCue>> {
     if cues.is_empty() {
         anyhow::bail!("Subtitle file contains no cues");
     }
-
-    cues.sort_by(|a, b| a.start_time.total_cmp(&b.start_time));
     Ok(cues)
 }
 
@@ -73,9 +71,9 @@ fn format_srt(cues: &[SubtitleCue]) -> String {
     for (index, cue) in cues.iter().enumerate() {
         output.push_str(&(index + 1).to_string());
         output.push('\n');
-        output.push_str(&format_timestamp(cue.start_time));
+        output.push_str(&format_clock(cue.start_time, ','));
         output.push_str(" --> ");
-        output.push_str(&format_timestamp(cue.end_time));
+        output.push_str(&format_clock(cue.end_time, ','));
         output.push('\n');
         output.push_str(&cue.text);
         output.push_str("\n\n");
@@ -104,7 +102,9 @@ fn parse_timestamp(value: &str) -> Result<f32> {
     Ok((hours * 3600 + minutes * 60 + seconds) as f32 + millis as f32 / 1000.0)
 }
 
-fn format_timestamp(seconds: f32) -> String {
+/// `HH:MM:SS<decimal>mmm`: SRT separates milliseconds with a comma, WebVTT
+/// with a period.
+pub(super) fn format_clock(seconds: f32, decimal: char) -> String {
     let total_millis = (seconds.max(0.0) as f64 * 1000.0).round() as u64;
     let millis = total_millis % 1000;
     let total_seconds = total_millis / 1000;
@@ -112,7 +112,7 @@ fn format_timestamp(seconds: f32) -> String {
     let total_minutes = total_seconds / 60;
     let minutes = total_minutes % 60;
     let hours = total_minutes / 60;
-    format!("{hours:02}:{minutes:02}:{seconds:02},{millis:03}")
+    format!("{hours:02}:{minutes:02}:{seconds:02}{decimal}{millis:03}")
 }
 
 #[cfg(test)]
@@ -9,6 +9,9 @@ pub struct TimedWord {
     pub text: String,
     pub start_time: f32,
     pub end_time: f32,
+    /// A later piece of a word too long for one line, so written text joins
+    /// it to the previous piece without a space.
+    pub joins_previous: bool,
 }
 
 pub struct WhisperTranscriber {
@@ -45,7 +48,7 @@ impl WhisperTranscriber {
         sample_rate: u32,
     ) -> Result<Vec<TimedWord>> {
         let samples_16k = if sample_rate != 16000 {
-            resample_to_16k(samples, sample_rate)?
+            crate::audio::resample::resample(samples, sample_rate, 16000)?
         } else {
             samples.to_vec()
         };
@@ -156,41 +159,6 @@ fn flush_word(bytes: &[u8], t0: i64, t1: i64) -> Option<TimedWord> {
         text: trimmed.to_string(),
         start_time: t0 as f32 / 100.0,
         end_time: t1 as f32 / 100.0,
+        joins_previous: false,
     })
 }
-
-/// Resample mono f32 audio from `from_rate` to 16000 Hz using rubato.
-fn resample_to_16k(samples: &[f32], from_rate: u32) -> Result<Vec<f32>> {
-    use rubato::audioadapter_buffers::direct::SequentialSlice;
-    use rubato::{
-        Async, FixedAsync, Resampler, SincInterpolationParameters, SincInterpolationType,
-        WindowFunction,
-    };
-
-    let params = SincInterpolationParameters {
-        sinc_len: 256,
-        f_cutoff: None, // automatic cutoff for the chosen sinc length and window
-        interpolation: SincInterpolationType::Linear,
-        oversampling_factor: 256,
-        window: WindowFunction::BlackmanHarris2,
-    };
-
-    let ratio = 16000.0 / from_rate as f64;
-    let mut resampler = Async::<f32>::new_sinc(
-        ratio,
-        2.0, // max relative ratio
-        &params,
-        1024, // chunk size in frames
-        1,    // mono
-        FixedAsync::Input,
-    )
-    .context("Failed to create resampler")?;
-
-    let input = SequentialSlice::new(samples, 1, samples.len())
-        .map_err(|e| anyhow::anyhow!("Failed to wrap input samples: {e}"))?;
-    let output = resampler
-        .process_all(&input, samples.len(), None)
-        .context("Resampling failed")?;
-
-    Ok(output.take_data())
-}
@@ -0,0 +1,239 @@
+use super::cue::SubtitleCue;
+use super::srt::format_clock;
+use anyhow::{Context, Result};
+use std::path::Path;
+
+pub fn read_vtt(path: &Path) -> Result<Vec<SubtitleCue>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read subtitle file: {}", path.display()))?;
+    parse_vtt(&content)
+        .with_context(|| format!("Failed to parse subtitle file: {}", path.display()))
+}
+
+/// Write cues as WebVTT. With `word_timestamps`, each word after the first
+/// is prefixed with a `<HH:MM:SS.mmm>` tag so karaoke-capable players can
+/// highlight words as they are spoken.
+pub fn write_vtt(path: &Path, cues: &[SubtitleCue], word_timestamps: bool) -> Result<()> {
+    let content = format_vtt(cues, word_timestamps);
+    std::fs::write(path, content)
+        .with_context(|| format!("Failed to write subtitle file: {}", path.display()))
+}
+
+fn format_vtt(cues: &[SubtitleCue], word_timestamps: bool) -> String {
+    let mut output = String::from("WEBVTT\n\n");
+    for cue in cues {
+        output.push_str(&format_clock(cue.start_time, '.'));
+        output.push_str(" --> ");
+        output.push_str(&format_clock(cue.end_time, '.'));
+        output.push('\n');
+        if word_timestamps && !cue.words.is_empty() {
+            for (i, word) in cue.words.iter().enumerate() {
+                if i > 0 {
+                    if !word.joins_previous {
+                        output.push(' ');
+                    }
+                    output.push('<');
+                    output.push_str(&format_clock(word.start_time, '.'));
+                    output.push('>');
+                }
+                output.push_str(&escape(&word.text));
+            }
+        } else {
+            output.push_str(&escape(&cue.text));
+        }
+        output.push_str("\n\n");
+    }
+    output
+}
+
+/// Parse WebVTT cues. Cue settings, NOTE/STYLE/REGION blocks, and markup
+/// (including karaoke word timestamps) are dropped; only each cue's timing
+/// and plain text are kept.
+pub(super) fn parse_vtt(content: &str) -> Result<Vec<SubtitleCue>> {
+    let normalized = content.trim_start_matches('\u{feff}').replace("\r\n", "\n");
+    let mut blocks = normalized.split("\n\n");
+    let header = blocks.next().unwrap_or_default();
+    if !header.starts_with("WEBVTT") {
+        anyhow::bail!("WebVTT file must start with a WEBVTT header");
+    }
+
+    let mut cues = Vec::new();
+    for block in blocks {
+        let lines = block
+            .lines()
+            .map(str::trim_end)
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>();
+        let Some(first) = lines.first() else {
+            continue;
+        };
+        if ["NOTE", "STYLE", "REGION"]
+            .iter()
+            .any(|keyword| first.split_whitespace().next() == Some(keyword))
+        {
+            continue;
+        }
+
+        let timing_index = lines
+            .iter()
+            .position(|line| line.contains("-->"))
+            .context("Subtitle cue is missing a timestamp line")?;
+        let timing = lines[timing_index];
+        let (start, end) = timing
+            .split_once("-->")
+            .context("Invalid WebVTT timestamp separator")?;
+        let start_time = parse_timestamp(start.trim())?;
+        let end_token = end
+            .split_whitespace()
+            .next()
+            .context("Subtitle cue is missing an end timestamp")?;
+        let end_time = parse_timestamp(end_token)?;
+        if end_time < start_time {
+            anyhow::bail!("Subtitle cue ends before it starts: {timing}");
+        }
+
+        let text = lines[timing_index + 1..]
+            .iter()
+            .map(|line| unescape(&strip_tags(line)))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if text.is_empty() {
+            anyhow::bail!("Subtitle cue at {timing} has no text");
+        }
+
+        cues.push(SubtitleCue {
+            text,
+            start_time,
+            end_time,
+            words: Vec::new(),
+        });
+    }
+
+    if cues.is_empty() {
+        anyhow::bail!("Subtitle file contains no cues");
+    }
+    Ok(cues)
+}
+
+/// `[HH:]MM:SS.mmm`: unlike SRT, WebVTT may leave out the hours.
+fn parse_timestamp(value: &str) -> Result<f32> {
+    let (clock, millis) = value
+        .split_once('.')
+        .context("WebVTT timestamp must include milliseconds")?;
+    let parts = clock.split(':').collect::<Vec<_>>();
+    let (hours, minutes, seconds) = match parts.as_slice() {
+        [hours, minutes, seconds] => (hours.parse::<u64>()?, minutes.parse::<u64>()?, seconds.parse::<u64>()?),
+        [minutes, seconds] => (0, minutes.parse::<u64>()?, seconds.parse::<u64>()?),
+        _ => anyhow::bail!("Invalid WebVTT timestamp: {value}"),
+    };
+    let millis: u64 = millis.parse()?;
+    if minutes >= 60 || seconds >= 60 || millis >= 1000 {
+        anyhow::bail!("Invalid WebVTT timestamp: {value}");
+    }
+
+    Ok((hours * 3600 + minutes * 60 + seconds) as f32 + millis as f32 / 1000.0)
+}
+
+/// Remove `<...>` markup: voice and style spans, and word timestamps.
+fn strip_tags(line: &str) -> String {
+    let mut text = String::with_capacity(line.len());
+    let mut in_tag = false;
+    for ch in line.chars() {
+        match ch {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => text.push(ch),
+            _ => {}
+        }
+    }
+    text
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+/// Escape the characters WebVTT cue text reserves for markup. A bare `-->`
+/// cannot appear either, and is broken up by escaping its `>`.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::subtitle::transcribe::TimedWord;
+
+    fn word(text: &str, start_time: f32, end_time: f32) -> TimedWord {
+        TimedWord {
+            text: text.to_string(),
+            start_time,
+            end_time,
+            joins_previous: false,
+        }
+    }
+
+    fn cues() -> Vec<SubtitleCue> {
+        vec![SubtitleCue {
+            text: "Rock & roll".to_string(),
+            start_time: 61.5,
+            end_time: 63.25,
+            words: vec![
+                word("Rock", 61.5, 62.0),
+                word("&", 62.0, 62.3),
+                word("roll", 62.4, 63.25),
+            ],
+        }]
+    }
+
+    #[test]
+    fn formats_header_and_cues() {
+        let vtt = format_vtt(&cues(), false);
+
+        assert_eq!(
+            vtt,
+            "WEBVTT\n\n00:01:01.500 --> 00:01:03.250\nRock &amp; roll\n\n"
+        );
+    }
+
+    #[test]
+    fn tags_words_with_their_start_times() {
+        let vtt = format_vtt(&cues(), true);
+
+        assert!(vtt.contains("Rock <00:01:02.000>&amp; <00:01:02.400>roll\n"));
+    }
+
+    #[test]
+    fn parses_what_it_writes_without_markup() {
+        let decoded = parse_vtt(&format_vtt(&cues(), true)).unwrap();
+
+        assert_eq!(decoded.len(), 1);
+        assert_eq!(decoded[0].text, "Rock & roll");
+        assert_eq!(decoded[0].start_time, 61.5);
+        assert_eq!(decoded[0].end_time, 63.25);
+        assert!(decoded[0].words.is_empty());
+    }
+
+    #[test]
+    fn skips_notes_settings_and_short_timestamps() {
+        let input = "WEBVTT - captions\r\n\r\nNOTE edited by hand\r\n\r\nintro\r\n00:01.250 --> 00:03.500 align:start line:90%\r\n<v Host>Hello</v>\r\nthere\r\n";
+
+        let cues = parse_vtt(input).unwrap();
+
+        assert_eq!(cues.len(), 1);
+        assert_eq!(cues[0].text, "Hello there");
+        assert_eq!(cues[0].start_time, 1.25);
+        assert_eq!(cues[0].end_time, 3.5);
+    }
+
+    #[test]
+    fn rejects_a_missing_header() {
+        assert!(parse_vtt("00:00:01.000 --> 00:00:02.000\nHello\n").is_err());
+    }
+}
@@ -0,0 +1,151 @@
+use anyhow::{Context, Result};
+use std::fs::{File, OpenOptions};
+use std::hash::{BuildHasher, Hasher};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Paths guarded by live [`TempFile`]s, for [`remove_all`].
+static LIVE: Mutex<Vec<(PathBuf, Match)>> = Mutex::new(Vec::new());
+
+/// Names tried before giving up on finding a free one.
+const ATTEMPTS: u32 = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Match {
+    /// The file at the path.
+    Exact,
+    /// Every file in the path's directory whose name starts with its name,
+    /// for the numbered files FFmpeg writes from a `-passlogfile` prefix.
+    Prefix,
+}
+
+/// A temporary file that is deleted when the guard drops. A second Ctrl-C
+/// exits without dropping anything, so it calls [`remove_all`] first.
+#[derive(Debug)]
+pub struct TempFile {
+    path: PathBuf,
+    matches: Match,
+}
+
+impl TempFile {
+    /// Create `sonica-<kind>-<random><suffix>` in the system temp dir. The
+    /// file is opened with `create_new`, so a file or symlink already at the
+    /// name is never followed or truncated.
+    pub fn create(kind: &str, suffix: &str) -> Result<(Self, File)> {
+        let dir = std::env::temp_dir();
+        for _ in 0..ATTEMPTS {
+            let path = dir.join(format!("sonica-{kind}-{:016x}{suffix}", random()));
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(file) => return Ok((Self::guard(path, Match::Exact), file)),
+                Err(err) if err.kind() == ErrorKind::AlreadyExists => continue,
+                Err(err) => {
+                    return Err(err).with_context(|| format!("Failed to create temporary file {}", path.display()))
+                }
+            }
+        }
+        anyhow::bail!("Failed to find a free temporary file name in {}", dir.display())
+    }
+
+    /// Take over deleting `path`, a file something else (FFmpeg) writes.
+    pub fn claim(path: PathBuf) -> Self {
+        Self::guard(path, Match::Exact)
+    }
+
+    /// Take over deleting every file whose name starts with `prefix`'s.
+    pub fn claim_prefix(prefix: PathBuf) -> Self {
+        Self::guard(prefix, Match::Prefix)
+    }
+
+    fn guard(path: PathBuf, matches: Match) -> Self {
+        lock().push((path.clone(), matches));
+        Self { path, matches }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        remove(&self.path, self.matches);
+        let mut live = lock();
+        if let Some(at) = live.iter().position(|(path, _)| *path == self.path) {
+            live.swap_remove(at);
+        }
+    }
+}
+
+/// Delete the files of every live [`TempFile`], for an exit that skips
+/// their drops.
+pub fn remove_all() {
+    for (path, matches) in lock().drain(..) {
+        remove(&path, matches);
+    }
+}
+
+fn lock() -> std::sync::MutexGuard<'static, Vec<(PathBuf, Match)>> {
+    LIVE.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn remove(path: &Path, matches: Match) {
+    if matches == Match::Exact {
+        let _ = std::fs::remove_file(path);
+        return;
+    }
+    let (Some(dir), Some(prefix)) = (path.parent(), path.file_name()) else {
+        return;
+    };
+    let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+    let prefix = prefix.to_string_lossy();
+    if let Ok(entries) = std::fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            if entry.file_name().to_string_lossy().starts_with(prefix.as_ref()) {
+                let _ = std::fs::remove_file(entry.path());
+            }
+        }
+    }
+}
+
+/// 64 unpredictable bits: `RandomState` is seeded from the OS once per
+/// process and stepped for each new instance.
+fn random() -> u64 {
+    let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+    hasher.write_u32(std::process::id());
+    if let Ok(now) = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH) {
+        hasher.write_u128(now.as_nanos());
+    }
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn creates_unique_files_and_removes_them_on_drop() {
+        let (first, _) = TempFile::create("test", ".txt").unwrap();
+        let (second, _) = TempFile::create("test", ".txt").unwrap();
+        assert_ne!(first.path(), second.path());
+        assert!(first.path().exists() && first.path().extension().is_some_and(|ext| ext == "txt"));
+
+        let path = first.path().to_path_buf();
+        drop(first);
+        assert!(!path.exists());
+        assert!(second.path().exists());
+    }
+
+    #[test]
+    fn prefix_guards_remove_every_numbered_file() {
+        let dir = std::env::temp_dir().join(format!("sonica-temp-test-{:016x}", random()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in ["out.mp4.sonica-2pass-0.log", "out.mp4.sonica-2pass-0.log.mbtree", "out.mp4"] {
+            std::fs::write(dir.join(name), b"").unwrap();
+        }
+        drop(TempFile::claim_prefix(dir.join("out.mp4.sonica-2pass")));
+        let left: Vec<_> = std::fs::read_dir(&dir).unwrap().map(|entry| entry.unwrap().file_name()).collect();
+        assert_eq!(left, ["out.mp4"]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
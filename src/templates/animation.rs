@@ -0,0 +1,259 @@
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+
+use super::manifest::TemplateManifest;
+
+/// Binding of the per-frame `param_anim` uniform block.
+pub const PARAM_ANIM_BINDING: u32 = 3;
+
+/// When a keyframe happens: seconds from the start, or the end of the render.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum KeyTime {
+    Seconds(f32),
+    End,
+}
+
+impl KeyTime {
+    fn resolve(self, duration: f32) -> f32 {
+        match self {
+            Self::Seconds(seconds) => seconds,
+            Self::End => duration,
+        }
+    }
+}
+
+/// A `--param-anim` curve: `name=time:value,time:value,...`, where `time` is
+/// seconds or `end`. Values are interpolated linearly between keyframes and
+/// held before the first and after the last.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParamCurve {
+    pub name: String,
+    keyframes: Vec<(KeyTime, f32)>,
+}
+
+impl ParamCurve {
+    pub fn parse(spec: &str) -> Result<Self> {
+        let (name, keys) = spec
+            .split_once('=')
+            .with_context(|| format!("Invalid --param-anim '{spec}': expected NAME=TIME:VALUE,..."))?;
+
+        let mut keyframes = Vec::new();
+        for key in keys.split(',') {
+            let (time, value) = key
+                .split_once(':')
+                .with_context(|| format!("Invalid keyframe '{key}' in --param-anim '{spec}': expected TIME:VALUE"))?;
+            let time = match time.trim() {
+                "end" => KeyTime::End,
+                seconds => match seconds.parse::<f32>() {
+                    Ok(seconds) if seconds.is_finite() && seconds >= 0.0 => KeyTime::Seconds(seconds),
+                    _ => anyhow::bail!(
+                        "Invalid keyframe time '{seconds}' in --param-anim '{spec}': use seconds or 'end'"
+                    ),
+                },
+            };
+            let value: f32 = value
+                .trim()
+                .parse()
+                .ok()
+                .filter(|value: &f32| value.is_finite())
+                .with_context(|| format!("Invalid keyframe value '{value}' in --param-anim '{spec}'"))?;
+            keyframes.push((time, value));
+        }
+
+        Ok(Self {
+            name: name.trim().to_string(),
+            keyframes,
+        })
+    }
+
+    /// A curve from `(seconds, value)` keyframes.
+    pub fn from_keyframes(name: impl Into<String>, keyframes: &[(f32, f32)]) -> Self {
+        Self {
+            name: name.into(),
+            keyframes: keyframes.iter().map(|&(time, value)| (KeyTime::Seconds(time), value)).collect(),
+        }
+    }
+
+    pub fn value_at(&self, time: f32, duration: f32) -> f32 {
+        let mut keys: Vec<(f32, f32)> = self
+            .keyframes
+            .iter()
+            .map(|&(key_time, value)| (key_time.resolve(duration), value))
+            .collect();
+        keys.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let next = keys.partition_point(|&(key_time, _)| key_time <= time);
+        if next == 0 {
+            return keys[0].1;
+        }
+        if next == keys.len() {
+            return keys[keys.len() - 1].1;
+        }
+        let (t0, v0) = keys[next - 1];
+        let (t1, v1) = keys[next];
+        v0 + (v1 - v0) * (time - t0) / (t1 - t0)
+    }
+}
+
+/// The `param_anim` uniform block for one template: every float parameter,
+/// holding its static value (default or `--param`) unless a curve animates
+/// it. Templates opt in per parameter by reading `param_anim.<name>` instead
+/// of the `PARAM_<NAME>` const.
+pub struct AnimatedParams {
+    names: Vec<String>,
+    statics: Vec<f32>,
+    curves: Vec<Option<ParamCurve>>,
+}
+
+impl AnimatedParams {
+    pub fn new(
+        manifest: &TemplateManifest,
+        overrides: &HashMap<String, String>,
+        curves: &[ParamCurve],
+    ) -> Result<Self> {
+        for curve in curves {
+            match manifest.parameters.get(&curve.name) {
+                Some(def) if def.param_type == "float" => {}
+                Some(def) => anyhow::bail!(
+                    "--param-anim {}: only float parameters can be animated ('{}' is {})",
+                    curve.name,
+                    curve.name,
+                    def.param_type
+                ),
+                None => anyhow::bail!(
+                    "--param-anim {}: template '{}' has no parameter named '{}'",
+                    curve.name,
+                    manifest.name,
+                    curve.name
+                ),
+            }
+        }
+
+        let mut names: Vec<String> = manifest
+            .parameters
+            .iter()
+            .filter(|(_, def)| def.param_type == "float")
+            .map(|(name, _)| name.clone())
+            .collect();
+        names.sort();
+
+        let statics = names
+            .iter()
+            .map(|name| {
+                overrides
+                    .get(name)
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or_else(|| manifest.parameters[name].default.as_f64().unwrap_or(0.0) as f32)
+            })
+            .collect();
+        let curves = names
+            .iter()
+            .map(|name| curves.iter().rev().find(|curve| &curve.name == name).cloned())
+            .collect();
+
+        Ok(Self {
+            names,
+            statics,
+            curves,
+        })
+    }
+
+    /// Names of parameters with a curve that the shader never reads through
+    /// `param_anim`, so the animation would have no visible effect.
+    pub fn unused_curves(&self, shader_src: &str) -> Vec<&str> {
+        self.names
+            .iter()
+            .zip(&self.curves)
+            .filter(|(name, curve)| {
+                curve.is_some() && !shader_src.contains(&format!("param_anim.{name}"))
+            })
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    /// WGSL declaration of the block, prepended to the shader source. Empty
+    /// when the template has no float parameters (WGSL has no empty structs).
+    pub fn wgsl_decl(&self) -> String {
+        if self.names.is_empty() {
+            return String::new();
+        }
+        let mut decl = String::from("// Animated template parameters\nstruct ParamAnim {\n");
+        for name in &self.names {
+            decl.push_str(&format!("    {name}: f32,\n"));
+        }
+        decl.push_str(&format!(
+            "}};\n@group(0) @binding({PARAM_ANIM_BINDING}) var<uniform> param_anim: ParamAnim;\n\n"
+        ));
+        decl
+    }
+
+    /// Uniform buffer size: one f32 per parameter, rounded up to 16 bytes.
+    pub fn buffer_size(&self) -> u64 {
+        (self.names.len().max(1) * 4).next_multiple_of(16) as u64
+    }
+
+    /// Buffer contents at `time`, padded to `buffer_size`.
+    pub fn values_at(&self, time: f32, duration: f32) -> Vec<f32> {
+        let mut values: Vec<f32> = self
+            .statics
+            .iter()
+            .zip(&self.curves)
+            .map(|(&value, curve)| curve.as_ref().map_or(value, |curve| curve.value_at(time, duration)))
+            .collect();
+        values.resize(self.buffer_size() as usize / 4, 0.0);
+        values
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interpolates_keyframes_and_holds_ends() {
+        let curve = ParamCurve::parse("zoom=2:1,end:3").unwrap();
+
+        assert_eq!(curve.name, "zoom");
+        assert_eq!(curve.value_at(0.0, 12.0), 1.0);
+        assert_eq!(curve.value_at(7.0, 12.0), 2.0);
+        assert_eq!(curve.value_at(12.0, 12.0), 3.0);
+        assert_eq!(curve.value_at(20.0, 12.0), 3.0);
+    }
+
+    #[test]
+    fn rejects_malformed_curves() {
+        assert!(ParamCurve::parse("zoom").is_err());
+        assert!(ParamCurve::parse("zoom=0").is_err());
+        assert!(ParamCurve::parse("zoom=start:1").is_err());
+        assert!(ParamCurve::parse("zoom=0:big").is_err());
+    }
+
+    #[test]
+    fn animates_only_float_parameters() {
+        let manifest: TemplateManifest = serde_json::from_str(
+            r#"{
+                "name": "test",
+                "display_name": "Test",
+                "shaders": { "fragment": "main.wgsl" },
+                "parameters": {
+                    "zoom": { "type": "float", "default": 1.0 },
+                    "glow": { "type": "float", "default": 0.5 },
+                    "count": { "type": "int", "default": 6 }
+                }
+            }"#,
+        )
+        .unwrap();
+        let overrides = HashMap::from([("glow".to_string(), "0.25".to_string())]);
+        let curves = [ParamCurve::parse("zoom=0:1,end:3").unwrap()];
+
+        let params = AnimatedParams::new(&manifest, &overrides, &curves).unwrap();
+
+        assert_eq!(params.values_at(5.0, 10.0), [0.25, 2.0, 0.0, 0.0]);
+        assert!(params.wgsl_decl().contains("glow: f32,\n    zoom: f32,"));
+        assert_eq!(params.unused_curves("let z = PARAM_ZOOM;"), ["zoom"]);
+        assert!(params.unused_curves("let z = param_anim.zoom;").is_empty());
+
+        let int_curve = [ParamCurve::parse("count=0:1,end:3").unwrap()];
+        assert!(AnimatedParams::new(&manifest, &overrides, &int_curve).is_err());
+    }
+}
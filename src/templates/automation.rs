@@ -0,0 +1,116 @@
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use super::animation::ParamCurve;
+
+/// Binding of the per-frame `automation` storage block.
+pub const AUTOMATION_BINDING: u32 = 4;
+/// Number of `automation.values` slots, whether or not a file fills them.
+pub const AUTOMATION_SLOTS: usize = 16;
+
+/// `--automation`: named scalar tracks from an external timeline, e.g. cues
+/// exported from an editor or lyrics timing. The file maps each name to
+/// `[time, value]` keyframes in seconds:
+///
+/// ```json
+/// { "zoom": [[0, 1.0], [12.5, 2.0]], "flash": [[30, 0], [30.1, 1], [30.6, 0]] }
+/// ```
+///
+/// Tracks fill `automation.values` in name order, and each also gets an
+/// `AUTO_<NAME>` index constant. Values are interpolated like `--param-anim`.
+#[derive(Debug, Clone, Default)]
+pub struct Automation {
+    tracks: Vec<ParamCurve>,
+}
+
+impl Automation {
+    pub fn load(path: &Path) -> Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read automation file: {}", path.display()))?;
+        Self::parse(&json).with_context(|| format!("Invalid automation file: {}", path.display()))
+    }
+
+    pub fn parse(json: &str) -> Result<Self> {
+        let tracks: BTreeMap<String, Vec<(f32, f32)>> =
+            serde_json::from_str(json).context("expected an object of NAME: [[TIME, VALUE], ...]")?;
+        if tracks.len() > AUTOMATION_SLOTS {
+            anyhow::bail!("{} tracks given, but at most {} are supported", tracks.len(), AUTOMATION_SLOTS);
+        }
+
+        let mut curves = Vec::with_capacity(tracks.len());
+        for (name, keyframes) in tracks {
+            let mut chars = name.chars();
+            if !chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+                || !chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+            {
+                anyhow::bail!("track name '{name}' must be letters, digits and underscores");
+            }
+            if keyframes.is_empty() {
+                anyhow::bail!("track '{name}' has no keyframes");
+            }
+            if let Some((time, value)) = keyframes
+                .iter()
+                .find(|(time, value)| !(time.is_finite() && *time >= 0.0 && value.is_finite()))
+            {
+                anyhow::bail!("track '{name}' has an invalid keyframe [{time}, {value}]");
+            }
+            curves.push(ParamCurve::from_keyframes(name, &keyframes));
+        }
+        Ok(Self { tracks: curves })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tracks.is_empty()
+    }
+
+    /// WGSL declaration of the block and the track index constants,
+    /// prepended to every template so `automation.values` always exists.
+    pub fn wgsl_decl(&self) -> String {
+        let mut decl = format!(
+            "// External automation (--automation)\nstruct Automation {{\n    values: array<f32, {AUTOMATION_SLOTS}>,\n}};\n\
+             @group(0) @binding({AUTOMATION_BINDING}) var<storage, read> automation: Automation;\n"
+        );
+        for (i, track) in self.tracks.iter().enumerate() {
+            decl.push_str(&format!("const AUTO_{}: u32 = {i}u;\n", track.name.to_ascii_uppercase()));
+        }
+        decl.push('\n');
+        decl
+    }
+
+    /// Buffer contents at `time`; unused slots hold 0.
+    pub fn values_at(&self, time: f32, duration: f32) -> [f32; AUTOMATION_SLOTS] {
+        let mut values = [0.0; AUTOMATION_SLOTS];
+        for (value, track) in values.iter_mut().zip(&self.tracks) {
+            *value = track.value_at(time, duration);
+        }
+        values
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracks_fill_slots_in_name_order() {
+        let automation = Automation::parse(r#"{ "zoom": [[0, 1], [10, 3]], "flash": [[2, 1]] }"#).unwrap();
+
+        assert_eq!(automation.values_at(5.0, 20.0)[..3], [1.0, 2.0, 0.0]);
+        let decl = automation.wgsl_decl();
+        assert!(decl.contains("const AUTO_FLASH: u32 = 0u;"));
+        assert!(decl.contains("const AUTO_ZOOM: u32 = 1u;"));
+        assert!(Automation::default().wgsl_decl().contains("var<storage, read> automation: Automation;"));
+    }
+
+    #[test]
+    fn rejects_malformed_files() {
+        assert!(Automation::parse(r#"[1, 2]"#).is_err());
+        assert!(Automation::parse(r#"{ "zoom": [] }"#).is_err());
+        assert!(Automation::parse(r#"{ "zoom level": [[0, 1]] }"#).is_err());
+        assert!(Automation::parse(r#"{ "zoom": [[-1, 1]] }"#).is_err());
+        let too_many: BTreeMap<String, Vec<(f32, f32)>> =
+            (0..=AUTOMATION_SLOTS).map(|i| (format!("t{i}"), vec![(0.0, 1.0)])).collect();
+        assert!(Automation::parse(&serde_json::to_string(&too_many).unwrap()).is_err());
+    }
+}
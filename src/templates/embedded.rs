@@ -1,6 +1,9 @@
 pub struct EmbeddedTemplate {
     pub manifest_json: &'static str,
     pub fragment_wgsl: &'static str,
+    pub compute_wgsl: Option<&'static str>,
+    /// Texture files referenced by the manifest, keyed by their manifest path.
+    pub textures: &'static [(&'static str, &'static [u8])],
 }
 
 pub fn embedded_templates() -> &'static [(&'static str, EmbeddedTemplate)] {
@@ -10,6 +13,8 @@ pub fn embedded_templates() -> &'static [(&'static str, EmbeddedTemplate)] {
             EmbeddedTemplate {
                 manifest_json: include_str!("../../templates/circular_spectrum/manifest.json"),
                 fragment_wgsl: include_str!("../../templates/circular_spectrum/main.wgsl"),
+                compute_wgsl: None,
+                textures: &[],
             },
         ),
         (
@@ -17,6 +22,8 @@ pub fn embedded_templates() -> &'static [(&'static str, EmbeddedTemplate)] {
             EmbeddedTemplate {
                 manifest_json: include_str!("../../templates/frequency_bars/manifest.json"),
                 fragment_wgsl: include_str!("../../templates/frequency_bars/main.wgsl"),
+                compute_wgsl: None,
+                textures: &[],
             },
         ),
         (
@@ -24,6 +31,8 @@ pub fn embedded_templates() -> &'static [(&'static str, EmbeddedTemplate)] {
             EmbeddedTemplate {
                 manifest_json: include_str!("../../templates/kaleidoscope/manifest.json"),
                 fragment_wgsl: include_str!("../../templates/kaleidoscope/main.wgsl"),
+                compute_wgsl: None,
+                textures: &[],
             },
         ),
         (
@@ -31,6 +40,17 @@ pub fn embedded_templates() -> &'static [(&'static str, EmbeddedTemplate)] {
             EmbeddedTemplate {
                 manifest_json: include_str!("../../templates/particle_burst/manifest.json"),
                 fragment_wgsl: include_str!("../../templates/particle_burst/main.wgsl"),
+                compute_wgsl: None,
+                textures: &[],
+            },
+        ),
+        (
+            "ring_scope",
+            EmbeddedTemplate {
+                manifest_json: include_str!("../../templates/ring_scope/manifest.json"),
+                fragment_wgsl: include_str!("../../templates/ring_scope/main.wgsl"),
+                compute_wgsl: None,
+                textures: &[],
             },
         ),
         (
@@ -38,6 +58,8 @@ pub fn embedded_templates() -> &'static [(&'static str, EmbeddedTemplate)] {
             EmbeddedTemplate {
                 manifest_json: include_str!("../../templates/spectrogram/manifest.json"),
                 fragment_wgsl: include_str!("../../templates/spectrogram/main.wgsl"),
+                compute_wgsl: Some(include_str!("../../templates/spectrogram/history.wgsl")),
+                textures: &[],
             },
         ),
         (
@@ -45,6 +67,8 @@ pub fn embedded_templates() -> &'static [(&'static str, EmbeddedTemplate)] {
             EmbeddedTemplate {
                 manifest_json: include_str!("../../templates/waveform_scope/manifest.json"),
                 fragment_wgsl: include_str!("../../templates/waveform_scope/main.wgsl"),
+                compute_wgsl: None,
+                textures: &[],
             },
         ),
     ]
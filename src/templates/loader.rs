@@ -1,14 +1,62 @@
 use anyhow::{Context, Result};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Component, Path, PathBuf};
 
 use super::embedded;
 use super::manifest::TemplateManifest;
+use crate::render::feedback::FEEDBACK_BINDING;
 
 pub struct LoadedTemplate {
     pub manifest: TemplateManifest,
     pub fragment_shader: String,
     pub compute_shader: Option<String>,
+    pub textures: Vec<TextureAsset>,
+}
+
+/// Encoded image bytes for a manifest `textures` entry, decoded at upload time.
+pub struct TextureAsset {
+    pub binding: u32,
+    pub file: String,
+    pub linear: bool,
+    pub bytes: Vec<u8>,
+}
+
+/// User template directories, mirroring the global config lookup in main.rs:
+/// `~/.config/sonica/templates`, then the platform config dir
+/// (`~/Library/Application Support/sonica/templates` on macOS).
+fn user_templates_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(home) = dirs::home_dir() {
+        dirs.push(home.join(".config").join("sonica").join("templates"));
+    }
+    if let Some(config_dir) = dirs::config_dir() {
+        let platform = config_dir.join("sonica").join("templates");
+        if !dirs.contains(&platform) {
+            dirs.push(platform);
+        }
+    }
+    dirs.retain(|dir| dir.exists());
+    dirs
+}
+
+/// Every filesystem template directory in precedence order: user dirs first
+/// so a same-named template overrides the installed one, then the install
+/// dir. Embedded templates come after all of these.
+fn template_search_dirs() -> Vec<PathBuf> {
+    let mut dirs = user_templates_dirs();
+    dirs.extend(find_templates_dir());
+    dirs
+}
+
+/// Whether templates placed in `dir` are found by `-t`.
+pub fn is_template_search_dir(dir: &Path) -> bool {
+    let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+    let Ok(dir) = dir.canonicalize() else {
+        return false;
+    };
+    template_search_dirs()
+        .iter()
+        .any(|search_dir| search_dir.canonicalize().is_ok_and(|search_dir| search_dir == dir))
 }
 
 /// Discover templates from built-in templates directory
@@ -84,15 +132,17 @@ fn find_shaders_dir() -> Option<PathBuf> {
 pub fn list_templates() -> Result<Vec<String>> {
     let mut names: Vec<String> = Vec::new();
 
-    // Filesystem templates
-    if let Some(dir) = find_templates_dir() {
+    // Filesystem templates (user dirs, then the install dir)
+    for dir in template_search_dirs() {
         for entry in std::fs::read_dir(&dir)? {
             let entry = entry?;
             if entry.file_type()?.is_dir() {
                 let manifest_path = entry.path().join("manifest.json");
                 if manifest_path.exists() {
                     if let Some(name) = entry.file_name().to_str() {
-                        names.push(name.to_string());
+                        if !names.iter().any(|n| n == name) {
+                            names.push(name.to_string());
+                        }
                     }
                 }
             }
@@ -120,22 +170,76 @@ pub fn load_template(name: &str) -> Result<LoadedTemplate> {
     load_template_embedded(name)
 }
 
+/// `--copy-template`: write `name`'s manifest, shaders and textures into
+/// `dest` as a new template named after the directory. Shaders are written
+/// with their `#import`s inlined, so the copy doesn't depend on `shaders/`.
+/// Returns the new template's name.
+pub fn copy_template(name: &str, dest: &Path) -> Result<String> {
+    let new_name = dest
+        .file_name()
+        .and_then(|n| n.to_str())
+        .with_context(|| format!("Invalid template directory: {}", dest.display()))?
+        .to_string();
+    if dest.join("manifest.json").exists() {
+        anyhow::bail!("{} already contains a template", dest.display());
+    }
+
+    let mut loaded = load_template(name)?;
+    loaded.manifest.name = new_name.clone();
+    loaded.manifest.display_name = display_name(&new_name);
+
+    std::fs::create_dir_all(dest)
+        .with_context(|| format!("Failed to create {}", dest.display()))?;
+    let write = |file: &str, contents: &[u8]| -> Result<()> {
+        let path = dest.join(file);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, contents).with_context(|| format!("Failed to write {}", path.display()))
+    };
+
+    write(&loaded.manifest.shaders.fragment, loaded.fragment_shader.as_bytes())?;
+    if let (Some(file), Some(source)) = (&loaded.manifest.shaders.compute, &loaded.compute_shader) {
+        write(file, source.as_bytes())?;
+    }
+    for texture in &loaded.textures {
+        write(&texture.file, &texture.bytes)?;
+    }
+    let manifest = serde_json::to_string_pretty(&loaded.manifest)?;
+    write("manifest.json", (manifest + "\n").as_bytes())?;
+
+    Ok(new_name)
+}
+
+/// `my_bars` -> `My Bars`
+fn display_name(name: &str) -> String {
+    name.split(['_', '-', ' '])
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            chars.next().map_or_else(String::new, |first| first.to_uppercase().chain(chars).collect())
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
 fn try_load_template_fs(name: &str) -> Result<Option<LoadedTemplate>> {
-    let dir = match find_templates_dir() {
+    let template_dir = match template_search_dirs()
+        .into_iter()
+        .map(|dir| dir.join(name))
+        .find(|dir| dir.join("manifest.json").exists())
+    {
         Some(d) => d,
         None => return Ok(None),
     };
-
-    let template_dir = dir.join(name);
-    if !template_dir.exists() {
-        return Ok(None);
-    }
+    log::debug!("Loading template '{}' from {}", name, template_dir.display());
 
     let manifest_path = template_dir.join("manifest.json");
     let manifest_str = std::fs::read_to_string(&manifest_path)
         .with_context(|| format!("Failed to read manifest: {}", manifest_path.display()))?;
     let manifest: TemplateManifest = serde_json::from_str(&manifest_str)
         .with_context(|| format!("Failed to parse manifest: {}", manifest_path.display()))?;
+    validate_file_paths(&manifest)?;
 
     let fragment_path = template_dir.join(&manifest.shaders.fragment);
     let fragment_raw = std::fs::read_to_string(&fragment_path)
@@ -151,10 +255,25 @@ fn try_load_template_fs(name: &str) -> Result<Option<LoadedTemplate>> {
         None
     };
 
+    validate_texture_bindings(&manifest)?;
+    let mut textures = Vec::with_capacity(manifest.textures.len());
+    for def in &manifest.textures {
+        let path = template_dir.join(&def.file);
+        let bytes = std::fs::read(&path)
+            .with_context(|| format!("Failed to read texture: {}", path.display()))?;
+        textures.push(TextureAsset {
+            binding: def.binding,
+            file: def.file.clone(),
+            linear: def.linear,
+            bytes,
+        });
+    }
+
     Ok(Some(LoadedTemplate {
         manifest,
         fragment_shader,
         compute_shader,
+        textures,
     }))
 }
 
@@ -177,16 +296,116 @@ fn load_template_embedded(name: &str) -> Result<LoadedTemplate> {
 
     let manifest: TemplateManifest = serde_json::from_str(tmpl.manifest_json)
         .with_context(|| format!("Failed to parse embedded manifest for '{}'", name))?;
+    validate_file_paths(&manifest)?;
 
     let fragment_shader = preprocess_imports(tmpl.fragment_wgsl)?;
+    let compute_shader = tmpl.compute_wgsl.map(preprocess_imports).transpose()?;
+
+    validate_texture_bindings(&manifest)?;
+    let mut textures = Vec::with_capacity(manifest.textures.len());
+    for def in &manifest.textures {
+        let bytes = tmpl
+            .textures
+            .iter()
+            .find(|(file, _)| *file == def.file)
+            .map(|(_, bytes)| bytes.to_vec())
+            .with_context(|| format!("Embedded template '{}' is missing texture '{}'", name, def.file))?;
+        textures.push(TextureAsset {
+            binding: def.binding,
+            file: def.file.clone(),
+            linear: def.linear,
+            bytes,
+        });
+    }
 
     Ok(LoadedTemplate {
         manifest,
         fragment_shader,
-        compute_shader: None,
+        compute_shader,
+        textures,
     })
 }
 
+/// Shader and texture files are read from the template directory and
+/// written into a `--copy-template` one, so they must stay inside it: no
+/// absolute paths and no `..`.
+fn validate_file_paths(manifest: &TemplateManifest) -> Result<()> {
+    let shaders = std::iter::once(manifest.shaders.fragment.as_str()).chain(manifest.shaders.compute.as_deref());
+    let textures = manifest.textures.iter().map(|def| def.file.as_str());
+    for file in shaders.chain(textures) {
+        let inside = Path::new(file)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+        if !inside || file.is_empty() {
+            anyhow::bail!(
+                "Template '{}': file '{}' must be a relative path inside the template directory",
+                manifest.name,
+                file
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Bindings 0-4 belong to the shared uniforms/FFT/waveform/`param_anim`/
+/// `automation` contract and binding 15 to `waveform_r`, and each texture
+/// (the history and feedback textures included) also claims `binding + 1`
+/// for its sampler, so those must not overlap.
+fn validate_texture_bindings(manifest: &TemplateManifest) -> Result<()> {
+    let mut claimed: Vec<u32> = vec![
+        0,
+        1,
+        2,
+        super::animation::PARAM_ANIM_BINDING,
+        super::automation::AUTOMATION_BINDING,
+        crate::render::pipeline::WAVEFORM_R_BINDING,
+    ];
+    let textures = manifest.textures.iter().map(|def| (def.file.as_str(), def.binding));
+    let history = manifest.history.as_ref().map(|def| ("history", def.binding));
+    let feedback = manifest.feedback.then_some(("feedback", FEEDBACK_BINDING));
+    for (file, texture_binding) in textures.chain(history).chain(feedback) {
+        let Some(sampler_binding) = texture_binding.checked_add(1) else {
+            anyhow::bail!(
+                "Template '{}': texture '{}' at binding {} leaves no binding for its sampler",
+                manifest.name,
+                file,
+                texture_binding
+            );
+        };
+        for binding in [texture_binding, sampler_binding] {
+            if claimed.contains(&binding) {
+                anyhow::bail!(
+                    "Template '{}': texture '{}' at binding {} collides with binding {} \
+                     (bindings 0-4 and 15 are reserved, 13-14 with feedback, and each texture also uses binding + 1 for its sampler)",
+                    manifest.name,
+                    file,
+                    texture_binding,
+                    binding
+                );
+            }
+            claimed.push(binding);
+        }
+    }
+
+    match (&manifest.shaders.compute, &manifest.history) {
+        (Some(_), None) => anyhow::bail!(
+            "Template '{}': a compute shader needs a `history` texture to write to",
+            manifest.name
+        ),
+        (None, Some(_)) => anyhow::bail!(
+            "Template '{}': the `history` texture is only updated by a compute shader",
+            manifest.name
+        ),
+        _ => {}
+    }
+    if let Some(history) = &manifest.history {
+        if history.width == 0 || history.height == 0 {
+            anyhow::bail!("Template '{}': history width and height must be positive", manifest.name);
+        }
+    }
+    Ok(())
+}
+
 pub fn load_shared_shader(relative_path: &str) -> Result<String> {
     // Try filesystem first
     if let Some(dir) = find_shaders_dir() {
@@ -291,3 +510,75 @@ pub fn inject_params(
     consts.push('\n');
     format!("{}{}", consts, shader_src)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn copies_a_template_under_a_new_name() {
+        let dest = std::env::temp_dir()
+            .join(format!("sonica-copy-template-{}", std::process::id()))
+            .join("my_bars");
+
+        let name = copy_template("frequency_bars", &dest).unwrap();
+        let manifest: TemplateManifest =
+            serde_json::from_str(&std::fs::read_to_string(dest.join("manifest.json")).unwrap()).unwrap();
+        let shader = std::fs::read_to_string(dest.join(&manifest.shaders.fragment)).unwrap();
+        let again = copy_template("frequency_bars", &dest);
+        std::fs::remove_dir_all(dest.parent().unwrap()).unwrap();
+
+        assert_eq!(name, "my_bars");
+        assert_eq!(manifest.name, "my_bars");
+        assert_eq!(manifest.display_name, "My Bars");
+        assert!(!shader.contains("// #import"));
+        assert!(again.is_err(), "refuses to overwrite an existing template");
+    }
+
+    #[test]
+    fn file_paths_stay_inside_the_template() {
+        let manifest = |fragment: &str, texture: &str| -> TemplateManifest {
+            serde_json::from_str(&format!(
+                r#"{{"name": "t", "display_name": "T", "shaders": {{"fragment": "{fragment}"}},
+                    "textures": [{{"binding": 5, "file": "{texture}"}}], "parameters": {{}}}}"#
+            ))
+            .unwrap()
+        };
+        assert!(validate_file_paths(&manifest("main.wgsl", "textures/ramp.png")).is_ok());
+        assert!(validate_file_paths(&manifest("./main.wgsl", "ramp.png")).is_ok());
+        assert!(validate_file_paths(&manifest("main.wgsl", "../../x")).is_err());
+        assert!(validate_file_paths(&manifest("textures/../../main.wgsl", "ramp.png")).is_err());
+        assert!(validate_file_paths(&manifest("/etc/main.wgsl", "ramp.png")).is_err());
+        assert!(validate_file_paths(&manifest("main.wgsl", "")).is_err());
+    }
+
+    #[test]
+    fn history_texture_comes_with_a_compute_shader() {
+        let spectrogram = load_template("spectrogram").unwrap();
+        assert!(spectrogram.compute_shader.as_deref().is_some_and(|src| src.contains("cs_main")));
+        let embedded = load_template_embedded("spectrogram").unwrap();
+        assert!(embedded.compute_shader.is_some());
+
+        let manifest = |shaders: &str, history: &str| -> TemplateManifest {
+            serde_json::from_str(&format!(
+                r#"{{"name": "t", "display_name": "T", "shaders": {shaders}, {history} "parameters": {{}}}}"#
+            ))
+            .unwrap()
+        };
+        let with_compute = r#"{"fragment": "main.wgsl", "compute": "c.wgsl"}"#;
+        assert!(validate_texture_bindings(&manifest(with_compute, r#""history": {"binding": 5, "width": 64, "height": 32},"#)).is_ok());
+        assert!(validate_texture_bindings(&manifest(with_compute, "")).is_err());
+        assert!(validate_texture_bindings(&manifest(r#"{"fragment": "main.wgsl"}"#, r#""history": {"binding": 5, "width": 64, "height": 32},"#)).is_err());
+        assert!(validate_texture_bindings(&manifest(with_compute, r#""history": {"binding": 4, "width": 64, "height": 32},"#)).is_err());
+        assert!(validate_texture_bindings(&manifest(with_compute, r#""history": {"binding": 5, "width": 0, "height": 32},"#)).is_err());
+
+        // The feedback texture claims its binding pair only when enabled.
+        let fragment = r#"{"fragment": "main.wgsl"}"#;
+        let texture_at_13 = r#""textures": [{"binding": 13, "file": "ramp.png"}],"#;
+        assert!(validate_texture_bindings(&manifest(fragment, texture_at_13)).is_ok());
+        let texture_at_max = r#""textures": [{"binding": 4294967295, "file": "ramp.png"}],"#;
+        assert!(validate_texture_bindings(&manifest(fragment, texture_at_max)).is_err());
+        assert!(validate_texture_bindings(&manifest(fragment, &format!(r#"{texture_at_13} "feedback": true,"#))).is_err());
+        assert!(validate_texture_bindings(&manifest(fragment, r#""feedback": true,"#)).is_ok());
+    }
+}
@@ -1,7 +1,7 @@
-use serde::Deserialize;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct TemplateManifest {
     #[allow(dead_code)]
     pub name: String,
@@ -9,28 +9,114 @@ pub struct TemplateManifest {
     #[serde(default)]
     pub description: String,
     pub shaders: ShaderPaths,
-    #[serde(default)]
-    pub default_effects: Vec<String>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub default_effects: Vec<DefaultEffect>,
+    #[serde(default, serialize_with = "sorted_parameters")]
     pub parameters: HashMap<String, ParamDef>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub textures: Vec<TextureDef>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub history: Option<HistoryDef>,
+    /// Keep the previous frame in the render target instead of clearing it,
+    /// and alpha-blend the fragment output over it. The shader is responsible
+    /// for fading old content, e.g. by returning a low alpha where it draws
+    /// nothing new.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub accumulate: bool,
+    /// Bind the previous frame's render target as a texture at
+    /// `FEEDBACK_BINDING`, with its sampler at the binding after.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub feedback: bool,
+}
+
+/// An entry of `default_effects`: an effect spec as on the command line
+/// (`"vignette"`, `"mirror:quad"`), or `{"name": ..., "intensity": ...}` to
+/// ship the effect tuned as part of the template's look.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum DefaultEffect {
+    Spec(String),
+    Tuned { name: String, intensity: f32 },
+}
+
+impl DefaultEffect {
+    /// The entry as an effect spec: `{"name": "crt", "intensity": 0.6}`
+    /// becomes `crt=0.6`.
+    pub fn spec(&self) -> String {
+        match self {
+            Self::Spec(spec) => spec.clone(),
+            Self::Tuned { name, intensity } => match name.split_once('@') {
+                Some((name, scale)) => format!("{name}={intensity}@{scale}"),
+                None => format!("{name}={intensity}"),
+            },
+        }
+    }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct ShaderPaths {
     pub fragment: String,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub compute: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+/// An image shipped alongside the template. The texture is bound at `binding`
+/// and its sampler at `binding + 1`.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TextureDef {
+    pub binding: u32,
+    pub file: String,
+    /// Upload as linear data (noise, lookup tables) instead of sRGB colour.
+    #[serde(default)]
+    pub linear: bool,
+}
+
+/// A 2D texture that persists between frames. The template's compute shader
+/// reads last frame's copy and writes this frame's, and the fragment shader
+/// samples the result at `binding` with its sampler at `binding + 1`.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct HistoryDef {
+    pub binding: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
 pub struct ParamDef {
     #[serde(rename = "type")]
     pub param_type: String,
     pub default: serde_json::Value,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     #[allow(dead_code)]
     pub min: Option<serde_json::Value>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     #[allow(dead_code)]
     pub max: Option<serde_json::Value>,
 }
+
+/// Write parameters in name order so copied manifests diff cleanly.
+fn sorted_parameters<S: serde::Serializer>(
+    parameters: &HashMap<String, ParamDef>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    parameters.iter().collect::<BTreeMap<_, _>>().serialize(serializer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_effects_take_specs_or_tuned_objects() {
+        let effects: Vec<DefaultEffect> = serde_json::from_str(
+            r#"["bloom@0.5", {"name": "vignette", "intensity": 0.6}, {"name": "crt@0.5", "intensity": 1.5}]"#,
+        )
+        .unwrap();
+        let specs: Vec<String> = effects.iter().map(DefaultEffect::spec).collect();
+        assert_eq!(specs, ["bloom@0.5", "vignette=0.6", "crt=1.5@0.5"]);
+
+        // --copy-template writes entries back in the form they were read.
+        let written = serde_json::to_string(&effects).unwrap();
+        assert_eq!(written, r#"["bloom@0.5",{"name":"vignette","intensity":0.6},{"name":"crt@0.5","intensity":1.5}]"#);
+    }
+}
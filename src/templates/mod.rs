@@ -1,3 +1,5 @@
+pub mod animation;
+pub mod automation;
 pub mod embedded;
 pub mod loader;
 pub mod manifest;
@@ -15,7 +15,15 @@ struct FrameUniforms {
     bass: f32,
     mid: f32,
     high: f32,
-    _padding: f32,
+    spectral_centroid_hz: f32,
+    spectral_flux_raw: f32,
+    onset_strength: f32,
+    _padding1: f32,
+    _padding2: f32,
+    left_bands_low: vec4<f32>,
+    left_bands_high: vec4<f32>,
+    right_bands_low: vec4<f32>,
+    right_bands_high: vec4<f32>,
 };
 
 @group(0) @binding(0) var<uniform> u: FrameUniforms;
@@ -82,7 +90,7 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     let norm_angle = (angle + PI) / TWO_PI; // 0..1
 
     // Inner radius with beat pulse
-    let inner_r = PARAM_INNER_RADIUS + u.beat_intensity * 0.06;
+    let inner_r = param_anim.inner_radius + u.beat_intensity * 0.06;
 
     // Sample FFT at this angle (quantized to segments)
     let seg = floor(norm_angle * f32(PARAM_SEGMENTS));
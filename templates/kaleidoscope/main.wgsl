@@ -15,7 +15,15 @@ struct FrameUniforms {
     bass: f32,
     mid: f32,
     high: f32,
-    _padding: f32,
+    spectral_centroid_hz: f32,
+    spectral_flux_raw: f32,
+    onset_strength: f32,
+    _padding1: f32,
+    _padding2: f32,
+    left_bands_low: vec4<f32>,
+    left_bands_high: vec4<f32>,
+    right_bands_low: vec4<f32>,
+    right_bands_high: vec4<f32>,
 };
 
 @group(0) @binding(0) var<uniform> u: FrameUniforms;
@@ -80,7 +88,7 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     p.y *= max(1.0 / aspect, 1.0);
 
     // Audio-reactive zoom
-    let zoom = PARAM_ZOOM + 0.5 + u.bass * 0.5 + u.beat_intensity * 0.3;
+    let zoom = param_anim.zoom + 0.5 + u.bass * 0.5 + u.beat_intensity * 0.3;
     p *= zoom;
 
     // Rotation driven by time and spectral centroid
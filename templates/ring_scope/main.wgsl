@@ -0,0 +1,133 @@
+// Ring Scope - per-frame waveform wrapped around a circle
+
+struct FrameUniforms {
+    resolution: vec2<f32>,
+    time: f32,
+    frame: u32,
+    fps: f32,
+    duration: f32,
+    rms: f32,
+    spectral_centroid: f32,
+    spectral_flux: f32,
+    beat_intensity: f32,
+    beat_phase: f32,
+    is_beat: f32,
+    bass: f32,
+    mid: f32,
+    high: f32,
+    spectral_centroid_hz: f32,
+    spectral_flux_raw: f32,
+    onset_strength: f32,
+    _padding1: f32,
+    _padding2: f32,
+    left_bands_low: vec4<f32>,
+    left_bands_high: vec4<f32>,
+    right_bands_low: vec4<f32>,
+    right_bands_high: vec4<f32>,
+};
+
+@group(0) @binding(0) var<uniform> u: FrameUniforms;
+@group(0) @binding(1) var<storage, read> fft_bins: array<f32>;
+@group(0) @binding(2) var<storage, read> waveform: array<f32>;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    var out: VertexOutput;
+    let x = f32(i32(vertex_index) / 2) * 4.0 - 1.0;
+    let y = f32(i32(vertex_index) % 2) * 4.0 - 1.0;
+    out.position = vec4<f32>(x, y, 0.0, 1.0);
+    out.uv = vec2<f32>((x + 1.0) * 0.5, (1.0 - y) * 0.5);
+    return out;
+}
+
+fn hsv2rgb(h: f32, s: f32, v: f32) -> vec3<f32> {
+    let c = v * s;
+    let hp = h * 6.0;
+    let x = c * (1.0 - abs(hp % 2.0 - 1.0));
+    var rgb: vec3<f32>;
+    if hp < 1.0 { rgb = vec3<f32>(c, x, 0.0); }
+    else if hp < 2.0 { rgb = vec3<f32>(x, c, 0.0); }
+    else if hp < 3.0 { rgb = vec3<f32>(0.0, c, x); }
+    else if hp < 4.0 { rgb = vec3<f32>(0.0, x, c); }
+    else if hp < 5.0 { rgb = vec3<f32>(x, 0.0, c); }
+    else { rgb = vec3<f32>(c, 0.0, x); }
+    let m = v - c;
+    return rgb + vec3<f32>(m);
+}
+
+const PI: f32 = 3.14159265;
+const TWO_PI: f32 = 6.2831853;
+
+// Waveform [min, max] envelope at t (0..1 around the ring), linearly
+// interpolated. Tapered to zero at both ends so the first and last points
+// meet at the seam.
+fn sample_ring(t: f32, num_points: u32) -> vec2<f32> {
+    let idx_f = t * f32(num_points - 1u);
+    let idx0 = u32(floor(idx_f));
+    let idx1 = min(idx0 + 1u, num_points - 1u);
+    let frac = idx_f - floor(idx_f);
+    let v = vec2<f32>(
+        mix(waveform[idx0 * 2u], waveform[idx1 * 2u], frac),
+        mix(waveform[idx0 * 2u + 1u], waveform[idx1 * 2u + 1u], frac),
+    );
+    let taper = smoothstep(0.0, 0.04, t) * smoothstep(1.0, 0.96, t);
+    return v * taper;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Interleaved [min, max] pairs, one per point
+    let num_points = arrayLength(&waveform) / 2u;
+    let aspect = u.resolution.x / u.resolution.y;
+
+    // Center and correct aspect ratio (fit to shorter axis)
+    var p = in.uv - vec2<f32>(0.5);
+    p.x *= max(aspect, 1.0);
+    p.y *= max(1.0 / aspect, 1.0);
+
+    let dist = length(p);
+    let angle = atan2(p.y, p.x);
+    let t = (angle + PI) / TWO_PI; // 0..1, seam on the left
+
+    // Base radius breathes with the beat; waveform displaces it outward/inward
+    let base_r = PARAM_RADIUS + u.beat_intensity * 0.03;
+    let envelope = sample_ring(t, num_points);
+    let scale = PARAM_AMPLITUDE * (1.0 + u.rms);
+    let inner_r = base_r + envelope.x * scale;
+    let outer_r = base_r + envelope.y * scale;
+
+    // Approximate pixel distance to the band the envelope sweeps
+    let d = max(max(inner_r - dist, dist - outer_r), 0.0);
+    let px = 1.0 / min(u.resolution.x, u.resolution.y);
+    let thickness = PARAM_LINE_THICKNESS * px * (1.0 + u.rms * 2.0);
+
+    let line = smoothstep(thickness, 0.0, d);
+    let glow_size = thickness * 8.0;
+    let glow = exp(-d * d / (glow_size * glow_size)) * PARAM_GLOW_INTENSITY;
+
+    // Background: dark with a faint guide circle at the rest radius
+    var color = vec3<f32>(0.01, 0.01, 0.03);
+    let guide = smoothstep(px * 1.5, 0.0, abs(dist - base_r));
+    color += vec3<f32>(0.06, 0.06, 0.1) * guide;
+
+    // Colour follows spectral centroid: warm for dark timbres, cool for bright
+    let hue = 0.55 + u.spectral_centroid * 0.35 + t * 0.1;
+    let line_color = hsv2rgb(fract(hue), 0.8, 1.0);
+    let glow_color = hsv2rgb(fract(hue + 0.08), 0.6, 0.8);
+
+    color += line_color * line;
+    color += glow_color * glow * (0.5 + u.beat_intensity * 0.5);
+
+    // Soft inner fill driven by bass
+    if dist < base_r {
+        let fill = (1.0 - dist / base_r) * u.bass * 0.08;
+        color += hsv2rgb(fract(hue), 0.5, 1.0) * fill;
+    }
+
+    return vec4<f32>(color, 1.0);
+}
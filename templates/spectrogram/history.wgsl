@@ -0,0 +1,37 @@
+// Spectrogram history - runs once per frame before the fragment shader.
+// Shifts every column of the history one texel left and writes the current
+// FFT into the rightmost column, on a log frequency axis with the lowest
+// frequencies at the bottom.
+
+@group(0) @binding(1) var<storage, read> fft_bins: array<f32>;
+@group(0) @binding(3) var previous: texture_2d<f32>;
+@group(0) @binding(4) var next: texture_storage_2d<rgba16float, write>;
+
+@compute @workgroup_size(8, 8)
+fn cs_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = textureDimensions(next);
+    if id.x >= size.x || id.y >= size.y {
+        return;
+    }
+
+    // Scroll: every column but the newest copies its right-hand neighbour
+    if id.x + 1u < size.x {
+        textureStore(next, id.xy, textureLoad(previous, vec2<u32>(id.x + 1u, id.y), 0));
+        return;
+    }
+
+    // Newest column: row 0 is the top of the image, the highest frequency
+    let num_bins = arrayLength(&fft_bins);
+    let freq_t = 1.0 - (f32(id.y) + 0.5) / f32(size.y);
+
+    // Log frequency mapping
+    let min_freq = 20.0;
+    let max_freq = 20000.0;
+    let freq = min_freq * pow(max_freq / min_freq, freq_t);
+    let bin_f = freq / max_freq * f32(num_bins);
+    let bin_lo = min(u32(floor(bin_f)), num_bins - 1u);
+    let bin_hi = min(bin_lo + 1u, num_bins - 1u);
+    let fft_val = mix(fft_bins[bin_lo], fft_bins[bin_hi], fract(bin_f));
+
+    textureStore(next, id.xy, vec4<f32>(fft_val, 0.0, 0.0, 1.0));
+}
@@ -1,6 +1,6 @@
 // Spectrogram - scrolling time-frequency heatmap
-// Since we can't do feedback textures without compute, we display the current
-// frame's FFT as a column and use time-based positioning
+// history.wgsl keeps the last frames' FFT columns in the history texture,
+// newest on the right; this shader colors the most recent part of it.
 
 struct FrameUniforms {
     resolution: vec2<f32>,
@@ -17,12 +17,22 @@ struct FrameUniforms {
     bass: f32,
     mid: f32,
     high: f32,
-    _padding: f32,
+    spectral_centroid_hz: f32,
+    spectral_flux_raw: f32,
+    onset_strength: f32,
+    _padding1: f32,
+    _padding2: f32,
+    left_bands_low: vec4<f32>,
+    left_bands_high: vec4<f32>,
+    right_bands_low: vec4<f32>,
+    right_bands_high: vec4<f32>,
 };
 
 @group(0) @binding(0) var<uniform> u: FrameUniforms;
 @group(0) @binding(1) var<storage, read> fft_bins: array<f32>;
 @group(0) @binding(2) var<storage, read> waveform: array<f32>;
+@group(0) @binding(5) var history: texture_2d<f32>;
+@group(0) @binding(6) var history_sampler: sampler;
 
 struct VertexOutput {
     @builtin(position) position: vec4<f32>,
@@ -88,56 +98,20 @@ fn colormap(t: f32) -> vec3<f32> {
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     let uv = in.uv;
-    let num_bins = arrayLength(&fft_bins);
 
-    // Vertical axis = frequency (log scale), bottom = low freq
-    let freq_t = 1.0 - uv.y;
-
-    // Log frequency mapping
-    let min_freq = 20.0;
-    let max_freq = 20000.0;
-    let freq = min_freq * pow(max_freq / min_freq, freq_t);
-    let bin_f = freq / max_freq * f32(num_bins);
-    let bin_lo = u32(floor(bin_f));
-    let bin_hi = min(bin_lo + 1u, num_bins - 1u);
-
-    var fft_val = 0.0;
-    if bin_lo < num_bins {
-        let frac = bin_f - floor(bin_f);
-        fft_val = mix(fft_bins[bin_lo], fft_bins[min(bin_hi, num_bins - 1u)], frac);
-    }
-
-    // Current column position (rightmost = current time)
-    let progress = u.time / max(u.duration, 0.001);
-    let col_pos = progress;
-
-    // Horizontal: current frame is drawn as a thin band at the current position
-    // Everything else fades to black
-    let dist_from_cursor = abs(uv.x - col_pos);
-
-    // Show a narrow column of the current FFT
-    let column_width = 1.5 / u.resolution.x;
-
-    if dist_from_cursor < column_width {
-        // Current column
-        let intensity = pow(fft_val, 0.6);
-        let color = colormap(intensity);
-        return vec4<f32>(color, 1.0);
-    }
-
-    // Trail: fade based on distance from cursor
-    let trail_length = 0.3 / PARAM_SCROLL_SPEED;
-    let behind = col_pos - uv.x;
-    if behind > 0.0 && behind < trail_length {
-        let fade = 1.0 - behind / trail_length;
-        let trail_intensity = pow(fft_val, 0.6) * fade * fade;
-        let color = colormap(trail_intensity) * fade;
-        return vec4<f32>(color, 1.0);
-    }
+    // Horizontal: the newest column at the right edge. scroll_speed 1.0 shows
+    // the latest half of the history; faster speeds show less of it, spread
+    // wider, so it scrolls faster.
+    let visible = min(0.5 / PARAM_SCROLL_SPEED, 1.0);
+    let history_x = 1.0 - (1.0 - uv.x) * visible;
+    let fft_val = textureSampleLevel(history, history_sampler, vec2<f32>(history_x, uv.y), 0.0).r;
 
     // Background with frequency scale hint
+    let freq_t = 1.0 - uv.y;
     let grid_lines = smoothstep(0.005, 0.0, abs(fract(freq_t * 10.0) - 0.5) - 0.48);
     let bg = vec3<f32>(0.01, 0.01, 0.02) + vec3<f32>(0.02) * grid_lines;
 
-    return vec4<f32>(bg, 1.0);
+    let intensity = pow(max(fft_val, 0.0), 0.6);
+    let color = max(colormap(intensity) * smoothstep(0.0, 0.05, intensity), bg);
+    return vec4<f32>(color, 1.0);
 }
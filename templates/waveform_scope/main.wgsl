@@ -15,7 +15,15 @@ struct FrameUniforms {
     bass: f32,
     mid: f32,
     high: f32,
-    _padding: f32,
+    spectral_centroid_hz: f32,
+    spectral_flux_raw: f32,
+    onset_strength: f32,
+    _padding1: f32,
+    _padding2: f32,
+    left_bands_low: vec4<f32>,
+    left_bands_high: vec4<f32>,
+    right_bands_low: vec4<f32>,
+    right_bands_high: vec4<f32>,
 };
 
 @group(0) @binding(0) var<uniform> u: FrameUniforms;
@@ -55,7 +63,8 @@ fn hsv2rgb(h: f32, s: f32, v: f32) -> vec3<f32> {
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     let uv = in.uv;
-    let num_samples = arrayLength(&waveform);
+    // Interleaved [min, max] pairs, one per point
+    let num_points = arrayLength(&waveform) / 2u;
 
     // Background: dark with subtle grid
     var color = vec3<f32>(0.02, 0.02, 0.04);
@@ -71,16 +80,19 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     let center_line = smoothstep(0.002, 0.0, center_dist);
     color = mix(color, vec3<f32>(0.1, 0.1, 0.15), center_line * 0.5);
 
-    // Sample the waveform
-    let sample_idx_f = uv.x * f32(num_samples - 1u);
+    // Sample the waveform envelope
+    let sample_idx_f = uv.x * f32(num_points - 1u);
     let idx0 = u32(floor(sample_idx_f));
-    let idx1 = min(idx0 + 1u, num_samples - 1u);
+    let idx1 = min(idx0 + 1u, num_points - 1u);
     let frac = sample_idx_f - floor(sample_idx_f);
 
-    let sample_val = mix(waveform[idx0], waveform[idx1], frac);
-    let wave_y = 0.5 - sample_val * 0.4;
+    let min_val = mix(waveform[idx0 * 2u], waveform[idx1 * 2u], frac);
+    let max_val = mix(waveform[idx0 * 2u + 1u], waveform[idx1 * 2u + 1u], frac);
+    let top_y = 0.5 - max_val * 0.4;
+    let bottom_y = 0.5 - min_val * 0.4;
 
-    let dist = abs(uv.y - wave_y);
+    // Distance to the band between min and max; a plain line when they meet
+    let dist = max(max(top_y - uv.y, uv.y - bottom_y), 0.0);
 
     // Line thickness based on RMS
     let base_thickness = PARAM_LINE_THICKNESS / u.resolution.y;